@@ -0,0 +1,236 @@
+//! The syntax tree of a datalog program. Its `Display` implementations pretty-print it in the
+//! layout of the book: a rule's body is indented on the lines following its head.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Declaration(Declaration),
+    /// An `.input` directive, for the relation of this name.
+    Input(String),
+    /// An `.output` directive, for the relation of this name.
+    Output(String),
+    Rule(Rule),
+}
+
+/// A `.decl` of a relation and its columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub comments: Vec<String>,
+    pub relation: String,
+    pub columns: Vec<Column>,
+}
+
+/// A column of a declared relation, like `Origin:origin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// The lines of the comment preceding the rule.
+    pub comments: Vec<String>,
+    pub head: Atom,
+    /// The literals joined by the rule, in order.
+    pub body: Vec<Literal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Atom(Atom),
+    Negated(Atom),
+    /// Parenthesized alternatives, like `(live(O, P); placeholder(O, _))`: each of them is a
+    /// conjunction of literals.
+    Alternatives(Vec<Vec<Literal>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Atom {
+    pub relation: String,
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Variable(String),
+    /// The `_` wildcard.
+    Wildcard,
+}
+
+impl Program {
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Rule(rule) => Some(rule),
+            _ => None,
+        })
+    }
+
+    pub fn declarations(&self) -> impl Iterator<Item = &Declaration> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Declaration(declaration) => Some(declaration),
+            _ => None,
+        })
+    }
+
+    /// Whether an `.input` directive names the `relation`.
+    pub fn is_input(&self, relation: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, Item::Input(input) if input == relation))
+    }
+
+    /// Whether an `.output` directive names the `relation`.
+    pub fn is_output(&self, relation: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, Item::Output(output) if output == relation))
+    }
+}
+
+impl Rule {
+    /// The label of the rule, like `R3`, when its comment starts with one, like `// R3: ...`.
+    pub fn label(&self) -> Option<&str> {
+        let (prefix, _) = self.comments.first()?.split_once(':')?;
+        let digits = prefix.strip_prefix('R')?;
+        if digits.starts_with(|c: char| c.is_ascii_digit())
+            && digits.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Some(prefix)
+        } else {
+            None
+        }
+    }
+
+    /// The comment preceding the rule, without its label.
+    pub fn documentation(&self) -> Vec<&str> {
+        let mut documentation: Vec<&str> = self.comments.iter().map(|line| line.as_str()).collect();
+        if let Some(label) = self.label() {
+            documentation[0] = documentation[0][label.len() + 1..].trim_start();
+        }
+        documentation
+    }
+
+    /// The atoms of the body, in order, and whether they're negated.
+    pub fn atoms(&self) -> Vec<(&Atom, bool)> {
+        fn visit<'a>(literals: &'a [Literal], atoms: &mut Vec<(&'a Atom, bool)>) {
+            for literal in literals {
+                match literal {
+                    Literal::Atom(atom) => atoms.push((atom, false)),
+                    Literal::Negated(atom) => atoms.push((atom, true)),
+                    Literal::Alternatives(alternatives) => {
+                        for alternative in alternatives {
+                            visit(alternative, atoms);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut atoms = Vec::new();
+        visit(&self.body, &mut atoms);
+        atoms
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            // the directives follow their declaration, the other items are separated
+            let directive = matches!(item, Item::Input(_) | Item::Output(_));
+            if index > 0 && !directive {
+                writeln!(fmt)?;
+            }
+            write!(fmt, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Declaration(declaration) => write!(fmt, "{}", declaration),
+            Item::Input(relation) => writeln!(fmt, ".input {}", relation),
+            Item::Output(relation) => writeln!(fmt, ".output {}", relation),
+            Item::Rule(rule) => write!(fmt, "{}", rule),
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_comments(fmt, &self.comments)?;
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("{}:{}", column.name, column.ty))
+            .collect();
+        writeln!(fmt, ".decl {}({})", self.relation, columns.join(", "))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_comments(fmt, &self.comments)?;
+        writeln!(fmt, "{} :-", self.head)?;
+        for (index, literal) in self.body.iter().enumerate() {
+            let separator = if index + 1 < self.body.len() {
+                ","
+            } else {
+                "."
+            };
+            writeln!(fmt, "  {}{}", literal, separator)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Atom(atom) => write!(fmt, "{}", atom),
+            Literal::Negated(atom) => write!(fmt, "!{}", atom),
+            Literal::Alternatives(alternatives) => {
+                let alternatives: Vec<String> = alternatives
+                    .iter()
+                    .map(|literals| {
+                        let literals: Vec<String> =
+                            literals.iter().map(|literal| literal.to_string()).collect();
+                        literals.join(", ")
+                    })
+                    .collect();
+                write!(fmt, "({})", alternatives.join("; "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self.terms.iter().map(|term| term.to_string()).collect();
+        write!(fmt, "{}({})", self.relation, terms.join(", "))
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Variable(name) => fmt.write_str(name),
+            Term::Wildcard => fmt.write_str("_"),
+        }
+    }
+}
+
+fn write_comments(fmt: &mut fmt::Formatter<'_>, comments: &[String]) -> fmt::Result {
+    for comment in comments {
+        writeln!(fmt, "// {}", comment)?;
+    }
+    Ok(())
+}
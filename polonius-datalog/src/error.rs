@@ -0,0 +1,45 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The `character` at `line` doesn't start any token.
+    UnexpectedCharacter { line: usize, character: char },
+
+    /// The token `found` at `line` is not the `expected` one.
+    UnexpectedToken {
+        line: usize,
+        found: String,
+        expected: &'static str,
+    },
+}
+
+impl ParseError {
+    /// The line of the input where the error is, from 1.
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::UnexpectedCharacter { line, .. }
+            | ParseError::UnexpectedToken { line, .. } => *line,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedCharacter { line, character } => {
+                write!(fmt, "line {}: unexpected character `{}`", line, character)
+            }
+            ParseError::UnexpectedToken {
+                line,
+                found,
+                expected,
+            } => write!(
+                fmt,
+                "line {}: found {}, but expected {}",
+                line, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
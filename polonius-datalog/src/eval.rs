@@ -0,0 +1,525 @@
+//! A semi-naive interpreter of the rules of a [`Program`](crate::ast::Program), to evaluate them
+//! against a [`Database`] of tuples without implementing them with datafrog.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::ast::{Atom, Literal, Program, Rule, Term};
+use crate::lint::{lint, rule_name, Lint};
+
+/// A tuple of a relation: the indices of its atoms, like the ones of interned facts.
+pub type Tuple = Vec<u32>;
+
+/// The tuples of the relations, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Database {
+    relations: BTreeMap<String, BTreeSet<Tuple>>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the `tuple` to the `relation`, and returns whether it's new.
+    pub fn insert(&mut self, relation: &str, tuple: Tuple) -> bool {
+        self.relations
+            .entry(relation.to_string())
+            .or_default()
+            .insert(tuple)
+    }
+
+    pub fn extend(&mut self, relation: &str, tuples: impl IntoIterator<Item = Tuple>) {
+        self.relations
+            .entry(relation.to_string())
+            .or_default()
+            .extend(tuples);
+    }
+
+    /// The tuples of the `relation`, in order: none when it's unknown.
+    pub fn tuples(&self, relation: &str) -> impl Iterator<Item = &Tuple> {
+        self.relations.get(relation).into_iter().flatten()
+    }
+
+    /// The number of tuples of the `relation`.
+    pub fn len(&self, relation: &str) -> usize {
+        self.relations.get(relation).map_or(0, BTreeSet::len)
+    }
+
+    /// The names of the relations, in order.
+    pub fn relations(&self) -> impl Iterator<Item = &str> {
+        self.relations.keys().map(String::as_str)
+    }
+
+    fn contains(&self, relation: &str, tuple: &Tuple) -> bool {
+        self.relations
+            .get(relation)
+            .is_some_and(|tuples| tuples.contains(tuple))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// The rules can't be evaluated: the lint is an error.
+    Invalid(Lint),
+
+    /// An atom or a tuple of the `relation` has `found` columns, but it has `expected` ones.
+    Arity {
+        relation: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Invalid(lint) => write!(fmt, "{}", lint),
+            EvalError::Arity {
+                relation,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "error: `{}` has {} columns, but is used with {}",
+                relation, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Evaluates the rules of the `program` against the `database`, and adds the tuples they derive
+/// to it. The rules are evaluated by strata, so that the relations they negate are complete, and
+/// semi-naively: after the first round, a rule only joins the tuples derived by the previous one.
+pub fn evaluate(program: &Program, database: &mut Database) -> Result<(), EvalError> {
+    if let Some(error) = lint(program).into_iter().find(Lint::is_error) {
+        return Err(EvalError::Invalid(error));
+    }
+    check_arities(program, database)?;
+
+    let strata = strata(program);
+    let mut rules: Vec<Vec<&Rule>> = Vec::new();
+    for rule in program.rules() {
+        let stratum = strata[rule.head.relation.as_str()];
+        if rules.len() <= stratum {
+            rules.resize_with(stratum + 1, Vec::new);
+        }
+        rules[stratum].push(rule);
+    }
+
+    for rules in rules {
+        // the relations derived in this stratum, whose new tuples are joined in the next rounds
+        let recursive: BTreeSet<&str> = rules
+            .iter()
+            .map(|rule| rule.head.relation.as_str())
+            .collect();
+        let mut plans = Vec::new();
+        for rule in rules {
+            for conjunction in conjunctions(&rule.body) {
+                plans.extend(Plan::compile(rule, &conjunction, &recursive)?);
+            }
+        }
+        evaluate_stratum(&plans, database);
+    }
+    Ok(())
+}
+
+/// Checks that the atoms and the tuples of each relation have the columns of its declaration, or
+/// of its first atom.
+fn check_arities(program: &Program, database: &Database) -> Result<(), EvalError> {
+    let mut arities: BTreeMap<String, usize> = BTreeMap::new();
+    let mut check = |relation: &str, found: usize| match arities.get(relation) {
+        Some(&expected) if expected != found => Err(EvalError::Arity {
+            relation: relation.to_string(),
+            expected,
+            found,
+        }),
+        Some(_) => Ok(()),
+        None => {
+            arities.insert(relation.to_string(), found);
+            Ok(())
+        }
+    };
+
+    for declaration in program.declarations() {
+        check(&declaration.relation, declaration.columns.len())?;
+    }
+    for rule in program.rules() {
+        check(&rule.head.relation, rule.head.terms.len())?;
+        for (atom, _) in rule.atoms() {
+            check(&atom.relation, atom.terms.len())?;
+        }
+    }
+    for relation in database.relations() {
+        if let Some(tuple) = database.tuples(relation).next() {
+            check(relation, tuple.len())?;
+        }
+    }
+    Ok(())
+}
+
+/// The stratum of each derived relation: not before the strata of the relations it's derived
+/// from, and after the ones of the relations it negates.
+fn strata(program: &Program) -> BTreeMap<&str, usize> {
+    let mut strata: BTreeMap<&str, usize> = program
+        .rules()
+        .map(|rule| (rule.head.relation.as_str(), 0))
+        .collect();
+
+    // the negations are stratified, so this terminates
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for rule in program.rules() {
+            for (atom, negated) in rule.atoms() {
+                let stratum = match strata.get(atom.relation.as_str()) {
+                    Some(&stratum) if negated => stratum + 1,
+                    Some(&stratum) => stratum,
+                    None => continue,
+                };
+                let head = strata.get_mut(rule.head.relation.as_str()).unwrap();
+                if *head < stratum {
+                    *head = stratum;
+                    changed = true;
+                }
+            }
+        }
+    }
+    strata
+}
+
+/// The conjunctions of atoms, and whether they're negated, which the `literals` are the
+/// disjunction of: one for each choice among their alternatives.
+fn conjunctions(literals: &[Literal]) -> Vec<Vec<(&Atom, bool)>> {
+    let mut conjunctions = vec![Vec::new()];
+    for literal in literals {
+        match literal {
+            Literal::Atom(atom) => {
+                for conjunction in &mut conjunctions {
+                    conjunction.push((atom, false));
+                }
+            }
+            Literal::Negated(atom) => {
+                for conjunction in &mut conjunctions {
+                    conjunction.push((atom, true));
+                }
+            }
+            Literal::Alternatives(alternatives) => {
+                let mut expanded = Vec::new();
+                for conjunction in &conjunctions {
+                    for alternative in alternatives {
+                        for atoms in self::conjunctions(alternative) {
+                            let mut conjunction = conjunction.clone();
+                            conjunction.extend(atoms);
+                            expanded.push(conjunction);
+                        }
+                    }
+                }
+                conjunctions = expanded;
+            }
+        }
+    }
+    conjunctions
+}
+
+/// A conjunction of a rule, as the lookups binding the variables of its head.
+#[derive(Debug)]
+struct Plan<'a> {
+    head: &'a str,
+
+    /// The slots of the values of the head's columns.
+    output: Vec<usize>,
+
+    steps: Vec<Step<'a>>,
+
+    /// The number of variables of the conjunction.
+    slots: usize,
+
+    /// Whether the first step joins the tuples derived by the previous round, rather than all of
+    /// them.
+    delta: bool,
+}
+
+/// The lookup of the tuples of an atom.
+#[derive(Debug)]
+struct Step<'a> {
+    relation: &'a str,
+    negated: bool,
+
+    /// The columns whose values are bound by the previous steps: the key of the lookup.
+    columns: Vec<usize>,
+
+    /// The slots of the values of the key's `columns`.
+    key: Vec<usize>,
+
+    /// The columns binding new variables, and their slots.
+    binds: Vec<(usize, usize)>,
+
+    /// The columns repeating a variable bound by a previous column of the atom, and its slot.
+    checks: Vec<(usize, usize)>,
+}
+
+impl<'a> Plan<'a> {
+    /// The plans of a conjunction of the `rule`: one joining all the tuples, for the first round,
+    /// and one for each atom of a `recursive` relation, starting with its new tuples.
+    fn compile(
+        rule: &'a Rule,
+        conjunction: &[(&'a Atom, bool)],
+        recursive: &BTreeSet<&str>,
+    ) -> Result<Vec<Plan<'a>>, EvalError> {
+        // the positive atoms bind the variables of the negated ones
+        let positive: Vec<&Atom> = conjunction
+            .iter()
+            .filter(|&&(_, negated)| !negated)
+            .map(|&(atom, _)| atom)
+            .collect();
+        let negated: Vec<&Atom> = conjunction
+            .iter()
+            .filter(|&&(_, negated)| negated)
+            .map(|&(atom, _)| atom)
+            .collect();
+
+        let mut plans = vec![Plan::new(rule, &positive, &negated, false)?];
+        for (index, atom) in positive.iter().enumerate() {
+            if recursive.contains(atom.relation.as_str()) {
+                let mut order = positive.clone();
+                let first = order.remove(index);
+                order.insert(0, first);
+                plans.push(Plan::new(rule, &order, &negated, true)?);
+            }
+        }
+        Ok(plans)
+    }
+
+    fn new(
+        rule: &'a Rule,
+        positive: &[&'a Atom],
+        negated: &[&'a Atom],
+        delta: bool,
+    ) -> Result<Plan<'a>, EvalError> {
+        let mut slots: HashMap<&str, usize> = HashMap::new();
+        let unbound = |variable: &str| {
+            EvalError::Invalid(Lint::UnboundVariable {
+                rule: rule_name(rule),
+                variable: variable.to_string(),
+            })
+        };
+
+        let mut steps = Vec::new();
+        for &atom in positive {
+            let mut step = Step::new(atom, false);
+            let bound = slots.len();
+            for (column, term) in atom.terms.iter().enumerate() {
+                let variable = match term {
+                    Term::Variable(variable) => variable.as_str(),
+                    Term::Wildcard => continue,
+                };
+                let next = slots.len();
+                match *slots.entry(variable).or_insert(next) {
+                    slot if slot < bound => {
+                        step.columns.push(column);
+                        step.key.push(slot);
+                    }
+                    slot if slot < next => step.checks.push((column, slot)),
+                    slot => step.binds.push((column, slot)),
+                }
+            }
+            steps.push(step);
+        }
+        for &atom in negated {
+            let mut step = Step::new(atom, true);
+            for (column, term) in atom.terms.iter().enumerate() {
+                if let Term::Variable(variable) = term {
+                    let slot = *slots
+                        .get(variable.as_str())
+                        .ok_or_else(|| unbound(variable))?;
+                    step.columns.push(column);
+                    step.key.push(slot);
+                }
+            }
+            steps.push(step);
+        }
+
+        let output = rule
+            .head
+            .terms
+            .iter()
+            .map(|term| match term {
+                Term::Variable(variable) => slots
+                    .get(variable.as_str())
+                    .copied()
+                    .ok_or_else(|| unbound(variable)),
+                Term::Wildcard => Err(unbound("_")),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Plan {
+            head: &rule.head.relation,
+            output,
+            steps,
+            slots: slots.len(),
+            delta,
+        })
+    }
+
+    /// Adds the head's tuples of the conjunction to the `derived` ones.
+    fn execute(&self, full: &Relations<'_>, delta: &Relations<'_>, derived: &mut Vec<Tuple>) {
+        let mut slots = vec![0; self.slots];
+        self.join(0, full, delta, &mut slots, derived);
+    }
+
+    fn join(
+        &self,
+        index: usize,
+        full: &Relations<'_>,
+        delta: &Relations<'_>,
+        slots: &mut Vec<u32>,
+        derived: &mut Vec<Tuple>,
+    ) {
+        let step = match self.steps.get(index) {
+            Some(step) => step,
+            None => {
+                derived.push(self.output.iter().map(|&slot| slots[slot]).collect());
+                return;
+            }
+        };
+
+        let relations = if self.delta && index == 0 {
+            delta
+        } else {
+            full
+        };
+        let key: Tuple = step.key.iter().map(|&slot| slots[slot]).collect();
+        let tuples = relations
+            .get(step.relation)
+            .map_or(&[][..], |relation| relation.lookup(&step.columns, &key));
+
+        if step.negated {
+            if tuples.is_empty() {
+                self.join(index + 1, full, delta, slots, derived);
+            }
+            return;
+        }
+
+        'tuples: for tuple in tuples {
+            for &(column, slot) in &step.binds {
+                slots[slot] = tuple[column];
+            }
+            for &(column, slot) in &step.checks {
+                if tuple[column] != slots[slot] {
+                    continue 'tuples;
+                }
+            }
+            self.join(index + 1, full, delta, slots, derived);
+        }
+    }
+}
+
+impl<'a> Step<'a> {
+    fn new(atom: &'a Atom, negated: bool) -> Self {
+        Step {
+            relation: &atom.relation,
+            negated,
+            columns: Vec::new(),
+            key: Vec::new(),
+            binds: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+}
+
+type Relations<'a> = HashMap<&'a str, Indexed>;
+
+/// The tuples of a relation, indexed by the values of the columns the steps look them up with.
+#[derive(Debug, Default)]
+struct Indexed {
+    indexes: HashMap<Vec<usize>, HashMap<Tuple, Vec<Tuple>>>,
+}
+
+impl Indexed {
+    fn new<'t>(columns: &BTreeSet<&Vec<usize>>, tuples: impl Iterator<Item = &'t Tuple>) -> Self {
+        let mut relation = Indexed {
+            indexes: columns
+                .iter()
+                .map(|&columns| (columns.clone(), HashMap::new()))
+                .collect(),
+        };
+        for tuple in tuples {
+            relation.insert(tuple);
+        }
+        relation
+    }
+
+    fn insert(&mut self, tuple: &Tuple) {
+        for (columns, index) in &mut self.indexes {
+            let key = columns.iter().map(|&column| tuple[column]).collect();
+            index.entry(key).or_default().push(tuple.clone());
+        }
+    }
+
+    /// The tuples whose `columns` have the values of the `key`.
+    fn lookup(&self, columns: &[usize], key: &[u32]) -> &[Tuple] {
+        self.indexes[columns].get(key).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Evaluates the `plans` of the rules of a stratum until they derive no new tuples.
+fn evaluate_stratum(plans: &[Plan<'_>], database: &mut Database) {
+    // the columns each relation is looked up with, and the ones of the new tuples
+    let mut columns: BTreeMap<&str, BTreeSet<&Vec<usize>>> = BTreeMap::new();
+    let mut delta_columns: BTreeMap<&str, BTreeSet<&Vec<usize>>> = BTreeMap::new();
+    for plan in plans {
+        columns.entry(plan.head).or_default();
+        for (index, step) in plan.steps.iter().enumerate() {
+            columns
+                .entry(step.relation)
+                .or_default()
+                .insert(&step.columns);
+            if plan.delta && index == 0 {
+                delta_columns
+                    .entry(step.relation)
+                    .or_default()
+                    .insert(&step.columns);
+            }
+        }
+    }
+
+    let mut full: Relations<'_> = columns
+        .iter()
+        .map(|(&relation, columns)| (relation, Indexed::new(columns, database.tuples(relation))))
+        .collect();
+    let mut delta: Relations<'_> = HashMap::new();
+    let mut first_round = true;
+    loop {
+        let mut derived: BTreeMap<&str, BTreeSet<Tuple>> = BTreeMap::new();
+        for plan in plans.iter().filter(|plan| plan.delta != first_round) {
+            let mut tuples = Vec::new();
+            plan.execute(&full, &delta, &mut tuples);
+            let new = tuples
+                .into_iter()
+                .filter(|tuple| !database.contains(plan.head, tuple));
+            derived.entry(plan.head).or_default().extend(new);
+        }
+        first_round = false;
+
+        if derived.values().all(BTreeSet::is_empty) {
+            break;
+        }
+        for (&relation, tuples) in &derived {
+            let indexed = full.get_mut(relation).unwrap();
+            for tuple in tuples {
+                indexed.insert(tuple);
+            }
+            database.extend(relation, tuples.iter().cloned());
+        }
+        delta = derived
+            .iter()
+            .map(|(&relation, tuples)| {
+                let columns = delta_columns.get(relation).cloned().unwrap_or_default();
+                (relation, Indexed::new(&columns, tuples.iter()))
+            })
+            .collect();
+    }
+}
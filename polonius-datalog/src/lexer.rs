@@ -0,0 +1,91 @@
+//! Defines the [`Lexer`].
+
+use crate::error::ParseError;
+use crate::token::{Token, TokenKind};
+
+/// Input tokenizer: the whitespace is skipped, except for the blank lines.
+pub struct Lexer<'input> {
+    input: &'input str,
+}
+
+impl<'input> Lexer<'input> {
+    pub fn new(input: &'input str) -> Self {
+        Self { input }
+    }
+
+    /// The tokens of the whole input, ending with a single [`TokenKind::Eof`].
+    pub fn tokenize(&self) -> Result<Vec<Token>, ParseError> {
+        let mut tokens = Vec::new();
+        for (index, text) in self.input.lines().enumerate() {
+            let line = index + 1;
+            if text.trim().is_empty() {
+                tokens.push(Token {
+                    kind: TokenKind::BlankLine,
+                    line,
+                });
+                continue;
+            }
+
+            let mut chars = text.char_indices().peekable();
+            while let Some((position, c)) = chars.next() {
+                let kind = match c {
+                    c if c.is_whitespace() => continue,
+                    ',' => TokenKind::Comma,
+                    ';' => TokenKind::Semi,
+                    '!' => TokenKind::Bang,
+                    '(' => TokenKind::LParen,
+                    ')' => TokenKind::RParen,
+                    ':' if chars.peek().map(|&(_, c)| c) == Some('-') => {
+                        chars.next();
+                        TokenKind::Turnstile
+                    }
+                    ':' => TokenKind::Colon,
+                    '/' if chars.peek().map(|&(_, c)| c) == Some('/') => {
+                        // the comment is the rest of the line
+                        let comment = text[position + 2..].trim().to_string();
+                        tokens.push(Token {
+                            kind: TokenKind::Comment(comment),
+                            line,
+                        });
+                        break;
+                    }
+                    '.' if chars.peek().is_some_and(|&(_, c)| c.is_alphabetic()) => {
+                        TokenKind::Directive(take_identifier(text, position + 1, &mut chars))
+                    }
+                    '.' => TokenKind::Dot,
+                    c if c.is_alphanumeric() || c == '_' => {
+                        TokenKind::Ident(take_identifier(text, position, &mut chars))
+                    }
+                    character => {
+                        return Err(ParseError::UnexpectedCharacter { line, character });
+                    }
+                };
+                tokens.push(Token { kind, line });
+            }
+        }
+
+        let line = self.input.lines().count() + 1;
+        tokens.push(Token {
+            kind: TokenKind::Eof,
+            line,
+        });
+        Ok(tokens)
+    }
+}
+
+/// Consumes the characters of the identifier starting at `start` in the `text`.
+fn take_identifier(
+    text: &str,
+    start: usize,
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+) -> String {
+    let mut end = text.len();
+    while let Some(&(position, c)) = chars.peek() {
+        if !(c.is_alphanumeric() || c == '_') {
+            end = position;
+            break;
+        }
+        chars.next();
+    }
+    text[start..end].to_string()
+}
@@ -0,0 +1,41 @@
+//! The datalog dialect the Polonius rules are written in, in the book and the engine's comments:
+//! `.decl`arations of relations, `.input` and `.output` directives, and rules whose bodies join
+//! atoms, negated atoms, and parenthesized alternatives separated by `;`.
+//!
+//! ```
+//! let program = polonius_datalog::parse(
+//!     "// R8: compute illegal access errors
+//!      errors(Loan, Point) :- loan_invalidated_at(Loan, Point), loan_live_at(Loan, Point).",
+//! )
+//! .unwrap();
+//! let rule = program.rules().next().unwrap();
+//! assert_eq!(rule.label(), Some("R8"));
+//! assert_eq!(
+//!     rule.to_string(),
+//!     "// R8: compute illegal access errors
+//! errors(Loan, Point) :-
+//!   loan_invalidated_at(Loan, Point),
+//!   loan_live_at(Loan, Point).
+//! "
+//! );
+//! ```
+
+pub mod ast;
+mod error;
+mod eval;
+mod lexer;
+mod lint;
+mod parser;
+mod token;
+pub use crate::error::ParseError;
+pub use crate::eval::{evaluate, Database, EvalError, Tuple};
+pub use crate::lint::{lint, Lint};
+pub type Result<T> = std::result::Result<T, ParseError>;
+mod tests;
+
+/// Parses a datalog `input`: the comments directly preceding an item, without blank lines
+/// between them, are kept as its documentation.
+pub fn parse(input: &str) -> Result<ast::Program> {
+    let tokens = lexer::Lexer::new(input).tokenize()?;
+    parser::Parser::new(tokens).parse_program()
+}
@@ -0,0 +1,212 @@
+//! Checks the rules of a [`Program`](crate::ast::Program) for mistakes, and for the hazards of
+//! implementing them with datafrog.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ast::{Literal, Program, Rule, Term};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// The `variable` of the head, or of a negated atom, of the `rule` is not bound by the atoms
+    /// of its body.
+    UnboundVariable { rule: String, variable: String },
+
+    /// The declared `relation` is not used by any rule, nor is it an input or an output.
+    UnusedDeclaration { relation: String },
+
+    /// The `rule` negates the `relation`, which depends on the rule's own relation.
+    UnstratifiedNegation { rule: String, relation: String },
+
+    /// The body of the `rule` joins relations derived by other rules, but it doesn't start with
+    /// one of them: datafrog requires a `Variable` as the first literal of a join.
+    JoinOrder { rule: String, first: String },
+}
+
+impl Lint {
+    /// Whether the lint is a mistake, rather than a hazard for the implementation.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Lint::UnboundVariable { .. } | Lint::UnstratifiedNegation { .. }
+        )
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = if self.is_error() { "error" } else { "warning" };
+        match self {
+            Lint::UnboundVariable { rule, variable } => write!(
+                fmt,
+                "{}: rule {}: `{}` is not bound by the atoms of the body",
+                level, rule, variable
+            ),
+            Lint::UnusedDeclaration { relation } => write!(
+                fmt,
+                "{}: `{}` is declared, but is not used by any rule",
+                level, relation
+            ),
+            Lint::UnstratifiedNegation { rule, relation } => write!(
+                fmt,
+                "{}: rule {}: the negated `{}` depends on the rule itself",
+                level, rule, relation
+            ),
+            Lint::JoinOrder { rule, first } => write!(
+                fmt,
+                "{}: rule {}: starts with `{}`, which is not derived by the rules, before joining derived relations",
+                level, rule, first
+            ),
+        }
+    }
+}
+
+/// The lints of all the rules and declarations of the `program`, in order.
+pub fn lint(program: &Program) -> Vec<Lint> {
+    let derived: BTreeSet<&str> = program
+        .rules()
+        .map(|rule| rule.head.relation.as_str())
+        .collect();
+
+    // the relations each relation is derived from, and whether they're negated
+    let mut dependencies: BTreeMap<&str, Vec<(&str, bool)>> = BTreeMap::new();
+    for rule in program.rules() {
+        for (atom, negated) in rule.atoms() {
+            dependencies
+                .entry(&rule.head.relation)
+                .or_default()
+                .push((&atom.relation, negated));
+        }
+    }
+
+    let mut lints = Vec::new();
+    for rule in program.rules() {
+        lint_bindings(rule, &mut lints);
+
+        for (atom, negated) in rule.atoms() {
+            if negated && depends_on(&dependencies, &atom.relation, &rule.head.relation) {
+                lints.push(Lint::UnstratifiedNegation {
+                    rule: rule_name(rule),
+                    relation: atom.relation.clone(),
+                });
+            }
+        }
+
+        let joins_derived = rule
+            .atoms()
+            .iter()
+            .any(|&(atom, negated)| !negated && derived.contains(atom.relation.as_str()));
+        let first = match rule.body.first() {
+            Some(Literal::Atom(atom)) if derived.contains(atom.relation.as_str()) => None,
+            Some(literal) => Some(literal.to_string()),
+            None => None,
+        };
+        if let (true, Some(first)) = (joins_derived, first) {
+            lints.push(Lint::JoinOrder {
+                rule: rule_name(rule),
+                first,
+            });
+        }
+    }
+
+    let used: BTreeSet<&str> = program
+        .rules()
+        .flat_map(|rule| {
+            rule.atoms()
+                .into_iter()
+                .map(|(atom, _)| atom.relation.as_str())
+                .chain(Some(rule.head.relation.as_str()))
+        })
+        .collect();
+    for declaration in program.declarations() {
+        let relation = declaration.relation.as_str();
+        if !used.contains(relation) && !program.is_input(relation) && !program.is_output(relation) {
+            lints.push(Lint::UnusedDeclaration {
+                relation: relation.to_string(),
+            });
+        }
+    }
+    lints
+}
+
+/// The rule's label, or its head when it has none.
+pub(crate) fn rule_name(rule: &Rule) -> String {
+    match rule.label() {
+        Some(label) => format!("`{}`", label),
+        None => format!("`{}`", rule.head),
+    }
+}
+
+/// Lints the variables of the head and of the negated atoms which the positive atoms don't bind.
+fn lint_bindings(rule: &Rule, lints: &mut Vec<Lint>) {
+    let bound = bound_variables(&rule.body);
+    let mut unbound = BTreeSet::new();
+    let negated = rule
+        .atoms()
+        .into_iter()
+        .filter(|&(_, negated)| negated)
+        .map(|(atom, _)| atom);
+    for atom in Some(&rule.head).into_iter().chain(negated) {
+        for term in &atom.terms {
+            if let Term::Variable(variable) = term {
+                if !bound.contains(variable.as_str()) && unbound.insert(variable.as_str()) {
+                    lints.push(Lint::UnboundVariable {
+                        rule: rule_name(rule),
+                        variable: variable.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The variables bound by a conjunction of literals: the ones of its positive atoms, and the
+/// ones bound by every alternative of its alternatives.
+fn bound_variables(literals: &[Literal]) -> BTreeSet<&str> {
+    let mut bound = BTreeSet::new();
+    for literal in literals {
+        match literal {
+            Literal::Atom(atom) => {
+                for term in &atom.terms {
+                    if let Term::Variable(variable) = term {
+                        bound.insert(variable.as_str());
+                    }
+                }
+            }
+            Literal::Negated(_) => {}
+            Literal::Alternatives(alternatives) => {
+                let mut alternatives = alternatives
+                    .iter()
+                    .map(|literals| bound_variables(literals));
+                if let Some(first) = alternatives.next() {
+                    let common = alternatives.fold(first, |common, bound| {
+                        common.intersection(&bound).copied().collect()
+                    });
+                    bound.extend(common);
+                }
+            }
+        }
+    }
+    bound
+}
+
+/// Whether the `relation` is derived, transitively, from the `target` relation.
+fn depends_on(
+    dependencies: &BTreeMap<&str, Vec<(&str, bool)>>,
+    relation: &str,
+    target: &str,
+) -> bool {
+    let mut visited = BTreeSet::new();
+    let mut stack = vec![relation];
+    while let Some(relation) = stack.pop() {
+        if relation == target {
+            return true;
+        }
+        if visited.insert(relation) {
+            if let Some(sources) = dependencies.get(relation) {
+                stack.extend(sources.iter().map(|&(source, _)| source));
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,193 @@
+//! Defines the [`Parser`], building the [`ast`](crate::ast) of the tokens of the lexer.
+
+use crate::ast::{Atom, Column, Declaration, Item, Literal, Program, Rule, Term};
+use crate::error::ParseError;
+use crate::token::{Token, TokenKind};
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.position].kind
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::Eof {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected<T>(&self, expected: &'static str) -> Result<T, ParseError> {
+        let token = &self.tokens[self.position];
+        let found = match &token.kind {
+            TokenKind::Ident(name) => format!("`{}`", name),
+            TokenKind::Directive(name) => format!("`.{}`", name),
+            TokenKind::Eof => "the end of the input".to_string(),
+            kind => format!("{:?}", kind),
+        };
+        Err(ParseError::UnexpectedToken {
+            line: token.line,
+            found,
+            expected,
+        })
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &'static str) -> Result<(), ParseError> {
+        if *self.peek() == kind {
+            self.bump();
+            Ok(())
+        } else {
+            self.unexpected(expected)
+        }
+    }
+
+    fn identifier(&mut self, expected: &'static str) -> Result<String, ParseError> {
+        match self.peek() {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.bump();
+                Ok(name)
+            }
+            _ => self.unexpected(expected),
+        }
+    }
+
+    pub fn parse_program(&mut self) -> Result<Program, ParseError> {
+        let mut items = Vec::new();
+        let mut comments = Vec::new();
+        loop {
+            match self.peek().clone() {
+                TokenKind::Eof => break,
+                TokenKind::BlankLine => {
+                    self.bump();
+                    comments.clear();
+                }
+                TokenKind::Comment(comment) => {
+                    self.bump();
+                    comments.push(comment);
+                }
+                TokenKind::Directive(directive) => {
+                    let comments = std::mem::take(&mut comments);
+                    items.push(self.parse_directive(&directive, comments)?);
+                }
+                TokenKind::Ident(_) => {
+                    let comments = std::mem::take(&mut comments);
+                    items.push(Item::Rule(self.parse_rule(comments)?));
+                }
+                _ => return self.unexpected("a declaration, a directive, or a rule"),
+            }
+        }
+        Ok(Program { items })
+    }
+
+    fn parse_directive(
+        &mut self,
+        directive: &str,
+        comments: Vec<String>,
+    ) -> Result<Item, ParseError> {
+        match directive {
+            "decl" => {
+                self.bump();
+                let relation = self.identifier("the name of the declared relation")?;
+                self.expect(TokenKind::LParen, "`(`")?;
+                let mut columns = Vec::new();
+                while *self.peek() != TokenKind::RParen {
+                    if !columns.is_empty() {
+                        self.expect(TokenKind::Comma, "`,` or `)`")?;
+                    }
+                    let name = self.identifier("the name of a column")?;
+                    self.expect(TokenKind::Colon, "`:`")?;
+                    let ty = self.identifier("the type of a column")?;
+                    columns.push(Column { name, ty });
+                }
+                self.bump();
+                Ok(Item::Declaration(Declaration {
+                    comments,
+                    relation,
+                    columns,
+                }))
+            }
+            "input" => {
+                self.bump();
+                Ok(Item::Input(self.identifier("the name of a relation")?))
+            }
+            "output" => {
+                self.bump();
+                Ok(Item::Output(self.identifier("the name of a relation")?))
+            }
+            _ => self.unexpected("`.decl`, `.input`, or `.output`"),
+        }
+    }
+
+    fn parse_rule(&mut self, comments: Vec<String>) -> Result<Rule, ParseError> {
+        let head = self.parse_atom()?;
+        self.expect(TokenKind::Turnstile, "`:-`")?;
+        let body = self.parse_conjunction()?;
+        self.expect(TokenKind::Dot, "`,` or `.`")?;
+        Ok(Rule {
+            comments,
+            head,
+            body,
+        })
+    }
+
+    fn parse_conjunction(&mut self) -> Result<Vec<Literal>, ParseError> {
+        let mut literals = vec![self.parse_literal()?];
+        while *self.peek() == TokenKind::Comma {
+            self.bump();
+            literals.push(self.parse_literal()?);
+        }
+        Ok(literals)
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ParseError> {
+        match self.peek() {
+            TokenKind::Bang => {
+                self.bump();
+                Ok(Literal::Negated(self.parse_atom()?))
+            }
+            TokenKind::LParen => {
+                self.bump();
+                let mut alternatives = vec![self.parse_conjunction()?];
+                while *self.peek() == TokenKind::Semi {
+                    self.bump();
+                    alternatives.push(self.parse_conjunction()?);
+                }
+                self.expect(TokenKind::RParen, "`;` or `)`")?;
+                Ok(Literal::Alternatives(alternatives))
+            }
+            _ => Ok(Literal::Atom(self.parse_atom()?)),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Atom, ParseError> {
+        let relation = self.identifier("the name of a relation")?;
+        self.expect(TokenKind::LParen, "`(`")?;
+        let mut terms = Vec::new();
+        while *self.peek() != TokenKind::RParen {
+            if !terms.is_empty() {
+                self.expect(TokenKind::Comma, "`,` or `)`")?;
+            }
+            let name = self.identifier("a variable, or `_`")?;
+            terms.push(if name == "_" {
+                Term::Wildcard
+            } else {
+                Term::Variable(name)
+            });
+        }
+        self.bump();
+        Ok(Atom { relation, terms })
+    }
+}
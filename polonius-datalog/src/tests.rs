@@ -0,0 +1,304 @@
+#![cfg(test)]
+
+use crate::ast::{Atom, Column, Item, Literal, Term};
+use crate::{evaluate, lint, parse, Database, EvalError, Lint, ParseError};
+
+fn atom(relation: &str, terms: &[&str]) -> Atom {
+    Atom {
+        relation: relation.to_string(),
+        terms: terms
+            .iter()
+            .map(|&term| match term {
+                "_" => Term::Wildcard,
+                name => Term::Variable(name.to_string()),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn declarations() {
+    let program = parse(
+        r"
+        // Indicates that the `loan` is invalidated by some action
+        // taking place at `point`.
+        .decl loan_invalidated_at(Loan:loan, Point:point)
+        .input loan_invalidated_at
+        ",
+    )
+    .expect("Declarations");
+
+    let declaration = program.declarations().next().unwrap();
+    assert_eq!(declaration.relation, "loan_invalidated_at");
+    assert_eq!(declaration.comments.len(), 2);
+    assert_eq!(
+        declaration.columns,
+        [
+            Column {
+                name: "Loan".to_string(),
+                ty: "loan".to_string()
+            },
+            Column {
+                name: "Point".to_string(),
+                ty: "point".to_string()
+            }
+        ]
+    );
+    assert!(program.is_input("loan_invalidated_at"));
+    assert!(!program.is_input("errors"));
+}
+
+#[test]
+fn rules() {
+    let program = parse(
+        r"
+        // A comment separated from the rule by a blank line
+
+        // R6: propagate loans along the CFG, according to liveness
+        origin_contains_loan_on_entry(Origin, Loan, TargetPoint) :-
+          origin_contains_loan_on_entry(Origin, Loan, SourcePoint),
+          !loan_killed_at(Loan, SourcePoint),
+          cfg_edge(SourcePoint, TargetPoint),
+          (origin_live_on_entry(Origin, TargetPoint); placeholder(Origin, _)).
+        ",
+    )
+    .expect("Rules");
+
+    let rule = program.rules().next().unwrap();
+    assert_eq!(rule.label(), Some("R6"));
+    assert_eq!(
+        rule.documentation(),
+        ["propagate loans along the CFG, according to liveness"]
+    );
+    assert_eq!(
+        rule.head,
+        atom(
+            "origin_contains_loan_on_entry",
+            &["Origin", "Loan", "TargetPoint"]
+        )
+    );
+    assert_eq!(
+        rule.body[1],
+        Literal::Negated(atom("loan_killed_at", &["Loan", "SourcePoint"]))
+    );
+    assert_eq!(
+        rule.body[3],
+        Literal::Alternatives(vec![
+            vec![Literal::Atom(atom(
+                "origin_live_on_entry",
+                &["Origin", "TargetPoint"]
+            ))],
+            vec![Literal::Atom(atom("placeholder", &["Origin", "_"]))],
+        ])
+    );
+
+    let atoms: Vec<_> = rule
+        .atoms()
+        .into_iter()
+        .map(|(atom, negated)| (atom.relation.as_str(), negated))
+        .collect();
+    assert_eq!(
+        atoms,
+        [
+            ("origin_contains_loan_on_entry", false),
+            ("loan_killed_at", true),
+            ("cfg_edge", false),
+            ("origin_live_on_entry", false),
+            ("placeholder", false),
+        ]
+    );
+}
+
+/// The pretty-printed program is parsed back to the same program.
+#[test]
+fn pretty_print_roundtrip() {
+    let source = r"
+        .decl subset(Origin1:origin, Origin2:origin, Point:point)
+        .output subset
+
+        // R2: compute the subset transitive closure, at a given point
+        subset(Origin1, Origin3, Point) :- subset(Origin1, Origin2, Point), subset(Origin2, Origin3, Point).
+    ";
+    let program = parse(source).expect("Program");
+    let printed = program.to_string();
+    assert_eq!(
+        printed,
+        ".decl subset(Origin1:origin, Origin2:origin, Point:point)
+.output subset
+
+// R2: compute the subset transitive closure, at a given point
+subset(Origin1, Origin3, Point) :-
+  subset(Origin1, Origin2, Point),
+  subset(Origin2, Origin3, Point).
+"
+    );
+    assert_eq!(parse(&printed).expect("Printed program"), program);
+    assert!(matches!(program.items[1], Item::Output(_)));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        parse("subset(Origin1, Origin2) :- subset_base(Origin1, Origin2)"),
+        Err(ParseError::UnexpectedToken {
+            line: 2,
+            found: "the end of the input".to_string(),
+            expected: "`,` or `.`",
+        })
+    );
+    assert_eq!(
+        parse("\n.decl loan(Loan:loan) = 3")
+            .unwrap_err()
+            .to_string(),
+        "line 2: unexpected character `=`"
+    );
+    assert_eq!(
+        parse(".decl loan(Loan)").unwrap_err().to_string(),
+        "line 1: found RParen, but expected `:`"
+    );
+}
+
+#[test]
+fn lints() {
+    let program = parse(
+        r"
+        .decl edge(From:node, To:node)
+        .input edge
+        .decl unused(Node:node)
+        .decl reachable(From:node, To:node)
+        .output reachable
+
+        reachable(From, To) :- edge(From, To).
+        reachable(From, To) :- edge(From, Mid), reachable(Mid, To).
+        unreachable(From, To) :- edge(From, _), !reachable(From, To).
+        cycle(Node) :- reachable(Node, Node), !cycle(Node).
+        ",
+    )
+    .expect("Program");
+
+    let lints = lint(&program);
+    assert_eq!(
+        lints,
+        [
+            Lint::JoinOrder {
+                rule: "`reachable(From, To)`".to_string(),
+                first: "edge(From, Mid)".to_string(),
+            },
+            Lint::UnboundVariable {
+                rule: "`unreachable(From, To)`".to_string(),
+                variable: "To".to_string(),
+            },
+            Lint::UnstratifiedNegation {
+                rule: "`cycle(Node)`".to_string(),
+                relation: "cycle".to_string(),
+            },
+            Lint::UnusedDeclaration {
+                relation: "unused".to_string(),
+            },
+        ]
+    );
+    assert!(lints[1].is_error());
+    assert!(!lints[3].is_error());
+
+    // the variables bound in every alternative are bound
+    let program = parse(
+        r"
+        // R1: the origins live at a point
+        live(Origin, Point) :- (use(Origin, Point); drop(Origin, Point, _)), node(Point).
+        partial(Origin, Point) :- (use(Origin, Point); drop(Origin, _, _)).
+        ",
+    )
+    .expect("Alternatives");
+    assert_eq!(
+        lint(&program),
+        [Lint::UnboundVariable {
+            rule: "`partial(Origin, Point)`".to_string(),
+            variable: "Point".to_string(),
+        }]
+    );
+    assert_eq!(
+        lint(&program)[0].to_string(),
+        "error: rule `partial(Origin, Point)`: `Point` is not bound by the atoms of the body"
+    );
+}
+
+fn tuples(database: &Database, relation: &str) -> Vec<Vec<u32>> {
+    database.tuples(relation).cloned().collect()
+}
+
+#[test]
+fn evaluation() {
+    let program = parse(
+        r"
+        .decl edge(From:node, To:node)
+        .input edge
+
+        reachable(From, To) :- edge(From, To).
+        reachable(From, To) :- reachable(From, Mid), reachable(Mid, To).
+        cycle(Node) :- reachable(Node, Node).
+        acyclic(From, To) :- edge(From, To), !cycle(From), !cycle(To).
+        marked(Node) :- (reachable(Node, _); mark(Node)), !cycle(Node).
+        ",
+    )
+    .expect("Program");
+
+    let mut database = Database::new();
+    database.extend(
+        "edge",
+        vec![vec![0, 1], vec![1, 2], vec![2, 1], vec![2, 3], vec![4, 5]],
+    );
+    database.insert("mark", vec![6]);
+    evaluate(&program, &mut database).expect("Evaluation");
+
+    assert_eq!(
+        tuples(&database, "reachable"),
+        [
+            vec![0, 1],
+            vec![0, 2],
+            vec![0, 3],
+            vec![1, 1],
+            vec![1, 2],
+            vec![1, 3],
+            vec![2, 1],
+            vec![2, 2],
+            vec![2, 3],
+            vec![4, 5],
+        ]
+    );
+    // the repeated variable only joins the reflexive tuples
+    assert_eq!(tuples(&database, "cycle"), [vec![1], vec![2]]);
+    // the negated relations are complete when they're joined
+    assert_eq!(tuples(&database, "acyclic"), [vec![4, 5]]);
+    assert_eq!(tuples(&database, "marked"), [vec![0], vec![4], vec![6]]);
+    assert_eq!(database.len("edge"), 5);
+}
+
+#[test]
+fn evaluation_errors() {
+    let program = parse("unreachable(From, To) :- edge(From, _), !reachable(From, To).")
+        .expect("Unbound variable");
+    assert_eq!(
+        evaluate(&program, &mut Database::new()),
+        Err(EvalError::Invalid(Lint::UnboundVariable {
+            rule: "`unreachable(From, To)`".to_string(),
+            variable: "To".to_string(),
+        }))
+    );
+
+    let program = parse("reachable(From, To) :- edge(From, To).").expect("Arity");
+    let mut database = Database::new();
+    database.insert("edge", vec![0, 1, 2]);
+    let error = evaluate(&program, &mut database).unwrap_err();
+    assert_eq!(
+        error,
+        EvalError::Arity {
+            relation: "edge".to_string(),
+            expected: 2,
+            found: 3,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "error: `edge` has 2 columns, but is used with 3"
+    );
+}
@@ -0,0 +1,31 @@
+//! Defines the output of the [`Lexer`](crate::lexer::Lexer).
+
+/// [`Token`]s produced by the lexer, with the `line` they start at, from 1.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+}
+
+/// Represents what input was lexed into a [`Token`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TokenKind {
+    /// A relation, variable, or type name.
+    Ident(String),
+    /// A directive, like `.decl`, without its dot.
+    Directive(String),
+    /// The text of a `//` comment, without the slashes.
+    Comment(String),
+    /// A line without any token, separating the comments from the items they're not about.
+    BlankLine,
+    Comma,
+    Colon,
+    Semi,
+    Dot,
+    Bang,
+    LParen,
+    RParen,
+    /// The `:-` between the head and the body of a rule.
+    Turnstile,
+    Eof,
+}
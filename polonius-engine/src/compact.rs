@@ -0,0 +1,81 @@
+//! A `FactTypes` instantiation whose atoms are backed by `u32` indices, like the ones rustc uses.
+//!
+//! The relations are stored as tuples of atoms: with these compact atoms, a
+//! `loan_issued_at(origin, loan, point)` tuple takes 12 bytes instead of the 24 bytes
+//! machine-word atoms would use on 64-bit targets, which matters on inputs with tens of millions
+//! of tuples.
+
+use std::convert::TryFrom;
+
+use crate::facts::{Atom, AtomOverflow, FactTypes};
+
+macro_rules! compact_atom {
+    ($(#[$attr:meta])* $t:ident) => {
+        $(#[$attr])*
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Debug, Hash)]
+        pub struct $t {
+            index: u32,
+        }
+
+        impl From<usize> for $t {
+            fn from(index: usize) -> $t {
+                $t::try_from_index(index).unwrap_or_else(|overflow| panic!("{}", overflow))
+            }
+        }
+
+        impl From<$t> for usize {
+            fn from(atom: $t) -> usize {
+                atom.index as usize
+            }
+        }
+
+        impl Atom for $t {
+            fn index(self) -> usize {
+                self.into()
+            }
+
+            fn try_from_index(index: usize) -> Result<$t, AtomOverflow> {
+                match u32::try_from(index) {
+                    Ok(index) => Ok($t { index }),
+                    Err(_) => Err(AtomOverflow {
+                        kind: stringify!($t),
+                        index,
+                    }),
+                }
+            }
+        }
+    };
+}
+
+compact_atom!(
+    /// An origin, backed by a `u32` index.
+    Origin
+);
+compact_atom!(
+    /// A loan, backed by a `u32` index.
+    Loan
+);
+compact_atom!(
+    /// A point, backed by a `u32` index.
+    Point
+);
+compact_atom!(
+    /// A variable, backed by a `u32` index.
+    Variable
+);
+compact_atom!(
+    /// A move path, backed by a `u32` index.
+    Path
+);
+
+/// The `FactTypes` using the compact `u32`-backed atoms of this module.
+#[derive(Copy, Clone, Debug)]
+pub struct CompactFacts;
+
+impl FactTypes for CompactFacts {
+    type Origin = Origin;
+    type Loan = Loan;
+    type Point = Point;
+    type Variable = Variable;
+    type Path = Path;
+}
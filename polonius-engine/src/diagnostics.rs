@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::facts::{Atom, FactTypes};
+use crate::output::Output;
+
+/// A typed view over the errors found by the analysis, so that consumers of an `Output` don't
+/// need to interpret the raw tuples of its different error relations.
+#[derive(Clone, Copy, Debug)]
+pub enum Diagnostic<T: FactTypes> {
+    /// The `loan` is invalidated at `point` while still live.
+    IllegalAccess { loan: T::Loan, point: T::Point },
+
+    /// The placeholder origin `sub` is required to be a subset of the placeholder origin `sup`
+    /// at `point`, but this relation is not part of the known placeholder subsets.
+    IllegalSubset {
+        sup: T::Origin,
+        sub: T::Origin,
+        point: T::Point,
+    },
+
+    /// The `path` is accessed at `point`, while it may be uninitialized.
+    MoveError { path: T::Path, point: T::Point },
+}
+
+/// Maps atoms back to their human-readable names, e.g. the strings they were interned from,
+/// to display diagnostics.
+pub trait AtomNames<T: FactTypes> {
+    fn origin_name(&self, origin: T::Origin) -> Cow<'_, str>;
+    fn loan_name(&self, loan: T::Loan) -> Cow<'_, str>;
+    fn point_name(&self, point: T::Point) -> Cow<'_, str>;
+    fn path_name(&self, path: T::Path) -> Cow<'_, str>;
+}
+
+impl<T: FactTypes> Diagnostic<T> {
+    /// The point where the error occurs.
+    pub fn point(&self) -> T::Point {
+        match *self {
+            Diagnostic::IllegalAccess { point, .. }
+            | Diagnostic::IllegalSubset { point, .. }
+            | Diagnostic::MoveError { point, .. } => point,
+        }
+    }
+
+    /// Returns a value which displays this diagnostic with the atoms' names.
+    pub fn display<'a, N: AtomNames<T>>(&'a self, names: &'a N) -> DisplayDiagnostic<'a, T, N> {
+        DisplayDiagnostic {
+            diagnostic: self,
+            names,
+        }
+    }
+
+    /// A key ordering diagnostics by point first, then by kind, then by atoms.
+    fn sort_key(&self) -> (T::Point, u8, usize, usize) {
+        match *self {
+            Diagnostic::IllegalAccess { loan, point } => (point, 0, loan.index(), 0),
+            Diagnostic::IllegalSubset { sup, sub, point } => (point, 1, sub.index(), sup.index()),
+            Diagnostic::MoveError { path, point } => (point, 2, path.index(), 0),
+        }
+    }
+}
+
+impl<T: FactTypes> PartialEq for Diagnostic<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl<T: FactTypes> Eq for Diagnostic<T> {}
+
+impl<T: FactTypes> PartialOrd for Diagnostic<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: FactTypes> Ord for Diagnostic<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl<T: FactTypes> fmt::Display for Diagnostic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Diagnostic::IllegalAccess { loan, point } => write!(
+                f,
+                "loan {:?} is invalidated at {:?} while still live",
+                loan, point
+            ),
+            Diagnostic::IllegalSubset { sup, sub, point } => write!(
+                f,
+                "unknown subset requirement {:?} <= {:?} at {:?}",
+                sub, sup, point
+            ),
+            Diagnostic::MoveError { path, point } => write!(
+                f,
+                "path {:?} is accessed at {:?} while maybe uninitialized",
+                path, point
+            ),
+        }
+    }
+}
+
+/// Displays a `Diagnostic` using the atoms' names, see `Diagnostic::display`.
+pub struct DisplayDiagnostic<'a, T: FactTypes, N: AtomNames<T>> {
+    diagnostic: &'a Diagnostic<T>,
+    names: &'a N,
+}
+
+impl<'a, T: FactTypes, N: AtomNames<T>> fmt::Display for DisplayDiagnostic<'a, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.names;
+        match *self.diagnostic {
+            Diagnostic::IllegalAccess { loan, point } => write!(
+                f,
+                "loan {} is invalidated at {} while still live",
+                names.loan_name(loan),
+                names.point_name(point)
+            ),
+            Diagnostic::IllegalSubset { sup, sub, point } => write!(
+                f,
+                "unknown subset requirement {} <= {} at {}",
+                names.origin_name(sub),
+                names.origin_name(sup),
+                names.point_name(point)
+            ),
+            Diagnostic::MoveError { path, point } => write!(
+                f,
+                "path {} is accessed at {} while maybe uninitialized",
+                names.path_name(path),
+                names.point_name(point)
+            ),
+        }
+    }
+}
+
+/// The differences between the errors of two `Output`s, see `Output::diff_errors`.
+#[derive(Clone, Debug)]
+pub struct ErrorsDiff<T: FactTypes> {
+    /// The errors of the first output, missing from the second one.
+    pub removed: Vec<Diagnostic<T>>,
+
+    /// The errors of the second output, missing from the first one.
+    pub added: Vec<Diagnostic<T>>,
+}
+
+impl<T: FactTypes> ErrorsDiff<T> {
+    /// Whether both outputs have the same errors.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+impl<T: FactTypes> Output<T> {
+    /// All the errors of this output as `Diagnostic`s, ordered by point.
+    pub fn diagnostics(&self) -> Vec<Diagnostic<T>> {
+        let mut diagnostics: Vec<_> = self
+            .errors
+            .iter()
+            .flat_map(|(&point, loans)| {
+                loans
+                    .iter()
+                    .map(move |&loan| Diagnostic::IllegalAccess { loan, point })
+            })
+            .chain(self.subset_errors.iter().flat_map(|(&point, origins)| {
+                origins
+                    .iter()
+                    .map(move |&(sub, sup)| Diagnostic::IllegalSubset { sup, sub, point })
+            }))
+            .chain(self.move_errors.iter().flat_map(|(&point, paths)| {
+                paths
+                    .iter()
+                    .map(move |&path| Diagnostic::MoveError { path, point })
+            }))
+            .collect();
+        diagnostics.sort();
+        diagnostics
+    }
+
+    /// Compares the errors of this output with the ones of the `other` output, e.g. the output
+    /// of the same analysis on slightly different facts.
+    pub fn diff_errors(&self, other: &Output<T>) -> ErrorsDiff<T> {
+        let errors: BTreeSet<_> = self.diagnostics().into_iter().collect();
+        let other_errors: BTreeSet<_> = other.diagnostics().into_iter().collect();
+
+        ErrorsDiff {
+            removed: errors.difference(&other_errors).cloned().collect(),
+            added: other_errors.difference(&errors).cloned().collect(),
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use std::fmt::Debug;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::{self, Debug};
 use std::hash::Hash;
 
 /// The "facts" which are the basis of the NLL borrow analysis.
@@ -13,6 +15,11 @@ pub struct AllFacts<T: FactTypes> {
     /// `universal_region(origin)` -- this is a "free region" within fn body
     pub universal_region: Vec<T::Origin>,
 
+    /// `universal_region_live_at(origin, point)` restricts the points where the universal
+    /// region `origin` is live: the universal regions appearing in this relation are only live
+    /// at the points listed here, while the others are live at every point of the CFG.
+    pub universal_region_live_at: Vec<(T::Origin, T::Point)>,
+
     /// `cfg_edge(point1, point2)` for each edge `point1 -> point2` in the control flow
     pub cfg_edge: Vec<(T::Point, T::Point)>,
 
@@ -23,14 +30,26 @@ pub struct AllFacts<T: FactTypes> {
     /// no longer invalidate the loan)
     pub loan_killed_at: Vec<(T::Loan, T::Point)>,
 
+    /// `loan_shallow_killed_at(loan, point)` when the path borrowed at `loan` is overwritten at
+    /// `point` by a shallow write, which doesn't reach the data borrowed through it, like the
+    /// contents of a `Box` reborrowed elsewhere. Unlike `loan_killed_at`, the loan is only killed
+    /// in the origins it was issued with: the origins it flowed into keep tracking it.
+    pub loan_shallow_killed_at: Vec<(T::Loan, T::Point)>,
+
     /// `subset_base(origin1, origin2, point)` when we require `origin1@point: origin2@point`.
     /// Indicates that `origin1 <= origin2` -- i.e., the set of loans in `origin1` are a subset
     /// of those in `origin2`.
     pub subset_base: Vec<(T::Origin, T::Origin, T::Point)>,
 
-    /// `loan_invalidated_at(point, loan)` indicates that the `loan` is invalidated by some action
+    /// `loan_invalidated_at(loan, point)` indicates that the `loan` is invalidated by some action
     /// taking place at `point`; if any origin that references this loan is live, this is an error.
-    pub loan_invalidated_at: Vec<(T::Point, T::Loan)>,
+    pub loan_invalidated_at: Vec<(T::Loan, T::Point)>,
+
+    /// `loan_activated_at(loan, point)` when the two-phase borrow `loan` is activated at `point`:
+    /// until then, the loan is only reserved, and is not invalidated by the accesses which only
+    /// conflict with its activation. Only used with `ComputeOptions::two_phase_borrows`, and the
+    /// loans without activations are active as soon as they are issued.
+    pub loan_activated_at: Vec<(T::Loan, T::Point)>,
 
     /// `var_used_at(var, point)` when the variable `var` is used for anything
     /// but a drop at `point`
@@ -53,6 +72,13 @@ pub struct AllFacts<T: FactTypes> {
     /// the `origin` and uses it when dropping
     pub drop_of_var_derefs_origin: Vec<(T::Variable, T::Origin)>,
 
+    /// `origin_live_on_entry(origin, point)` is an optional, precomputed liveness of the origins,
+    /// e.g. by rustc's own liveness pass, including the universal regions as
+    /// `compute_origin_liveness` does. When present, the engine skips its own liveness
+    /// computation, as long as these facts include the origins of the variables used at each
+    /// point: see `AllFacts::uncovered_liveness_points`.
+    pub origin_live_on_entry: Vec<(T::Origin, T::Point)>,
+
     /// `child_path(child, parent)` when the path `child` is the direct child of
     /// `parent`, e.g. `child_path(x.y, x)`, but not `child_path(x.y.z, x)`.
     pub child_path: Vec<(T::Path, T::Path)>,
@@ -94,15 +120,19 @@ impl<T: FactTypes> Default for AllFacts<T> {
         AllFacts {
             loan_issued_at: Vec::default(),
             universal_region: Vec::default(),
+            universal_region_live_at: Vec::default(),
             cfg_edge: Vec::default(),
             loan_killed_at: Vec::default(),
+            loan_shallow_killed_at: Vec::default(),
             subset_base: Vec::default(),
             loan_invalidated_at: Vec::default(),
+            loan_activated_at: Vec::default(),
             var_used_at: Vec::default(),
             var_defined_at: Vec::default(),
             var_dropped_at: Vec::default(),
             use_of_var_derefs_origin: Vec::default(),
             drop_of_var_derefs_origin: Vec::default(),
+            origin_live_on_entry: Vec::default(),
             child_path: Vec::default(),
             path_is_var: Vec::default(),
             path_assigned_at_base: Vec::default(),
@@ -114,12 +144,73 @@ impl<T: FactTypes> Default for AllFacts<T> {
     }
 }
 
+impl<T: FactTypes> AllFacts<T> {
+    /// The points where the precomputed `origin_live_on_entry` facts lack an origin which is
+    /// necessarily live, in order, e.g. because they were computed for other facts: the origins
+    /// in the type of a variable used at the point. No origin may be live at the other points,
+    /// so they are not checked. There are none when the liveness isn't precomputed.
+    pub fn uncovered_liveness_points(&self) -> Vec<T::Point> {
+        if self.origin_live_on_entry.is_empty() {
+            return Vec::new();
+        }
+
+        let live: BTreeSet<(T::Origin, T::Point)> =
+            self.origin_live_on_entry.iter().cloned().collect();
+        let mut used_origins: BTreeMap<T::Variable, Vec<T::Origin>> = BTreeMap::new();
+        for &(var, origin) in &self.use_of_var_derefs_origin {
+            used_origins.entry(var).or_default().push(origin);
+        }
+
+        let uncovered: BTreeSet<T::Point> = self
+            .var_used_at
+            .iter()
+            .filter(|&&(var, point)| {
+                used_origins.get(&var).is_some_and(|origins| {
+                    origins
+                        .iter()
+                        .any(|&origin| !live.contains(&(origin, point)))
+                })
+            })
+            .map(|&(_var, point)| point)
+            .collect();
+        uncovered.into_iter().collect()
+    }
+}
+
 pub trait Atom:
-    From<usize> + Into<usize> + Copy + Clone + Debug + Eq + Ord + Hash + 'static
+    From<usize> + Into<usize> + Copy + Clone + Debug + Eq + Ord + Hash + Send + Sync + 'static
 {
     fn index(self) -> usize;
+
+    /// The atom of the given `index`, or an error if the index doesn't fit in the atoms'
+    /// representation, unlike `From<usize>` which can only panic.
+    fn try_from_index(index: usize) -> Result<Self, AtomOverflow> {
+        Ok(Self::from(index))
+    }
+}
+
+/// An index too large for the representation of its atoms: there are more atoms than their
+/// index type supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtomOverflow {
+    /// The kind of atom, e.g. `"Point"`.
+    pub kind: &'static str,
+
+    pub index: usize,
 }
 
+impl fmt::Display for AtomOverflow {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} index {} overflows the index type of the atoms",
+            self.kind, self.index
+        )
+    }
+}
+
+impl Error for AtomOverflow {}
+
 pub trait FactTypes: Copy + Clone + Debug {
     type Origin: Atom;
     type Loan: Atom;
@@ -0,0 +1,222 @@
+//! The joins of the variants' rules, behind the `JoinBackend` trait, so that other backends, e.g.
+//! computing the joins with arrow kernels or on a GPU, can be plugged in for experimentation with
+//! `Output::compute_with_backend`.
+//!
+//! A backend only implements the joins of sorted slices of tuples. The joins of the relations,
+//! and the semi-naive joins of the variables of a fixpoint iteration, are built on top of them:
+//! the iterations themselves, their `Variable`s and the leapers of the leapjoins, are still
+//! datafrog's.
+
+use datafrog::{JoinInput, Leapers, Relation, Variable};
+use std::cmp::Ordering;
+
+pub trait JoinBackend {
+    /// Calls `result` for each pair of tuples of `input1` and `input2` with the same key. Both
+    /// inputs are sorted.
+    fn join<K: Ord, V1, V2>(
+        input1: &[(K, V1)],
+        input2: &[(K, V2)],
+        result: impl FnMut(&K, &V1, &V2),
+    );
+
+    /// Calls `result` for each tuple of `input1` whose key is not in `input2`. Both inputs are
+    /// sorted.
+    fn antijoin<K: Ord, V>(input1: &[(K, V)], input2: &[K], result: impl FnMut(&K, &V));
+
+    /// Calls `result` for each tuple of the `source` and each value proposed for it by all the
+    /// `leapers`.
+    fn leapjoin<'leap, Tuple, Val: 'leap>(
+        source: &[Tuple],
+        leapers: impl Leapers<'leap, Tuple, Val>,
+        result: impl FnMut(&Tuple, &Val),
+    );
+
+    /// The join of two relations, like `Relation::from_join`.
+    fn join_relations<K: Ord, V1: Ord, V2: Ord, R: Ord>(
+        input1: &Relation<(K, V1)>,
+        input2: &Relation<(K, V2)>,
+        mut logic: impl FnMut(&K, &V1, &V2) -> R,
+    ) -> Relation<R> {
+        let mut results = Vec::new();
+        Self::join(&input1.elements, &input2.elements, |key, value1, value2| {
+            results.push(logic(key, value1, value2))
+        });
+        Relation::from_vec(results)
+    }
+
+    /// Adds the join of the variable `input1`, and a variable or relation `input2`, to the
+    /// `output`, like `Variable::from_join`: only the joins involving the tuples added by the
+    /// last round of the iteration are computed.
+    fn join_into<'me, K: Ord, V1: Ord, V2: Ord, R: Ord>(
+        output: &Variable<R>,
+        input1: &'me Variable<(K, V1)>,
+        input2: impl JoinInput<'me, (K, V2)>,
+        mut logic: impl FnMut(&K, &V1, &V2) -> R,
+    ) {
+        let mut results = Vec::new();
+        {
+            let mut push =
+                |key: &K, value1: &V1, value2: &V2| results.push(logic(key, value1, value2));
+            let recent1 = input1.recent.borrow();
+            let recent2 = input2.recent();
+            for batch2 in input2.stable().iter() {
+                Self::join(&recent1.elements, &batch2.elements, &mut push);
+            }
+            for batch1 in input1.stable.borrow().iter() {
+                Self::join(&batch1.elements, &recent2, &mut push);
+            }
+            Self::join(&recent1.elements, &recent2, &mut push);
+        }
+        output.insert(Relation::from_vec(results));
+    }
+
+    /// The tuples of the relation `input1` whose key is not in `input2`, like
+    /// `Relation::from_antijoin`.
+    fn antijoin_relation<K: Ord, V: Ord, R: Ord>(
+        input1: &Relation<(K, V)>,
+        input2: &Relation<K>,
+        mut logic: impl FnMut(&K, &V) -> R,
+    ) -> Relation<R> {
+        let mut results = Vec::new();
+        Self::antijoin(&input1.elements, &input2.elements, |key, value| {
+            results.push(logic(key, value))
+        });
+        Relation::from_vec(results)
+    }
+
+    /// Adds the tuples of the variable `input1` added by the last round, whose key is not in
+    /// `input2`, to the `output`, like `Variable::from_antijoin`.
+    fn antijoin_into<K: Ord, V: Ord, R: Ord>(
+        output: &Variable<R>,
+        input1: &Variable<(K, V)>,
+        input2: &Relation<K>,
+        mut logic: impl FnMut(&K, &V) -> R,
+    ) {
+        let mut results = Vec::new();
+        Self::antijoin(
+            &input1.recent.borrow().elements,
+            &input2.elements,
+            |key, value| results.push(logic(key, value)),
+        );
+        output.insert(Relation::from_vec(results));
+    }
+
+    /// The leapjoin of the relation `source`, like `Relation::from_leapjoin`.
+    fn leapjoin_relation<'leap, Tuple: Ord, Val: Ord + 'leap, R: Ord>(
+        source: &Relation<Tuple>,
+        leapers: impl Leapers<'leap, Tuple, Val>,
+        mut logic: impl FnMut(&Tuple, &Val) -> R,
+    ) -> Relation<R> {
+        let mut results = Vec::new();
+        Self::leapjoin(&source.elements, leapers, |tuple, value| {
+            results.push(logic(tuple, value))
+        });
+        Relation::from_vec(results)
+    }
+
+    /// Adds the leapjoin of the tuples of the variable `source` added by the last round to the
+    /// `output`, like `Variable::from_leapjoin`.
+    fn leapjoin_into<'leap, Tuple: Ord, Val: Ord + 'leap, R: Ord>(
+        output: &Variable<R>,
+        source: &Variable<Tuple>,
+        leapers: impl Leapers<'leap, Tuple, Val>,
+        mut logic: impl FnMut(&Tuple, &Val) -> R,
+    ) {
+        let mut results = Vec::new();
+        Self::leapjoin(&source.recent.borrow().elements, leapers, |tuple, value| {
+            results.push(logic(tuple, value))
+        });
+        output.insert(Relation::from_vec(results));
+    }
+}
+
+/// The default backend: the merge joins and the treefrog leapjoin of datafrog.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DatafrogJoins;
+
+impl JoinBackend for DatafrogJoins {
+    fn join<K: Ord, V1, V2>(
+        mut input1: &[(K, V1)],
+        mut input2: &[(K, V2)],
+        mut result: impl FnMut(&K, &V1, &V2),
+    ) {
+        while !input1.is_empty() && !input2.is_empty() {
+            match input1[0].0.cmp(&input2[0].0) {
+                Ordering::Less => input1 = gallop(input1, |x| x.0 < input2[0].0),
+                Ordering::Greater => input2 = gallop(input2, |x| x.0 < input1[0].0),
+                Ordering::Equal => {
+                    let count1 = input1.iter().take_while(|x| x.0 == input1[0].0).count();
+                    let count2 = input2.iter().take_while(|x| x.0 == input2[0].0).count();
+                    for (key, value1) in &input1[..count1] {
+                        for (_, value2) in &input2[..count2] {
+                            result(key, value1, value2);
+                        }
+                    }
+                    input1 = &input1[count1..];
+                    input2 = &input2[count2..];
+                }
+            }
+        }
+    }
+
+    fn antijoin<K: Ord, V>(input1: &[(K, V)], mut input2: &[K], mut result: impl FnMut(&K, &V)) {
+        for (key, value) in input1 {
+            input2 = gallop(input2, |k| k < key);
+            if input2.first() != Some(key) {
+                result(key, value);
+            }
+        }
+    }
+
+    fn leapjoin<'leap, Tuple, Val: 'leap>(
+        source: &[Tuple],
+        mut leapers: impl Leapers<'leap, Tuple, Val>,
+        mut result: impl FnMut(&Tuple, &Val),
+    ) {
+        let mut values = Vec::new();
+        for tuple in source {
+            // The values are proposed by the leaper with the fewest of them, and intersected
+            // with the others' values.
+            let mut min_index = usize::MAX;
+            let mut min_count = usize::MAX;
+            leapers.for_each_count(tuple, |index, count| {
+                if min_count > count {
+                    min_count = count;
+                    min_index = index;
+                }
+            });
+            assert!(min_count < usize::MAX, "no leaper restricts the values");
+
+            if min_count > 0 {
+                leapers.propose(tuple, min_index, &mut values);
+                leapers.intersect(tuple, min_index, &mut values);
+                for value in values.drain(..) {
+                    result(tuple, value);
+                }
+            }
+        }
+    }
+}
+
+/// Skips the leading elements of the sorted `slice` satisfying `cmp`, with an exponential then
+/// binary search.
+fn gallop<T>(mut slice: &[T], mut cmp: impl FnMut(&T) -> bool) -> &[T] {
+    if !slice.is_empty() && cmp(&slice[0]) {
+        let mut step = 1;
+        while step < slice.len() && cmp(&slice[step]) {
+            slice = &slice[step..];
+            step <<= 1;
+        }
+
+        step >>= 1;
+        while step > 0 {
+            if step < slice.len() && cmp(&slice[step]) {
+                slice = &slice[step..];
+            }
+            step >>= 1;
+        }
+
+        slice = &slice[1..];
+    }
+    slice
+}
@@ -2,15 +2,39 @@
 /// Input is fed in via AllFacts, and outputs are returned via Output
 extern crate datafrog;
 #[macro_use]
-extern crate log;
+extern crate tracing;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 extern crate rustc_hash;
 
+pub mod compact;
+mod diagnostics;
 mod facts;
+mod join;
 mod output;
+mod progress;
+mod time;
+mod transform;
 
 // Reexports of facts
+pub use compact::CompactFacts;
+pub use diagnostics::{AtomNames, Diagnostic, DisplayDiagnostic, ErrorsDiff};
 pub use facts::AllFacts;
 pub use facts::Atom;
+pub use facts::AtomOverflow;
 pub use facts::FactTypes;
+pub use join::{DatafrogJoins, JoinBackend};
 pub use output::Algorithm;
-pub use output::Output;
+pub use output::OriginKind;
+pub use output::{compute_origin_liveness, prune_facts, PruningStats};
+pub use output::{ComputeOptions, KnownSubsets, MoveErrorKind, MoveErrorPolicy, PointOrder};
+pub use output::{Errors, InitializationOutput, LivenessOutput, Output, SubsetErrorWitness};
+pub use output::{PeakSize, RelationSizes, RoundLimitExceeded, VariableSize};
+pub use progress::{ProgressEvent, ProgressHook};
+pub use transform::{FactTransform, Pruning};
+
+// The relations returned by some of the standalone computations
+pub use datafrog::Relation;
+
+// The leapers of the leapjoins computed by a `JoinBackend`
+pub use datafrog::Leapers;
@@ -0,0 +1,278 @@
+// Copyright 2019 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The CFG, prepared once from the `cfg_edge` facts, in the forms the computations need: as
+//! relations for the rules' joins, and as adjacency lists to walk it.
+
+use datafrog::Relation;
+use rustc_hash::FxHashMap;
+use std::iter;
+
+use crate::facts::{AllFacts, Atom, FactTypes};
+use crate::output::{MoveErrorKind, Output};
+
+pub(super) struct Cfg<T: FactTypes> {
+    /// The `cfg_edge(point1, point2)` relation.
+    pub(super) edges: Relation<(T::Point, T::Point)>,
+
+    /// The `cfg_edge` relation, with the edges reversed: `(point2, point1)`.
+    pub(super) reverse_edges: Relation<(T::Point, T::Point)>,
+
+    /// The points with an edge from or to them, in order.
+    pub(super) nodes: Vec<T::Point>,
+
+    /// The successors and predecessors of each point, indexed by the points' dense indices.
+    successors: Vec<Vec<T::Point>>,
+    predecessors: Vec<Vec<T::Point>>,
+
+    /// The points in reverse post-order of a depth-first walk from the entry points, those
+    /// without predecessors, followed by the points only reachable from cycles. Each point comes
+    /// before its successors, except along the back edges of loops.
+    pub(super) reverse_post_order: Vec<T::Point>,
+}
+
+impl<T: FactTypes> Cfg<T> {
+    pub(super) fn new(edges: Relation<(T::Point, T::Point)>) -> Self {
+        let reverse_edges =
+            Relation::from_iter(edges.iter().map(|&(point1, point2)| (point2, point1)));
+
+        let mut nodes: Vec<_> = edges
+            .iter()
+            .flat_map(|&(point1, point2)| iter::once(point1).chain(iter::once(point2)))
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+
+        let len = nodes.last().map_or(0, |point| point.index() + 1);
+        let mut successors = vec![Vec::new(); len];
+        let mut predecessors = vec![Vec::new(); len];
+        for &(point1, point2) in edges.iter() {
+            successors[point1.index()].push(point2);
+            predecessors[point2.index()].push(point1);
+        }
+
+        let mut cfg = Cfg {
+            edges,
+            reverse_edges,
+            nodes,
+            successors,
+            predecessors,
+            reverse_post_order: Vec::new(),
+        };
+        cfg.reverse_post_order = cfg.compute_reverse_post_order();
+        cfg
+    }
+
+    /// The points following the `point` in the CFG.
+    pub(super) fn successors(&self, point: T::Point) -> &[T::Point] {
+        self.successors
+            .get(point.index())
+            .map_or(&[], |successors| successors.as_slice())
+    }
+
+    /// The points preceding the `point` in the CFG.
+    pub(super) fn predecessors(&self, point: T::Point) -> &[T::Point] {
+        self.predecessors
+            .get(point.index())
+            .map_or(&[], |predecessors| predecessors.as_slice())
+    }
+
+    fn compute_reverse_post_order(&self) -> Vec<T::Point> {
+        let entries = self
+            .nodes
+            .iter()
+            .filter(|&&point| self.predecessors(point).is_empty());
+
+        let mut visited = vec![false; self.successors.len()];
+        let mut post_order = Vec::with_capacity(self.nodes.len());
+        for &root in entries.chain(self.nodes.iter()) {
+            if visited[root.index()] {
+                continue;
+            }
+
+            // The points being visited, with the position of their next successor to visit: an
+            // explicit call stack, as CFGs can be too deep for recursion.
+            visited[root.index()] = true;
+            let mut visiting = vec![(root, 0)];
+            while let Some(&(point, position)) = visiting.last() {
+                if let Some(&successor) = self.successors(point).get(position) {
+                    visiting.last_mut().unwrap().1 += 1;
+                    if !visited[successor.index()] {
+                        visited[successor.index()] = true;
+                        visiting.push((successor, 0));
+                    }
+                    continue;
+                }
+
+                visiting.pop();
+                post_order.push(point);
+            }
+        }
+
+        post_order.reverse();
+        post_order
+    }
+}
+
+/// Renumbers the points of a set of facts in reverse post-order of their CFG, followed by the
+/// points outside the CFG, so that the points' order follows the flow of the facts, and restores
+/// the original points in the output of the computation.
+pub(super) struct PointRenumbering<T: FactTypes> {
+    renumbered: FxHashMap<T::Point, T::Point>,
+    original: Vec<T::Point>,
+}
+
+impl<T: FactTypes> PointRenumbering<T> {
+    pub(super) fn new(all_facts: &AllFacts<T>) -> Self {
+        let cfg = Cfg::<T>::new(all_facts.cfg_edge.clone().into());
+        let mut renumbering = PointRenumbering {
+            renumbered: FxHashMap::default(),
+            original: Vec::new(),
+        };
+        for &point in &cfg.reverse_post_order {
+            renumbering.renumber(point);
+        }
+        renumbering
+    }
+
+    fn renumber(&mut self, point: T::Point) -> T::Point {
+        let original = &mut self.original;
+        *self.renumbered.entry(point).or_insert_with(|| {
+            original.push(point);
+            T::Point::from(original.len() - 1)
+        })
+    }
+
+    /// The facts with their points renumbered.
+    pub(super) fn renumber_facts(&mut self, all_facts: &AllFacts<T>) -> AllFacts<T> {
+        let mut facts = all_facts.clone();
+        for (_, _, point) in &mut facts.loan_issued_at {
+            *point = self.renumber(*point);
+        }
+        for (point1, point2) in &mut facts.cfg_edge {
+            *point1 = self.renumber(*point1);
+            *point2 = self.renumber(*point2);
+        }
+        for (_, _, point) in &mut facts.subset_base {
+            *point = self.renumber(*point);
+        }
+        for relation in [
+            &mut facts.loan_killed_at,
+            &mut facts.loan_shallow_killed_at,
+            &mut facts.loan_invalidated_at,
+            &mut facts.loan_activated_at,
+        ] {
+            for (_, point) in relation.iter_mut() {
+                *point = self.renumber(*point);
+            }
+        }
+        for relation in [
+            &mut facts.universal_region_live_at,
+            &mut facts.origin_live_on_entry,
+        ] {
+            for (_, point) in relation.iter_mut() {
+                *point = self.renumber(*point);
+            }
+        }
+        for relation in [
+            &mut facts.var_used_at,
+            &mut facts.var_defined_at,
+            &mut facts.var_dropped_at,
+        ] {
+            for (_, point) in relation.iter_mut() {
+                *point = self.renumber(*point);
+            }
+        }
+        for relation in [
+            &mut facts.path_assigned_at_base,
+            &mut facts.path_moved_at_base,
+            &mut facts.path_accessed_at_base,
+        ] {
+            for (_, point) in relation.iter_mut() {
+                *point = self.renumber(*point);
+            }
+        }
+        facts
+    }
+
+    /// Restores the original points of the `output`, computed from the renumbered facts.
+    pub(super) fn restore_output(&self, output: &mut Output<T>) {
+        macro_rules! restore_points {
+            ($($relation:ident,)*) => {
+                $(
+                    output.$relation = output
+                        .$relation
+                        .drain()
+                        .map(|(point, value)| (self.original[point.index()], value))
+                        .collect();
+                )*
+            };
+        }
+
+        restore_points! {
+            errors,
+            subset_errors,
+            move_errors,
+            loan_live_at,
+            origin_contains_loan_at,
+            origin_live_on_entry,
+            loan_invalidated_at,
+            subset,
+            var_live_on_entry,
+            var_drop_live_on_entry,
+            var_dropped_while_init_at,
+            path_maybe_initialized_on_exit,
+            path_maybe_uninitialized_on_exit,
+            path_definitely_initialized_on_exit,
+            path_moved_at,
+            path_assigned_at,
+            path_accessed_at,
+            var_maybe_partly_initialized_on_exit,
+        }
+
+        let original = |point: T::Point| self.original[point.index()];
+        for witnesses in output.subset_error_witnesses.values_mut() {
+            for witness in witnesses.values_mut() {
+                for (_, _, point) in &mut witness.subsets {
+                    *point = original(*point);
+                }
+                for (_, _, point) in &mut witness.loans {
+                    *point = original(*point);
+                }
+            }
+        }
+        for kinds in output.move_error_kinds.values_mut() {
+            for kind in kinds.values_mut() {
+                if let MoveErrorKind::MovedOut { moved_at } = kind {
+                    for point in moved_at.iter_mut() {
+                        *point = original(*point);
+                    }
+                }
+            }
+        }
+        restore_points! {
+            subset_error_witnesses,
+            move_error_kinds,
+            loans_out_of_scope_at,
+        }
+        // the potential errors stay in the order of the renumbered points
+        for points in output.potential_errors.values_mut() {
+            for point in points.iter_mut() {
+                *point = original(*point);
+            }
+        }
+        for points in output.loan_scopes.values_mut() {
+            *points = points.iter().map(|&point| original(point)).collect();
+        }
+        for points in output.origin_live_ranges.values_mut() {
+            *points = points.iter().map(|&point| original(point)).collect();
+        }
+    }
+}
@@ -8,13 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::time::Instant;
 use datafrog::{Iteration, Relation, RelationLeaper};
-use std::time::Instant;
 
 use crate::facts::FactTypes;
-use crate::output::{Context, Output};
+use crate::join::JoinBackend;
+use crate::output::{
+    record_loan_live_at, variable_len, Context, Output, RelationSizes, ShallowKill, VariableSize,
+};
 
-pub(super) fn compute<T: FactTypes>(
+pub(super) fn compute<T: FactTypes, B: JoinBackend>(
     ctx: &Context<'_, T>,
     result: &mut Output<T>,
 ) -> (
@@ -26,11 +29,32 @@ pub(super) fn compute<T: FactTypes>(
     let (errors, subset_errors) = {
         // Static inputs
         let origin_live_on_entry_rel = &ctx.origin_live_on_entry;
-        let cfg_edge_rel = &ctx.cfg_edge;
+        let cfg_edge_rel = &ctx.cfg.edges;
         let loan_killed_at = &ctx.loan_killed_at;
+        let loan_shallow_killed_at = &ctx.loan_shallow_killed_at;
+        let loan_shallow_killed_at_olp =
+            Relation::from_iter(loan_shallow_killed_at.iter().map(|&tuple| (tuple, ())));
         let known_placeholder_subset = &ctx.known_placeholder_subset;
         let placeholder_origin = &ctx.placeholder_origin;
 
+        // After the `Hybrid` pre-pass, only the first placeholders of its potential subset errors
+        // can have subset errors: the transitive closure of the subsets is only computed from
+        // these placeholders. The pre-pass follows all the `placeholder` loans, so its first
+        // origins are not necessarily placeholders.
+        let potential_placeholder_origin;
+        let subset_placeholder_origin = match ctx.potential_subset_errors {
+            Some(ref potential_subset_errors) => {
+                potential_placeholder_origin = Relation::from_iter(
+                    potential_subset_errors
+                        .iter()
+                        .map(|&(origin1, _origin2)| (origin1, ()))
+                        .filter(|tuple| placeholder_origin.binary_search(tuple).is_ok()),
+                );
+                &potential_placeholder_origin
+            }
+            None => placeholder_origin,
+        };
+
         // Create a new iteration context, ...
         let mut iteration = Iteration::new();
 
@@ -117,6 +141,14 @@ pub(super) fn compute<T: FactTypes>(
         let dying_can_reach_live = iteration
             .variable::<((T::Origin, T::Point, T::Point), T::Origin)>("dying_can_reach_live");
 
+        // .decl dying_region_transfers(((origin2, loan), point1), point2)
+        //
+        // The `loan` of a dying origin is communicated to the live `origin2`, along the edge
+        // `point1 -> point2`. Only used when there are shallow kills, which can stop the
+        // transfer when `origin2` is the origin the `loan` was issued with.
+        let dying_region_transfers =
+            iteration.variable::<(ShallowKill<T>, T::Point)>("dying_region_transfers");
+
         // .decl dead_borrow_region_can_reach_root((origin, point), loan)
         //
         // Indicates a "borrow region" `origin` at `point` which is not live on
@@ -139,40 +171,96 @@ pub(super) fn compute<T: FactTypes>(
         let subset_placeholder_o2p = iteration.variable_indistinct("subset_placeholder_o2p");
 
         // Make "variable" versions of the relations, needed for joins.
-        loan_issued_at_op.extend(
-            ctx.loan_issued_at
-                .iter()
-                .map(|&(origin, loan, point)| ((origin, point), loan)),
-        );
-        loan_invalidated_at.extend(
-            ctx.loan_invalidated_at
-                .iter()
-                .map(|&(loan, point)| ((loan, point), ())),
-        );
-        origin_live_on_entry_var.extend(
-            origin_live_on_entry_rel
-                .iter()
-                .map(|&(origin, point)| ((origin, point), ())),
-        );
+        loan_issued_at_op.insert(ctx.loan_issued_at_op().clone());
+        loan_invalidated_at.insert(ctx.loan_invalidated_at_lp().clone());
+        origin_live_on_entry_var.insert(ctx.origin_live_on_entry_op().clone());
 
         // subset(origin1, origin2, point) :-
         //   subset_base(origin1, origin2, point).
-        subset_o1p.extend(
-            ctx.subset_base
-                .iter()
-                .map(|&(origin1, origin2, point)| ((origin1, point), origin2)),
-        );
+        subset_o1p.insert(ctx.subset_base_o1p().clone());
 
         // origin_contains_loan_on_entry(origin, loan, point) :-
         //   loan_issued_at(origin, loan, point).
-        origin_contains_loan_on_entry_op.extend(
-            ctx.loan_issued_at
-                .iter()
-                .map(|&(origin, loan, point)| ((origin, point), loan)),
-        );
+        origin_contains_loan_on_entry_op.insert(ctx.loan_issued_at_op().clone());
+
+        // Track the relations' sizes, for debugging
+        macro_rules! record_sizes {
+            ($sizes:ident; $($variable:ident,)*) => {
+                $($sizes.record(stringify!($variable), &$variable);)*
+            };
+        }
+
+        let mut relation_sizes = if result.dump_enabled {
+            Some(RelationSizes::default())
+        } else {
+            None
+        };
 
         // .. and then start iterating rules!
+        let mut round = 0;
         while iteration.changed() {
+            round += 1;
+            let _round = ctx.report_round(round, || {
+                variable_len(&subset_o1p)
+                    + variable_len(&origin_contains_loan_on_entry_op)
+                    + variable_len(&loan_live_at)
+            });
+            let round_limit = ctx.check_round_limit("DatafrogOpt", round, &mut iteration, || {
+                variable_sizes![
+                    loan_invalidated_at,
+                    origin_live_on_entry_var,
+                    loan_issued_at_op,
+                    subset_o1p,
+                    origin_contains_loan_on_entry_op,
+                    loan_live_at,
+                    live_to_dying_regions_o2pq,
+                    dying_region_requires,
+                    dying_can_reach_origins,
+                    dying_can_reach_o2q,
+                    dying_can_reach_1,
+                    dying_can_reach_live,
+                    dying_region_transfers,
+                    dead_borrow_region_can_reach_root,
+                    dead_borrow_region_can_reach_dead,
+                    dead_borrow_region_can_reach_dead_1,
+                    errors,
+                    subset_errors,
+                    subset_placeholder,
+                    subset_placeholder_o2p,
+                ]
+            });
+            if let Err(error) = round_limit {
+                result.aborted = Some(error);
+                break;
+            }
+
+            if let Some(ref mut sizes) = relation_sizes {
+                sizes.start_round();
+                record_sizes!(
+                    sizes;
+                    loan_invalidated_at,
+                    origin_live_on_entry_var,
+                    loan_issued_at_op,
+                    subset_o1p,
+                    origin_contains_loan_on_entry_op,
+                    loan_live_at,
+                    live_to_dying_regions_o2pq,
+                    dying_region_requires,
+                    dying_can_reach_origins,
+                    dying_can_reach_o2q,
+                    dying_can_reach_1,
+                    dying_can_reach_live,
+                    dying_region_transfers,
+                    dead_borrow_region_can_reach_root,
+                    dead_borrow_region_can_reach_dead,
+                    dead_borrow_region_can_reach_dead_1,
+                    errors,
+                    subset_errors,
+                    subset_placeholder,
+                    subset_placeholder_o2p,
+                );
+            }
+
             // Cleanup step: remove symmetries
             // - remove origins which are `subset`s of themselves
             //
@@ -201,7 +289,8 @@ pub(super) fn compute<T: FactTypes>(
             //   cfg_edge(point1, point2),
             //   origin_live_on_entry(origin1, point2),
             //   !origin_live_on_entry(origin2, point2).
-            live_to_dying_regions_o2pq.from_leapjoin(
+            B::leapjoin_into(
+                &live_to_dying_regions_o2pq,
                 &subset_o1p,
                 (
                     cfg_edge_rel.extend_with(|&((_, point1), _)| point1),
@@ -216,7 +305,8 @@ pub(super) fn compute<T: FactTypes>(
             //   !loan_killed_at(loan, point1),
             //   cfg_edge(point1, point2),
             //   !origin_live_on_entry(origin, point2).
-            dying_region_requires.from_leapjoin(
+            B::leapjoin_into(
+                &dying_region_requires,
                 &origin_contains_loan_on_entry_op,
                 (
                     loan_killed_at.filter_anti(|&((_, point1), loan)| (loan, point1)),
@@ -226,6 +316,29 @@ pub(super) fn compute<T: FactTypes>(
                 |&((origin, point1), loan), &point2| ((origin, point1, point2), loan),
             );
 
+            if !loan_shallow_killed_at.is_empty() {
+                // The origins where a loan is shallowly killed require it like dying origins,
+                // even when they are live: the loan is communicated to the live origins they
+                // can reach, which keep tracking it.
+                //
+                // dying_region_requires((origin, point1, point2), loan) :-
+                //   origin_contains_loan_on_entry(origin, loan, point1),
+                //   loan_shallow_killed_at((origin, loan), point1),
+                //   !loan_killed_at(loan, point1),
+                //   cfg_edge(point1, point2).
+                B::leapjoin_into(
+                    &dying_region_requires,
+                    &origin_contains_loan_on_entry_op,
+                    (
+                        loan_shallow_killed_at
+                            .filter_with(|&((origin, point1), loan)| ((origin, loan), point1)),
+                        loan_killed_at.filter_anti(|&((_, point1), loan)| (loan, point1)),
+                        cfg_edge_rel.extend_with(|&((_, point1), _)| point1),
+                    ),
+                    |&((origin, point1), loan), &point2| ((origin, point1, point2), loan),
+                );
+            }
+
             // dying_can_reach_origins(origin2, point1, point2) :-
             //   live_to_dying_regions(_, origin2, point1, point2).
             dying_can_reach_origins.from_map(
@@ -243,7 +356,8 @@ pub(super) fn compute<T: FactTypes>(
             // dying_can_reach(origin1, origin2, point1, point2) :-
             //   dying_can_reach_origins(origin1, point1, point2),
             //   subset(origin1, origin2, point1).
-            dying_can_reach_o2q.from_join(
+            B::join_into(
+                &dying_can_reach_o2q,
                 &dying_can_reach_origins,
                 &subset_o1p,
                 |&(origin1, point1), &point2, &origin2| ((origin2, point2), (origin1, point1)),
@@ -257,12 +371,14 @@ pub(super) fn compute<T: FactTypes>(
             // This is the "transitive closure" rule, but
             // note that we only apply it with the
             // "intermediate" `origin2` is dead at `point2`.
-            dying_can_reach_1.from_antijoin(
+            B::antijoin_into(
+                &dying_can_reach_1,
                 &dying_can_reach_o2q,
                 &origin_live_on_entry_rel,
                 |&(origin2, point2), &(origin1, point1)| ((origin2, point1), (origin1, point2)),
             );
-            dying_can_reach_o2q.from_join(
+            B::join_into(
+                &dying_can_reach_o2q,
                 &dying_can_reach_1,
                 &subset_o1p,
                 |&(_origin2, point1), &(origin1, point2), &origin3| {
@@ -273,7 +389,8 @@ pub(super) fn compute<T: FactTypes>(
             // dying_can_reach_live(origin1, origin2, point1, point2) :-
             //   dying_can_reach(origin1, origin2, point1, point2),
             //   origin_live_on_entry(origin2, point2).
-            dying_can_reach_live.from_join(
+            B::join_into(
+                &dying_can_reach_live,
                 &dying_can_reach_o2q,
                 &origin_live_on_entry_var,
                 |&(origin2, point2), &(origin1, point1), _| ((origin1, point1, point2), origin2),
@@ -287,7 +404,8 @@ pub(super) fn compute<T: FactTypes>(
             //
             // Carry `origin1 <= origin2` from `point1` into `point2` if both `origin1` and
             // `origin2` are live in `point2`.
-            subset_o1p.from_leapjoin(
+            B::leapjoin_into(
+                &subset_o1p,
                 &subset_o1p,
                 (
                     cfg_edge_rel.extend_with(|&((_, point1), _)| point1),
@@ -300,7 +418,8 @@ pub(super) fn compute<T: FactTypes>(
             // subset(origin1, origin3, point2) :-
             //   live_to_dying_regions(origin1, origin2, point1, point2),
             //   dying_can_reach_live(origin2, origin3, point1, point2).
-            subset_o1p.from_join(
+            B::join_into(
+                &subset_o1p,
                 &live_to_dying_regions_o2pq,
                 &dying_can_reach_live,
                 |&(_origin2, _point1, point2), &origin1, &origin3| ((origin1, point2), origin3),
@@ -315,21 +434,65 @@ pub(super) fn compute<T: FactTypes>(
             // that case, for each origin `origin2` live in `point2`
             // where `origin1 <= origin2` in `point1`, we add `origin2 contains loan`
             // to `point2`.
-            origin_contains_loan_on_entry_op.from_join(
-                &dying_region_requires,
-                &dying_can_reach_live,
-                |&(_origin1, _point1, point2), &loan, &origin2| ((origin2, point2), loan),
-            );
+            if loan_shallow_killed_at.is_empty() {
+                B::join_into(
+                    &origin_contains_loan_on_entry_op,
+                    &dying_region_requires,
+                    &dying_can_reach_live,
+                    |&(_origin1, _point1, point2), &loan, &origin2| ((origin2, point2), loan),
+                );
+            } else {
+                // As above, unless `origin2` is shallowly killed, like in the rule below.
+                //
+                // dying_region_transfers(((origin2, loan), point1), point2) :-
+                //   dying_region_requires(origin1, loan, point1, point2),
+                //   dying_can_reach_live(origin1, origin2, point1, point2).
+                B::join_into(
+                    &dying_region_transfers,
+                    &dying_region_requires,
+                    &dying_can_reach_live,
+                    |&(_origin1, point1, point2), &loan, &origin2| {
+                        (((origin2, loan), point1), point2)
+                    },
+                );
+
+                // origin_contains_loan_on_entry(origin2, loan, point2) :-
+                //   dying_region_transfers(((origin2, loan), point1), point2),
+                //   !loan_shallow_killed_at((origin2, loan), point1).
+                B::antijoin_into(
+                    &origin_contains_loan_on_entry_op,
+                    &dying_region_transfers,
+                    loan_shallow_killed_at,
+                    |&((origin2, loan), _point1), &point2| ((origin2, point2), loan),
+                );
+
+                // When `origin2` is shallowly killed, it still requires the loan like a dying
+                // origin, to communicate it to the live origins it can reach in turn.
+                //
+                // dying_region_requires((origin2, point1, point2), loan) :-
+                //   dying_region_transfers(((origin2, loan), point1), point2),
+                //   loan_shallow_killed_at((origin2, loan), point1).
+                B::join_into(
+                    &dying_region_requires,
+                    &dying_region_transfers,
+                    &loan_shallow_killed_at_olp,
+                    |&((origin2, loan), point1), &point2, &()| ((origin2, point1, point2), loan),
+                );
+            }
 
             // origin_contains_loan_on_entry(origin, loan, point2) :-
             //   origin_contains_loan_on_entry(origin, loan, point1),
             //   !loan_killed_at(loan, point1),
+            //   !loan_shallow_killed_at((origin, loan), point1),
             //   cfg_edge(point1, point2),
             //   origin_live_on_entry(origin, point2).
-            origin_contains_loan_on_entry_op.from_leapjoin(
+            B::leapjoin_into(
+                &origin_contains_loan_on_entry_op,
                 &origin_contains_loan_on_entry_op,
                 (
                     loan_killed_at.filter_anti(|&((_, point1), loan)| (loan, point1)),
+                    loan_shallow_killed_at
+                        .filter_anti(|&((origin, point1), loan)| ((origin, loan), point1)),
                     cfg_edge_rel.extend_with(|&((_, point1), _)| point1),
                     origin_live_on_entry_rel.extend_with(|&((origin, _), _)| origin),
                 ),
@@ -339,7 +502,8 @@ pub(super) fn compute<T: FactTypes>(
             // dead_borrow_region_can_reach_root((origin, point), loan) :-
             //   loan_issued_at(origin, loan, point),
             //   !origin_live_on_entry(origin, point).
-            dead_borrow_region_can_reach_root.from_antijoin(
+            B::antijoin_into(
+                &dead_borrow_region_can_reach_root,
                 &loan_issued_at_op,
                 &origin_live_on_entry_rel,
                 |&(origin, point), &loan| ((origin, point), loan),
@@ -354,12 +518,14 @@ pub(super) fn compute<T: FactTypes>(
             //   dead_borrow_region_can_reach_dead(origin1, loan, point),
             //   subset(origin1, origin2, point),
             //   !origin_live_on_entry(origin2, point).
-            dead_borrow_region_can_reach_dead_1.from_join(
+            B::join_into(
+                &dead_borrow_region_can_reach_dead_1,
                 &dead_borrow_region_can_reach_dead,
                 &subset_o1p,
                 |&(_origin1, point), &loan, &origin2| ((origin2, point), loan),
             );
-            dead_borrow_region_can_reach_dead.from_antijoin(
+            B::antijoin_into(
+                &dead_borrow_region_can_reach_dead,
                 &dead_borrow_region_can_reach_dead_1,
                 &origin_live_on_entry_rel,
                 |&(origin2, point), &loan| ((origin2, point), loan),
@@ -368,7 +534,8 @@ pub(super) fn compute<T: FactTypes>(
             // loan_live_at(loan, point) :-
             //   origin_contains_loan_on_entry(origin, loan, point),
             //   origin_live_on_entry(origin, point).
-            loan_live_at.from_join(
+            B::join_into(
+                &loan_live_at,
                 &origin_contains_loan_on_entry_op,
                 &origin_live_on_entry_var,
                 |&(_origin, point), &loan, _| ((loan, point), ()),
@@ -383,7 +550,8 @@ pub(super) fn compute<T: FactTypes>(
             // `dead_borrow_region_can_reach_dead_1`, which is equal
             // to `dead_borrow_region_can_reach_dead` and `subset`
             // joined together.
-            loan_live_at.from_join(
+            B::join_into(
+                &loan_live_at,
                 &dead_borrow_region_can_reach_dead_1,
                 &origin_live_on_entry_var,
                 |&(_origin2, point), &loan, _| ((loan, point), ()),
@@ -392,7 +560,8 @@ pub(super) fn compute<T: FactTypes>(
             // errors(loan, point) :-
             //   loan_invalidated_at(loan, point),
             //   loan_live_at(loan, point).
-            errors.from_join(
+            B::join_into(
+                &errors,
                 &loan_invalidated_at,
                 &loan_live_at,
                 |&(loan, point), _, _| (loan, point),
@@ -401,10 +570,11 @@ pub(super) fn compute<T: FactTypes>(
             // subset_placeholder(Origin1, Origin2, Point) :-
             //     subset(Origin1, Origin2, Point),
             //     placeholder_origin(Origin1).
-            subset_placeholder.from_leapjoin(
+            B::leapjoin_into(
+                &subset_placeholder,
                 &subset_o1p,
                 (
-                    placeholder_origin.extend_with(|&((origin1, _point), _origin2)| origin1),
+                    subset_placeholder_origin.extend_with(|&((origin1, _point), _origin2)| origin1),
                     // remove symmetries:
                     datafrog::ValueFilter::from(|&((origin1, _point), origin2), _| {
                         origin1 != origin2
@@ -419,7 +589,8 @@ pub(super) fn compute<T: FactTypes>(
             // subset_placeholder(Origin1, Origin3, Point) :-
             //     subset_placeholder(Origin1, Origin2, Point),
             //     subset(Origin2, Origin3, Point).
-            subset_placeholder.from_join(
+            B::join_into(
+                &subset_placeholder,
                 &subset_placeholder_o2p,
                 &subset_o1p,
                 |&(_origin2, point), &origin1, &origin3| (origin1, origin3, point),
@@ -429,7 +600,8 @@ pub(super) fn compute<T: FactTypes>(
             //     subset_placeholder(Origin1, Origin2, Point),
             //     placeholder_origin(Origin2),
             //     !known_placeholder_subset(Origin1, Origin2).
-            subset_errors.from_leapjoin(
+            B::leapjoin_into(
+                &subset_errors,
                 &subset_placeholder,
                 (
                     placeholder_origin.extend_with(|&(_origin1, origin2, _point)| origin2),
@@ -444,6 +616,15 @@ pub(super) fn compute<T: FactTypes>(
             );
         }
 
+        if let Some(ref sizes) = relation_sizes {
+            info!(
+                rounds = sizes.rounds,
+                largest_relation = ?sizes.peaks.iter().max_by_key(|(_, peak)| peak.tuples),
+                "fixpoint reached"
+            );
+        }
+        result.relation_sizes = relation_sizes;
+
         if result.dump_enabled {
             let subset_o1p = subset_o1p.complete();
             assert!(
@@ -474,21 +655,21 @@ pub(super) fn compute<T: FactTypes>(
                     .or_default()
                     .insert(loan);
             }
+        }
 
+        if ctx.record_loan_live_at {
             let loan_live_at = loan_live_at.complete();
-            for &((loan, location), _) in loan_live_at.iter() {
-                result.loan_live_at.entry(location).or_default().push(loan);
-            }
+            record_loan_live_at(loan_live_at.iter().map(|&(tuple, ())| tuple), result);
         }
 
         (errors.complete(), subset_errors.complete())
     };
 
     info!(
-        "analysis done: {} `errors` tuples, {} `subset_errors` tuples, {:?}",
-        errors.len(),
-        subset_errors.len(),
-        timer.elapsed()
+        errors = errors.len(),
+        subset_errors = subset_errors.len(),
+        elapsed = ?timer.elapsed(),
+        "analysis done"
     );
 
     (errors, subset_errors)
@@ -1,7 +1,14 @@
-use std::time::Instant;
+use crate::time::Instant;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::BTreeSet;
 
 use crate::facts::FactTypes;
-use crate::output::{InitializationContext, Output};
+use crate::join::JoinBackend;
+use crate::output::{
+    check_round_limit, report_round, variable_len, InitializationContext, MoveErrorKind, Output,
+    VariableSize,
+};
+use crate::progress::ProgressHook;
 
 use datafrog::{Iteration, Relation, RelationLeaper};
 
@@ -30,7 +37,7 @@ pub(super) struct InitializationResult<T: FactTypes>(
 // - moves of a path
 // FIXME: transitive rooting in a variable (path_begins_with_var)
 // Note that this step may not be entirely necessary!
-fn compute_transitive_paths<T: FactTypes>(
+fn compute_transitive_paths<T: FactTypes, B: JoinBackend>(
     child_path: Vec<(T::Path, T::Path)>,
     path_assigned_at_base: Vec<(T::Path, T::Point)>,
     path_moved_at_base: Vec<(T::Path, T::Point)>,
@@ -67,7 +74,8 @@ fn compute_transitive_paths<T: FactTypes>(
         // ancestor_path(Grandparent, Child) :-
         //    ancestor_path(Parent, Child),
         //    child_path(Parent, Grandparent).
-        ancestor_path.from_join(
+        B::join_into(
+            &ancestor_path,
             &ancestor_path,
             &child_path,
             |&_parent, &child, &grandparent| (grandparent, child),
@@ -77,31 +85,41 @@ fn compute_transitive_paths<T: FactTypes>(
         // path_moved_at(Child, Point) :-
         //     path_moved_at(Parent, Point),
         //     ancestor_path(Parent, Child).
-        path_moved_at.from_join(&path_moved_at, &ancestor_path, |&_parent, &p, &child| {
-            (child, p)
-        });
+        B::join_into(
+            &path_moved_at,
+            &path_moved_at,
+            &ancestor_path,
+            |&_parent, &p, &child| (child, p),
+        );
 
         // initialising x at p initialises all x:s children
         // path_assigned_at(Child, point) :-
         //     path_assigned_at(Parent, point),
         //     ancestor_path(Parent, Child).
-        path_assigned_at.from_join(&path_assigned_at, &ancestor_path, |&_parent, &p, &child| {
-            (child, p)
-        });
+        B::join_into(
+            &path_assigned_at,
+            &path_assigned_at,
+            &ancestor_path,
+            |&_parent, &p, &child| (child, p),
+        );
 
         // accessing x at p accesses all x:s children at p (actually,
         // accesses should be maximally precise and this shouldn't happen?)
         // path_accessed_at(Child, point) :-
         //   path_accessed_at(Parent, point),
         //   ancestor_path(Parent, Child).
-        path_accessed_at.from_join(&path_accessed_at, &ancestor_path, |&_parent, &p, &child| {
-            (child, p)
-        });
+        B::join_into(
+            &path_accessed_at,
+            &path_accessed_at,
+            &ancestor_path,
+            |&_parent, &p, &child| (child, p),
+        );
 
         // path_begins_with_var(Child, Var) :-
         //   path_begins_with_var(Parent, Var)
         //   ancestor_path(Parent, Child).
-        path_begins_with_var.from_join(
+        B::join_into(
+            &path_begins_with_var,
             &path_begins_with_var,
             &ancestor_path,
             |&_parent, &var, &child| (child, var),
@@ -117,10 +135,13 @@ fn compute_transitive_paths<T: FactTypes>(
 }
 
 // Step 2: Compute path initialization and deinitialization across the CFG.
-fn compute_move_errors<T: FactTypes>(
-    ctx: TransitivePaths<T>,
+fn compute_move_errors<T: FactTypes, B: JoinBackend>(
+    ctx: &TransitivePaths<T>,
     cfg_edge: &Relation<(T::Point, T::Point)>,
     output: &mut Output<T>,
+    record_relations: bool,
+    progress: Option<&ProgressHook>,
+    max_rounds: Option<usize>,
 ) -> InitializationStatus<T> {
     let mut iteration = Iteration::new();
     // Variables
@@ -155,12 +176,33 @@ fn compute_move_errors<T: FactTypes>(
     // path_maybe_uninitialized_on_exit(path, point) :- path_moved_at(path, point).
     path_maybe_uninitialized_on_exit.insert(ctx.path_moved_at.clone());
 
+    let mut round = 0;
     while iteration.changed() {
+        round += 1;
+        let _round = report_round(progress, round, || {
+            variable_len(&path_maybe_initialized_on_exit)
+                + variable_len(&path_maybe_uninitialized_on_exit)
+        });
+        let round_limit =
+            check_round_limit(max_rounds, "initialization", round, &mut iteration, || {
+                variable_sizes![
+                    var_maybe_partly_initialized_on_exit,
+                    path_maybe_initialized_on_exit,
+                    path_maybe_uninitialized_on_exit,
+                    move_error,
+                ]
+            });
+        if let Err(error) = round_limit {
+            output.aborted = Some(error);
+            break;
+        }
+
         // path_maybe_initialized_on_exit(path, point2) :-
         //     path_maybe_initialized_on_exit(path, point1),
         //     cfg_edge(point1, point2),
         //     !path_moved_at(path, point2).
-        path_maybe_initialized_on_exit.from_leapjoin(
+        B::leapjoin_into(
+            &path_maybe_initialized_on_exit,
             &path_maybe_initialized_on_exit,
             (
                 cfg_edge.extend_with(|&(_path, point1)| point1),
@@ -173,7 +215,8 @@ fn compute_move_errors<T: FactTypes>(
         //     path_maybe_uninitialized_on_exit(path, point1),
         //     cfg_edge(point1, point2)
         //     !path_assigned_at(path, point2).
-        path_maybe_uninitialized_on_exit.from_leapjoin(
+        B::leapjoin_into(
+            &path_maybe_uninitialized_on_exit,
             &path_maybe_uninitialized_on_exit,
             (
                 cfg_edge.extend_with(|&(_path, point1)| point1),
@@ -185,7 +228,8 @@ fn compute_move_errors<T: FactTypes>(
         // var_maybe_partly_initialized_on_exit(var, point) :-
         //     path_maybe_initialized_on_exit(path, point).
         //     path_begins_with_var(path, var).
-        var_maybe_partly_initialized_on_exit.from_leapjoin(
+        B::leapjoin_into(
+            &var_maybe_partly_initialized_on_exit,
             &path_maybe_initialized_on_exit,
             ctx.path_begins_with_var.extend_with(|&(path, _point)| path),
             |&(_path, point), &var| (var, point),
@@ -195,7 +239,8 @@ fn compute_move_errors<T: FactTypes>(
         //   path_maybe_uninitialized_on_exit(Path, SourceNode),
         //   cfg_edge(SourceNode, TargetNode),
         //   path_accessed_at(Path, TargetNode).
-        move_error.from_leapjoin(
+        B::leapjoin_into(
+            &move_error,
             &path_maybe_uninitialized_on_exit,
             (
                 cfg_edge.extend_with(|&(_path, source_node)| source_node),
@@ -206,8 +251,9 @@ fn compute_move_errors<T: FactTypes>(
         );
     }
 
-    if output.dump_enabled {
-        for &(path, location) in path_maybe_initialized_on_exit.complete().iter() {
+    if record_relations {
+        let path_maybe_initialized_on_exit = path_maybe_initialized_on_exit.complete();
+        for &(path, location) in path_maybe_initialized_on_exit.iter() {
             output
                 .path_maybe_initialized_on_exit
                 .entry(location)
@@ -215,13 +261,51 @@ fn compute_move_errors<T: FactTypes>(
                 .push(path);
         }
 
-        for &(path, location) in path_maybe_uninitialized_on_exit.complete().iter() {
+        let path_maybe_uninitialized_on_exit = path_maybe_uninitialized_on_exit.complete();
+        for &(path, location) in path_maybe_uninitialized_on_exit.iter() {
             output
                 .path_maybe_uninitialized_on_exit
                 .entry(location)
                 .or_default()
                 .push(path);
         }
+
+        // path_definitely_initialized_on_exit(Path, Point) :-
+        //   path_maybe_initialized_on_exit(Path, Point),
+        //   !path_maybe_uninitialized_on_exit(Path, Point).
+        for &(path, location) in path_maybe_initialized_on_exit.iter() {
+            if path_maybe_uninitialized_on_exit
+                .binary_search(&(path, location))
+                .is_err()
+            {
+                output
+                    .path_definitely_initialized_on_exit
+                    .entry(location)
+                    .or_default()
+                    .push(path);
+            }
+        }
+
+        // Also record the transitive closures of the path operations
+        for &(path, location) in ctx.path_moved_at.iter() {
+            output.path_moved_at.entry(location).or_default().push(path);
+        }
+
+        for &(path, location) in ctx.path_assigned_at.iter() {
+            output
+                .path_assigned_at
+                .entry(location)
+                .or_default()
+                .push(path);
+        }
+
+        for &(path, location) in ctx.path_accessed_at.iter() {
+            output
+                .path_accessed_at
+                .entry(location)
+                .or_default()
+                .push(path);
+        }
     }
 
     InitializationStatus {
@@ -230,6 +314,106 @@ fn compute_move_errors<T: FactTypes>(
     }
 }
 
+// Step 3: classify the move errors, by the last moves of their paths on the way to the error.
+// The paths start uninitialized by being moved out at the entry of the CFG: when only these
+// moves reach an error, the path may have never been initialized.
+fn classify_move_errors<T: FactTypes, B: JoinBackend>(
+    ctx: &TransitivePaths<T>,
+    cfg_edge: &Relation<(T::Point, T::Point)>,
+    move_error: &Relation<(T::Path, T::Point)>,
+    output: &mut Output<T>,
+) {
+    let mut iteration = Iteration::new();
+
+    // path_last_moved_on_exit(path, move_point, point): upon leaving `point`, the last move of
+    // `path` on some path through the CFG was at `move_point`, and it hasn't been re-initialized
+    // since. Only the paths with move errors are tracked.
+    let path_last_moved_on_exit =
+        iteration.variable::<(T::Path, T::Point, T::Point)>("path_last_moved_on_exit");
+
+    // path_last_moved_on_exit(path, point, point) :-
+    //     path_moved_at(path, point),
+    //     move_error(path, _).
+    let error_paths: Relation<(T::Path, ())> = move_error
+        .iter()
+        .map(|&(path, _point)| (path, ()))
+        .collect();
+    path_last_moved_on_exit.insert(B::join_relations(
+        &ctx.path_moved_at,
+        &error_paths,
+        |&path, &point, &()| (path, point, point),
+    ));
+
+    while iteration.changed() {
+        // path_last_moved_on_exit(path, move_point, point2) :-
+        //     path_last_moved_on_exit(path, move_point, point1),
+        //     cfg_edge(point1, point2),
+        //     !path_assigned_at(path, point2),
+        //     !path_moved_at(path, point2).
+        B::leapjoin_into(
+            &path_last_moved_on_exit,
+            &path_last_moved_on_exit,
+            (
+                cfg_edge.extend_with(|&(_path, _move_point, point1)| point1),
+                ctx.path_assigned_at
+                    .extend_anti(|&(path, _move_point, _point1)| path),
+                ctx.path_moved_at
+                    .extend_anti(|&(path, _move_point, _point1)| path),
+            ),
+            |&(path, move_point, _point1), &point2| (path, move_point, point2),
+        );
+    }
+
+    // path_last_moved_on_entry(path, move_point, target_node) :-
+    //     path_last_moved_on_exit(path, move_point, source_node),
+    //     cfg_edge(source_node, target_node).
+    let path_last_moved_on_entry = B::leapjoin_relation(
+        &path_last_moved_on_exit.complete(),
+        cfg_edge.extend_with(|&(_path, _move_point, source_node)| source_node),
+        |&(path, move_point, _source_node), &target_node| (path, target_node, move_point),
+    );
+
+    // The last moves reaching each move error.
+    let mut last_moves: FxHashMap<_, BTreeSet<T::Point>> = FxHashMap::default();
+    for &(path, location, move_point) in path_last_moved_on_entry.iter() {
+        if move_error.binary_search(&(path, location)).is_ok() {
+            last_moves
+                .entry((path, location))
+                .or_default()
+                .insert(move_point);
+        }
+    }
+
+    let entry_points: FxHashSet<T::Point> = {
+        let targets: FxHashSet<T::Point> =
+            cfg_edge.iter().map(|&(_point1, point2)| point2).collect();
+        cfg_edge
+            .iter()
+            .map(|&(point1, _point2)| point1)
+            .filter(|point1| !targets.contains(point1))
+            .collect()
+    };
+
+    for &(path, location) in move_error.iter() {
+        let moved_at: Vec<T::Point> = last_moves
+            .remove(&(path, location))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|move_point| !entry_points.contains(move_point))
+            .collect();
+        let kind = if moved_at.is_empty() {
+            MoveErrorKind::Uninitialized
+        } else {
+            MoveErrorKind::MovedOut { moved_at }
+        };
+        output
+            .move_error_kinds
+            .entry(location)
+            .or_default()
+            .insert(path, kind);
+    }
+}
+
 // Compute two things:
 //
 // - an over-approximation of the initialization of variables. This is used in
@@ -244,33 +428,46 @@ fn compute_move_errors<T: FactTypes>(
 // 2. Use this to compute both paths that may be initialized and paths that may
 //   have been deinitialized, which in turn can be used to find move errors (an
 //   access to a path that may be deinitialized).
-pub(super) fn compute<T: FactTypes>(
+pub(super) fn compute<T: FactTypes, B: JoinBackend>(
     ctx: InitializationContext<T>,
     cfg_edge: &Relation<(T::Point, T::Point)>,
     output: &mut Output<T>,
+    record_relations: bool,
 ) -> InitializationResult<T> {
     let timer = Instant::now();
 
-    let transitive_paths = compute_transitive_paths::<T>(
+    let transitive_paths = compute_transitive_paths::<T, B>(
         ctx.child_path,
         ctx.path_assigned_at_base,
         ctx.path_moved_at_base,
         ctx.path_accessed_at_base,
         ctx.path_is_var,
     );
-    info!("initialization phase 1 completed: {:?}", timer.elapsed());
+    info!(elapsed = ?timer.elapsed(), "initialization phase 1 completed");
 
     let InitializationStatus {
         var_maybe_partly_initialized_on_exit,
         move_error,
-    } = compute_move_errors::<T>(transitive_paths, cfg_edge, output);
+    } = compute_move_errors::<T, B>(
+        &transitive_paths,
+        cfg_edge,
+        output,
+        record_relations,
+        ctx.progress.as_ref(),
+        ctx.max_rounds,
+    );
     info!(
-        "initialization phase 2: {} move errors in {:?}",
-        move_error.elements.len(),
-        timer.elapsed()
+        move_errors = move_error.elements.len(),
+        elapsed = ?timer.elapsed(),
+        "initialization phase 2 completed"
     );
 
-    if output.dump_enabled {
+    if !move_error.is_empty() && output.aborted.is_none() {
+        classify_move_errors::<T, B>(&transitive_paths, cfg_edge, &move_error, output);
+        info!(elapsed = ?timer.elapsed(), "initialization phase 3 completed");
+    }
+
+    if record_relations {
         for &(var, location) in var_maybe_partly_initialized_on_exit.iter() {
             output
                 .var_maybe_partly_initialized_on_exit
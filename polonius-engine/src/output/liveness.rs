@@ -10,38 +10,81 @@
 
 //! An implementation of the origin liveness calculation logic
 
+use crate::time::Instant;
 use std::collections::BTreeSet;
-use std::time::Instant;
 
 use crate::facts::FactTypes;
-use crate::output::{LivenessContext, Output};
+use crate::join::JoinBackend;
+use crate::output::cfg::Cfg;
+use crate::output::{
+    check_round_limit, report_round, variable_len, LivenessContext, Output, VariableSize,
+};
 
 use datafrog::{Iteration, Relation, RelationLeaper};
 
-pub(super) fn compute_live_origins<T: FactTypes>(
+/// Computes the drops of variables which may be (partly) initialized when they are dropped: the
+/// drops of moved-out variables are no-ops, which don't need their origins to be live.
+pub(super) fn compute_var_dropped_while_init_at<T: FactTypes, B: JoinBackend>(
+    var_dropped_at: Vec<(T::Variable, T::Point)>,
+    cfg: &Cfg<T>,
+    var_maybe_partly_initialized_on_exit: &Relation<(T::Variable, T::Point)>,
+    output: &mut Output<T>,
+    record_variables: bool,
+) -> Relation<(T::Variable, T::Point)> {
+    let var_dropped_at: Relation<((T::Variable, T::Point), ())> = var_dropped_at
+        .into_iter()
+        .map(|(var, point)| ((var, point), ()))
+        .collect();
+
+    // var_maybe_partly_initialized_on_entry(var, point2) :-
+    //     var_maybe_partly_initialized_on_exit(var, point1),
+    //     cfg_edge(point1, point2).
+    let var_maybe_partly_initialized_on_entry = B::leapjoin_relation(
+        var_maybe_partly_initialized_on_exit,
+        cfg.edges.extend_with(|&(_var, point1)| point1),
+        |&(var, _point1), &point2| ((var, point2), ()),
+    );
+
+    // var_dropped_while_init_at(var, point) :-
+    //     var_dropped_at(var, point),
+    //     var_maybe_partly_initialized_on_entry(var, point).
+    let var_dropped_while_init_at = B::join_relations(
+        &var_dropped_at,
+        &var_maybe_partly_initialized_on_entry,
+        |&(var, point), _, _| (var, point),
+    );
+
+    if record_variables {
+        for &(var, location) in var_dropped_while_init_at.iter() {
+            output
+                .var_dropped_while_init_at
+                .entry(location)
+                .or_default()
+                .push(var);
+        }
+    }
+
+    var_dropped_while_init_at
+}
+
+pub(super) fn compute_live_origins<T: FactTypes, B: JoinBackend>(
     ctx: LivenessContext<T>,
-    cfg_edge: &Relation<(T::Point, T::Point)>,
+    cfg: &Cfg<T>,
+    var_dropped_while_init_at: Relation<(T::Variable, T::Point)>,
     var_maybe_partly_initialized_on_exit: Relation<(T::Variable, T::Point)>,
     output: &mut Output<T>,
+    record_variables: bool,
 ) -> Vec<(T::Origin, T::Point)> {
     let timer = Instant::now();
     let mut iteration = Iteration::new();
 
     // Relations
     let var_defined_at: Relation<(T::Variable, T::Point)> = ctx.var_defined_at.into();
-    let cfg_edge_reverse: Relation<(T::Point, T::Point)> = cfg_edge
-        .iter()
-        .map(|&(point1, point2)| (point2, point1))
-        .collect();
+    let cfg_edge_reverse = &cfg.reverse_edges;
     let use_of_var_derefs_origin: Relation<(T::Variable, T::Origin)> =
         ctx.use_of_var_derefs_origin.into();
     let drop_of_var_derefs_origin: Relation<(T::Variable, T::Origin)> =
         ctx.drop_of_var_derefs_origin.into();
-    let var_dropped_at: Relation<((T::Variable, T::Point), ())> = ctx
-        .var_dropped_at
-        .into_iter()
-        .map(|(var, point)| ((var, point), ()))
-        .collect();
 
     // Variables
 
@@ -57,29 +100,35 @@ pub(super) fn compute_live_origins<T: FactTypes>(
     // This propagates the relation `var_live_on_entry(var, point) :- var_used_at(var, point)`:
     var_live_on_entry.insert(ctx.var_used_at.into());
 
-    // var_maybe_partly_initialized_on_entry(var, point2) :-
-    //     var_maybe_partly_initialized_on_exit(var, point1),
-    //     cfg_edge(point1, point2).
-    let var_maybe_partly_initialized_on_entry = Relation::from_leapjoin(
-        &var_maybe_partly_initialized_on_exit,
-        cfg_edge.extend_with(|&(_var, point1)| point1),
-        |&(var, _point1), &point2| ((var, point2), ()),
-    );
-
-    // var_drop_live_on_entry(var, point) :-
-    //     var_dropped_at(var, point),
-    //     var_maybe_partly_initialized_on_entry(var, point).
-    var_drop_live_on_entry.insert(Relation::from_join(
-        &var_dropped_at,
-        &var_maybe_partly_initialized_on_entry,
-        |&(var, point), _, _| (var, point),
-    ));
+    // var_drop_live_on_entry(var, point) :- var_dropped_while_init_at(var, point).
+    var_drop_live_on_entry.insert(var_dropped_while_init_at);
 
+    let mut round = 0;
     while iteration.changed() {
+        round += 1;
+        let _round = report_round(ctx.progress.as_ref(), round, || {
+            variable_len(&var_live_on_entry)
+                + variable_len(&var_drop_live_on_entry)
+                + variable_len(&origin_live_on_entry)
+        });
+        let round_limit =
+            check_round_limit(ctx.max_rounds, "liveness", round, &mut iteration, || {
+                variable_sizes![
+                    var_live_on_entry,
+                    var_drop_live_on_entry,
+                    origin_live_on_entry,
+                ]
+            });
+        if let Err(error) = round_limit {
+            output.aborted = Some(error);
+            break;
+        }
+
         // origin_live_on_entry(origin, point) :-
         //   var_drop_live_on_entry(var, point),
         //   drop_of_var_derefs_origin(var, origin).
-        origin_live_on_entry.from_join(
+        B::join_into(
+            &origin_live_on_entry,
             &var_drop_live_on_entry,
             &drop_of_var_derefs_origin,
             |_var, &point, &origin| (origin, point),
@@ -88,7 +137,8 @@ pub(super) fn compute_live_origins<T: FactTypes>(
         // origin_live_on_entry(origin, point) :-
         //   var_live_on_entry(var, point),
         //   use_of_var_derefs_origin(var, origin).
-        origin_live_on_entry.from_join(
+        B::join_into(
+            &origin_live_on_entry,
             &var_live_on_entry,
             &use_of_var_derefs_origin,
             |_var, &point, &origin| (origin, point),
@@ -98,7 +148,8 @@ pub(super) fn compute_live_origins<T: FactTypes>(
         //     var_live_on_entry(var, point2),
         //     cfg_edge(point1, point2),
         //     !var_defined(var, point1).
-        var_live_on_entry.from_leapjoin(
+        B::leapjoin_into(
+            &var_live_on_entry,
             &var_live_on_entry,
             (
                 var_defined_at.extend_anti(|&(var, _point2)| var),
@@ -112,7 +163,8 @@ pub(super) fn compute_live_origins<T: FactTypes>(
         //   cfg_edge(SourceNode, TargetNode),
         //   !var_defined_at(Var, SourceNode),
         //   var_maybe_partly_initialized_on_exit(Var, SourceNode).
-        var_drop_live_on_entry.from_leapjoin(
+        B::leapjoin_into(
+            &var_drop_live_on_entry,
             &var_drop_live_on_entry,
             (
                 var_defined_at.extend_anti(|&(var, _target_node)| var),
@@ -126,12 +178,12 @@ pub(super) fn compute_live_origins<T: FactTypes>(
     let origin_live_on_entry = origin_live_on_entry.complete();
 
     info!(
-        "compute_live_origins() completed: {} tuples, {:?}",
-        origin_live_on_entry.len(),
-        timer.elapsed(),
+        origin_live_on_entry = origin_live_on_entry.len(),
+        elapsed = ?timer.elapsed(),
+        "compute_live_origins() completed"
     );
 
-    if output.dump_enabled {
+    if record_variables {
         let var_drop_live_on_entry = var_drop_live_on_entry.complete();
         for &(var, location) in var_drop_live_on_entry.iter() {
             output
@@ -156,13 +208,26 @@ pub(super) fn compute_live_origins<T: FactTypes>(
 
 pub(super) fn make_universal_regions_live<T: FactTypes>(
     origin_live_on_entry: &mut Vec<(T::Origin, T::Point)>,
-    cfg_node: &BTreeSet<T::Point>,
+    cfg_node: &[T::Point],
     universal_regions: &[T::Origin],
+    universal_region_live_at: &[(T::Origin, T::Point)],
 ) {
     debug!("make_universal_regions_live()");
 
+    // The universal regions whose liveness is restricted to specific points are live there,
+    // the others are live everywhere.
+    let restricted: BTreeSet<T::Origin> = universal_region_live_at
+        .iter()
+        .map(|&(origin, _point)| origin)
+        .collect();
+    origin_live_on_entry.extend(universal_region_live_at.iter().cloned());
+
     origin_live_on_entry.reserve(universal_regions.len() * cfg_node.len());
     for &origin in universal_regions.iter() {
+        if restricted.contains(&origin) {
+            continue;
+        }
+
         for &point in cfg_node.iter() {
             origin_live_on_entry.push((origin, point));
         }
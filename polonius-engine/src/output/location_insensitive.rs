@@ -8,18 +8,24 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::time::Instant;
 use datafrog::{Iteration, Relation, RelationLeaper};
-use std::time::Instant;
+use rustc_hash::FxHashMap;
 
 use crate::facts::FactTypes;
-use crate::output::{Context, Output};
+use crate::join::JoinBackend;
+use crate::output::{record_loan_live_at, variable_len, Context, Output, VariableSize};
 
-pub(super) fn compute<T: FactTypes>(
+/// Computes the potential errors, and the potential subset errors at a representative point:
+/// the earliest point in the CFG where the first origin's placeholder loan flows into the second
+/// origin. The points of the potential errors of each loan are also recorded in
+/// `Output::potential_errors`.
+pub(super) fn compute<T: FactTypes, B: JoinBackend>(
     ctx: &Context<'_, T>,
     result: &mut Output<T>,
 ) -> (
     Relation<(T::Loan, T::Point)>,
-    Relation<(T::Origin, T::Origin)>,
+    Relation<(T::Origin, T::Origin, T::Point)>,
 ) {
     let timer = Instant::now();
 
@@ -69,14 +75,33 @@ pub(super) fn compute<T: FactTypes>(
         );
 
         // .. and then start iterating rules!
+        let mut round = 0;
         while iteration.changed() {
+            round += 1;
+            let _round = ctx.report_round(round, || {
+                variable_len(&origin_contains_loan_on_entry) + variable_len(&potential_errors)
+            });
+            let round_limit =
+                ctx.check_round_limit("LocationInsensitive", round, &mut iteration, || {
+                    variable_sizes![
+                        origin_contains_loan_on_entry,
+                        potential_errors,
+                        potential_subset_errors,
+                    ]
+                });
+            if let Err(error) = round_limit {
+                result.aborted = Some(error);
+                break;
+            }
+
             // origin_contains_loan_on_entry(Origin2, Loan) :-
             //   origin_contains_loan_on_entry(Origin1, Loan),
             //   subset(Origin1, Origin2).
             //
             // Note: Since `subset` is effectively a static input, this join can be ported to
             // a leapjoin. Doing so, however, was 7% slower on `clap`.
-            origin_contains_loan_on_entry.from_join(
+            B::join_into(
+                &origin_contains_loan_on_entry,
                 &origin_contains_loan_on_entry,
                 &subset,
                 |&_origin1, &loan, &origin2| (origin2, loan),
@@ -93,7 +118,8 @@ pub(super) fn compute<T: FactTypes>(
             // Note: we don't need to materialize `loan_live_at` here
             // so we can inline it in the `potential_errors` relation.
             //
-            potential_errors.from_leapjoin(
+            B::leapjoin_into(
+                &potential_errors,
                 &origin_contains_loan_on_entry,
                 (
                     origin_live_on_entry.extend_with(|&(origin, _loan)| origin),
@@ -107,7 +133,8 @@ pub(super) fn compute<T: FactTypes>(
             //   placeholder(Origin2, _),
             //   origin_contains_loan_on_entry(Origin2, Loan1),
             //   !known_contains(Origin2, Loan1).
-            potential_subset_errors.from_leapjoin(
+            B::leapjoin_into(
+                &potential_subset_errors,
                 &origin_contains_loan_on_entry,
                 (
                     known_contains.filter_anti(|&(origin2, loan1)| (origin2, loan1)),
@@ -120,6 +147,18 @@ pub(super) fn compute<T: FactTypes>(
             );
         }
 
+        let origin_contains_loan_on_entry = origin_contains_loan_on_entry.complete();
+        let potential_subset_errors = potential_subset_errors.complete();
+        let potential_subset_errors = if potential_subset_errors.is_empty() {
+            Relation::from_vec(Vec::new())
+        } else {
+            representative_points::<T, B>(
+                ctx,
+                &origin_contains_loan_on_entry,
+                &potential_subset_errors,
+            )
+        };
+
         if result.dump_enabled {
             for &(origin1, origin2) in subset.iter() {
                 result
@@ -129,7 +168,6 @@ pub(super) fn compute<T: FactTypes>(
                     .insert(origin2);
             }
 
-            let origin_contains_loan_on_entry = origin_contains_loan_on_entry.complete();
             for &(origin, loan) in origin_contains_loan_on_entry.iter() {
                 result
                     .origin_contains_loan_anywhere
@@ -139,18 +177,125 @@ pub(super) fn compute<T: FactTypes>(
             }
         }
 
-        (
-            potential_errors.complete(),
-            potential_subset_errors.complete(),
-        )
+        if ctx.record_loan_live_at {
+            // The loans which may be in scope: the ones contained anywhere in the live origins.
+            //
+            // loan_live_at(Loan, Point) :-
+            //   origin_contains_loan_on_entry(Origin, Loan),
+            //   origin_live_on_entry(Origin, Point)
+            let loan_live_at = B::join_relations(
+                &origin_contains_loan_on_entry,
+                &ctx.origin_live_on_entry,
+                |_origin, &loan, &point| (loan, point),
+            );
+            record_loan_live_at(loan_live_at.elements, result);
+        }
+
+        let potential_errors = potential_errors.complete();
+
+        // The tuples are sorted by loan, and then by point: the earliest invalidation of each
+        // loan comes first.
+        if result.aborted.is_none() {
+            for &(loan, point) in potential_errors.iter() {
+                result.potential_errors.entry(loan).or_default().push(point);
+            }
+        }
+
+        (potential_errors, potential_subset_errors)
     };
 
     info!(
-        "analysis done: {} `potential_errors` tuples, {} `potential_subset_errors` tuples, {:?}",
-        potential_errors.len(),
-        potential_subset_errors.len(),
-        timer.elapsed()
+        potential_errors = potential_errors.len(),
+        potential_subset_errors = potential_subset_errors.len(),
+        elapsed = ?timer.elapsed(),
+        "analysis done"
     );
 
     (potential_errors, potential_subset_errors)
 }
+
+/// Finds a representative point for each of the `potential_subset_errors`: the earliest point in
+/// the CFG's reverse post-order where the placeholder loan of `Origin1` flows into `Origin2`,
+/// through a `subset_base` fact, or is issued in `Origin2`. The points outside the CFG come after
+/// the others, by index. `Origin2` only contains the loan through one of these facts, so every
+/// error has such a point: one without it would be skipped, rather than located anywhere.
+fn representative_points<T: FactTypes, B: JoinBackend>(
+    ctx: &Context<'_, T>,
+    origin_contains_loan_on_entry: &Relation<(T::Origin, T::Loan)>,
+    potential_subset_errors: &Relation<(T::Origin, T::Origin)>,
+) -> Relation<(T::Origin, T::Origin, T::Point)> {
+    // origin_contains_placeholder_of(Origin, Origin1) :-
+    //   origin_contains_loan_on_entry(Origin, Loan1),
+    //   placeholder_loan(Loan1, Origin1).
+    let origin_contains_loan_on_entry_l = Relation::from_iter(
+        origin_contains_loan_on_entry
+            .iter()
+            .map(|&(origin, loan1)| (loan1, origin)),
+    );
+    let origin_contains_placeholder_of = B::join_relations(
+        &origin_contains_loan_on_entry_l,
+        &ctx.placeholder_loan,
+        |&_loan1, &origin, &origin1| (origin, origin1),
+    );
+
+    // placeholder_flows_at((Origin1, Origin2), Point) :-
+    //   origin_contains_placeholder_of(Origin, Origin1),
+    //   subset_base(Origin, Origin2, Point).
+    let subset_base_o = Relation::from_iter(
+        ctx.subset_base
+            .iter()
+            .map(|&(origin, origin2, point)| (origin, (origin2, point))),
+    );
+    let placeholder_flows_at = B::join_relations(
+        &origin_contains_placeholder_of,
+        &subset_base_o,
+        |&_origin, &origin1, &(origin2, point)| ((origin1, origin2), point),
+    );
+
+    // placeholder_issued_at((Origin1, Origin2), Point) :-
+    //   loan_issued_at(Origin2, Loan1, Point),
+    //   placeholder_loan(Loan1, Origin1).
+    let loan_issued_at_l = Relation::from_iter(
+        ctx.loan_issued_at
+            .iter()
+            .map(|&(origin2, loan1, point)| (loan1, (origin2, point))),
+    );
+    let placeholder_issued_at = B::join_relations(
+        &loan_issued_at_l,
+        &ctx.placeholder_loan,
+        |&_loan1, &(origin2, point), &origin1| ((origin1, origin2), point),
+    );
+
+    let order: FxHashMap<T::Point, usize> = ctx
+        .cfg
+        .reverse_post_order
+        .iter()
+        .enumerate()
+        .map(|(position, &point)| (point, position))
+        .collect();
+    let cfg_order = |point: T::Point| (order.get(&point).copied().unwrap_or(usize::MAX), point);
+
+    let mut earliest: FxHashMap<(T::Origin, T::Origin), T::Point> = FxHashMap::default();
+    for &(origins, point) in placeholder_flows_at
+        .iter()
+        .chain(placeholder_issued_at.iter())
+    {
+        earliest
+            .entry(origins)
+            .and_modify(|earliest| {
+                if cfg_order(point) < cfg_order(*earliest) {
+                    *earliest = point;
+                }
+            })
+            .or_insert(point);
+    }
+
+    Relation::from_iter(
+        potential_subset_errors
+            .iter()
+            .filter_map(|&(origin1, origin2)| {
+                let point = earliest.get(&(origin1, origin2))?;
+                Some((origin1, origin2, *point))
+            }),
+    )
+}
@@ -8,18 +8,43 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use datafrog::Relation;
+use datafrog::{Iteration, Relation, Variable};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
-
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::sync::OnceLock;
+use tracing::field;
+use tracing::span::EnteredSpan;
+
+use self::cfg::{Cfg, PointRenumbering};
+use crate::diagnostics::{Diagnostic, ErrorsDiff};
 use crate::facts::{AllFacts, Atom, FactTypes};
+use crate::join::{DatafrogJoins, JoinBackend};
+use crate::progress::{ProgressEvent, ProgressHook};
+use crate::transform::FactTransform;
+
+/// The sizes of the given variables of a fixpoint iteration, named after them, see
+/// `check_round_limit`.
+macro_rules! variable_sizes {
+    ($($variable:ident),* $(,)*) => {
+        vec![$(VariableSize::new(stringify!($variable), &$variable)),*]
+    };
+}
 
+mod cfg;
 mod datafrog_opt;
 mod initialization;
 mod liveness;
 mod location_insensitive;
 mod naive;
+mod parallel_naive;
+mod pruning;
+mod witnesses;
+
+pub use self::pruning::{prune_facts, PruningStats};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Algorithm {
@@ -40,19 +65,34 @@ pub enum Algorithm {
     /// Combination of the fast `LocationInsensitive` pre-pass, followed by
     /// the more expensive `DatafrogOpt` variant.
     Hybrid,
+
+    /// The `Naive` rules, evaluated on each strongly-connected component of the CFG in turn.
+    /// With the `rayon` feature, independent components are evaluated in parallel.
+    ParallelNaive,
+
+    /// Only computes the liveness of variables and origins, and the move errors they depend
+    /// on, but doesn't borrow check the facts: see `Output::liveness`.
+    LivenessOnly,
+
+    /// Only computes the move errors, and the initialization of paths and variables: see
+    /// `Output::initialization`.
+    InitializationOnly,
 }
 
 impl Algorithm {
     /// Optimized variants that ought to be equivalent to "naive"
-    pub const OPTIMIZED: &'static [Algorithm] = &[Algorithm::DatafrogOpt];
+    pub const OPTIMIZED: &'static [Algorithm] = &[Algorithm::DatafrogOpt, Algorithm::ParallelNaive];
 
-    pub fn variants() -> [&'static str; 5] {
+    pub fn variants() -> [&'static str; 8] {
         [
             "Naive",
             "DatafrogOpt",
             "LocationInsensitive",
             "Compare",
             "Hybrid",
+            "ParallelNaive",
+            "LivenessOnly",
+            "InitializationOnly",
         ]
     }
 }
@@ -66,23 +106,214 @@ impl ::std::str::FromStr for Algorithm {
             "locationinsensitive" => Ok(Algorithm::LocationInsensitive),
             "compare" => Ok(Algorithm::Compare),
             "hybrid" => Ok(Algorithm::Hybrid),
+            "parallelnaive" => Ok(Algorithm::ParallelNaive),
+            "livenessonly" => Ok(Algorithm::LivenessOnly),
+            "initializationonly" => Ok(Algorithm::InitializationOnly),
             _ => Err(String::from(
-                "valid values: Naive, DatafrogOpt, LocationInsensitive, Compare, Hybrid",
+                "valid values: Naive, DatafrogOpt, LocationInsensitive, Compare, Hybrid, \
+                 ParallelNaive, LivenessOnly, InitializationOnly",
             )),
         }
     }
 }
 
+/// What to do when the initialization analysis finds move errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveErrorPolicy {
+    /// Compute liveness and borrow check the facts regardless of move errors.
+    #[default]
+    Continue,
+
+    /// Stop after the initialization analysis: liveness and loan errors can't be computed
+    /// accurately in the presence of move errors. The `Output` is marked as partial.
+    Stop,
+}
+
+/// Options tweaking the computation done by `Output::compute_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeOptions {
+    /// Whether to record the intermediate relations, for debugging.
+    pub dump_enabled: bool,
+
+    pub move_error_policy: MoveErrorPolicy,
+
+    pub point_order: PointOrder,
+
+    pub known_subsets: KnownSubsets,
+
+    /// Whether to model two-phase borrows with the `loan_activated_at` facts: the invalidations
+    /// of a loan before its activation are not errors. Otherwise, these facts are ignored, and
+    /// the loans are active as soon as they are issued.
+    pub two_phase_borrows: bool,
+
+    /// The hook receiving the progress of the computation, e.g. to show it while checking big
+    /// functions.
+    pub progress: Option<ProgressHook>,
+
+    /// The maximum number of rounds of the fixpoint iteration of each phase, if any: the
+    /// computation is aborted when an iteration doesn't converge within them, instead of looping
+    /// for minutes on pathological facts, see `Output::aborted`.
+    pub max_rounds: Option<usize>,
+
+    /// Whether to skip computing `Output::loan_live_at`, the loans in scope at each point, for
+    /// the runs only interested in the errors. Otherwise, all the variants compute them.
+    pub skip_loan_live_at: bool,
+
+    /// Whether to compute the scope of each loan, and the live range of each origin, see
+    /// `Output::loan_scopes` and `Output::origin_live_ranges`.
+    pub scopes: bool,
+
+    /// Whether to compute the points where each loan goes out of scope, see
+    /// `Output::loans_out_of_scope_at`.
+    pub loans_out_of_scope: bool,
+}
+
+/// The order of the points during the computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointOrder {
+    /// The points are used as they are numbered in the facts.
+    #[default]
+    Input,
+
+    /// The points are renumbered in reverse post-order of the CFG, so that the tuples at a point
+    /// are usually sorted before the tuples at its successors, in the relations indexed by point.
+    /// The output's points are the original ones.
+    ReversePostOrder,
+}
+
+/// Which subsets between placeholder origins are known to hold, when checking the subsets the
+/// analysis requires: the subset errors are the required subsets which aren't known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KnownSubsets {
+    /// The `known_placeholder_subset` facts are closed transitively: when `'a: 'b` and `'b: 'c`
+    /// are known, so is `'a: 'c`.
+    #[default]
+    Transitive,
+
+    /// Only the `known_placeholder_subset` facts are known, as given.
+    AsGiven,
+}
+
+/// Why a computation was aborted: the fixpoint iteration of one of its phases didn't converge
+/// within `ComputeOptions::max_rounds`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundLimitExceeded {
+    /// The phase of the iteration, e.g. `liveness`, or the name of a variant, like `DatafrogOpt`.
+    pub phase: &'static str,
+
+    pub max_rounds: usize,
+
+    /// The sizes of the iteration's variables when it was aborted, to see which relations keep
+    /// growing.
+    pub variables: Vec<VariableSize>,
+}
+
+/// The size of a variable of a fixpoint iteration, see `RoundLimitExceeded`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableSize {
+    pub name: &'static str,
+
+    /// The number of tuples of the variable.
+    pub tuples: usize,
+
+    /// The number of tuples added by the last round.
+    pub delta: usize,
+}
+
+impl VariableSize {
+    fn new<Tuple: Ord>(name: &'static str, variable: &Variable<Tuple>) -> Self {
+        VariableSize {
+            name,
+            tuples: variable_len(variable),
+            delta: variable.recent.borrow().len(),
+        }
+    }
+}
+
+impl fmt::Display for RoundLimitExceeded {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "the {} iteration didn't converge within {} rounds",
+            self.phase, self.max_rounds
+        )?;
+        for variable in &self.variables {
+            write!(
+                fmt,
+                "\n    {}: {} tuples (+{} in the last round)",
+                variable.name, variable.tuples, variable.delta
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for RoundLimitExceeded {}
+
+/// Whether an origin is a placeholder or an existential origin, see `Output::origin_kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OriginKind {
+    /// An origin of the function's signature, like a lifetime parameter: a `universal_region`.
+    /// It's live everywhere, and only the subsets between placeholders which are known to hold
+    /// are allowed.
+    Placeholder,
+
+    /// Any other origin, inferred from the constraints of the function's body.
+    Existential,
+}
+
 #[derive(Clone, Debug)]
 pub struct Output<T: FactTypes> {
     pub errors: FxHashMap<T::Point, Vec<T::Loan>>,
     pub subset_errors: FxHashMap<T::Point, BTreeSet<(T::Origin, T::Origin)>>,
     pub move_errors: FxHashMap<T::Point, Vec<T::Path>>,
 
+    /// The kind of each of the `move_errors`, at the same point.
+    pub move_error_kinds: FxHashMap<T::Point, BTreeMap<T::Path, MoveErrorKind<T>>>,
+
     pub dump_enabled: bool,
 
-    // these are just for debugging
+    /// Whether the computation stopped before borrow checking, because of move errors (see
+    /// `MoveErrorPolicy::Stop`): the output then only contains the move errors.
+    pub partial: bool,
+
+    /// Why the computation was aborted, if an iteration exceeded `ComputeOptions::max_rounds`:
+    /// the output then has no loan or subset errors, only the move errors found before the
+    /// aborted iteration, if any.
+    pub aborted: Option<RoundLimitExceeded>,
+
+    /// The loans in scope at each point, i.e. contained in a live origin, unless
+    /// `ComputeOptions::skip_loan_live_at`, see `Output::loans_live_at`. The
+    /// `LocationInsensitive` variant only computes an over-approximation: the loans which may be
+    /// contained in the origins live at each point. The `Hybrid` variant also does for the loans
+    /// its pre-pass finds no potential errors for.
     pub loan_live_at: FxHashMap<T::Point, Vec<T::Loan>>,
+
+    /// The points where each loan may be invalidated while it's in scope, found by the
+    /// location-insensitive analysis of the `LocationInsensitive` variant and of the `Hybrid`
+    /// variant's pre-pass: the loans which can be errors, in the order of the points, the first
+    /// one being the earliest invalidation. With `PointOrder::ReversePostOrder`, they are in the
+    /// order of the CFG. It's empty for the other variants.
+    pub potential_errors: FxHashMap<T::Loan, Vec<T::Point>>,
+
+    /// The scope of each loan, with `ComputeOptions::scopes`: the points where it's in
+    /// `loan_live_at`, to compare polonius' scopes with lexical lifetimes and NLL's scopes. They
+    /// are empty with `ComputeOptions::skip_loan_live_at`.
+    pub loan_scopes: FxHashMap<T::Loan, BTreeSet<T::Point>>,
+
+    /// The live range of each origin, with `ComputeOptions::scopes`: the points where it's live
+    /// on entry, in `origin_live_on_entry`.
+    pub origin_live_ranges: FxHashMap<T::Origin, BTreeSet<T::Point>>,
+
+    /// The points where each loan goes out of scope, with `ComputeOptions::loans_out_of_scope`:
+    /// the first points reachable from where it's issued, without going through a point where
+    /// it's killed, at which it isn't in `loan_live_at`. This is the map of the loans going out
+    /// of scope at each location which rustc's location-sensitive `-Zpolonius=next` borrow
+    /// checker kills them with, so that this engine can serve as its oracle. It's empty with
+    /// `ComputeOptions::skip_loan_live_at`.
+    pub loans_out_of_scope_at: FxHashMap<T::Point, Vec<T::Loan>>,
+
+    // these are just for debugging
     pub origin_contains_loan_at: FxHashMap<T::Point, BTreeMap<T::Origin, BTreeSet<T::Loan>>>,
     pub origin_contains_loan_anywhere: FxHashMap<T::Origin, BTreeSet<T::Loan>>,
     pub origin_live_on_entry: FxHashMap<T::Point, Vec<T::Origin>>,
@@ -91,10 +322,256 @@ pub struct Output<T: FactTypes> {
     pub subset_anywhere: FxHashMap<T::Origin, BTreeSet<T::Origin>>,
     pub var_live_on_entry: FxHashMap<T::Point, Vec<T::Variable>>,
     pub var_drop_live_on_entry: FxHashMap<T::Point, Vec<T::Variable>>,
+    pub var_dropped_while_init_at: FxHashMap<T::Point, Vec<T::Variable>>,
     pub path_maybe_initialized_on_exit: FxHashMap<T::Point, Vec<T::Path>>,
     pub path_maybe_uninitialized_on_exit: FxHashMap<T::Point, Vec<T::Path>>,
+    pub path_definitely_initialized_on_exit: FxHashMap<T::Point, Vec<T::Path>>,
+    pub path_moved_at: FxHashMap<T::Point, Vec<T::Path>>,
+    pub path_assigned_at: FxHashMap<T::Point, Vec<T::Path>>,
+    pub path_accessed_at: FxHashMap<T::Point, Vec<T::Path>>,
     pub known_contains: FxHashMap<T::Origin, BTreeSet<T::Loan>>,
     pub var_maybe_partly_initialized_on_exit: FxHashMap<T::Point, Vec<T::Variable>>,
+
+    /// The sizes reached by the intermediate relations of the `DatafrogOpt` variant.
+    pub relation_sizes: Option<RelationSizes>,
+
+    /// The subsets between placeholder origins known to hold, see `KnownSubsets`: the
+    /// `known_placeholder_subset` facts, closed transitively by default. It can be used to find
+    /// the subset errors which are only consequences of another one, and a known subset.
+    pub known_placeholder_subset: FxHashMap<T::Origin, BTreeSet<T::Origin>>,
+
+    /// The facts explaining each of the `subset_errors`, at the same point.
+    pub subset_error_witnesses: SubsetErrorWitnesses<T>,
+
+    /// The kind of each origin of the facts, from which the variants find the placeholders.
+    pub origin_kind: FxHashMap<T::Origin, OriginKind>,
+
+    /// The differences between the errors of the `Naive` and `DatafrogOpt` variants, computed by
+    /// the `Compare` variant: the `removed` errors are only found by `Naive`, and the `added`
+    /// ones only by `DatafrogOpt`.
+    pub comparison: Option<ErrorsDiff<T>>,
+}
+
+/// Why a path accessed by a move error may be uninitialized, see `Output::move_error_kinds`.
+#[derive(Clone, Debug)]
+pub enum MoveErrorKind<T: FactTypes> {
+    /// The path may have never been initialized: it's only moved out at the entry of the CFG, on
+    /// the way to the error.
+    Uninitialized,
+
+    /// The path may have been moved out: these are its last moves on the way to the error,
+    /// without the ones at the entry of the CFG.
+    MovedOut { moved_at: Vec<T::Point> },
+}
+
+/// The facts explaining how a subset error `(origin1, origin2)` was created, see
+/// `Output::subset_error_witnesses`.
+#[derive(Clone, Debug)]
+pub struct SubsetErrorWitness<T: FactTypes> {
+    /// A shortest chain of `subset_base` facts making `origin1` flow into `origin2`, at points
+    /// from which the error's point can be reached.
+    pub subsets: Vec<(T::Origin, T::Origin, T::Point)>,
+
+    /// The loans issued in the origins of the chain, at points from which the error's point can
+    /// be reached.
+    pub loans: Vec<(T::Origin, T::Loan, T::Point)>,
+}
+
+/// The subset errors at each point: the pairs of placeholder origins `(origin1, origin2)` where
+/// `origin1` flows into `origin2` without this subset being known to hold.
+type SubsetErrors<T> = FxHashMap<
+    <T as FactTypes>::Point,
+    BTreeSet<(<T as FactTypes>::Origin, <T as FactTypes>::Origin)>,
+>;
+
+/// The witnesses of the subset errors at each point, by error.
+type SubsetErrorWitnesses<T> = FxHashMap<
+    <T as FactTypes>::Point,
+    BTreeMap<(<T as FactTypes>::Origin, <T as FactTypes>::Origin), SubsetErrorWitness<T>>,
+>;
+
+/// Only the errors found by the analysis, without the debugging data an `Output` can carry, see
+/// `Output::errors_only`.
+#[derive(Clone, Debug)]
+pub struct Errors<T: FactTypes> {
+    pub errors: FxHashMap<T::Point, Vec<T::Loan>>,
+    pub subset_errors: SubsetErrors<T>,
+    pub move_errors: FxHashMap<T::Point, Vec<T::Path>>,
+}
+
+/// The liveness of variables and origins, independently of borrow checking, see
+/// `Output::liveness`.
+#[derive(Clone, Debug)]
+pub struct LivenessOutput<T: FactTypes> {
+    pub var_live_on_entry: FxHashMap<T::Point, Vec<T::Variable>>,
+    pub var_drop_live_on_entry: FxHashMap<T::Point, Vec<T::Variable>>,
+
+    /// The drops of variables which may be initialized, the only ones making variables drop-live.
+    pub var_dropped_while_init_at: FxHashMap<T::Point, Vec<T::Variable>>,
+
+    /// The live origins, including the universal regions.
+    pub origin_live_on_entry: FxHashMap<T::Point, Vec<T::Origin>>,
+}
+
+/// The move errors, and the initialization of paths and variables, independently of liveness
+/// and borrow checking, see `Output::initialization`.
+#[derive(Clone, Debug)]
+pub struct InitializationOutput<T: FactTypes> {
+    pub move_errors: FxHashMap<T::Point, Vec<T::Path>>,
+    pub move_error_kinds: FxHashMap<T::Point, BTreeMap<T::Path, MoveErrorKind<T>>>,
+    pub path_maybe_initialized_on_exit: FxHashMap<T::Point, Vec<T::Path>>,
+    pub path_maybe_uninitialized_on_exit: FxHashMap<T::Point, Vec<T::Path>>,
+    pub var_maybe_partly_initialized_on_exit: FxHashMap<T::Point, Vec<T::Variable>>,
+}
+
+/// The sizes reached by the intermediate relations of a computation, to investigate
+/// tuple explosions.
+#[derive(Clone, Debug, Default)]
+pub struct RelationSizes {
+    /// The number of rounds until the computation reached its fixpoint.
+    pub rounds: usize,
+
+    /// The peak size of each relation, by name.
+    pub peaks: BTreeMap<&'static str, PeakSize>,
+}
+
+/// The largest number of tuples a relation contained, see `RelationSizes`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeakSize {
+    pub tuples: usize,
+
+    /// The first round where the relation contained this many tuples.
+    pub round: usize,
+}
+
+impl RelationSizes {
+    /// Starts recording the sizes of a new round.
+    fn start_round(&mut self) {
+        self.rounds += 1;
+    }
+
+    /// Records the current size of the `variable`, and updates its peak size.
+    fn record<Tuple: Ord>(&mut self, name: &'static str, variable: &Variable<Tuple>) {
+        let tuples = variable_len(variable);
+        let round = self.rounds;
+        let peak = self.peaks.entry(name).or_default();
+        if tuples > peak.tuples {
+            *peak = PeakSize { tuples, round };
+        }
+    }
+}
+
+/// Computes only the liveness of origins, `origin_live_on_entry(Origin, Point)`, for consumers
+/// which don't need to borrow check the facts. This runs the initialization of variables, which
+/// liveness depends on, and the liveness computation: the universal regions are live everywhere,
+/// or at the points of `universal_region_live_at`, as in the full computation. Unlike
+/// `Output::liveness`, nothing else is recorded nor returned. A precomputed
+/// `origin_live_on_entry` covering the points of the facts is used as is.
+pub fn compute_origin_liveness<T: FactTypes>(
+    all_facts: &AllFacts<T>,
+) -> Relation<(T::Origin, T::Point)> {
+    let _computation = info_span!("compute_origin_liveness").entered();
+    let options = ComputeOptions::default();
+    let mut result = Output::new(false);
+    let cfg = Cfg::new(all_facts.cfg_edge.clone().into());
+
+    let initialization::InitializationResult::<T>(var_maybe_partly_initialized_on_exit, _) =
+        Output::compute_initialization::<DatafrogJoins>(
+            all_facts,
+            &cfg,
+            &options,
+            &mut result,
+            false,
+        );
+
+    let origin_kind = Output::<T>::compute_origin_kind(all_facts);
+    let placeholder_origins = Output::<T>::placeholder_origins(&origin_kind);
+    let origin_live_on_entry = Output::compute_liveness::<DatafrogJoins>(
+        all_facts,
+        &cfg,
+        var_maybe_partly_initialized_on_exit,
+        &placeholder_origins,
+        &options,
+        &mut result,
+        false,
+    );
+    Relation::from_vec(origin_live_on_entry)
+}
+
+/// Records the `loan_live_at` tuples of a variant in the `result`.
+fn record_loan_live_at<T: FactTypes>(
+    loan_live_at: impl IntoIterator<Item = (T::Loan, T::Point)>,
+    result: &mut Output<T>,
+) {
+    for (loan, location) in loan_live_at {
+        result.loan_live_at.entry(location).or_default().push(loan);
+    }
+}
+
+/// Checks that the fixpoint `iteration` of the `phase` can start this `round` within the
+/// `max_rounds`, if any. Otherwise, the iteration must be aborted: the error has the sizes of its
+/// `variables`, to diagnose why it doesn't converge, and is also logged. The tuples of the last
+/// round are then settled, so that the variables can still be completed.
+fn check_round_limit(
+    max_rounds: Option<usize>,
+    phase: &'static str,
+    round: usize,
+    iteration: &mut Iteration,
+    variables: impl FnOnce() -> Vec<VariableSize>,
+) -> Result<(), RoundLimitExceeded> {
+    match max_rounds {
+        Some(max_rounds) if round > max_rounds => {
+            let error = RoundLimitExceeded {
+                phase,
+                max_rounds,
+                variables: variables(),
+            };
+            error!("{}", error);
+            while iteration.changed() {}
+            Err(error)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Starts a `phase` of the computation: reports it to the `progress` hook, if any, and enters
+/// its `phase` span, until the returned guard is dropped.
+fn report_phase(progress: Option<&ProgressHook>, phase: &'static str) -> EnteredSpan {
+    if let Some(progress) = progress {
+        progress.report(ProgressEvent::Phase(phase));
+    }
+    info_span!("phase", phase).entered()
+}
+
+/// Starts a `round` of the fixpoint iteration of the current phase: reports it to the `progress`
+/// hook, if any, and enters its `round` span, until the returned guard is dropped. The number of
+/// `tuples` found by the previous rounds is only counted when there is a hook, or when the span
+/// is enabled, to be recorded in its `tuples` field.
+fn report_round(
+    progress: Option<&ProgressHook>,
+    round: usize,
+    tuples: impl FnOnce() -> usize,
+) -> EnteredSpan {
+    let span = debug_span!("round", round, tuples = field::Empty);
+    if progress.is_some() || !span.is_disabled() {
+        let tuples = tuples();
+        span.record("tuples", tuples);
+        if let Some(progress) = progress {
+            progress.report(ProgressEvent::Round { round, tuples });
+        }
+    }
+    span.entered()
+}
+
+/// The number of tuples of the `variable`: its stable and recent tuples.
+fn variable_len<Tuple: Ord>(variable: &Variable<Tuple>) -> usize {
+    let stable: usize = variable
+        .stable
+        .borrow()
+        .iter()
+        .map(|batch| batch.len())
+        .sum();
+    stable + variable.recent.borrow().len()
 }
 
 /// Subset of `AllFacts` dedicated to initialization
@@ -104,17 +581,30 @@ struct InitializationContext<T: FactTypes> {
     path_assigned_at_base: Vec<(T::Path, T::Point)>,
     path_moved_at_base: Vec<(T::Path, T::Point)>,
     path_accessed_at_base: Vec<(T::Path, T::Point)>,
+    progress: Option<ProgressHook>,
+    max_rounds: Option<usize>,
 }
 
 /// Subset of `AllFacts` dedicated to liveness
 struct LivenessContext<T: FactTypes> {
     var_used_at: Vec<(T::Variable, T::Point)>,
     var_defined_at: Vec<(T::Variable, T::Point)>,
-    var_dropped_at: Vec<(T::Variable, T::Point)>,
     use_of_var_derefs_origin: Vec<(T::Variable, T::Origin)>,
     drop_of_var_derefs_origin: Vec<(T::Variable, T::Origin)>,
+    progress: Option<ProgressHook>,
+    max_rounds: Option<usize>,
 }
 
+/// A shallow kill of a loan in one of the origins it was issued with: `((Origin, Loan), Point)`.
+type ShallowKill<T> = (
+    (<T as FactTypes>::Origin, <T as FactTypes>::Loan),
+    <T as FactTypes>::Point,
+);
+
+/// A relation keyed by an atom and a point, for the joins of the variants: e.g. `loan_live_at`
+/// as `((Loan, Point), ())`.
+type PointKeyed<T, A, V> = Relation<((A, <T as FactTypes>::Point), V)>;
+
 /// Subset of `AllFacts` dedicated to borrow checking, and data ready to use by the variants
 struct Context<'ctx, T: FactTypes> {
     // `Relation`s used as static inputs, by all variants
@@ -127,7 +617,14 @@ struct Context<'ctx, T: FactTypes> {
 
     // static inputs used by variants other than `LocationInsensitive`
     loan_killed_at: Relation<(T::Loan, T::Point)>,
+
+    // The `loan_shallow_killed_at` facts, keyed by the origins the loans were issued with, where
+    // they are killed.
+    loan_shallow_killed_at: Relation<ShallowKill<T>>,
     known_contains: Relation<(T::Origin, T::Loan)>,
+    origin_kind: FxHashMap<T::Origin, OriginKind>,
+
+    // The origins of kind `Placeholder`, to join with.
     placeholder_origin: Relation<(T::Origin, ())>,
     placeholder_loan: Relation<(T::Loan, T::Origin)>,
 
@@ -138,16 +635,124 @@ struct Context<'ctx, T: FactTypes> {
 
     // while this static input is unused by `LocationInsensitive`, it's depended on by
     // initialization and liveness, so already computed by the time we get to borrowcking.
-    cfg_edge: Relation<(T::Point, T::Point)>,
+    cfg: Cfg<T>,
 
-    // Partial results possibly used by other variants as input. Not currently used yet.
-    #[allow(dead_code)]
-    potential_errors: Option<FxHashSet<T::Loan>>,
-    #[allow(dead_code)]
+    // The potential subset errors found by the `Hybrid` pre-pass, restricting the placeholders
+    // the `DatafrogOpt` variant checks for subset errors.
     potential_subset_errors: Option<Relation<(T::Origin, T::Origin)>>,
+
+    // Indices of the static inputs, built once and shared by the variants using the same context
+    // like the ones `Compare` runs.
+    indices: Indices<T>,
+
+    // The hook receiving the progress of the variants' iterations.
+    progress: Option<ProgressHook>,
+
+    // The maximum number of rounds of the variants' iterations.
+    max_rounds: Option<usize>,
+
+    // Whether the variants record the `loan_live_at` output.
+    record_loan_live_at: bool,
+}
+
+/// The static inputs indexed by different keys, ready for the joins of the variants which need
+/// them. Each index is only built on first use.
+struct Indices<T: FactTypes> {
+    origin_live_on_entry_op: OnceLock<PointKeyed<T, T::Origin, ()>>,
+    loan_invalidated_at_lp: OnceLock<PointKeyed<T, T::Loan, ()>>,
+    subset_base_o1p: OnceLock<PointKeyed<T, T::Origin, T::Origin>>,
+    loan_issued_at_op: OnceLock<PointKeyed<T, T::Origin, T::Loan>>,
+}
+
+impl<T: FactTypes> Default for Indices<T> {
+    fn default() -> Self {
+        Indices {
+            origin_live_on_entry_op: OnceLock::new(),
+            loan_invalidated_at_lp: OnceLock::new(),
+            subset_base_o1p: OnceLock::new(),
+            loan_issued_at_op: OnceLock::new(),
+        }
+    }
+}
+
+impl<T: FactTypes> Context<'_, T> {
+    /// Starts the borrow checking `phase`, see `report_phase`.
+    fn report_phase(&self, phase: &'static str) -> EnteredSpan {
+        report_phase(self.progress.as_ref(), phase)
+    }
+
+    /// Checks that the iteration of the borrow checking `phase` can start this `round`, see
+    /// `check_round_limit`.
+    fn check_round_limit(
+        &self,
+        phase: &'static str,
+        round: usize,
+        iteration: &mut Iteration,
+        variables: impl FnOnce() -> Vec<VariableSize>,
+    ) -> Result<(), RoundLimitExceeded> {
+        check_round_limit(self.max_rounds, phase, round, iteration, variables)
+    }
+
+    /// Starts a `round` of the current phase, see `report_round`.
+    fn report_round(&self, round: usize, tuples: impl FnOnce() -> usize) -> EnteredSpan {
+        report_round(self.progress.as_ref(), round, tuples)
+    }
+
+    /// `origin_live_on_entry`, indexed by `(Origin, Point)`.
+    fn origin_live_on_entry_op(&self) -> &PointKeyed<T, T::Origin, ()> {
+        self.indices.origin_live_on_entry_op.get_or_init(|| {
+            Relation::from_iter(
+                self.origin_live_on_entry
+                    .iter()
+                    .map(|&(origin, point)| ((origin, point), ())),
+            )
+        })
+    }
+
+    /// `loan_invalidated_at`, indexed by `(Loan, Point)`.
+    fn loan_invalidated_at_lp(&self) -> &PointKeyed<T, T::Loan, ()> {
+        self.indices.loan_invalidated_at_lp.get_or_init(|| {
+            Relation::from_iter(
+                self.loan_invalidated_at
+                    .iter()
+                    .map(|&(loan, point)| ((loan, point), ())),
+            )
+        })
+    }
+
+    /// `subset_base`, indexed by `(Origin1, Point)`.
+    fn subset_base_o1p(&self) -> &PointKeyed<T, T::Origin, T::Origin> {
+        self.indices.subset_base_o1p.get_or_init(|| {
+            Relation::from_iter(
+                self.subset_base
+                    .iter()
+                    .map(|&(origin1, origin2, point)| ((origin1, point), origin2)),
+            )
+        })
+    }
+
+    /// `loan_issued_at`, indexed by `(Origin, Point)`.
+    fn loan_issued_at_op(&self) -> &PointKeyed<T, T::Origin, T::Loan> {
+        self.indices.loan_issued_at_op.get_or_init(|| {
+            Relation::from_iter(
+                self.loan_issued_at
+                    .iter()
+                    .map(|&(origin, loan, point)| ((origin, point), loan)),
+            )
+        })
+    }
 }
 
 impl<T: FactTypes> Output<T> {
+    /// Computes the analysis with the default `ComputeOptions`, see `compute_with_options`.
+    pub fn compute(all_facts: &AllFacts<T>, algorithm: Algorithm, dump_enabled: bool) -> Self {
+        let options = ComputeOptions {
+            dump_enabled,
+            ..ComputeOptions::default()
+        };
+        Output::compute_with_options(all_facts, algorithm, &options)
+    }
+
     /// All variants require the same initial preparations, done in multiple
     /// successive steps:
     /// - compute initialization data
@@ -156,63 +761,138 @@ impl<T: FactTypes> Output<T> {
     /// - in cases where `LocationInsensitive` variant is ran as a filtering pre-pass,
     ///   partial results can also be stored in the context, so that the following
     ///   variant can use it to prune its own input data
-    pub fn compute(all_facts: &AllFacts<T>, algorithm: Algorithm, dump_enabled: bool) -> Self {
+    ///
+    /// The computation has no state outside of its arguments: the facts and outputs are `Send`
+    /// and `Sync`, and different facts can be analyzed concurrently on different threads.
+    pub fn compute_with_options(
+        all_facts: &AllFacts<T>,
+        algorithm: Algorithm,
+        options: &ComputeOptions,
+    ) -> Self {
+        Output::compute_with_backend::<DatafrogJoins>(all_facts, algorithm, options)
+    }
+
+    /// Computes the analysis like `compute_with_options`, on the facts modified by the
+    /// `transforms`, applied in order, e.g. to experiment with synthetic facts.
+    pub fn compute_with_transforms(
+        all_facts: &AllFacts<T>,
+        algorithm: Algorithm,
+        options: &ComputeOptions,
+        transforms: &[&dyn FactTransform<T>],
+    ) -> Self {
+        if transforms.is_empty() {
+            return Output::compute_with_options(all_facts, algorithm, options);
+        }
+
+        let mut transformed_facts = all_facts.clone();
+        for transform in transforms {
+            transform.transform(&mut transformed_facts);
+        }
+        Output::compute_with_options(&transformed_facts, algorithm, options)
+    }
+
+    /// Computes the analysis like `compute_with_options`, with the joins of the variants' rules
+    /// done by the `B` backend instead of datafrog's, e.g. to experiment with other join
+    /// implementations.
+    pub fn compute_with_backend<B: JoinBackend>(
+        all_facts: &AllFacts<T>,
+        algorithm: Algorithm,
+        options: &ComputeOptions,
+    ) -> Self {
+        if options.point_order == PointOrder::ReversePostOrder {
+            let mut renumbering = PointRenumbering::new(all_facts);
+            let all_facts = renumbering.renumber_facts(all_facts);
+            let options = ComputeOptions {
+                point_order: PointOrder::Input,
+                ..options.clone()
+            };
+            let mut result = Output::compute_with_backend::<B>(&all_facts, algorithm, &options);
+            renumbering.restore_output(&mut result);
+            return result;
+        }
+
+        let _computation = info_span!("compute", ?algorithm).entered();
+        let dump_enabled = options.dump_enabled;
         let mut result = Output::new(dump_enabled);
 
         // TODO: remove all the cloning thereafter, but that needs to be done in concert with rustc
 
-        let cfg_edge = all_facts.cfg_edge.clone().into();
+        let cfg = Cfg::new(all_facts.cfg_edge.clone().into());
 
         // 1) Initialization
-        let initialization_ctx = InitializationContext {
-            child_path: all_facts.child_path.clone(),
-            path_is_var: all_facts.path_is_var.clone(),
-            path_assigned_at_base: all_facts.path_assigned_at_base.clone(),
-            path_moved_at_base: all_facts.path_moved_at_base.clone(),
-            path_accessed_at_base: all_facts.path_accessed_at_base.clone(),
-        };
-
+        let progress = options.progress.as_ref();
+        let initialization_phase = report_phase(progress, "initialization");
+        let initialization_only = matches!(algorithm, Algorithm::InitializationOnly);
         let initialization::InitializationResult::<T>(
             var_maybe_partly_initialized_on_exit,
             move_errors,
-        ) = initialization::compute(initialization_ctx, &cfg_edge, &mut result);
+        ) = Output::compute_initialization::<B>(
+            all_facts,
+            &cfg,
+            options,
+            &mut result,
+            dump_enabled || initialization_only,
+        );
+        if result.aborted.is_some() {
+            return result;
+        }
 
-        // FIXME: move errors should prevent the computation from continuing: we can't compute
-        // liveness and analyze loans accurately when there are move errors, and should early
-        // return here.
         for &(path, location) in move_errors.iter() {
             result.move_errors.entry(location).or_default().push(path);
         }
 
-        // 2) Liveness
-        let liveness_ctx = LivenessContext {
-            var_used_at: all_facts.var_used_at.clone(),
-            var_defined_at: all_facts.var_defined_at.clone(),
-            var_dropped_at: all_facts.var_dropped_at.clone(),
-            use_of_var_derefs_origin: all_facts.use_of_var_derefs_origin.clone(),
-            drop_of_var_derefs_origin: all_facts.drop_of_var_derefs_origin.clone(),
-        };
+        if initialization_only {
+            return result;
+        }
 
-        let mut origin_live_on_entry = liveness::compute_live_origins(
-            liveness_ctx,
-            &cfg_edge,
+        // We can't compute liveness and analyze loans accurately when there are move errors:
+        // depending on the caller's policy, we can early return here.
+        if !move_errors.is_empty() && options.move_error_policy == MoveErrorPolicy::Stop {
+            info!(
+                move_errors = move_errors.len(),
+                "stopping after move errors"
+            );
+            result.partial = true;
+            return result;
+        }
+
+        // 2) Liveness
+        drop(initialization_phase);
+        let liveness_phase = report_phase(progress, "liveness");
+        let origin_kind = Output::<T>::compute_origin_kind(all_facts);
+        let placeholder_origins = Output::<T>::placeholder_origins(&origin_kind);
+
+        let liveness_only = matches!(algorithm, Algorithm::LivenessOnly);
+        let origin_live_on_entry = Output::compute_liveness::<B>(
+            all_facts,
+            &cfg,
             var_maybe_partly_initialized_on_exit,
+            &placeholder_origins,
+            options,
             &mut result,
+            dump_enabled || liveness_only,
         );
+        if result.aborted.is_some() {
+            return result;
+        }
 
-        let cfg_node = cfg_edge
-            .iter()
-            .map(|&(point1, _)| point1)
-            .chain(cfg_edge.iter().map(|&(_, point2)| point2))
-            .collect();
-
-        liveness::make_universal_regions_live::<T>(
-            &mut origin_live_on_entry,
-            &cfg_node,
-            &all_facts.universal_region,
-        );
+        if liveness_only {
+            let origin_live_on_entry = Relation::from_vec(origin_live_on_entry);
+            for &(origin, location) in origin_live_on_entry.iter() {
+                result
+                    .origin_live_on_entry
+                    .entry(location)
+                    .or_default()
+                    .push(origin);
+            }
+            if options.scopes {
+                result.record_scopes(&origin_live_on_entry);
+            }
+            return result;
+        }
 
         // 3) Borrow checking
+        drop(liveness_phase);
 
         // Prepare data as datafrog relations, ready to join.
         //
@@ -226,37 +906,67 @@ impl<T: FactTypes> Output<T> {
 
         let origin_live_on_entry = origin_live_on_entry.into();
 
-        // TODO: also flip the order of this relation's arguments in rustc
-        // from `loan_invalidated_at(point, loan)` to `loan_invalidated_at(loan, point)`.
-        // to avoid this allocation.
-        let loan_invalidated_at = Relation::from_iter(
-            all_facts
-                .loan_invalidated_at
-                .iter()
-                .map(|&(point, loan)| (loan, point)),
-        );
+        let loan_invalidated_at = if options.two_phase_borrows {
+            Output::<T>::compute_active_loan_invalidated_at::<B>(all_facts, &cfg)
+        } else {
+            all_facts.loan_invalidated_at.clone().into()
+        };
 
         let loan_killed_at = all_facts.loan_killed_at.clone().into();
 
+        // loan_shallow_killed_at((Origin, Loan), Point) :-
+        //   loan_shallow_killed_at(Loan, Point),
+        //   loan_issued_at(Origin, Loan, _).
+        let loan_shallow_killed_at = {
+            let loan_shallow_killed_at: Relation<_> =
+                all_facts.loan_shallow_killed_at.clone().into();
+            let loan_issued_at = Relation::from_iter(
+                all_facts
+                    .loan_issued_at
+                    .iter()
+                    .map(|&(origin, loan, _point)| (loan, origin)),
+            );
+            B::join_relations(
+                &loan_shallow_killed_at,
+                &loan_issued_at,
+                |&loan, &point, &origin| ((origin, loan), point),
+            )
+        };
+
         // `known_placeholder_subset` is a list of all the `'a: 'b` subset relations the user gave:
         // it's not required to be transitive. `known_contains` is its transitive closure: a list
         // of all the known placeholder loans that each of these placeholder origins contains.
         // Given the `known_placeholder_subset`s `'a: 'b` and `'b: 'c`: in the `known_contains`
         // relation, `'a` will also contain `'c`'s placeholder loan.
         let known_placeholder_subset = all_facts.known_placeholder_subset.clone().into();
-        let known_contains =
-            Output::<T>::compute_known_contains(&known_placeholder_subset, &all_facts.placeholder);
+        let (known_contains, known_placeholder_subset) = match options.known_subsets {
+            KnownSubsets::Transitive => {
+                let known_contains = Output::<T>::compute_known_contains::<B>(
+                    &known_placeholder_subset,
+                    &all_facts.placeholder,
+                );
 
-        // Fully close over the `known_placeholder_subset` relation.
-        let known_placeholder_subset =
-            Output::<T>::compute_known_placeholder_subset(&known_placeholder_subset);
+                // Fully close over the `known_placeholder_subset` relation.
+                let known_placeholder_subset =
+                    Output::<T>::compute_known_placeholder_subset::<B>(&known_placeholder_subset);
+                (known_contains, known_placeholder_subset)
+            }
+            KnownSubsets::AsGiven => {
+                // known_contains(Origin2, Loan1) :-
+                //   placeholder(Origin1, Loan1),
+                //   known_placeholder_subset(Origin1, Origin2).
+                let placeholder: Relation<_> = all_facts.placeholder.clone().into();
+                let known_contains = B::join_relations(
+                    &placeholder,
+                    &known_placeholder_subset,
+                    |&_origin1, &loan1, &origin2| (origin2, loan1),
+                );
+                (known_contains.merge(placeholder), known_placeholder_subset)
+            }
+        };
 
-        let placeholder_origin: Relation<_> = Relation::from_iter(
-            all_facts
-                .universal_region
-                .iter()
-                .map(|&origin| (origin, ())),
-        );
+        let placeholder_origin: Relation<_> =
+            Relation::from_iter(placeholder_origins.iter().map(|&origin| (origin, ())));
 
         let placeholder_loan = Relation::from_iter(
             all_facts
@@ -265,69 +975,125 @@ impl<T: FactTypes> Output<T> {
                 .map(|&(origin, loan)| (loan, origin)),
         );
 
+        // The `Hybrid` variant restricts the input loans to the ones the pre-pass found
+        // potential errors for: this sliced input needs to outlive the context borrowing it.
+        let potential_loan_issued_at;
+
         // Ask the variants to compute errors in their own way
         let mut ctx = Context {
             origin_live_on_entry,
             loan_invalidated_at,
-            cfg_edge,
+            cfg,
             subset_base: &all_facts.subset_base,
             loan_issued_at: &all_facts.loan_issued_at,
             loan_killed_at,
+            loan_shallow_killed_at,
             known_contains,
             known_placeholder_subset,
+            origin_kind,
             placeholder_origin,
             placeholder_loan,
-            potential_errors: None,
             potential_subset_errors: None,
+            indices: Indices::default(),
+            progress: options.progress.clone(),
+            max_rounds: options.max_rounds,
+            record_loan_live_at: !options.skip_loan_live_at,
         };
 
         let (errors, subset_errors) = match algorithm {
             Algorithm::LocationInsensitive => {
-                let (potential_errors, potential_subset_errors) =
-                    location_insensitive::compute(&ctx, &mut result);
-
-                // Note: the error location is meaningless for a location-insensitive
-                // subset error analysis. This is acceptable here as this variant is not one
-                // which should be used directly besides debugging, the `Hybrid` variant will
-                // take advantage of its result.
-                let potential_subset_errors: Relation<(T::Origin, T::Origin, T::Point)> =
-                    Relation::from_iter(
-                        potential_subset_errors
-                            .into_iter()
-                            .map(|&(origin1, origin2)| (origin1, origin2, 0.into())),
-                    );
-
-                (potential_errors, potential_subset_errors)
+                // Note: the subset errors are located at a representative point, where the
+                // subsets making the error hold, rather than at every point where they do.
+                let _phase = ctx.report_phase("LocationInsensitive");
+                location_insensitive::compute::<T, B>(&ctx, &mut result)
+            }
+            Algorithm::LivenessOnly | Algorithm::InitializationOnly => {
+                unreachable!("{:?} doesn't borrow check the facts", algorithm)
+            }
+            Algorithm::Naive => {
+                let _phase = ctx.report_phase("Naive");
+                naive::compute::<T, B>(&ctx, &mut result)
+            }
+            Algorithm::DatafrogOpt => {
+                let _phase = ctx.report_phase("DatafrogOpt");
+                datafrog_opt::compute::<T, B>(&ctx, &mut result)
+            }
+            Algorithm::ParallelNaive => {
+                let _phase = ctx.report_phase("ParallelNaive");
+                parallel_naive::compute::<T, B>(&ctx, &mut result)
             }
-            Algorithm::Naive => naive::compute(&ctx, &mut result),
-            Algorithm::DatafrogOpt => datafrog_opt::compute(&ctx, &mut result),
             Algorithm::Hybrid => {
                 // Execute the fast `LocationInsensitive` computation as a pre-pass:
                 // if it finds no possible errors, we don't need to do the more complex
                 // computations as they won't find errors either, and we can return early.
+                let pre_pass_phase = ctx.report_phase("LocationInsensitive");
                 let (potential_errors, potential_subset_errors) =
-                    location_insensitive::compute(&ctx, &mut result);
+                    location_insensitive::compute::<T, B>(&ctx, &mut result);
+                drop(pre_pass_phase);
+                if result.aborted.is_some() {
+                    return result;
+                }
 
                 if potential_errors.is_empty() && potential_subset_errors.is_empty() {
                     // There are no loan errors, nor subset errors, we can early return
                     // empty errors lists and avoid doing the heavy analysis.
                     (potential_errors, Vec::new().into())
                 } else {
-                    // Record these potential errors as they can be used to limit the next
-                    // variant's work to only these loans.
-                    ctx.potential_errors =
-                        Some(potential_errors.iter().map(|&(loan, _)| loan).collect());
-                    ctx.potential_subset_errors = Some(potential_subset_errors);
+                    // Limit the next variant's work to only the loans with potential errors:
+                    // the others can't be errors, as `LocationInsensitive` has no
+                    // false-negatives. The placeholder loans are kept, for the subset errors.
+                    let loans: FxHashSet<T::Loan> =
+                        result.potential_errors.keys().cloned().collect();
+
+                    // The pre-pass' approximate scopes of these loans are replaced by the ones
+                    // of the next variant.
+                    for live_loans in result.loan_live_at.values_mut() {
+                        live_loans.retain(|loan| !loans.contains(loan));
+                    }
+
+                    potential_loan_issued_at =
+                        slice_loans(ctx.loan_issued_at, &loans, |&(_, loan, _)| loan);
+                    ctx.loan_issued_at = &potential_loan_issued_at;
+                    ctx.loan_killed_at =
+                        slice_loans(&ctx.loan_killed_at, &loans, |&(loan, _)| loan).into();
+                    ctx.loan_shallow_killed_at =
+                        slice_loans(&ctx.loan_shallow_killed_at, &loans, |&((_, loan), _)| loan)
+                            .into();
+                    ctx.loan_invalidated_at =
+                        slice_loans(&ctx.loan_invalidated_at, &loans, |&(loan, _)| loan).into();
+                    ctx.indices = Indices::default();
+
+                    // Record these potential errors to limit the next variant's work to only
+                    // these origins.
+                    ctx.potential_subset_errors = Some(Relation::from_iter(
+                        potential_subset_errors
+                            .iter()
+                            .map(|&(origin1, origin2, _point)| (origin1, origin2)),
+                    ));
 
-                    datafrog_opt::compute(&ctx, &mut result)
+                    let _phase = ctx.report_phase("DatafrogOpt");
+                    let errors = datafrog_opt::compute::<T, B>(&ctx, &mut result);
+                    for live_loans in result.loan_live_at.values_mut() {
+                        live_loans.sort();
+                        live_loans.dedup();
+                    }
+                    errors
                 }
             }
             Algorithm::Compare => {
-                // Ensure the `Naive` and `DatafrogOpt` errors are the same
-                let (naive_errors, naive_subset_errors) = naive::compute(&ctx, &mut result);
-                let (opt_errors, _) = datafrog_opt::compute(&ctx, &mut result);
-
-                // TODO: compare illegal subset relations errors as well here ?
+                // Compare the `Naive` and `DatafrogOpt` errors, and record their differences
+                let naive_phase = ctx.report_phase("Naive");
+                let (naive_errors, naive_subset_errors) = naive::compute::<T, B>(&ctx, &mut result);
+                drop(naive_phase);
+                if result.aborted.is_some() {
+                    return result;
+                }
+                // The loans in scope are the ones of `Naive`, like the errors.
+                let opt_phase = ctx.report_phase("DatafrogOpt");
+                ctx.record_loan_live_at = false;
+                let (opt_errors, opt_subset_errors) =
+                    datafrog_opt::compute::<T, B>(&ctx, &mut result);
+                drop(opt_phase);
 
                 let mut naive_errors_by_point = FxHashMap::default();
                 for &(loan, point) in naive_errors.iter() {
@@ -345,20 +1111,44 @@ impl<T: FactTypes> Output<T> {
                         .push(loan);
                 }
 
-                if compare_errors(&naive_errors_by_point, &opt_errors_by_point) {
-                    panic!(concat!(
-                        "The errors reported by the naive algorithm differ from ",
-                        "the errors reported by the optimized algorithm. ",
-                        "See the error log for details."
-                    ));
-                } else {
+                // Each loan error found by only one of the variants is logged here, and
+                // recorded in the differences below, along with the subset errors.
+                let errors_differ = compare_errors(&naive_errors_by_point, &opt_errors_by_point);
+
+                let naive = borrow_diagnostics(&naive_errors, &naive_subset_errors);
+                let opt = borrow_diagnostics(&opt_errors, &opt_subset_errors);
+                let comparison = ErrorsDiff {
+                    removed: naive.difference(&opt).cloned().collect(),
+                    added: opt.difference(&naive).cloned().collect(),
+                };
+                assert_eq!(
+                    errors_differ,
+                    comparison
+                        .removed
+                        .iter()
+                        .chain(comparison.added.iter())
+                        .any(|diagnostic| matches!(diagnostic, Diagnostic::IllegalAccess { .. })),
+                    "the loan errors' differences weren't all recorded"
+                );
+                if comparison.is_empty() {
                     debug!("Naive and optimized algorithms reported the same errors.");
+                } else {
+                    error!(
+                        only_naive = comparison.removed.len(),
+                        only_opt = comparison.added.len(),
+                        "The naive and optimized algorithms reported different errors."
+                    );
                 }
+                result.comparison = Some(comparison);
 
                 (naive_errors, naive_subset_errors)
             }
         };
 
+        if result.aborted.is_some() {
+            return result;
+        }
+
         // Record illegal access errors
         for &(loan, location) in errors.iter() {
             result.errors.entry(location).or_default().push(loan);
@@ -373,6 +1163,30 @@ impl<T: FactTypes> Output<T> {
                 .insert((origin1, origin2));
         }
 
+        // The location-insensitive errors' points are only representative: their witnesses are
+        // not restricted to the facts reaching them.
+        let cfg = match algorithm {
+            Algorithm::LocationInsensitive => None,
+            _ => Some(&ctx.cfg),
+        };
+        result.subset_error_witnesses =
+            witnesses::compute_subset_error_witnesses(all_facts, cfg, &result.subset_errors);
+
+        for &(origin1, origin2) in ctx.known_placeholder_subset.iter() {
+            result
+                .known_placeholder_subset
+                .entry(origin1)
+                .or_default()
+                .insert(origin2);
+        }
+
+        if options.scopes {
+            result.record_scopes(&ctx.origin_live_on_entry);
+        }
+        if options.loans_out_of_scope && !options.skip_loan_live_at {
+            result.record_loans_out_of_scope(all_facts, &ctx.cfg);
+        }
+
         // Record more debugging info when asked to do so
         if dump_enabled {
             for &(origin, location) in ctx.origin_live_on_entry.iter() {
@@ -390,14 +1204,441 @@ impl<T: FactTypes> Output<T> {
                     .or_default()
                     .insert(loan);
             }
+
+            result.origin_kind = ctx.origin_kind;
+        }
+
+        result
+    }
+
+    /// Computes the errors of the given `algorithm` with the given `options`, restricted to the
+    /// chosen `loans`: the input relations are sliced so that the other loans are not tracked at
+    /// all.
+    ///
+    /// The placeholder loans are not sliced, as they are needed to compute illegal subset
+    /// errors. Since the other loans are missing, the outputs about loans, including the
+    /// debugging data, only cover the chosen loans and the placeholders'.
+    pub fn compute_for_loans(
+        all_facts: &AllFacts<T>,
+        algorithm: Algorithm,
+        options: &ComputeOptions,
+        loans: impl IntoIterator<Item = T::Loan>,
+    ) -> Self {
+        let loans: FxHashSet<T::Loan> = loans.into_iter().collect();
+
+        let mut sliced_facts = all_facts.clone();
+        sliced_facts.loan_issued_at =
+            slice_loans(&all_facts.loan_issued_at, &loans, |&(_, loan, _)| loan);
+        sliced_facts.loan_killed_at =
+            slice_loans(&all_facts.loan_killed_at, &loans, |&(loan, _)| loan);
+        sliced_facts.loan_shallow_killed_at =
+            slice_loans(&all_facts.loan_shallow_killed_at, &loans, |&(loan, _)| loan);
+        sliced_facts.loan_invalidated_at =
+            slice_loans(&all_facts.loan_invalidated_at, &loans, |&(loan, _)| loan);
+        sliced_facts.loan_activated_at =
+            slice_loans(&all_facts.loan_activated_at, &loans, |&(loan, _)| loan);
+
+        Output::compute_with_options(&sliced_facts, algorithm, options)
+    }
+
+    /// Computes the errors of the given `algorithm` with the given `options`, by splitting the
+    /// loans into `partitions` sets, computed separately with `compute_for_loans`, and merging
+    /// their outputs. The rules of a loan don't depend on the other loans, so the outputs are the
+    /// same as computing all the loans at once, with less memory per computation. With the
+    /// `rayon` feature, the partitions are computed in parallel.
+    ///
+    /// The outputs which don't depend on the loans, like the subset and move errors, are taken
+    /// from any partition, as are the `relation_sizes` of `DatafrogOpt`.
+    pub fn compute_partitioned(
+        all_facts: &AllFacts<T>,
+        algorithm: Algorithm,
+        options: &ComputeOptions,
+        partitions: usize,
+    ) -> Self {
+        let mut loans: Vec<T::Loan> = all_facts
+            .loan_issued_at
+            .iter()
+            .map(|&(_, loan, _)| loan)
+            .collect();
+        loans.sort();
+        loans.dedup();
+
+        let partitions = partitions.max(1).min(loans.len().max(1));
+        let partitioned_loans: Vec<Vec<T::Loan>> = (0..partitions)
+            .map(|partition| {
+                loans
+                    .iter()
+                    .skip(partition)
+                    .step_by(partitions)
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+
+        let compute = |loans: &Vec<T::Loan>| {
+            Output::compute_for_loans(all_facts, algorithm, options, loans.iter().cloned())
+        };
+
+        #[cfg(feature = "rayon")]
+        let outputs: Vec<Self> = {
+            use rayon::prelude::*;
+            partitioned_loans.par_iter().map(compute).collect()
+        };
+
+        #[cfg(not(feature = "rayon"))]
+        let outputs: Vec<Self> = partitioned_loans.iter().map(compute).collect();
+
+        let mut outputs = outputs.into_iter();
+        let mut result = outputs.next().expect("no partitions were computed");
+        for output in outputs {
+            result.merge_loan_partition(output);
+        }
+
+        // The placeholder loans are in the outputs computed by every partition.
+        for loans in result
+            .errors
+            .values_mut()
+            .chain(result.loan_live_at.values_mut())
+            .chain(result.loans_out_of_scope_at.values_mut())
+            .chain(result.loan_invalidated_at.values_mut())
+        {
+            loans.sort();
+            loans.dedup();
+        }
+        for witness in result
+            .subset_error_witnesses
+            .values_mut()
+            .flat_map(|witnesses| witnesses.values_mut())
+        {
+            witness.loans.sort();
+            witness.loans.dedup();
         }
 
         result
     }
 
+    /// Adds the outputs about the loans of another partition of the loans to this one's, see
+    /// `compute_partitioned`.
+    fn merge_loan_partition(&mut self, partition: Self) {
+        fn merge<K: Eq + std::hash::Hash, V>(
+            into: &mut FxHashMap<K, Vec<V>>,
+            from: FxHashMap<K, Vec<V>>,
+        ) {
+            for (key, values) in from {
+                into.entry(key).or_default().extend(values);
+            }
+        }
+
+        if self.aborted.is_none() {
+            self.aborted = partition.aborted;
+        }
+
+        merge(&mut self.errors, partition.errors);
+        merge(&mut self.loan_live_at, partition.loan_live_at);
+        merge(
+            &mut self.loans_out_of_scope_at,
+            partition.loans_out_of_scope_at,
+        );
+        merge(&mut self.loan_invalidated_at, partition.loan_invalidated_at);
+        self.potential_errors.extend(partition.potential_errors);
+        self.loan_scopes.extend(partition.loan_scopes);
+
+        for (point, origins) in partition.origin_contains_loan_at {
+            let contains = self.origin_contains_loan_at.entry(point).or_default();
+            for (origin, loans) in origins {
+                contains.entry(origin).or_default().extend(loans);
+            }
+        }
+        for (origin, loans) in partition.origin_contains_loan_anywhere {
+            self.origin_contains_loan_anywhere
+                .entry(origin)
+                .or_default()
+                .extend(loans);
+        }
+
+        // the subset errors are the same in every partition, but the loans of their witnesses
+        // are not
+        for (point, witnesses) in partition.subset_error_witnesses {
+            let merged = self.subset_error_witnesses.entry(point).or_default();
+            for (error, witness) in witnesses {
+                match merged.get_mut(&error) {
+                    Some(merged) => merged.loans.extend(witness.loans),
+                    None => {
+                        merged.insert(error, witness);
+                    }
+                }
+            }
+        }
+
+        // likewise, only the differences between the loan errors depend on the partition
+        if let (Some(comparison), Some(partition)) = (&mut self.comparison, partition.comparison) {
+            let is_loan_error =
+                |diagnostic: &Diagnostic<T>| matches!(diagnostic, Diagnostic::IllegalAccess { .. });
+            comparison
+                .removed
+                .extend(partition.removed.into_iter().filter(is_loan_error));
+            comparison
+                .added
+                .extend(partition.added.into_iter().filter(is_loan_error));
+        }
+    }
+
+    /// Computes only the errors of the given `algorithm`: the debugging data is neither
+    /// recorded nor returned, for consumers like rustc which have no use for it.
+    pub fn errors_only(all_facts: &AllFacts<T>, algorithm: Algorithm) -> Errors<T> {
+        let options = ComputeOptions {
+            skip_loan_live_at: true,
+            ..ComputeOptions::default()
+        };
+        let output = Output::compute_with_options(all_facts, algorithm, &options);
+        Errors {
+            errors: output.errors,
+            subset_errors: output.subset_errors,
+            move_errors: output.move_errors,
+        }
+    }
+
+    /// Computes only the liveness of variables and origins, as the `LivenessOnly` variant does,
+    /// without borrow checking the facts.
+    pub fn liveness(all_facts: &AllFacts<T>) -> LivenessOutput<T> {
+        let output = Output::compute(all_facts, Algorithm::LivenessOnly, false);
+        LivenessOutput {
+            var_live_on_entry: output.var_live_on_entry,
+            var_drop_live_on_entry: output.var_drop_live_on_entry,
+            var_dropped_while_init_at: output.var_dropped_while_init_at,
+            origin_live_on_entry: output.origin_live_on_entry,
+        }
+    }
+
+    /// Computes only the move errors and the initialization of paths and variables, as the
+    /// `InitializationOnly` variant does, without computing liveness nor borrow checking.
+    pub fn initialization(all_facts: &AllFacts<T>) -> InitializationOutput<T> {
+        let output = Output::compute(all_facts, Algorithm::InitializationOnly, false);
+        InitializationOutput {
+            move_errors: output.move_errors,
+            move_error_kinds: output.move_error_kinds,
+            path_maybe_initialized_on_exit: output.path_maybe_initialized_on_exit,
+            path_maybe_uninitialized_on_exit: output.path_maybe_uninitialized_on_exit,
+            var_maybe_partly_initialized_on_exit: output.var_maybe_partly_initialized_on_exit,
+        }
+    }
+
+    /// Computes the initialization of paths and variables, and the move errors.
+    fn compute_initialization<B: JoinBackend>(
+        all_facts: &AllFacts<T>,
+        cfg: &Cfg<T>,
+        options: &ComputeOptions,
+        result: &mut Output<T>,
+        record: bool,
+    ) -> initialization::InitializationResult<T> {
+        let initialization_ctx = InitializationContext {
+            child_path: all_facts.child_path.clone(),
+            path_is_var: all_facts.path_is_var.clone(),
+            path_assigned_at_base: all_facts.path_assigned_at_base.clone(),
+            path_moved_at_base: all_facts.path_moved_at_base.clone(),
+            path_accessed_at_base: all_facts.path_accessed_at_base.clone(),
+            progress: options.progress.clone(),
+            max_rounds: options.max_rounds,
+        };
+        initialization::compute::<T, B>(initialization_ctx, &cfg.edges, result, record)
+    }
+
+    /// Computes the live origins, including the universal regions, from the initialization
+    /// of variables, unless the facts have a precomputed liveness covering their points, see
+    /// `AllFacts::uncovered_liveness_points`. The tuples are left unsorted, and are incomplete if
+    /// the computation was aborted.
+    fn compute_liveness<B: JoinBackend>(
+        all_facts: &AllFacts<T>,
+        cfg: &Cfg<T>,
+        var_maybe_partly_initialized_on_exit: Relation<(T::Variable, T::Point)>,
+        placeholder_origins: &[T::Origin],
+        options: &ComputeOptions,
+        result: &mut Output<T>,
+        record: bool,
+    ) -> Vec<(T::Origin, T::Point)> {
+        if !all_facts.origin_live_on_entry.is_empty() {
+            let uncovered = all_facts.uncovered_liveness_points();
+            if uncovered.is_empty() {
+                debug!("using the precomputed origin liveness");
+                let mut origin_live_on_entry = all_facts.origin_live_on_entry.clone();
+                liveness::make_universal_regions_live::<T>(
+                    &mut origin_live_on_entry,
+                    &cfg.nodes,
+                    placeholder_origins,
+                    &all_facts.universal_region_live_at,
+                );
+                return origin_live_on_entry;
+            }
+
+            warn!(
+                uncovered = uncovered.len(),
+                first = ?uncovered[0],
+                "the precomputed origin liveness doesn't cover the points of the facts, computing it instead"
+            );
+        }
+
+        let liveness_ctx = LivenessContext {
+            var_used_at: all_facts.var_used_at.clone(),
+            var_defined_at: all_facts.var_defined_at.clone(),
+            use_of_var_derefs_origin: all_facts.use_of_var_derefs_origin.clone(),
+            drop_of_var_derefs_origin: all_facts.drop_of_var_derefs_origin.clone(),
+            progress: options.progress.clone(),
+            max_rounds: options.max_rounds,
+        };
+
+        let var_dropped_while_init_at = liveness::compute_var_dropped_while_init_at::<T, B>(
+            all_facts.var_dropped_at.clone(),
+            cfg,
+            &var_maybe_partly_initialized_on_exit,
+            result,
+            record,
+        );
+        let mut origin_live_on_entry = liveness::compute_live_origins::<T, B>(
+            liveness_ctx,
+            cfg,
+            var_dropped_while_init_at,
+            var_maybe_partly_initialized_on_exit,
+            result,
+            record,
+        );
+        if result.aborted.is_some() {
+            return origin_live_on_entry;
+        }
+
+        liveness::make_universal_regions_live::<T>(
+            &mut origin_live_on_entry,
+            &cfg.nodes,
+            placeholder_origins,
+            &all_facts.universal_region_live_at,
+        );
+        origin_live_on_entry
+    }
+
+    /// The sorted placeholder origins of an `origin_kind` map.
+    fn placeholder_origins(origin_kind: &FxHashMap<T::Origin, OriginKind>) -> Vec<T::Origin> {
+        let mut placeholder_origins: Vec<_> = origin_kind
+            .iter()
+            .filter(|&(_, &kind)| kind == OriginKind::Placeholder)
+            .map(|(&origin, _)| origin)
+            .collect();
+        placeholder_origins.sort();
+        placeholder_origins
+    }
+
+    /// Computes the kind of each origin of the facts: the `universal_region`s are placeholders.
+    /// The other origins are existential, even the origins of `placeholder` loans which aren't
+    /// `universal_region`s.
+    fn compute_origin_kind(all_facts: &AllFacts<T>) -> FxHashMap<T::Origin, OriginKind> {
+        let mut origin_kind = FxHashMap::default();
+        for &origin in &all_facts.universal_region {
+            origin_kind.insert(origin, OriginKind::Placeholder);
+        }
+
+        let origins = all_facts
+            .loan_issued_at
+            .iter()
+            .map(|&(origin, _, _)| origin)
+            .chain(all_facts.placeholder.iter().map(|&(origin, _loan)| origin))
+            .chain(
+                all_facts
+                    .subset_base
+                    .iter()
+                    .flat_map(|&(origin1, origin2, _)| {
+                        iter::once(origin1).chain(iter::once(origin2))
+                    }),
+            )
+            .chain(
+                all_facts
+                    .use_of_var_derefs_origin
+                    .iter()
+                    .chain(&all_facts.drop_of_var_derefs_origin)
+                    .map(|&(_, origin)| origin),
+            );
+        for origin in origins {
+            origin_kind.entry(origin).or_insert(OriginKind::Existential);
+        }
+        origin_kind
+    }
+
+    /// Computes the invalidations of the loans where they are active: the two-phase borrows with
+    /// `loan_activated_at` facts are only reserved until then, and their earlier invalidations
+    /// don't conflict with them. This amounts to the error rule:
+    ///
+    /// ```text
+    /// errors(Loan, Point) :-
+    ///   loan_invalidated_at(Loan, Point),
+    ///   loan_live_at(Loan, Point),
+    ///   loan_active_at(Loan, Point).
+    /// ```
+    ///
+    /// where the loans without activations are active everywhere.
+    fn compute_active_loan_invalidated_at<B: JoinBackend>(
+        all_facts: &AllFacts<T>,
+        cfg: &Cfg<T>,
+    ) -> Relation<(T::Loan, T::Point)> {
+        use datafrog::{Iteration, RelationLeaper};
+
+        if all_facts.loan_activated_at.is_empty() {
+            return all_facts.loan_invalidated_at.clone().into();
+        }
+
+        let loan_issued_at: Relation<(T::Loan, T::Point)> = Relation::from_iter(
+            all_facts
+                .loan_issued_at
+                .iter()
+                .map(|&(_origin, loan, point)| (loan, point)),
+        );
+
+        let mut iteration = Iteration::new();
+        let loan_active_at = iteration.variable::<(T::Point, T::Loan)>("loan_active_at");
+
+        // loan_active_at(Loan, Point) :-
+        //   loan_activated_at(Loan, Point).
+        loan_active_at.extend(
+            all_facts
+                .loan_activated_at
+                .iter()
+                .map(|&(loan, point)| (point, loan)),
+        );
+
+        while iteration.changed() {
+            // The loan stays active until it's issued again, as a new reservation.
+            //
+            // loan_active_at(Loan, Point2) :-
+            //   loan_active_at(Loan, Point1),
+            //   cfg_edge(Point1, Point2),
+            //   !loan_issued_at(_, Loan, Point2).
+            B::leapjoin_into(
+                &loan_active_at,
+                &loan_active_at,
+                (
+                    cfg.edges.extend_with(|&(point1, _loan)| point1),
+                    loan_issued_at.extend_anti(|&(_point1, loan)| loan),
+                ),
+                |&(_point1, loan), &point2| (point2, loan),
+            );
+        }
+
+        let loan_active_at: FxHashSet<_> = loan_active_at.complete().iter().copied().collect();
+        let activated_loans: FxHashSet<_> = all_facts
+            .loan_activated_at
+            .iter()
+            .map(|&(loan, _point)| loan)
+            .collect();
+        Relation::from_iter(
+            all_facts
+                .loan_invalidated_at
+                .iter()
+                .filter(|&&(loan, point)| {
+                    !activated_loans.contains(&loan) || loan_active_at.contains(&(point, loan))
+                })
+                .copied(),
+        )
+    }
+
     /// Computes the transitive closure of the `known_placeholder_subset` relation, so that we have
     /// the full list of placeholder loans contained by the placeholder origins.
-    fn compute_known_contains(
+    fn compute_known_contains<B: JoinBackend>(
         known_placeholder_subset: &Relation<(T::Origin, T::Origin)>,
         placeholder: &[(T::Origin, T::Loan)],
     ) -> Relation<(T::Origin, T::Loan)> {
@@ -412,7 +1653,8 @@ impl<T: FactTypes> Output<T> {
             // known_contains(Origin2, Loan1) :-
             //   known_contains(Origin1, Loan1),
             //   known_placeholder_subset(Origin1, Origin2).
-            known_contains.from_join(
+            B::join_into(
+                &known_contains,
                 &known_contains,
                 known_placeholder_subset,
                 |&_origin1, &loan1, &origin2| (origin2, loan1),
@@ -423,7 +1665,7 @@ impl<T: FactTypes> Output<T> {
     }
 
     /// Computes the transitive closure of the `known_placeholder_subset` relation.
-    fn compute_known_placeholder_subset(
+    fn compute_known_placeholder_subset<B: JoinBackend>(
         known_placeholder_subset_base: &Relation<(T::Origin, T::Origin)>,
     ) -> Relation<(T::Origin, T::Origin)> {
         use datafrog::{Iteration, RelationLeaper};
@@ -439,7 +1681,8 @@ impl<T: FactTypes> Output<T> {
             // known_placeholder_subset(Origin1, Origin3) :-
             //   known_placeholder_subset(Origin1, Origin2),
             //   known_placeholder_subset_base(Origin2, Origin3).
-            known_placeholder_subset.from_leapjoin(
+            B::leapjoin_into(
+                &known_placeholder_subset,
                 &known_placeholder_subset,
                 known_placeholder_subset_base.extend_with(|&(_origin1, origin2)| origin2),
                 |&(origin1, _origin2), &origin3| (origin1, origin3),
@@ -454,20 +1697,87 @@ impl<T: FactTypes> Output<T> {
             errors: FxHashMap::default(),
             subset_errors: FxHashMap::default(),
             dump_enabled,
+            partial: false,
+            aborted: None,
             loan_live_at: FxHashMap::default(),
+            potential_errors: FxHashMap::default(),
+            loan_scopes: FxHashMap::default(),
+            origin_live_ranges: FxHashMap::default(),
+            loans_out_of_scope_at: FxHashMap::default(),
             origin_contains_loan_at: FxHashMap::default(),
             origin_contains_loan_anywhere: FxHashMap::default(),
             origin_live_on_entry: FxHashMap::default(),
             loan_invalidated_at: FxHashMap::default(),
             move_errors: FxHashMap::default(),
+            move_error_kinds: FxHashMap::default(),
             subset: FxHashMap::default(),
             subset_anywhere: FxHashMap::default(),
             var_live_on_entry: FxHashMap::default(),
             var_drop_live_on_entry: FxHashMap::default(),
+            var_dropped_while_init_at: FxHashMap::default(),
             path_maybe_initialized_on_exit: FxHashMap::default(),
             path_maybe_uninitialized_on_exit: FxHashMap::default(),
+            path_definitely_initialized_on_exit: FxHashMap::default(),
+            path_moved_at: FxHashMap::default(),
+            path_assigned_at: FxHashMap::default(),
+            path_accessed_at: FxHashMap::default(),
             var_maybe_partly_initialized_on_exit: FxHashMap::default(),
             known_contains: FxHashMap::default(),
+            relation_sizes: None,
+            known_placeholder_subset: FxHashMap::default(),
+            subset_error_witnesses: FxHashMap::default(),
+            origin_kind: FxHashMap::default(),
+            comparison: None,
+        }
+    }
+
+    /// Records the `loan_scopes`, from the loans in scope at each point, and the
+    /// `origin_live_ranges`, from the `origin_live_on_entry` relation.
+    fn record_scopes(&mut self, origin_live_on_entry: &[(T::Origin, T::Point)]) {
+        for (&location, loans) in &self.loan_live_at {
+            for &loan in loans {
+                self.loan_scopes.entry(loan).or_default().insert(location);
+            }
+        }
+        for &(origin, location) in origin_live_on_entry {
+            self.origin_live_ranges
+                .entry(origin)
+                .or_default()
+                .insert(location);
+        }
+    }
+
+    /// Records the `loans_out_of_scope_at`: the walk of the CFG from the point where each loan is
+    /// issued stops at the points where it's killed, and at the points where it's not in scope
+    /// anymore.
+    fn record_loans_out_of_scope(&mut self, all_facts: &AllFacts<T>, cfg: &Cfg<T>) {
+        let killed: FxHashSet<(T::Loan, T::Point)> =
+            all_facts.loan_killed_at.iter().cloned().collect();
+        for &(_, loan, issued_at) in &all_facts.loan_issued_at {
+            let mut visited = FxHashSet::default();
+            let mut stack = vec![issued_at];
+            while let Some(point) = stack.pop() {
+                if !visited.insert(point) || killed.contains(&(loan, point)) {
+                    continue;
+                }
+
+                let in_scope = self
+                    .loan_live_at
+                    .get(&point)
+                    .is_some_and(|loans| loans.contains(&loan));
+                if in_scope {
+                    stack.extend(cfg.successors(point));
+                } else {
+                    self.loans_out_of_scope_at
+                        .entry(point)
+                        .or_default()
+                        .push(loan);
+                }
+            }
+        }
+        for loans in self.loans_out_of_scope_at.values_mut() {
+            loans.sort();
+            loans.dedup();
         }
     }
 
@@ -478,13 +1788,19 @@ impl<T: FactTypes> Output<T> {
         }
     }
 
-    pub fn loans_in_scope_at(&self, location: T::Point) -> &[T::Loan] {
+    /// The loans in scope at the `location`, see `Output::loan_live_at`.
+    pub fn loans_live_at(&self, location: T::Point) -> &[T::Loan] {
         match self.loan_live_at.get(&location) {
             Some(p) => p,
             None => &[],
         }
     }
 
+    #[deprecated(note = "renamed to `loans_live_at`, like the `loan_live_at` relation")]
+    pub fn loans_in_scope_at(&self, location: T::Point) -> &[T::Loan] {
+        self.loans_live_at(location)
+    }
+
     pub fn origin_contains_loan_at(
         &self,
         location: T::Point,
@@ -514,15 +1830,71 @@ impl<T: FactTypes> Output<T> {
             None => Cow::Owned(BTreeMap::default()),
         }
     }
+
+    /// The paths which may be initialized upon leaving the `location`: they were assigned on
+    /// some path through the CFG, and not moved out since.
+    ///
+    /// The initialization of paths is recorded when `dump_enabled`, and by the
+    /// `InitializationOnly` variant: otherwise, there are no such paths.
+    pub fn paths_maybe_initialized_at(&self, location: T::Point) -> &[T::Path] {
+        match self.path_maybe_initialized_on_exit.get(&location) {
+            Some(v) => v,
+            None => &[],
+        }
+    }
+
+    /// The paths which may be moved out upon leaving the `location`: they were moved out, or
+    /// never initialized, on some path through the CFG, and not assigned since. Accessing them
+    /// there is a move error.
+    ///
+    /// The initialization of paths is recorded when `dump_enabled`, and by the
+    /// `InitializationOnly` variant: otherwise, there are no such paths.
+    pub fn paths_maybe_moved_at(&self, location: T::Point) -> &[T::Path] {
+        match self.path_maybe_uninitialized_on_exit.get(&location) {
+            Some(v) => v,
+            None => &[],
+        }
+    }
+}
+
+/// Returns the tuples of a loan-related relation whose loan is one of the given `loans`.
+fn slice_loans<Tuple: Copy, L: Atom>(
+    tuples: &[Tuple],
+    loans: &FxHashSet<L>,
+    loan: impl Fn(&Tuple) -> L,
+) -> Vec<Tuple> {
+    tuples
+        .iter()
+        .filter(|tuple| loans.contains(&loan(tuple)))
+        .cloned()
+        .collect()
+}
+
+/// The illegal access `errors` and `subset_errors` found by a variant, as `Diagnostic`s.
+fn borrow_diagnostics<T: FactTypes>(
+    errors: &Relation<(T::Loan, T::Point)>,
+    subset_errors: &Relation<(T::Origin, T::Origin, T::Point)>,
+) -> BTreeSet<Diagnostic<T>> {
+    let illegal_accesses = errors
+        .iter()
+        .map(|&(loan, point)| Diagnostic::IllegalAccess { loan, point });
+    let illegal_subsets = subset_errors
+        .iter()
+        .map(|&(sub, sup, point)| Diagnostic::IllegalSubset { sup, sub, point });
+    illegal_accesses.chain(illegal_subsets).collect()
 }
 
 /// Compares errors reported by Naive implementation with the errors
-/// reported by the optimized implementation.
+/// reported by the optimized implementation, logging each difference once.
 fn compare_errors<Loan: Atom, Point: Atom>(
     all_naive_errors: &FxHashMap<Point, Vec<Loan>>,
     all_opt_errors: &FxHashMap<Point, Vec<Loan>>,
 ) -> bool {
-    let points = all_naive_errors.keys().chain(all_opt_errors.keys());
+    // The points with errors in both variants are only compared once.
+    let points: BTreeSet<_> = all_naive_errors
+        .keys()
+        .chain(all_opt_errors.keys())
+        .collect();
 
     let mut differ = false;
     for point in points {
@@ -611,4 +1983,64 @@ mod tests {
         assert_eq!(true, compare(&errors1, &singleton2));
         assert_eq!(true, compare(&errors1, &singleton3));
     }
+
+    #[derive(Copy, Clone, Debug)]
+    struct UsizeFacts;
+
+    impl FactTypes for UsizeFacts {
+        type Origin = usize;
+        type Loan = usize;
+        type Point = usize;
+        type Variable = usize;
+        type Path = usize;
+    }
+
+    #[test]
+    fn test_facts_and_outputs_are_send_and_sync() {
+        fn assert_send_sync<S: Send + Sync>() {}
+
+        assert_send_sync::<AllFacts<UsizeFacts>>();
+        assert_send_sync::<ComputeOptions>();
+        assert_send_sync::<Output<UsizeFacts>>();
+        assert_send_sync::<Errors<UsizeFacts>>();
+        assert_send_sync::<LivenessOutput<UsizeFacts>>();
+        assert_send_sync::<InitializationOutput<UsizeFacts>>();
+        assert_send_sync::<Context<'static, UsizeFacts>>();
+    }
+
+    #[test]
+    fn test_cfg_reverse_post_order() {
+        // a loop between 1 and 2, a second entry point 5, and a cycle 7-8 without entry
+        let edges = vec![(0, 1), (1, 2), (2, 1), (2, 3), (5, 6), (7, 8), (8, 7)];
+        let cfg = Cfg::<UsizeFacts>::new(edges.into());
+
+        assert_eq!(cfg.nodes, [0, 1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(cfg.successors(2), [1, 3]);
+        assert_eq!(cfg.predecessors(1), [0, 2]);
+        assert!(cfg.successors(4).is_empty() && cfg.predecessors(42).is_empty());
+        assert_eq!(cfg.reverse_post_order, [7, 8, 5, 6, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_compact_atoms_overflow() {
+        use compact::Point;
+        use facts::AtomOverflow;
+
+        let last = u32::MAX as usize;
+        assert_eq!(Point::try_from_index(last).map(Atom::index), Ok(last));
+
+        let overflow = Point::try_from_index(last + 1).unwrap_err();
+        assert_eq!(
+            overflow,
+            AtomOverflow {
+                kind: "Point",
+                index: last + 1
+            }
+        );
+        assert_eq!(
+            overflow.to_string(),
+            "Point index 4294967296 overflows the index type of the atoms"
+        );
+    }
 }
@@ -10,13 +10,20 @@
 
 //! A version of the Naive datalog analysis using Datafrog.
 
+use crate::time::Instant;
 use datafrog::{Iteration, Relation, RelationLeaper};
-use std::time::Instant;
+use rustc_hash::FxHashMap;
+use std::hash::Hash;
 
 use crate::facts::FactTypes;
-use crate::output::{Context, Output};
-
-pub(super) fn compute<T: FactTypes>(
+use crate::join::JoinBackend;
+use crate::output::{
+    check_round_limit, record_loan_live_at, report_round, variable_len, Context, Output,
+    PointKeyed, RoundLimitExceeded, ShallowKill, VariableSize,
+};
+use crate::progress::ProgressHook;
+
+pub(super) fn compute<T: FactTypes, B: JoinBackend>(
     ctx: &Context<'_, T>,
     result: &mut Output<T>,
 ) -> (
@@ -25,275 +32,474 @@ pub(super) fn compute<T: FactTypes>(
 ) {
     let timer = Instant::now();
 
-    let (errors, subset_errors) = {
-        // Static inputs
-        let origin_live_on_entry_rel = &ctx.origin_live_on_entry;
-        let cfg_edge = &ctx.cfg_edge;
-        let loan_killed_at = &ctx.loan_killed_at;
-        let known_placeholder_subset = &ctx.known_placeholder_subset;
-        let placeholder_origin = &ctx.placeholder_origin;
-
-        // Create a new iteration context, ...
-        let mut iteration = Iteration::new();
-
-        // .. some variables, ..
-        let subset = iteration.variable::<(T::Origin, T::Origin, T::Point)>("subset");
-        let origin_contains_loan_on_entry =
-            iteration.variable::<(T::Origin, T::Loan, T::Point)>("origin_contains_loan_on_entry");
-        let loan_live_at = iteration.variable::<((T::Loan, T::Point), ())>("loan_live_at");
-
-        // `loan_invalidated_at` facts, stored ready for joins
-        let loan_invalidated_at = Relation::from_iter(
-            ctx.loan_invalidated_at
-                .iter()
-                .map(|&(loan, point)| ((loan, point), ())),
+    let inputs = Inputs {
+        subset_base: ctx.subset_base,
+        loan_issued_at: ctx.loan_issued_at,
+        cfg_edge: &ctx.cfg.edges,
+        origin_live_on_entry: &ctx.origin_live_on_entry,
+        origin_live_on_entry_op: ctx.origin_live_on_entry_op(),
+        loan_killed_at: &ctx.loan_killed_at,
+        loan_shallow_killed_at: &ctx.loan_shallow_killed_at,
+        loan_invalidated_at: ctx.loan_invalidated_at_lp(),
+        known_placeholder_subset: &ctx.known_placeholder_subset,
+        placeholder_origin: &ctx.placeholder_origin,
+        progress: ctx.progress.as_ref(),
+        max_rounds: ctx.max_rounds,
+    };
+    let mut results = evaluate::<T, B>(&inputs);
+    result.aborted = results.aborted.take();
+
+    if result.dump_enabled {
+        record_results(&results, result);
+    }
+    if ctx.record_loan_live_at {
+        record_loan_live_at(
+            results.loan_live_at.iter().map(|&(tuple, ())| tuple),
+            result,
         );
+    }
+
+    info!(
+        errors = results.errors.len(),
+        subset_errors = results.subset_errors.len(),
+        elapsed = ?timer.elapsed(),
+        "analysis done"
+    );
+
+    (results.errors, results.subset_errors)
+}
+
+/// The inputs of the naive rules. They are usually the whole function's facts, but can also be
+/// the facts of a part of its CFG, like in the `ParallelNaive` variant.
+pub(super) struct Inputs<'a, T: FactTypes> {
+    pub(super) subset_base: &'a [(T::Origin, T::Origin, T::Point)],
+    pub(super) loan_issued_at: &'a [(T::Origin, T::Loan, T::Point)],
+    pub(super) cfg_edge: &'a Relation<(T::Point, T::Point)>,
+    pub(super) origin_live_on_entry: &'a Relation<(T::Origin, T::Point)>,
+    pub(super) origin_live_on_entry_op: &'a PointKeyed<T, T::Origin, ()>,
+    pub(super) loan_killed_at: &'a Relation<(T::Loan, T::Point)>,
+    pub(super) loan_shallow_killed_at: &'a Relation<ShallowKill<T>>,
+    pub(super) loan_invalidated_at: &'a PointKeyed<T, T::Loan, ()>,
+    pub(super) known_placeholder_subset: &'a Relation<(T::Origin, T::Origin)>,
+    pub(super) placeholder_origin: &'a Relation<(T::Origin, ())>,
+
+    /// The hook receiving the rounds of the iteration, if any.
+    pub(super) progress: Option<&'a ProgressHook>,
+
+    /// The maximum number of rounds of the iteration, if any.
+    pub(super) max_rounds: Option<usize>,
+}
+
+/// The relations computed by the naive rules.
+pub(super) struct Results<T: FactTypes> {
+    pub(super) errors: Relation<(T::Loan, T::Point)>,
+    pub(super) subset_errors: Relation<(T::Origin, T::Origin, T::Point)>,
+    pub(super) subset: Relation<(T::Origin, T::Origin, T::Point)>,
+    pub(super) origin_contains_loan_on_entry: Relation<(T::Origin, T::Loan, T::Point)>,
+    pub(super) loan_live_at: PointKeyed<T, T::Loan, ()>,
+
+    /// Why the iteration was aborted, if it exceeded its maximum number of rounds: the results
+    /// are then incomplete.
+    pub(super) aborted: Option<RoundLimitExceeded>,
+}
+
+/// Evaluates the naive rules on the given inputs.
+pub(super) fn evaluate<T: FactTypes, B: JoinBackend>(inputs: &Inputs<'_, T>) -> Results<T> {
+    // Static inputs
+    let origin_live_on_entry_rel = inputs.origin_live_on_entry;
+    let cfg_edge = inputs.cfg_edge;
+    let loan_killed_at = inputs.loan_killed_at;
+    let loan_shallow_killed_at = inputs.loan_shallow_killed_at;
+    let known_placeholder_subset = inputs.known_placeholder_subset;
+    let placeholder_origin = inputs.placeholder_origin;
+    let loan_invalidated_at = inputs.loan_invalidated_at;
+
+    // Create a new iteration context, ...
+    let mut iteration = Iteration::new();
+
+    // .. some variables, ..
+    let subset = iteration.variable::<(T::Origin, T::Origin, T::Point)>("subset");
+    let origin_contains_loan_on_entry =
+        iteration.variable::<(T::Origin, T::Loan, T::Point)>("origin_contains_loan_on_entry");
+    let loan_live_at = iteration.variable::<((T::Loan, T::Point), ())>("loan_live_at");
+
+    // different index for `subset`.
+    let subset_o1p = iteration.variable_indistinct("subset_o1p");
+
+    // the transitive closure of `subset` at each point, maintained incrementally
+    let mut subset_closure: FxHashMap<T::Point, PointClosure<T::Origin>> = FxHashMap::default();
+
+    // different index for `origin_contains_loan_on_entry`.
+    let origin_contains_loan_on_entry_op =
+        iteration.variable_indistinct("origin_contains_loan_on_entry_op");
+
+    // Unfortunately, we need `origin_live_on_entry` in both variable and relation forms:
+    // We need:
+    // - `origin_live_on_entry` as a Relation for the leapjoins in rules 3 & 6
+    // - `origin_live_on_entry` as a Variable for the join in rule 7
+    //
+    // The leapjoins use `origin_live_on_entry` as `(Origin, Point)` tuples, while the join uses
+    // it as a `((O, P), ())` tuple to filter the `((Origin, Point), Loan)` tuples from
+    // `origin_contains_loan_on_entry_op`.
+    //
+    // The regular join in rule 7 could be turned into a `filter_with` leaper but that would
+    // result in a leapjoin with no `extend_*` leapers: a leapjoin that is not well-formed.
+    // Doing the filtering via an `extend_with` leaper would be extremely inefficient.
+    //
+    // Until there's an API in datafrog to handle this use-case better, we do a slightly less
+    // inefficient thing of copying the whole static input into a Variable to use a regular
+    // join, even though the liveness information can be quite heavy (around 1M tuples
+    // on `clap`).
+    // This is the Naive variant so this is not a big problem, but needs an
+    // explanation.
+    let origin_live_on_entry_var =
+        iteration.variable::<((T::Origin, T::Point), ())>("origin_live_on_entry");
+    origin_live_on_entry_var.insert(inputs.origin_live_on_entry_op.clone());
+
+    // output relations: illegal accesses errors, and illegal subset relations errors
+    let errors = iteration.variable("errors");
+    let subset_errors = iteration.variable::<(T::Origin, T::Origin, T::Point)>("subset_errors");
+
+    // load initial facts:
+
+    // Rule 1: the initial subsets are the non-transitive `subset_base` static input.
+    //
+    // subset(Origin1, Origin2, Point) :-
+    //   subset_base(Origin1, Origin2, Point).
+    subset.extend(inputs.subset_base.iter());
+
+    // Rule 4: the issuing origins are the ones initially containing loans.
+    //
+    // origin_contains_loan_on_entry(Origin, Loan, Point) :-
+    //   loan_issued_at(Origin, Loan, Point).
+    origin_contains_loan_on_entry.extend(inputs.loan_issued_at.iter());
+
+    // .. and then start iterating rules!
+    let mut round = 0;
+    let mut aborted = None;
+    while iteration.changed() {
+        round += 1;
+        let _round = report_round(inputs.progress, round, || {
+            variable_len(&subset)
+                + variable_len(&origin_contains_loan_on_entry)
+                + variable_len(&loan_live_at)
+        });
+        let round_limit =
+            check_round_limit(inputs.max_rounds, "Naive", round, &mut iteration, || {
+                variable_sizes![
+                    subset,
+                    subset_o1p,
+                    origin_contains_loan_on_entry,
+                    origin_contains_loan_on_entry_op,
+                    origin_live_on_entry_var,
+                    loan_live_at,
+                    errors,
+                    subset_errors,
+                ]
+            });
+        if let Err(error) = round_limit {
+            aborted = Some(error);
+            break;
+        }
 
-        // different indices for `subset`.
-        let subset_o1p = iteration.variable_indistinct("subset_o1p");
-        let subset_o2p = iteration.variable_indistinct("subset_o2p");
+        // Cleanup step: remove symmetries
+        // - remove origins which are `subset`s of themselves
+        //
+        // FIXME: investigate whether is there a better way to do that without complicating
+        // the rules too much, because it would also require temporary variables and
+        // impact performance. Until then, the big reduction in tuples improves performance
+        // a lot, even if we're potentially adding a small number of tuples
+        // per round just to remove them in the next round.
+        subset
+            .recent
+            .borrow_mut()
+            .elements
+            .retain(|&(origin1, origin2, _)| origin1 != origin2);
+
+        // Remap fields to re-index by keys, to prepare the data needed by the rules below.
+        subset_o1p.from_map(&subset, |&(origin1, origin2, point)| {
+            ((origin1, point), origin2)
+        });
+
+        origin_contains_loan_on_entry_op
+            .from_map(&origin_contains_loan_on_entry, |&(origin, loan, point)| {
+                ((origin, point), loan)
+            });
 
-        // different index for `origin_contains_loan_on_entry`.
-        let origin_contains_loan_on_entry_op =
-            iteration.variable_indistinct("origin_contains_loan_on_entry_op");
+        // Rule 1: done above, as part of the static input facts setup.
 
-        // Unfortunately, we need `origin_live_on_entry` in both variable and relation forms:
-        // We need:
-        // - `origin_live_on_entry` as a Relation for the leapjoins in rules 3 & 6
-        // - `origin_live_on_entry` as a Variable for the join in rule 7
+        // Rule 2: compute the subset transitive closure, at a given point.
         //
-        // The leapjoins use `origin_live_on_entry` as `(Origin, Point)` tuples, while the join uses
-        // it as a `((O, P), ())` tuple to filter the `((Origin, Point), Loan)` tuples from
-        // `origin_contains_loan_on_entry_op`.
+        // subset(Origin1, Origin3, Point) :-
+        //   subset(Origin1, Origin2, Point),
+        //   subset(Origin2, Origin3, Point).
         //
-        // The regular join in rule 7 could be turned into a `filter_with` leaper but that would
-        // result in a leapjoin with no `extend_*` leapers: a leapjoin that is not well-formed.
-        // Doing the filtering via an `extend_with` leaper would be extremely inefficient.
+        // This rule is an all-pairs reachability problem at each point, and dominates the
+        // runtime when evaluated as a self-join. Instead, the new `subset` tuples are added to
+        // the closure of their point, which returns the subsets it's missing. Like the
+        // symmetries removed by the cleanup step above, the reflexive subsets of the origins on
+        // a cycle are not returned: they're not part of the `subset` relation.
+        let mut closed_subsets = Vec::new();
+        for &(origin1, origin2, point) in subset.recent.borrow().iter() {
+            subset_closure
+                .entry(point)
+                .or_insert_with(PointClosure::default)
+                .insert(origin1, origin2, |origin1, origin3| {
+                    closed_subsets.push((origin1, origin3, point))
+                });
+        }
+        subset.extend(closed_subsets);
+
+        // Rule 3: propagate subsets along the CFG, according to liveness.
         //
-        // Until there's an API in datafrog to handle this use-case better, we do a slightly less
-        // inefficient thing of copying the whole static input into a Variable to use a regular
-        // join, even though the liveness information can be quite heavy (around 1M tuples
-        // on `clap`).
-        // This is the Naive variant so this is not a big problem, but needs an
-        // explanation.
-        let origin_live_on_entry_var =
-            iteration.variable::<((T::Origin, T::Point), ())>("origin_live_on_entry");
-        origin_live_on_entry_var.extend(
-            origin_live_on_entry_rel
-                .iter()
-                .map(|&(origin, point)| ((origin, point), ())),
+        // subset(Origin1, Origin2, Point2) :-
+        //   subset(Origin1, Origin2, Point1),
+        //   cfg_edge(Point1, Point2),
+        //   origin_live_on_entry(Origin1, Point2),
+        //   origin_live_on_entry(Origin2, Point2).
+        B::leapjoin_into(
+            &subset,
+            &subset,
+            (
+                cfg_edge.extend_with(|&(_origin1, _origin2, point1)| point1),
+                origin_live_on_entry_rel.extend_with(|&(origin1, _origin2, _point1)| origin1),
+                origin_live_on_entry_rel.extend_with(|&(_origin1, origin2, _point1)| origin2),
+            ),
+            |&(origin1, origin2, _point1), &point2| (origin1, origin2, point2),
         );
 
-        // output relations: illegal accesses errors, and illegal subset relations errors
-        let errors = iteration.variable("errors");
-        let subset_errors = iteration.variable::<(T::Origin, T::Origin, T::Point)>("subset_errors");
+        // Rule 4: done above as part of the static input facts setup.
 
-        // load initial facts:
+        // Rule 5: propagate loans within origins, at a given point, according to subsets.
+        //
+        // origin_contains_loan_on_entry(Origin2, Loan, Point) :-
+        //   origin_contains_loan_on_entry(Origin1, Loan, Point),
+        //   subset(Origin1, Origin2, Point).
+        B::join_into(
+            &origin_contains_loan_on_entry,
+            &origin_contains_loan_on_entry_op,
+            &subset_o1p,
+            |&(_origin1, point), &loan, &origin2| (origin2, loan, point),
+        );
 
-        // Rule 1: the initial subsets are the non-transitive `subset_base` static input.
+        // Rule 6: propagate loans along the CFG, according to liveness. The shallow kills only
+        // apply to the origins the loans were issued with.
         //
-        // subset(Origin1, Origin2, Point) :-
-        //   subset_base(Origin1, Origin2, Point).
-        subset.extend(ctx.subset_base.iter());
+        // origin_contains_loan_on_entry(Origin, Loan, Point2) :-
+        //   origin_contains_loan_on_entry(Origin, Loan, Point1),
+        //   !loan_killed_at(Loan, Point1),
+        //   !loan_shallow_killed_at((Origin, Loan), Point1),
+        //   cfg_edge(Point1, Point2),
+        //   origin_live_on_entry(Origin, Point2).
+        B::leapjoin_into(
+            &origin_contains_loan_on_entry,
+            &origin_contains_loan_on_entry,
+            (
+                loan_killed_at.filter_anti(|&(_origin, loan, point1)| (loan, point1)),
+                loan_shallow_killed_at
+                    .filter_anti(|&(origin, loan, point1)| ((origin, loan), point1)),
+                cfg_edge.extend_with(|&(_origin, _loan, point1)| point1),
+                origin_live_on_entry_rel.extend_with(|&(origin, _loan, _point1)| origin),
+            ),
+            |&(origin, loan, _point1), &point2| (origin, loan, point2),
+        );
 
-        // Rule 4: the issuing origins are the ones initially containing loans.
+        // Rule 7: compute whether a loan is live at a given point, i.e. whether it is
+        // contained in a live origin at this point.
         //
-        // origin_contains_loan_on_entry(Origin, Loan, Point) :-
-        //   loan_issued_at(Origin, Loan, Point).
-        origin_contains_loan_on_entry.extend(ctx.loan_issued_at.iter());
-
-        // .. and then start iterating rules!
-        while iteration.changed() {
-            // Cleanup step: remove symmetries
-            // - remove origins which are `subset`s of themselves
-            //
-            // FIXME: investigate whether is there a better way to do that without complicating
-            // the rules too much, because it would also require temporary variables and
-            // impact performance. Until then, the big reduction in tuples improves performance
-            // a lot, even if we're potentially adding a small number of tuples
-            // per round just to remove them in the next round.
-            subset
-                .recent
-                .borrow_mut()
-                .elements
-                .retain(|&(origin1, origin2, _)| origin1 != origin2);
-
-            // Remap fields to re-index by keys, to prepare the data needed by the rules below.
-            subset_o1p.from_map(&subset, |&(origin1, origin2, point)| {
-                ((origin1, point), origin2)
-            });
-            subset_o2p.from_map(&subset, |&(origin1, origin2, point)| {
-                ((origin2, point), origin1)
-            });
+        // loan_live_at(Loan, Point) :-
+        //   origin_contains_loan_on_entry(Origin, Loan, Point),
+        //   origin_live_on_entry(Origin, Point).
+        B::join_into(
+            &loan_live_at,
+            &origin_contains_loan_on_entry_op,
+            &origin_live_on_entry_var,
+            |&(_origin, point), &loan, _| ((loan, point), ()),
+        );
 
-            origin_contains_loan_on_entry_op
-                .from_map(&origin_contains_loan_on_entry, |&(origin, loan, point)| {
-                    ((origin, point), loan)
-                });
+        // Rule 8: compute illegal access errors, i.e. an invalidation of a live loan.
+        //
+        // Here again, this join acts as a pure filter and could be a more efficient leapjoin.
+        // However, similarly to the `origin_live_on_entry` example described above, the
+        // leapjoin with a single `filter_with` leaper would currently not be well-formed.
+        // We don't explictly need to materialize `loan_live_at` either, and that doesn't
+        // change the well-formedness situation, so we still materialize it (since that also
+        // helps in testing).
+        //
+        // errors(Loan, Point) :-
+        //   loan_invalidated_at(Loan, Point),
+        //   loan_live_at(Loan, Point).
+        B::join_into(
+            &errors,
+            &loan_live_at,
+            loan_invalidated_at,
+            |&(loan, point), _, _| (loan, point),
+        );
+
+        // Rule 9: compute illegal subset relations errors, i.e. the undeclared subsets
+        // between two placeholder origins.
+        // Here as well, WF-ness prevents this join from being a filter-only leapjoin. It
+        // doesn't matter much, as `placeholder_origin` is single-value relation.
+        //
+        // subset_error(Origin1, Origin2, Point) :-
+        //   subset(Origin1, Origin2, Point),
+        //   placeholder_origin(Origin1),
+        //   placeholder_origin(Origin2),
+        //   !known_placeholder_subset(Origin1, Origin2).
+        B::leapjoin_into(
+            &subset_errors,
+            &subset,
+            (
+                placeholder_origin.extend_with(|&(origin1, _origin2, _point)| origin1),
+                placeholder_origin.extend_with(|&(_origin1, origin2, _point)| origin2),
+                known_placeholder_subset
+                    .filter_anti(|&(origin1, origin2, _point)| (origin1, origin2)),
+                // remove symmetries:
+                datafrog::ValueFilter::from(|&(origin1, origin2, _point), _| origin1 != origin2),
+            ),
+            |&(origin1, origin2, point), _| (origin1, origin2, point),
+        );
+    }
+
+    Results {
+        errors: errors.complete(),
+        subset_errors: subset_errors.complete(),
+        subset: subset.complete(),
+        origin_contains_loan_on_entry: origin_contains_loan_on_entry.complete(),
+        loan_live_at: loan_live_at.complete(),
+        aborted,
+    }
+}
+
+/// Records the intermediate relations of the naive rules, as verbose output data.
+pub(super) fn record_results<T: FactTypes>(results: &Results<T>, result: &mut Output<T>) {
+    assert!(
+        results
+            .subset
+            .iter()
+            .filter(|&(origin1, origin2, _)| origin1 == origin2)
+            .count()
+            == 0,
+        "unwanted subset symmetries"
+    );
+    for &(origin1, origin2, location) in results.subset.iter() {
+        result
+            .subset
+            .entry(location)
+            .or_default()
+            .entry(origin1)
+            .or_default()
+            .insert(origin2);
+    }
+
+    for &(origin, loan, location) in results.origin_contains_loan_on_entry.iter() {
+        result
+            .origin_contains_loan_at
+            .entry(location)
+            .or_default()
+            .entry(origin)
+            .or_default()
+            .insert(loan);
+    }
+}
+
+/// The transitive closure of the `subset` relation at a single point: a bit matrix over the
+/// origins seen at this point.
+struct PointClosure<O> {
+    indices: FxHashMap<O, usize>,
+    origins: Vec<O>,
+
+    /// `supersets[i]` contains the indices of the origins which the origin `i` is a subset of.
+    supersets: Vec<BitSet>,
+}
 
-            // Rule 1: done above, as part of the static input facts setup.
-
-            // Rule 2: compute the subset transitive closure, at a given point.
-            //
-            // subset(Origin1, Origin3, Point) :-
-            //   subset(Origin1, Origin2, Point),
-            //   subset(Origin2, Origin3, Point).
-            subset.from_join(
-                &subset_o2p,
-                &subset_o1p,
-                |&(_origin2, point), &origin1, &origin3| (origin1, origin3, point),
-            );
-
-            // Rule 3: propagate subsets along the CFG, according to liveness.
-            //
-            // subset(Origin1, Origin2, Point2) :-
-            //   subset(Origin1, Origin2, Point1),
-            //   cfg_edge(Point1, Point2),
-            //   origin_live_on_entry(Origin1, Point2),
-            //   origin_live_on_entry(Origin2, Point2).
-            subset.from_leapjoin(
-                &subset,
-                (
-                    cfg_edge.extend_with(|&(_origin1, _origin2, point1)| point1),
-                    origin_live_on_entry_rel.extend_with(|&(origin1, _origin2, _point1)| origin1),
-                    origin_live_on_entry_rel.extend_with(|&(_origin1, origin2, _point1)| origin2),
-                ),
-                |&(origin1, origin2, _point1), &point2| (origin1, origin2, point2),
-            );
-
-            // Rule 4: done above as part of the static input facts setup.
-
-            // Rule 5: propagate loans within origins, at a given point, according to subsets.
-            //
-            // origin_contains_loan_on_entry(Origin2, Loan, Point) :-
-            //   origin_contains_loan_on_entry(Origin1, Loan, Point),
-            //   subset(Origin1, Origin2, Point).
-            origin_contains_loan_on_entry.from_join(
-                &origin_contains_loan_on_entry_op,
-                &subset_o1p,
-                |&(_origin1, point), &loan, &origin2| (origin2, loan, point),
-            );
-
-            // Rule 6: propagate loans along the CFG, according to liveness.
-            //
-            // origin_contains_loan_on_entry(Origin, Loan, Point2) :-
-            //   origin_contains_loan_on_entry(Origin, Loan, Point1),
-            //   !loan_killed_at(Loan, Point1),
-            //   cfg_edge(Point1, Point2),
-            //   origin_live_on_entry(Origin, Point2).
-            origin_contains_loan_on_entry.from_leapjoin(
-                &origin_contains_loan_on_entry,
-                (
-                    loan_killed_at.filter_anti(|&(_origin, loan, point1)| (loan, point1)),
-                    cfg_edge.extend_with(|&(_origin, _loan, point1)| point1),
-                    origin_live_on_entry_rel.extend_with(|&(origin, _loan, _point1)| origin),
-                ),
-                |&(origin, loan, _point1), &point2| (origin, loan, point2),
-            );
-
-            // Rule 7: compute whether a loan is live at a given point, i.e. whether it is
-            // contained in a live origin at this point.
-            //
-            // loan_live_at(Loan, Point) :-
-            //   origin_contains_loan_on_entry(Origin, Loan, Point),
-            //   origin_live_on_entry(Origin, Point).
-            loan_live_at.from_join(
-                &origin_contains_loan_on_entry_op,
-                &origin_live_on_entry_var,
-                |&(_origin, point), &loan, _| ((loan, point), ()),
-            );
-
-            // Rule 8: compute illegal access errors, i.e. an invalidation of a live loan.
-            //
-            // Here again, this join acts as a pure filter and could be a more efficient leapjoin.
-            // However, similarly to the `origin_live_on_entry` example described above, the
-            // leapjoin with a single `filter_with` leaper would currently not be well-formed.
-            // We don't explictly need to materialize `loan_live_at` either, and that doesn't
-            // change the well-formedness situation, so we still materialize it (since that also
-            // helps in testing).
-            //
-            // errors(Loan, Point) :-
-            //   loan_invalidated_at(Loan, Point),
-            //   loan_live_at(Loan, Point).
-            errors.from_join(
-                &loan_live_at,
-                &loan_invalidated_at,
-                |&(loan, point), _, _| (loan, point),
-            );
-
-            // Rule 9: compute illegal subset relations errors, i.e. the undeclared subsets
-            // between two placeholder origins.
-            // Here as well, WF-ness prevents this join from being a filter-only leapjoin. It
-            // doesn't matter much, as `placeholder_origin` is single-value relation.
-            //
-            // subset_error(Origin1, Origin2, Point) :-
-            //   subset(Origin1, Origin2, Point),
-            //   placeholder_origin(Origin1),
-            //   placeholder_origin(Origin2),
-            //   !known_placeholder_subset(Origin1, Origin2).
-            subset_errors.from_leapjoin(
-                &subset,
-                (
-                    placeholder_origin.extend_with(|&(origin1, _origin2, _point)| origin1),
-                    placeholder_origin.extend_with(|&(_origin1, origin2, _point)| origin2),
-                    known_placeholder_subset
-                        .filter_anti(|&(origin1, origin2, _point)| (origin1, origin2)),
-                    // remove symmetries:
-                    datafrog::ValueFilter::from(|&(origin1, origin2, _point), _| {
-                        origin1 != origin2
-                    }),
-                ),
-                |&(origin1, origin2, point), _| (origin1, origin2, point),
-            );
+impl<O> Default for PointClosure<O> {
+    fn default() -> Self {
+        PointClosure {
+            indices: FxHashMap::default(),
+            origins: Vec::new(),
+            supersets: Vec::new(),
         }
+    }
+}
 
-        // Handle verbose output data
-        if result.dump_enabled {
-            let subset = subset.complete();
-            assert!(
-                subset
-                    .iter()
-                    .filter(|&(origin1, origin2, _)| origin1 == origin2)
-                    .count()
-                    == 0,
-                "unwanted subset symmetries"
-            );
-            for &(origin1, origin2, location) in subset.iter() {
-                result
-                    .subset
-                    .entry(location)
-                    .or_default()
-                    .entry(origin1)
-                    .or_default()
-                    .insert(origin2);
-            }
+impl<O: Copy + Eq + Hash> PointClosure<O> {
+    fn index(&mut self, origin: O) -> usize {
+        let origins = &mut self.origins;
+        let supersets = &mut self.supersets;
+        *self.indices.entry(origin).or_insert_with(|| {
+            origins.push(origin);
+            supersets.push(BitSet::default());
+            origins.len() - 1
+        })
+    }
+
+    /// Adds the `origin1 <= origin2` subset to the closure, and calls `on_subset` with each
+    /// subset between two distinct origins that this adds to the closure, including this one.
+    /// The reflexive subsets of the origins on a cycle are not reported, as they're not part of
+    /// the `subset` relation.
+    fn insert(&mut self, origin1: O, origin2: O, mut on_subset: impl FnMut(O, O)) {
+        let idx1 = self.index(origin1);
+        let idx2 = self.index(origin2);
+        if idx1 == idx2 || self.supersets[idx1].contains(idx2) {
+            return;
+        }
 
-            let origin_contains_loan_on_entry = origin_contains_loan_on_entry.complete();
-            for &(origin, loan, location) in origin_contains_loan_on_entry.iter() {
-                result
-                    .origin_contains_loan_at
-                    .entry(location)
-                    .or_default()
-                    .entry(origin)
-                    .or_default()
-                    .insert(loan);
-            }
+        // Every origin reaching `origin1`, including itself, now reaches `origin2` and every
+        // origin that `origin2` reaches.
+        let mut reached = self.supersets[idx2].clone();
+        reached.insert(idx2);
 
-            let loan_live_at = loan_live_at.complete();
-            for &((loan, location), _) in loan_live_at.iter() {
-                result.loan_live_at.entry(location).or_default().push(loan);
+        for idx in 0..self.origins.len() {
+            if idx != idx1 && !self.supersets[idx].contains(idx1) {
+                continue;
             }
+
+            let origins = &self.origins;
+            self.supersets[idx].union_with(&reached, |added| {
+                if added != idx {
+                    on_subset(origins[idx], origins[added]);
+                }
+            });
         }
+    }
+}
 
-        (errors.complete(), subset_errors.complete())
-    };
+/// A growable set of small integers.
+#[derive(Clone, Default)]
+struct BitSet {
+    words: Vec<u64>,
+}
 
-    info!(
-        "analysis done: {} `errors` tuples, {} `subset_errors` tuples, {:?}",
-        errors.len(),
-        subset_errors.len(),
-        timer.elapsed()
-    );
+impl BitSet {
+    fn contains(&self, idx: usize) -> bool {
+        match self.words.get(idx / 64) {
+            Some(word) => word & (1 << (idx % 64)) != 0,
+            None => false,
+        }
+    }
 
-    (errors, subset_errors)
+    fn insert(&mut self, idx: usize) {
+        if self.words.len() <= idx / 64 {
+            self.words.resize(idx / 64 + 1, 0);
+        }
+        self.words[idx / 64] |= 1 << (idx % 64);
+    }
+
+    /// Adds the elements of `other` to this set, and calls `on_added` with the ones which were
+    /// missing.
+    fn union_with(&mut self, other: &BitSet, mut on_added: impl FnMut(usize)) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word_idx, (word, &other_word)) in self.words.iter_mut().zip(&other.words).enumerate() {
+            let mut added = other_word & !*word;
+            *word |= other_word;
+            while added != 0 {
+                let bit = added.trailing_zeros() as usize;
+                on_added(word_idx * 64 + bit);
+                added &= added - 1;
+            }
+        }
+    }
 }
@@ -0,0 +1,500 @@
+// Copyright 2019 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `Naive` rules, evaluated separately on each strongly-connected component of the CFG.
+//!
+//! Subsets and loans only flow forward along the CFG edges: the results at a point only depend
+//! on the facts at this point, and on the results at its predecessors. Once the components
+//! preceding a component have been computed, the results of this component can be computed in
+//! isolation, seeded with the results at its predecessors.
+//!
+//! The components are grouped in layers of the CFG's condensation, where the components of a
+//! layer only depend on the components of the previous layers. With the `rayon` feature, the
+//! components of a layer are evaluated in parallel.
+
+use crate::time::Instant;
+use datafrog::Relation;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::facts::FactTypes;
+use crate::join::JoinBackend;
+use crate::output::cfg::Cfg;
+use crate::output::naive::{self, Inputs, Results};
+use crate::output::{record_loan_live_at, Context, Output, RoundLimitExceeded, ShallowKill};
+
+pub(super) fn compute<T: FactTypes, B: JoinBackend>(
+    ctx: &Context<'_, T>,
+    result: &mut Output<T>,
+) -> ComputedErrors<T> {
+    let timer = Instant::now();
+
+    let facts = PointFacts::new(ctx);
+    let components = strongly_connected_components(&facts.points, &ctx.cfg);
+    let layers = condensation_layers(&components, &ctx.cfg);
+
+    info!(
+        points = facts.points.len(),
+        components = components.len(),
+        layers = layers.len(),
+        "condensed the CFG"
+    );
+
+    // The results at the points of the components computed so far, to seed their successors.
+    let mut computed = Computed::<T>::default();
+    let mut errors = Vec::new();
+    let mut subset_errors = Vec::new();
+    let mut loan_live_at = Vec::new();
+
+    for (index, layer) in layers.iter().enumerate() {
+        let _round = ctx.report_round(index + 1, || {
+            let subset: usize = computed.subset.values().map(Vec::len).sum();
+            let origin_contains_loan_on_entry: usize = computed
+                .origin_contains_loan_on_entry
+                .values()
+                .map(Vec::len)
+                .sum();
+            subset + origin_contains_loan_on_entry
+        });
+
+        let evaluate = |&component: &usize| {
+            evaluate_component::<T, B>(&components[component], &facts, &computed, ctx)
+        };
+
+        #[cfg(feature = "rayon")]
+        let layer_results: Vec<Results<T>> = layer.par_iter().map(evaluate).collect();
+
+        #[cfg(not(feature = "rayon"))]
+        let layer_results: Vec<Results<T>> = layer.iter().map(evaluate).collect();
+
+        for results in layer_results {
+            if let Some(aborted) = results.aborted {
+                result.aborted.get_or_insert(RoundLimitExceeded {
+                    phase: "ParallelNaive",
+                    ..aborted
+                });
+            }
+            errors.extend(results.errors.iter());
+            subset_errors.extend(results.subset_errors.iter());
+            if ctx.record_loan_live_at {
+                loan_live_at.extend(results.loan_live_at.iter());
+            }
+
+            for &(origin1, origin2, point) in results.subset.iter() {
+                computed
+                    .subset
+                    .entry(point)
+                    .or_default()
+                    .push((origin1, origin2, point));
+            }
+            for &(origin, loan, point) in results.origin_contains_loan_on_entry.iter() {
+                computed
+                    .origin_contains_loan_on_entry
+                    .entry(point)
+                    .or_default()
+                    .push((origin, loan, point));
+            }
+        }
+
+        if result.aborted.is_some() {
+            break;
+        }
+    }
+
+    let errors = Relation::from_vec(errors);
+    let subset_errors = Relation::from_vec(subset_errors);
+
+    if result.dump_enabled {
+        let results = Results {
+            errors: Vec::new().into(),
+            subset_errors: Vec::new().into(),
+            subset: Relation::from_iter(computed.subset.values().flatten().cloned()),
+            origin_contains_loan_on_entry: Relation::from_iter(
+                computed
+                    .origin_contains_loan_on_entry
+                    .values()
+                    .flatten()
+                    .cloned(),
+            ),
+            loan_live_at: Vec::new().into(),
+            aborted: None,
+        };
+        naive::record_results(&results, result);
+    }
+    if ctx.record_loan_live_at {
+        let loan_live_at = Relation::from_vec(loan_live_at);
+        record_loan_live_at(loan_live_at.iter().map(|&(tuple, ())| tuple), result);
+    }
+
+    info!(
+        errors = errors.len(),
+        subset_errors = subset_errors.len(),
+        elapsed = ?timer.elapsed(),
+        "analysis done"
+    );
+
+    (errors, subset_errors)
+}
+
+/// Tuples indexed by their point.
+type ByPoint<T, Tuple> = FxHashMap<<T as FactTypes>::Point, Vec<Tuple>>;
+
+/// The `errors` and `subset_errors` computed by the variant.
+type ComputedErrors<T> = (
+    Relation<(<T as FactTypes>::Loan, <T as FactTypes>::Point)>,
+    Relation<SubsetTuple<T>>,
+);
+
+/// The `(origin1, origin2, point)` tuples of `subset_base` and `subset`.
+type SubsetTuple<T> = (
+    <T as FactTypes>::Origin,
+    <T as FactTypes>::Origin,
+    <T as FactTypes>::Point,
+);
+
+/// The `(origin, loan, point)` tuples of `loan_issued_at` and `origin_contains_loan_on_entry`.
+type LoanTuple<T> = (
+    <T as FactTypes>::Origin,
+    <T as FactTypes>::Loan,
+    <T as FactTypes>::Point,
+);
+
+/// The point-indexed input facts.
+struct PointFacts<T: FactTypes> {
+    /// All the points mentioned by the CFG or the borrow checking facts.
+    points: Vec<T::Point>,
+    subset_base: ByPoint<T, SubsetTuple<T>>,
+    loan_issued_at: ByPoint<T, LoanTuple<T>>,
+    loan_killed_at: ByPoint<T, (T::Loan, T::Point)>,
+    loan_shallow_killed_at: ByPoint<T, ShallowKill<T>>,
+    loan_invalidated_at: ByPoint<T, (T::Loan, T::Point)>,
+    origin_live_on_entry: ByPoint<T, (T::Origin, T::Point)>,
+}
+
+impl<T: FactTypes> PointFacts<T> {
+    fn new(ctx: &Context<'_, T>) -> Self {
+        let subset_base = index_by_point(ctx.subset_base.iter(), |&(_, _, point)| point);
+        let loan_issued_at = index_by_point(ctx.loan_issued_at.iter(), |&(_, _, point)| point);
+        let loan_killed_at = index_by_point(ctx.loan_killed_at.iter(), |&(_, point)| point);
+        let loan_shallow_killed_at =
+            index_by_point(ctx.loan_shallow_killed_at.iter(), |&(_, point)| point);
+        let loan_invalidated_at =
+            index_by_point(ctx.loan_invalidated_at.iter(), |&(_, point)| point);
+        let origin_live_on_entry =
+            index_by_point(ctx.origin_live_on_entry.iter(), |&(_, point)| point);
+
+        let mut points: Vec<_> = ctx
+            .cfg
+            .nodes
+            .iter()
+            .chain(subset_base.keys())
+            .chain(loan_issued_at.keys())
+            .chain(loan_invalidated_at.keys())
+            .cloned()
+            .collect();
+        points.sort();
+        points.dedup();
+
+        PointFacts {
+            points,
+            subset_base,
+            loan_issued_at,
+            loan_killed_at,
+            loan_shallow_killed_at,
+            loan_invalidated_at,
+            origin_live_on_entry,
+        }
+    }
+}
+
+fn index_by_point<'a, Tuple: Copy + 'a, P: Copy + Eq + std::hash::Hash>(
+    tuples: impl Iterator<Item = &'a Tuple>,
+    point: impl Fn(&Tuple) -> P,
+) -> FxHashMap<P, Vec<Tuple>> {
+    let mut index: FxHashMap<P, Vec<Tuple>> = FxHashMap::default();
+    for tuple in tuples {
+        index.entry(point(tuple)).or_default().push(*tuple);
+    }
+    index
+}
+
+/// The results at the points of the components already evaluated.
+struct Computed<T: FactTypes> {
+    subset: ByPoint<T, SubsetTuple<T>>,
+    origin_contains_loan_on_entry: ByPoint<T, LoanTuple<T>>,
+}
+
+impl<T: FactTypes> Default for Computed<T> {
+    fn default() -> Self {
+        Computed {
+            subset: FxHashMap::default(),
+            origin_contains_loan_on_entry: FxHashMap::default(),
+        }
+    }
+}
+
+/// Evaluates the naive rules on the points of the `component`: the results at its predecessors
+/// from other components are added to its inputs, and propagated into the component along the
+/// CFG edges. Only the results at the points of the component are returned.
+fn evaluate_component<T: FactTypes, B: JoinBackend>(
+    component: &[T::Point],
+    facts: &PointFacts<T>,
+    computed: &Computed<T>,
+    ctx: &Context<'_, T>,
+) -> Results<T> {
+    let in_component: FxHashSet<T::Point> = component.iter().cloned().collect();
+    let mut external_predecessors = FxHashSet::default();
+
+    let mut subset_base = Vec::new();
+    let mut loan_issued_at = Vec::new();
+    let mut loan_killed_at = Vec::new();
+    let mut loan_shallow_killed_at = Vec::new();
+    let mut loan_invalidated_at = Vec::new();
+    let mut origin_live_on_entry = Vec::new();
+    let mut cfg_edge = Vec::new();
+
+    fn extend<K: Eq + std::hash::Hash, V: Copy>(
+        tuples: &mut Vec<V>,
+        index: &FxHashMap<K, Vec<V>>,
+        key: &K,
+    ) {
+        if let Some(values) = index.get(key) {
+            tuples.extend_from_slice(values);
+        }
+    }
+
+    for point in component {
+        extend(&mut subset_base, &facts.subset_base, point);
+        extend(&mut loan_issued_at, &facts.loan_issued_at, point);
+        extend(&mut loan_killed_at, &facts.loan_killed_at, point);
+        extend(
+            &mut loan_shallow_killed_at,
+            &facts.loan_shallow_killed_at,
+            point,
+        );
+        extend(&mut loan_invalidated_at, &facts.loan_invalidated_at, point);
+        extend(
+            &mut origin_live_on_entry,
+            &facts.origin_live_on_entry,
+            point,
+        );
+
+        for &predecessor in ctx.cfg.predecessors(*point) {
+            cfg_edge.push((predecessor, *point));
+
+            if !in_component.contains(&predecessor) && external_predecessors.insert(predecessor) {
+                // The results at the predecessor are complete: they are seeds, which will only
+                // flow into the component, where the loans they contain can be killed.
+                extend(&mut subset_base, &computed.subset, &predecessor);
+                extend(
+                    &mut loan_issued_at,
+                    &computed.origin_contains_loan_on_entry,
+                    &predecessor,
+                );
+                extend(&mut loan_killed_at, &facts.loan_killed_at, &predecessor);
+                extend(
+                    &mut loan_shallow_killed_at,
+                    &facts.loan_shallow_killed_at,
+                    &predecessor,
+                );
+            }
+        }
+    }
+
+    let origin_live_on_entry: Relation<_> = origin_live_on_entry.into();
+    let origin_live_on_entry_op = Relation::from_iter(
+        origin_live_on_entry
+            .iter()
+            .map(|&(origin, point)| ((origin, point), ())),
+    );
+    let loan_invalidated_at = Relation::from_iter(
+        loan_invalidated_at
+            .into_iter()
+            .map(|(loan, point)| ((loan, point), ())),
+    );
+
+    let inputs: Inputs<'_, T> = Inputs {
+        subset_base: &subset_base,
+        loan_issued_at: &loan_issued_at,
+        cfg_edge: &cfg_edge.into(),
+        origin_live_on_entry: &origin_live_on_entry,
+        origin_live_on_entry_op: &origin_live_on_entry_op,
+        loan_killed_at: &loan_killed_at.into(),
+        loan_shallow_killed_at: &loan_shallow_killed_at.into(),
+        loan_invalidated_at: &loan_invalidated_at,
+        known_placeholder_subset: &ctx.known_placeholder_subset,
+        placeholder_origin: &ctx.placeholder_origin,
+        // the components are evaluated concurrently: the progress is reported per layer instead
+        progress: None,
+        max_rounds: ctx.max_rounds,
+    };
+    let results = naive::evaluate::<T, B>(&inputs);
+
+    // The seeds at the external predecessors are not part of this component's results.
+    Results {
+        errors: Relation::from_iter(
+            results
+                .errors
+                .iter()
+                .filter(|&&(_, point)| in_component.contains(&point))
+                .cloned(),
+        ),
+        subset_errors: Relation::from_iter(
+            results
+                .subset_errors
+                .iter()
+                .filter(|&&(_, _, point)| in_component.contains(&point))
+                .cloned(),
+        ),
+        subset: Relation::from_iter(
+            results
+                .subset
+                .iter()
+                .filter(|&&(_, _, point)| in_component.contains(&point))
+                .cloned(),
+        ),
+        origin_contains_loan_on_entry: Relation::from_iter(
+            results
+                .origin_contains_loan_on_entry
+                .iter()
+                .filter(|&&(_, _, point)| in_component.contains(&point))
+                .cloned(),
+        ),
+        loan_live_at: Relation::from_iter(
+            results
+                .loan_live_at
+                .iter()
+                .filter(|&&((_, point), ())| in_component.contains(&point))
+                .cloned(),
+        ),
+        aborted: results.aborted,
+    }
+}
+
+/// Computes the strongly-connected components of the CFG, with Tarjan's algorithm, in reverse
+/// topological order.
+fn strongly_connected_components<T: FactTypes>(
+    points: &[T::Point],
+    cfg: &Cfg<T>,
+) -> Vec<Vec<T::Point>> {
+    let ids: FxHashMap<T::Point, usize> = points
+        .iter()
+        .enumerate()
+        .map(|(id, &point)| (point, id))
+        .collect();
+    let successors: Vec<Vec<usize>> = points
+        .iter()
+        .map(|point| {
+            cfg.successors(*point)
+                .iter()
+                .map(|successor| ids[successor])
+                .collect()
+        })
+        .collect();
+
+    let mut next_index = 0;
+    let mut index: Vec<Option<usize>> = vec![None; points.len()];
+    let mut lowlink = vec![0; points.len()];
+    let mut on_stack = vec![false; points.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+
+    for root in 0..points.len() {
+        if index[root].is_some() {
+            continue;
+        }
+
+        // The nodes being visited, with the position of their next successor to visit: an
+        // explicit call stack, as CFGs can be too deep for recursion.
+        let mut visiting = vec![(root, 0)];
+        index[root] = Some(next_index);
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&(node, position)) = visiting.last() {
+            if let Some(&successor) = successors[node].get(position) {
+                visiting.last_mut().unwrap().1 += 1;
+                match index[successor] {
+                    None => {
+                        index[successor] = Some(next_index);
+                        lowlink[successor] = next_index;
+                        next_index += 1;
+                        stack.push(successor);
+                        on_stack[successor] = true;
+                        visiting.push((successor, 0));
+                    }
+                    Some(successor_index) if on_stack[successor] => {
+                        lowlink[node] = lowlink[node].min(successor_index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            visiting.pop();
+            if let Some(&(parent, _)) = visiting.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+
+            if Some(lowlink[node]) == index[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(points[member]);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Groups the `components`, given in reverse topological order, in layers: each component is in
+/// the layer following the last layer of its predecessors.
+fn condensation_layers<T: FactTypes>(
+    components: &[Vec<T::Point>],
+    cfg: &Cfg<T>,
+) -> Vec<Vec<usize>> {
+    let component_of: FxHashMap<T::Point, usize> = components
+        .iter()
+        .enumerate()
+        .flat_map(|(component, points)| points.iter().map(move |&point| (point, component)))
+        .collect();
+
+    let mut layer_of = vec![0; components.len()];
+    let mut layers: Vec<Vec<usize>> = Vec::new();
+    for component in (0..components.len()).rev() {
+        let layer = components[component]
+            .iter()
+            .flat_map(|&point| cfg.predecessors(point))
+            .map(|predecessor| component_of[predecessor])
+            .filter(|&predecessor_component| predecessor_component != component)
+            .map(|predecessor_component| layer_of[predecessor_component] + 1)
+            .max()
+            .unwrap_or(0);
+        layer_of[component] = layer;
+
+        if layers.len() <= layer {
+            layers.resize(layer + 1, Vec::new());
+        }
+        layers[layer].push(component);
+    }
+
+    layers
+}
@@ -0,0 +1,227 @@
+// Copyright 2019 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A conservative pre-pass removing the borrow checking facts which provably cannot contribute
+//! to any error, so that the expensive variants have less data to process.
+//!
+//! The analysis is flow-insensitive: it only looks at which origins can reach one another via
+//! `subset_base`, ignoring the points at which these subsets hold. It is therefore an
+//! over-approximation of what the location-sensitive variants can derive, and pruning according
+//! to it does not change the errors they compute.
+
+use crate::time::Instant;
+use datafrog::{Iteration, Relation};
+use rustc_hash::FxHashSet;
+use std::fmt;
+
+use crate::facts::{AllFacts, FactTypes};
+use crate::output::Output;
+
+/// The number of tuples in each borrow checking relation, before and after pruning.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruningStats {
+    pub loans: (usize, usize),
+    pub loan_issued_at: (usize, usize),
+    pub loan_killed_at: (usize, usize),
+    pub loan_invalidated_at: (usize, usize),
+    pub subset_base: (usize, usize),
+}
+
+impl PruningStats {
+    /// The total number of tuples removed from the input relations.
+    pub fn pruned_tuples(&self) -> usize {
+        [
+            self.loan_issued_at,
+            self.loan_killed_at,
+            self.loan_invalidated_at,
+            self.subset_base,
+        ]
+        .iter()
+        .map(|&(before, after)| before - after)
+        .sum()
+    }
+}
+
+impl fmt::Display for PruningStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = [
+            ("loans", self.loans),
+            ("loan_issued_at", self.loan_issued_at),
+            ("loan_killed_at", self.loan_killed_at),
+            ("loan_invalidated_at", self.loan_invalidated_at),
+            ("subset_base", self.subset_base),
+        ];
+        for (idx, &(name, (before, after))) in rows.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{}: {} -> {} ({} pruned)",
+                name,
+                before,
+                after,
+                before - after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a copy of `all_facts` where the loans and subsets which cannot lead to an error
+/// have been removed, along with statistics about what was pruned:
+/// - a loan can only be an error if it is invalidated, and if the origin it is issued into can
+///   flow, via `subset_base`, into an origin which can ever be live. Placeholder loans are
+///   always kept, as they are involved in illegal subset errors.
+/// - a `subset_base` edge is only useful if loans can flow into its source origin, and out of
+///   its target origin into a potentially live origin.
+///
+/// The initialization and liveness facts are left untouched.
+pub fn prune_facts<T: FactTypes>(all_facts: &AllFacts<T>) -> (AllFacts<T>, PruningStats) {
+    let timer = Instant::now();
+
+    let subset_edges: Relation<(T::Origin, T::Origin)> = Relation::from_iter(
+        all_facts
+            .subset_base
+            .iter()
+            .map(|&(origin1, origin2, _point)| (origin1, origin2)),
+    );
+    let subset_edges_reverse: Relation<(T::Origin, T::Origin)> = Relation::from_iter(
+        subset_edges
+            .iter()
+            .map(|&(origin1, origin2)| (origin2, origin1)),
+    );
+
+    // The placeholder origins, as the variants find them: they are live everywhere, and contain
+    // their placeholder loans.
+    let placeholder_origins =
+        Output::<T>::placeholder_origins(&Output::<T>::compute_origin_kind(all_facts));
+
+    // The origins which can be live at some point: the ones which a variable's use or drop
+    // can make live, the placeholders which are live everywhere, and the ones of the
+    // precomputed liveness, if any.
+    let live_origins = all_facts
+        .use_of_var_derefs_origin
+        .iter()
+        .chain(all_facts.drop_of_var_derefs_origin.iter())
+        .map(|&(_var, origin)| origin)
+        .chain(placeholder_origins.iter().cloned())
+        .chain(
+            all_facts
+                .origin_live_on_entry
+                .iter()
+                .map(|&(origin, _point)| origin),
+        );
+
+    // reaches_live_origin(Origin1) :-
+    //   subset_base(Origin1, Origin2, _),
+    //   reaches_live_origin(Origin2).
+    let reaches_live_origin = reachable_origins::<T>(live_origins, &subset_edges_reverse);
+
+    let placeholder_loans: FxHashSet<T::Loan> = all_facts
+        .placeholder
+        .iter()
+        .map(|&(_origin, loan)| loan)
+        .collect();
+    let invalidated_loans: FxHashSet<T::Loan> = all_facts
+        .loan_invalidated_at
+        .iter()
+        .map(|&(loan, _point)| loan)
+        .collect();
+
+    let relevant_loans: FxHashSet<T::Loan> = all_facts
+        .loan_issued_at
+        .iter()
+        .filter(|&&(origin, loan, _point)| {
+            invalidated_loans.contains(&loan) && reaches_live_origin.contains(&origin)
+        })
+        .map(|&(_origin, loan, _point)| loan)
+        .chain(placeholder_loans.iter().cloned())
+        .collect();
+
+    // The origins where loans can be found: the ones they're issued into, the placeholders,
+    // and the ones these can flow into.
+    let loan_origins = all_facts
+        .loan_issued_at
+        .iter()
+        .filter(|&&(_origin, loan, _point)| relevant_loans.contains(&loan))
+        .map(|&(origin, _loan, _point)| origin)
+        .chain(all_facts.placeholder.iter().map(|&(origin, _loan)| origin))
+        .chain(placeholder_origins.iter().cloned());
+
+    // reachable_from_loan(Origin2) :-
+    //   reachable_from_loan(Origin1),
+    //   subset_base(Origin1, Origin2, _).
+    let reachable_from_loan = reachable_origins::<T>(loan_origins, &subset_edges);
+
+    let mut pruned = all_facts.clone();
+    pruned
+        .loan_issued_at
+        .retain(|&(_origin, loan, _point)| relevant_loans.contains(&loan));
+    pruned
+        .loan_killed_at
+        .retain(|&(loan, _point)| relevant_loans.contains(&loan));
+    pruned
+        .loan_shallow_killed_at
+        .retain(|&(loan, _point)| relevant_loans.contains(&loan));
+    pruned
+        .loan_invalidated_at
+        .retain(|&(loan, _point)| relevant_loans.contains(&loan));
+    pruned.subset_base.retain(|&(origin1, origin2, _point)| {
+        reachable_from_loan.contains(&origin1) && reaches_live_origin.contains(&origin2)
+    });
+
+    let all_loans: FxHashSet<T::Loan> = all_facts
+        .loan_issued_at
+        .iter()
+        .map(|&(_origin, loan, _point)| loan)
+        .chain(placeholder_loans.iter().cloned())
+        .chain(invalidated_loans.iter().cloned())
+        .collect();
+
+    let stats = PruningStats {
+        loans: (all_loans.len(), relevant_loans.len()),
+        loan_issued_at: (all_facts.loan_issued_at.len(), pruned.loan_issued_at.len()),
+        loan_killed_at: (all_facts.loan_killed_at.len(), pruned.loan_killed_at.len()),
+        loan_invalidated_at: (
+            all_facts.loan_invalidated_at.len(),
+            pruned.loan_invalidated_at.len(),
+        ),
+        subset_base: (all_facts.subset_base.len(), pruned.subset_base.len()),
+    };
+
+    info!(
+        pruned_tuples = stats.pruned_tuples(),
+        elapsed = ?timer.elapsed(),
+        "pruning done"
+    );
+
+    (pruned, stats)
+}
+
+/// Computes the set of origins reachable from the `roots`, following the given `edges`.
+fn reachable_origins<T: FactTypes>(
+    roots: impl Iterator<Item = T::Origin>,
+    edges: &Relation<(T::Origin, T::Origin)>,
+) -> FxHashSet<T::Origin> {
+    let mut iteration = Iteration::new();
+    let reachable = iteration.variable::<(T::Origin, ())>("reachable");
+    reachable.extend(roots.map(|origin| (origin, ())));
+
+    while iteration.changed() {
+        reachable.from_join(&reachable, edges, |&_origin1, &(), &origin2| (origin2, ()));
+    }
+
+    reachable
+        .complete()
+        .iter()
+        .map(|&(origin, ())| origin)
+        .collect()
+}
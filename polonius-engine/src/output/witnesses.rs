@@ -0,0 +1,140 @@
+// Copyright 2019 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The witnesses of the subset errors: the `subset_base` facts which made a placeholder origin
+//! flow into another one, when their subset is not known to hold.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::facts::{AllFacts, FactTypes};
+use crate::output::cfg::Cfg;
+use crate::output::{SubsetErrorWitness, SubsetErrorWitnesses, SubsetErrors};
+
+/// The `subset_base` facts by their first origin: the origins each one flows into, and where.
+type SubsetsFrom<T> =
+    FxHashMap<<T as FactTypes>::Origin, Vec<(<T as FactTypes>::Origin, <T as FactTypes>::Point)>>;
+
+/// A chain of `subset_base` facts, each one's second origin being the next one's first origin.
+type SubsetChain<T> = Vec<(
+    <T as FactTypes>::Origin,
+    <T as FactTypes>::Origin,
+    <T as FactTypes>::Point,
+)>;
+
+/// Computes a witness for each of the `subset_errors`, from the facts at the points which can
+/// reach the error's point. Without a `cfg`, for the location-insensitive errors, all the facts
+/// are used.
+pub(super) fn compute_subset_error_witnesses<T: FactTypes>(
+    all_facts: &AllFacts<T>,
+    cfg: Option<&Cfg<T>>,
+    subset_errors: &SubsetErrors<T>,
+) -> SubsetErrorWitnesses<T> {
+    let mut witnesses = FxHashMap::default();
+
+    for (&point, errors) in subset_errors.iter() {
+        let reaching = cfg.map(|cfg| points_reaching(cfg, point));
+        let is_relevant = |point: &T::Point| match reaching {
+            Some(ref reaching) => reaching.contains(point),
+            None => true,
+        };
+
+        let mut subset_base: SubsetsFrom<T> = FxHashMap::default();
+        for &(origin1, origin2, point) in all_facts.subset_base.iter() {
+            if is_relevant(&point) {
+                subset_base
+                    .entry(origin1)
+                    .or_default()
+                    .push((origin2, point));
+            }
+        }
+
+        let witnesses_at_point: &mut BTreeMap<_, _> = witnesses.entry(point).or_default();
+        for &(origin1, origin2) in errors.iter() {
+            let subsets = match shortest_chain::<T>(&subset_base, origin1, origin2) {
+                Some(subsets) => subsets,
+                None => {
+                    debug!(
+                        "no witness found for the subset error {:?}: {:?} at {:?}",
+                        origin1, origin2, point
+                    );
+                    continue;
+                }
+            };
+
+            let origins: FxHashSet<T::Origin> = subsets
+                .iter()
+                .flat_map(|&(origin1, origin2, _)| vec![origin1, origin2])
+                .collect();
+            let loans = all_facts
+                .loan_issued_at
+                .iter()
+                .filter(|&(origin, _, point)| origins.contains(origin) && is_relevant(point))
+                .cloned()
+                .collect();
+
+            witnesses_at_point.insert((origin1, origin2), SubsetErrorWitness { subsets, loans });
+        }
+    }
+
+    witnesses
+}
+
+/// The points from which the `point` can be reached in the CFG, including itself.
+fn points_reaching<T: FactTypes>(cfg: &Cfg<T>, point: T::Point) -> FxHashSet<T::Point> {
+    let mut reaching = FxHashSet::default();
+    reaching.insert(point);
+
+    let mut stack = vec![point];
+    while let Some(point) = stack.pop() {
+        for &predecessor in cfg.predecessors(point) {
+            if reaching.insert(predecessor) {
+                stack.push(predecessor);
+            }
+        }
+    }
+    reaching
+}
+
+/// A shortest chain of `subset_base` facts from `origin1` to `origin2`, found breadth-first.
+fn shortest_chain<T: FactTypes>(
+    subset_base: &SubsetsFrom<T>,
+    origin1: T::Origin,
+    origin2: T::Origin,
+) -> Option<SubsetChain<T>> {
+    // The fact by which each origin was first reached.
+    let mut reached_by: FxHashMap<T::Origin, (T::Origin, T::Point)> = FxHashMap::default();
+    let mut queue = VecDeque::new();
+    queue.push_back(origin1);
+
+    while let Some(origin) = queue.pop_front() {
+        if origin == origin2 {
+            break;
+        }
+
+        for &(successor, point) in subset_base.get(&origin).into_iter().flatten() {
+            if successor != origin1 && !reached_by.contains_key(&successor) {
+                reached_by.insert(successor, (origin, point));
+                queue.push_back(successor);
+            }
+        }
+    }
+
+    // Walk back the chain from `origin2`.
+    let mut chain = Vec::new();
+    let mut origin = origin2;
+    while origin != origin1 {
+        let &(predecessor, point) = reached_by.get(&origin)?;
+        chain.push((predecessor, origin, point));
+        origin = predecessor;
+    }
+    chain.reverse();
+    Some(chain)
+}
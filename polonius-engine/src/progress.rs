@@ -0,0 +1,39 @@
+//! Reporting the progress of a computation as it goes, to follow the long ones: e.g. to tell a
+//! computation still converging from a hung one.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// What the computation is doing, reported to a `ProgressHook`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// A phase of the computation starts: `initialization`, `liveness`, or the borrow checking
+    /// of a variant, named after it, e.g. `DatafrogOpt`.
+    Phase(&'static str),
+
+    /// A round of the fixpoint iteration of the current phase starts, with this total number of
+    /// tuples in its main relations, found by the previous rounds. The `ParallelNaive` variant
+    /// reports a round per layer of the CFG's components instead.
+    Round { round: usize, tuples: usize },
+}
+
+/// A callback receiving the `ProgressEvent`s of a computation, see `ComputeOptions::progress`.
+/// It's called on the thread running the computation.
+#[derive(Clone)]
+pub struct ProgressHook(Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressHook {
+    pub fn new(report: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        ProgressHook(Arc::new(report))
+    }
+
+    pub(crate) fn report(&self, event: ProgressEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
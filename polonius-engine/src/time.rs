@@ -0,0 +1,37 @@
+//! Timing of the analysis steps, for logging purposes.
+//!
+//! `std::time::Instant` panics on `wasm32-unknown-unknown`, which has no clock: on this target,
+//! timings are not measured.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use self::unmeasured::Instant;
+
+#[cfg(target_arch = "wasm32")]
+mod unmeasured {
+    use std::fmt;
+
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct Instant;
+
+    impl Instant {
+        pub(crate) fn now() -> Self {
+            Instant
+        }
+
+        pub(crate) fn elapsed(&self) -> Unmeasured {
+            Unmeasured
+        }
+    }
+
+    /// An unknown duration, displayed as such in the logs.
+    pub(crate) struct Unmeasured;
+
+    impl fmt::Debug for Unmeasured {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "(unmeasured)")
+        }
+    }
+}
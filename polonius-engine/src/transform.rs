@@ -0,0 +1,35 @@
+//! Transforming the facts before a computation, behind the `FactTransform` trait, e.g. to inject
+//! synthetic facts, rename origins, or prune the facts, as an extension point for experiments
+//! which doesn't need to patch the loaders: the transforms are applied in order by
+//! `Output::compute_with_transforms`.
+
+use crate::facts::{AllFacts, FactTypes};
+use crate::output::prune_facts;
+
+pub trait FactTransform<T: FactTypes> {
+    /// Modifies the facts which will be computed.
+    fn transform(&self, all_facts: &mut AllFacts<T>);
+}
+
+/// Any function modifying the facts is a transform.
+impl<T: FactTypes, F> FactTransform<T> for F
+where
+    F: Fn(&mut AllFacts<T>),
+{
+    fn transform(&self, all_facts: &mut AllFacts<T>) {
+        self(all_facts)
+    }
+}
+
+/// The transform removing the loans and subsets which cannot lead to an error, see
+/// `prune_facts`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pruning;
+
+impl<T: FactTypes> FactTransform<T> for Pruning {
+    fn transform(&self, all_facts: &mut AllFacts<T>) {
+        let (pruned_facts, stats) = prune_facts(all_facts);
+        debug!(?stats, "pruned the facts");
+        *all_facts = pruned_facts;
+    }
+}
@@ -0,0 +1,445 @@
+//! Helpers to generate the Polonius input facts from a compiler's control flow graph, for
+//! frontends other than rustc: teaching compilers, MIR-like IRs, etc.
+//!
+//! The [`FactsBuilder`] follows rustc's conventions: each statement of a basic block has two
+//! points, its Start point and its Mid point, where the statement takes effect. The builder
+//! creates these points and the `cfg_edge` facts between them, and emits each fact at the point
+//! rustc would:
+//!
+//! ```
+//! use polonius_engine::{Algorithm, CompactFacts, Output};
+//! use polonius_facts::FactsBuilder;
+//!
+//! let mut builder = FactsBuilder::<CompactFacts>::new();
+//! let (origin, loan, variable) = (0.into(), 0.into(), 0.into());
+//! builder.use_of_var_derefs_origin(variable, origin);
+//!
+//! // bb0: `let r = &x;`, then `x = 1;` and `use(r);` in bb1
+//! let bb0 = builder.add_block();
+//! let borrow = builder.add_statement(bb0);
+//! builder.loan_issued_at(origin, loan, borrow);
+//! let bb1 = builder.add_block();
+//! builder.goto(bb0, bb1);
+//! let assignment = builder.add_statement(bb1);
+//! builder.loan_invalidated_at(loan, assignment);
+//! let usage = builder.add_statement(bb1);
+//! builder.var_used_at(variable, usage);
+//!
+//! let (facts, locations) = builder.finish();
+//! let output = Output::compute(&facts, Algorithm::Naive, false);
+//! let errors: Vec<_> = output
+//!     .errors
+//!     .keys()
+//!     .map(|&point| locations.point_name(point))
+//!     .collect();
+//! assert_eq!(errors, ["Start(bb1[0])"]);
+//! ```
+
+use std::fmt;
+
+use polonius_engine::{AllFacts, FactTypes};
+
+/// A basic block of the CFG, created by [`FactsBuilder::add_block`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BasicBlock(usize);
+
+impl fmt::Display for BasicBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+/// A statement in the CFG, created by [`FactsBuilder::add_statement`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    pub block: BasicBlock,
+    pub statement_index: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.block, self.statement_index)
+    }
+}
+
+/// One of the two points of a statement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RichLocation {
+    /// The point before the statement takes effect.
+    Start(Location),
+
+    /// The point where the statement takes effect.
+    Mid(Location),
+}
+
+impl fmt::Display for RichLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RichLocation::Start(location) => write!(f, "Start({})", location),
+            RichLocation::Mid(location) => write!(f, "Mid({})", location),
+        }
+    }
+}
+
+/// Maps the statements of the CFG to their points, and back. As in rustc, a statement's Start
+/// and Mid points are numbered consecutively, in the order the statements are created.
+pub struct LocationTable<T: FactTypes> {
+    /// The Start point of each statement of each block: its Mid point is the next one.
+    statements: Vec<Vec<T::Point>>,
+
+    /// The location of each point, by index.
+    locations: Vec<RichLocation>,
+}
+
+impl<T: FactTypes> LocationTable<T> {
+    /// The Start point of the statement at `location`.
+    pub fn start_index(&self, location: Location) -> T::Point {
+        self.statements[location.block.0][location.statement_index]
+    }
+
+    /// The Mid point of the statement at `location`.
+    pub fn mid_index(&self, location: Location) -> T::Point {
+        T::Point::from(self.start_index(location).into() + 1)
+    }
+
+    /// The statement `point` is one of the points of.
+    pub fn to_location(&self, point: T::Point) -> RichLocation {
+        self.locations[point.into()]
+    }
+
+    /// The name of the `point`, like `Mid(bb0[1])`, in the format of rustc's facts.
+    pub fn point_name(&self, point: T::Point) -> String {
+        self.to_location(point).to_string()
+    }
+
+    /// All the points, in order.
+    pub fn all_points(&self) -> impl Iterator<Item = T::Point> {
+        (0..self.locations.len()).map(T::Point::from)
+    }
+}
+
+/// Builds the CFG of a function and its facts, creating the points of its statements, see the
+/// [crate documentation](crate).
+///
+/// The facts about a statement are emitted at its Mid point, except for the invalidations of
+/// loans, which rustc emits at the statement's Start point.
+pub struct FactsBuilder<T: FactTypes> {
+    facts: AllFacts<T>,
+    locations: LocationTable<T>,
+
+    /// The `goto` edges between blocks, resolved once all their statements are known.
+    gotos: Vec<(BasicBlock, BasicBlock)>,
+
+    /// The subsets required at every point, emitted once all the points are known.
+    subsets_everywhere: Vec<(T::Origin, T::Origin)>,
+
+    /// The paths assigned on entry to the function, like its arguments.
+    paths_assigned_on_entry: Vec<T::Path>,
+}
+
+impl<T: FactTypes> Default for FactsBuilder<T> {
+    fn default() -> Self {
+        FactsBuilder {
+            facts: AllFacts::default(),
+            locations: LocationTable {
+                statements: Vec::new(),
+                locations: Vec::new(),
+            },
+            gotos: Vec::new(),
+            subsets_everywhere: Vec::new(),
+            paths_assigned_on_entry: Vec::new(),
+        }
+    }
+}
+
+impl<T: FactTypes> FactsBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The points of the statements created so far.
+    pub fn locations(&self) -> &LocationTable<T> {
+        &self.locations
+    }
+
+    /// Creates a basic block. The first one is the entry block of the function.
+    pub fn add_block(&mut self) -> BasicBlock {
+        self.locations.statements.push(Vec::new());
+        BasicBlock(self.locations.statements.len() - 1)
+    }
+
+    /// Creates a statement at the end of the `block`, with its Start and Mid points, and the
+    /// edges from the previous statement to them.
+    pub fn add_statement(&mut self, block: BasicBlock) -> Location {
+        let location = Location {
+            block,
+            statement_index: self.locations.statements[block.0].len(),
+        };
+
+        let start = T::Point::from(self.locations.locations.len());
+        let mid = T::Point::from(self.locations.locations.len() + 1);
+        self.locations.locations.push(RichLocation::Start(location));
+        self.locations.locations.push(RichLocation::Mid(location));
+        self.locations.statements[block.0].push(start);
+
+        if location.statement_index > 0 {
+            let previous = Location {
+                block,
+                statement_index: location.statement_index - 1,
+            };
+            let previous_mid = self.locations.mid_index(previous);
+            self.facts.cfg_edge.push((previous_mid, start));
+        }
+        self.facts.cfg_edge.push((start, mid));
+        location
+    }
+
+    /// Adds an edge from the last statement of the block `from`, its terminator, to the first
+    /// statement of the block `to`. The edge is created by [`finish`](Self::finish), where both
+    /// blocks must have statements.
+    pub fn goto(&mut self, from: BasicBlock, to: BasicBlock) {
+        self.gotos.push((from, to));
+    }
+
+    /// `loan_issued_at(origin, loan, point)`: the `loan` is created by a borrow at the
+    /// `location`, with the `origin`.
+    pub fn loan_issued_at(&mut self, origin: T::Origin, loan: T::Loan, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.loan_issued_at.push((origin, loan, point));
+    }
+
+    /// `loan_killed_at(loan, point)`: the path borrowed by the `loan` is overwritten at the
+    /// `location`.
+    pub fn loan_killed_at(&mut self, loan: T::Loan, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.loan_killed_at.push((loan, point));
+    }
+
+    /// `loan_invalidated_at(loan, point)`: the statement at the `location` accesses the path
+    /// borrowed by the `loan` in a way which conflicts with it. This fact is emitted at the
+    /// statement's Start point.
+    pub fn loan_invalidated_at(&mut self, loan: T::Loan, location: Location) {
+        let point = self.locations.start_index(location);
+        self.facts.loan_invalidated_at.push((loan, point));
+    }
+
+    /// `subset_base(origin1, origin2, point)`: the statement at the `location` requires the
+    /// `origin1: origin2` subset.
+    pub fn subset_base(&mut self, origin1: T::Origin, origin2: T::Origin, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.subset_base.push((origin1, origin2, point));
+    }
+
+    /// `subset_base(origin1, origin2, point)` at every point of the CFG: a subset required by
+    /// the types of the function, rather than by one of its statements.
+    pub fn subset_base_everywhere(&mut self, origin1: T::Origin, origin2: T::Origin) {
+        self.subsets_everywhere.push((origin1, origin2));
+    }
+
+    /// `var_defined_at(var, point)`: the variable `var` is overwritten at the `location`.
+    pub fn var_defined_at(&mut self, var: T::Variable, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.var_defined_at.push((var, point));
+    }
+
+    /// `var_used_at(var, point)`: the variable `var` is used, but not dropped, at the
+    /// `location`.
+    pub fn var_used_at(&mut self, var: T::Variable, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.var_used_at.push((var, point));
+    }
+
+    /// `var_dropped_at(var, point)`: the variable `var` is dropped at the `location`.
+    pub fn var_dropped_at(&mut self, var: T::Variable, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.var_dropped_at.push((var, point));
+    }
+
+    /// `path_assigned_at_base(path, point)`: the `path` is initialized at the `location`.
+    pub fn path_assigned_at_base(&mut self, path: T::Path, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.path_assigned_at_base.push((path, point));
+    }
+
+    /// `path_assigned_at_base(path, point)` at the entry point of the function: the `path` is
+    /// initialized on entry, like an argument.
+    pub fn path_assigned_on_entry(&mut self, path: T::Path) {
+        self.paths_assigned_on_entry.push(path);
+    }
+
+    /// `path_moved_at_base(path, point)`: the `path` is moved out at the `location`.
+    pub fn path_moved_at_base(&mut self, path: T::Path, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.path_moved_at_base.push((path, point));
+    }
+
+    /// `path_accessed_at_base(path, point)`: the `path` is accessed at the `location`.
+    pub fn path_accessed_at_base(&mut self, path: T::Path, location: Location) {
+        let point = self.locations.mid_index(location);
+        self.facts.path_accessed_at_base.push((path, point));
+    }
+
+    /// `child_path(child, parent)`: the path `child` is a direct child of the path `parent`.
+    pub fn child_path(&mut self, child: T::Path, parent: T::Path) {
+        self.facts.child_path.push((child, parent));
+    }
+
+    /// `path_is_var(path, var)`: the `path` is the root path of the variable `var`.
+    pub fn path_is_var(&mut self, path: T::Path, var: T::Variable) {
+        self.facts.path_is_var.push((path, var));
+    }
+
+    /// `use_of_var_derefs_origin(var, origin)`: the type of the variable `var` contains the
+    /// `origin`, which is dereferenced when `var` is used.
+    pub fn use_of_var_derefs_origin(&mut self, var: T::Variable, origin: T::Origin) {
+        self.facts.use_of_var_derefs_origin.push((var, origin));
+    }
+
+    /// `drop_of_var_derefs_origin(var, origin)`: the type of the variable `var` contains the
+    /// `origin`, which is used when `var` is dropped.
+    pub fn drop_of_var_derefs_origin(&mut self, var: T::Variable, origin: T::Origin) {
+        self.facts.drop_of_var_derefs_origin.push((var, origin));
+    }
+
+    /// `universal_region(origin)` and `placeholder(origin, loan)`: the `origin` is a placeholder
+    /// of the function's signature, whose loans are represented by the placeholder `loan`.
+    pub fn placeholder(&mut self, origin: T::Origin, loan: T::Loan) {
+        self.facts.universal_region.push(origin);
+        self.facts.placeholder.push((origin, loan));
+    }
+
+    /// `known_placeholder_subset(origin1, origin2)`: the `origin1: origin2` subset between
+    /// placeholders is declared or implied by the function's signature.
+    pub fn known_placeholder_subset(&mut self, origin1: T::Origin, origin2: T::Origin) {
+        self.facts.known_placeholder_subset.push((origin1, origin2));
+    }
+
+    /// Emits the facts which need all the points of the CFG, and returns all the facts with the
+    /// table of their points.
+    ///
+    /// Panics if a `goto` is from or to a block without statements.
+    pub fn finish(mut self) -> (AllFacts<T>, LocationTable<T>) {
+        for &(from, to) in &self.gotos {
+            let statements = |block: BasicBlock| {
+                let statements = &self.locations.statements[block.0];
+                assert!(
+                    !statements.is_empty(),
+                    "the block {} of a goto has no statements",
+                    block
+                );
+                statements.len()
+            };
+            let terminator = Location {
+                block: from,
+                statement_index: statements(from) - 1,
+            };
+            statements(to);
+            let entry = Location {
+                block: to,
+                statement_index: 0,
+            };
+            self.facts.cfg_edge.push((
+                self.locations.mid_index(terminator),
+                self.locations.start_index(entry),
+            ));
+        }
+
+        for &(origin1, origin2) in &self.subsets_everywhere {
+            self.facts.subset_base.extend(
+                self.locations
+                    .all_points()
+                    .map(|point| (origin1, origin2, point)),
+            );
+        }
+
+        if !self.paths_assigned_on_entry.is_empty() {
+            let entry = self.locations.statements.first().and_then(|s| s.first());
+            let &entry = entry.expect("the paths assigned on entry need an entry statement");
+            self.facts.path_assigned_at_base.extend(
+                self.paths_assigned_on_entry
+                    .iter()
+                    .map(|&path| (path, entry)),
+            );
+        }
+
+        (self.facts, self.locations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polonius_engine::compact::Point;
+    use polonius_engine::CompactFacts;
+
+    #[test]
+    fn points_and_edges() {
+        let mut builder = FactsBuilder::<CompactFacts>::new();
+        let bb0 = builder.add_block();
+        let bb1 = builder.add_block();
+        let bb2 = builder.add_block();
+        builder.goto(bb0, bb1);
+        builder.goto(bb0, bb2);
+        builder.goto(bb2, bb1);
+
+        let first = builder.add_statement(bb0);
+        let second = builder.add_statement(bb0);
+        builder.add_statement(bb2);
+        builder.add_statement(bb1);
+        assert_eq!(second.to_string(), "bb0[1]");
+        assert_eq!(builder.locations().mid_index(first), Point::from(1));
+
+        builder.loan_issued_at(0.into(), 0.into(), second);
+        builder.loan_invalidated_at(0.into(), second);
+        builder.subset_base_everywhere(0.into(), 1.into());
+
+        let (facts, locations) = builder.finish();
+        let names = |points: &[Point]| -> Vec<String> {
+            points
+                .iter()
+                .map(|&point| locations.point_name(point))
+                .collect()
+        };
+
+        let edges: Vec<_> = facts
+            .cfg_edge
+            .iter()
+            .map(|&(point1, point2)| names(&[point1, point2]).join(" -> "))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                "Start(bb0[0]) -> Mid(bb0[0])",
+                "Mid(bb0[0]) -> Start(bb0[1])",
+                "Start(bb0[1]) -> Mid(bb0[1])",
+                "Start(bb2[0]) -> Mid(bb2[0])",
+                "Start(bb1[0]) -> Mid(bb1[0])",
+                "Mid(bb0[1]) -> Start(bb1[0])",
+                "Mid(bb0[1]) -> Start(bb2[0])",
+                "Mid(bb2[0]) -> Start(bb1[0])",
+            ]
+        );
+
+        assert_eq!(names(&[facts.loan_issued_at[0].2]), ["Mid(bb0[1])"]);
+        assert_eq!(names(&[facts.loan_invalidated_at[0].1]), ["Start(bb0[1])"]);
+        assert_eq!(facts.subset_base.len(), 8);
+        assert_eq!(
+            locations.to_location(Point::from(5)),
+            RichLocation::Mid(Location {
+                block: bb2,
+                statement_index: 0
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the block bb1 of a goto has no statements")]
+    fn goto_to_an_empty_block() {
+        let mut builder = FactsBuilder::<CompactFacts>::new();
+        let bb0 = builder.add_block();
+        let bb1 = builder.add_block();
+        builder.add_statement(bb0);
+        builder.goto(bb0, bb1);
+        builder.finish();
+    }
+}
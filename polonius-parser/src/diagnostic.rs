@@ -0,0 +1,58 @@
+//! Renders diagnostics about a [`Span`] of the source, with a caret-style snippet.
+
+use crate::token::Span;
+
+/// Renders the `message` about the `span` of the `source`, followed by the source lines it covers,
+/// underlined with carets:
+///
+/// ```text
+/// error: unexpected token
+///  --> 3:13
+///   |
+/// 3 |             loan_invalidated(L0);
+///   |             ^^^^^^^^^^^^^^^^
+/// ```
+///
+/// An empty span, like the end of the input, is pointed at with a single caret.
+pub fn render_diagnostic(source: &str, span: Span, message: &str) -> String {
+    let start = (span.start as usize).min(source.len());
+    let end = (span.end as usize).clamp(start, source.len());
+
+    // The lines covered by the span, with the byte offset where they start.
+    let first_line = source[..start].matches('\n').count();
+    let mut lines = Vec::new();
+    let mut line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    for (index, text) in source[line_start..].split('\n').enumerate() {
+        lines.push((first_line + index + 1, line_start, text));
+        line_start += text.len() + 1;
+        if line_start >= end {
+            break;
+        }
+    }
+
+    let gutter = lines
+        .last()
+        .map_or(1, |&(number, _, _)| number.to_string().len());
+    let padding = " ".repeat(gutter);
+    let column = source[lines[0].1..start].chars().count() + 1;
+
+    let mut rendered = format!("error: {}\n", message);
+    rendered.push_str(&format!("{} --> {}:{}\n", padding, lines[0].0, column));
+    rendered.push_str(&format!("{} |\n", padding));
+    for &(number, offset, text) in &lines {
+        // the part of the line covered by the span, at least one character wide
+        let from = start.max(offset) - offset;
+        let to = (end.min(offset + text.len()) - offset).max(from);
+        let indent = text[..from].chars().count();
+        let width = text[from..to].chars().count().max(1);
+
+        rendered.push_str(&format!("{:>width$} | {}\n", number, text, width = gutter));
+        rendered.push_str(&format!(
+            "{} | {}{}\n",
+            padding,
+            " ".repeat(indent),
+            "^".repeat(width)
+        ));
+    }
+    rendered
+}
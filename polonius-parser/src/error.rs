@@ -8,7 +8,27 @@ pub enum ParseError {
         found: TokenKind,
         expected: Vec<TokenKind>,
         position: Span,
+        /// The keyword closest to a misspelled one found instead, if any.
+        suggestion: Option<TokenKind>,
     },
+    /// A string is missing its closing quote: its `position` extends to the end of the input.
+    UnterminatedString { position: Span },
+}
+
+impl ParseError {
+    /// The span of the source where the error is.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { position, .. }
+            | ParseError::UnterminatedString { position } => *position,
+        }
+    }
+
+    /// Renders the error with a snippet of the `source` it was found in, see
+    /// [`render_diagnostic`](crate::render_diagnostic).
+    pub fn render(&self, source: &str) -> String {
+        crate::render_diagnostic(source, self.span(), &String::from(self))
+    }
 }
 
 impl From<&ParseError> for String {
@@ -18,15 +38,24 @@ impl From<&ParseError> for String {
                 found,
                 expected,
                 position,
+                suggestion,
             } => {
-                format!(
+                let mut message = format!(
                     "Unexpected token at {}-{}: found '{}', but expected {}",
                     position.start,
                     position.end,
                     found,
                     token_list_to_string(expected)
-                )
+                );
+                if let Some(suggestion) = suggestion {
+                    message.push_str(&format!("; did you mean `{}`?", suggestion));
+                }
+                message
             }
+            ParseError::UnterminatedString { position } => format!(
+                "Unterminated string at {}-{}: expected a closing '\"'",
+                position.start, position.end
+            ),
         }
     }
 }
@@ -47,8 +76,38 @@ fn token_list_to_string(tokens: &[TokenKind]) -> String {
     let res: Vec<String> = tokens.iter().map(|token| format!("'{}'", token)).collect();
     let mut res = res.join(", ");
     if let Some(pos) = res.rfind(", ") {
-        res.replace_range(0..0, "one of ");
         res.replace_range(pos..=pos + 1, " or ");
+        res.replace_range(0..0, "one of ");
     }
     res
 }
+
+/// Returns the keyword among `keywords` closest to the misspelled `text`, if it is within a few
+/// edits of it: a third of the keyword's length.
+pub(crate) fn closest_keyword(text: &str, keywords: &[TokenKind]) -> Option<TokenKind> {
+    keywords
+        .iter()
+        .map(|&keyword| (edit_distance(text, &keyword.to_string()), keyword))
+        .filter(|&(distance, keyword)| distance <= keyword.to_string().len() / 3)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, keyword)| keyword)
+}
+
+/// The Levenshtein distance between `a` and `b`: the number of single-character insertions,
+/// deletions, and substitutions needed to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // The distances from the prefix of `a` seen so far to each prefix of `b`.
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous_diagonal + (a != b) as usize;
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+    distances[b.len()]
+}
@@ -1,5 +1,10 @@
 //! test input data model
 
+use std::fmt;
+use std::ops::Deref;
+
+use crate::token::Span;
+
 #[derive(Debug)]
 pub struct Input {
     pub placeholders: Vec<Placeholder>,
@@ -7,6 +12,10 @@ pub struct Input {
     pub blocks: Vec<Block>,
     pub use_of_var_derefs_origin: Vec<(String, String)>,
     pub drop_of_var_derefs_origin: Vec<(String, String)>,
+
+    /// The errors the program is expected to have, from its `expect { .. }` section: none when
+    /// the section is missing.
+    pub expectations: Vec<Spanned<Expectation>>,
 }
 
 impl Input {
@@ -16,6 +25,7 @@ impl Input {
         use_of_var_derefs_origin: Vec<(String, String)>,
         drop_of_var_derefs_origin: Vec<(String, String)>,
         blocks: Vec<Block>,
+        expectations: Vec<Spanned<Expectation>>,
     ) -> Input {
         // set-up placeholders as origins with a placeholder loan of the same name
         let placeholders: Vec<_> = placeholders
@@ -32,6 +42,7 @@ impl Input {
             use_of_var_derefs_origin,
             drop_of_var_derefs_origin,
             blocks,
+            expectations,
         }
     }
 }
@@ -41,15 +52,45 @@ pub struct Block {
     pub name: String,
     pub statements: Vec<Statement>,
     pub goto: Vec<String>,
+
+    /// The source of the whole block, from the `block` keyword to its closing brace.
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Statement {
-    /// Effects destined to be emitted at the Statement's Start point
-    pub effects_start: Vec<Effect>,
+    /// Effects destined to be emitted at the Statement's Start point: those of its
+    /// `on start { .. }` clause, or before the `/` in the shorthand `start effects / mid effects;`
+    pub effects_start: Vec<Spanned<Effect>>,
 
-    /// Effects destined to be emitted at the Statement's Mid point
-    pub effects: Vec<Effect>,
+    /// Effects destined to be emitted at the Statement's Mid point: those of its `on mid { .. }`
+    /// clause, or the statement's effects when there is no clause
+    pub effects: Vec<Spanned<Effect>>,
+
+    /// The source of the statement, up to its closing semicolon.
+    pub span: Span,
+}
+
+/// An element of the IR, with the span of the source it was parsed from. It can be compared to
+/// an unspanned element, ignoring the span.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Spanned<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.node == *other
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -64,6 +105,8 @@ pub enum Fact {
     LoanIssuedAt { origin: String, loan: String },
     LoanInvalidatedAt { loan: String },
     LoanKilledAt { loan: String },
+    LoanShallowKilledAt { loan: String },
+    LoanActivatedAt { loan: String },
     OriginLiveOnEntry { origin: String },
     DefineVariable { variable: String },
     UseVariable { variable: String },
@@ -75,6 +118,46 @@ pub struct KnownSubset {
     pub b: String,
 }
 
+/// An error expected in the output, at the point given as a string, like `"Mid(B0[1])"`, or
+/// anywhere when the point is omitted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expectation {
+    /// `error(L0, "Mid(B0[1])")`: the loan is invalidated while live.
+    Error { loan: String, point: Option<String> },
+    /// `subset_error('b, 'a, "Mid(B0[1])")`: the subset `'b: 'a` between placeholders is required
+    /// but not known.
+    SubsetError {
+        a: String,
+        b: String,
+        point: Option<String>,
+    },
+}
+
+impl Expectation {
+    /// The point the error is expected at, if any.
+    pub fn point(&self) -> Option<&str> {
+        match self {
+            Expectation::Error { point, .. } | Expectation::SubsetError { point, .. } => {
+                point.as_deref()
+            }
+        }
+    }
+}
+
+/// Displays the expectation as it is written in programs.
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Error { loan, .. } => write!(f, "error({}", loan)?,
+            Expectation::SubsetError { a, b, .. } => write!(f, "subset_error({}, {}", a, b)?,
+        }
+        if let Some(point) = self.point() {
+            write!(f, ", \"{}\"", point)?;
+        }
+        write!(f, ")")
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Placeholder {
     pub origin: String,
@@ -82,18 +165,19 @@ pub struct Placeholder {
 }
 
 impl Statement {
-    pub(crate) fn new(effects: Vec<Effect>) -> Self {
+    pub(crate) fn new(effects: Vec<Spanned<Effect>>, span: Span) -> Self {
         // Anything live on entry to the "mid point" is also live on
         // entry to the start point.
         let effects_start = effects
             .iter()
-            .filter(|effect| matches!(effect, Effect::Fact(Fact::OriginLiveOnEntry { .. })))
+            .filter(|effect| matches!(effect.node, Effect::Fact(Fact::OriginLiveOnEntry { .. })))
             .cloned()
             .collect();
 
         Self {
             effects_start,
             effects,
+            span,
         }
     }
 }
@@ -10,7 +10,10 @@ use crate::{
 /// The primary way to use the lexer is through its implementation of [`Iterator`], which produces
 /// [`Token`]s lazily.
 /// A single [end-of-file token](crate::token::TokenKind::Eof) will be created at the end of the input.
-/// Erroneous inputs will result in [`T![error]`](crate::token::TokenKind::Error) tokens.
+/// Erroneous inputs will result in [`T![error]`](crate::token::TokenKind::Error) tokens, and a
+/// string missing its closing quote in a single
+/// [`T![unterminated string]`](crate::token::TokenKind::UnterminatedString) token, up to the end
+/// of the input.
 pub struct Lexer<'input> {
     input: &'input str,
     position: u32,
@@ -70,6 +73,11 @@ impl<'input> Lexer<'input> {
                     _ => unreachable!(),
                 },
             ),
+            [b'"', rest @ ..] => match rest.iter().position(|&c| c == b'"') {
+                Some(len) => (len as u32 + 2, T![string]),
+                // the string extends to the end of the input, where the parser reports it
+                None => (input.len() as u32, T![unterminated string]),
+            },
             [b'/', b'/', ..] => (
                 input
                     .char_indices()
@@ -98,6 +106,10 @@ impl<'input> Lexer<'input> {
             // CFG keywords
             kw if kw.starts_with("block".as_bytes()) => ("block".len() as u32, T![block]),
             kw if kw.starts_with("goto".as_bytes()) => ("goto".len() as u32, T![goto]),
+            // statement keywords
+            kw if kw.starts_with("on".as_bytes()) => ("on".len() as u32, T![on]),
+            kw if kw.starts_with("start".as_bytes()) => ("start".len() as u32, T![start]),
+            kw if kw.starts_with("mid".as_bytes()) => ("mid".len() as u32, T![mid]),
             // effect keywords - facts
             kw if kw.starts_with("outlives".as_bytes()) => ("outlives".len() as u32, T![outlives]),
             kw if kw.starts_with("loan_issued_at".as_bytes()) => {
@@ -109,6 +121,13 @@ impl<'input> Lexer<'input> {
             kw if kw.starts_with("loan_killed_at".as_bytes()) => {
                 ("loan_killed_at".len() as u32, T![loan_killed_at])
             }
+            kw if kw.starts_with("loan_shallow_killed_at".as_bytes()) => (
+                "loan_shallow_killed_at".len() as u32,
+                T![loan_shallow_killed_at],
+            ),
+            kw if kw.starts_with("loan_activated_at".as_bytes()) => {
+                ("loan_activated_at".len() as u32, T![loan_activated_at])
+            }
             kw if kw.starts_with("var_used_at".as_bytes()) => {
                 ("var_used_at".len() as u32, T![var_used_at])
             }
@@ -124,6 +143,12 @@ impl<'input> Lexer<'input> {
             }
             // effect keywords - use
             kw if kw.starts_with("use".as_bytes()) => ("use".len() as u32, T![use]),
+            // expectation keywords
+            kw if kw.starts_with("expect".as_bytes()) => ("expect".len() as u32, T![expect]),
+            kw if kw.starts_with("error".as_bytes()) => ("error".len() as u32, T![expect error]),
+            kw if kw.starts_with("subset_error".as_bytes()) => {
+                ("subset_error".len() as u32, T![subset_error])
+            }
             _ => return None,
         };
 
@@ -1,8 +1,12 @@
+mod diagnostic;
 mod error;
 pub mod ir;
 mod lexer;
 mod parser;
 mod token;
+pub use crate::diagnostic::render_diagnostic;
+pub use crate::error::ParseError;
+pub use crate::token::Span;
 pub type Result<T> = std::result::Result<T, error::ParseError>;
 mod tests;
 
@@ -3,7 +3,7 @@
 use std::iter::Peekable;
 use std::vec;
 
-use crate::error::ParseError;
+use crate::error::{closest_keyword, ParseError};
 use crate::ir::*;
 use crate::token::Span;
 use crate::token::Token;
@@ -11,6 +11,20 @@ use crate::token::TokenKind;
 use crate::Result;
 use crate::T;
 
+/// The keywords of the facts an effect can be.
+const FACT_KEYWORDS: [TokenKind; 10] = [
+    T![outlives],
+    T![loan_issued_at],
+    T![loan_invalidated_at],
+    T![loan_killed_at],
+    T![loan_shallow_killed_at],
+    T![loan_activated_at],
+    T![var_used_at],
+    T![var_defined_at],
+    T![origin_live_on_entry],
+    T![var_dropped_at],
+];
+
 /// Input parser.
 ///
 /// Construct with an iterator that produces [`Token`]s and choose any of the `parse_` methods as an entrypoint.
@@ -21,6 +35,9 @@ where
 {
     input: &'input str,
     lexer: Peekable<I>,
+
+    /// The end of the last token consumed, where the spans of the parsed elements end.
+    previous_end: u32,
 }
 
 impl<'input, I> Parser<'input, I>
@@ -31,6 +48,7 @@ where
         Self {
             input,
             lexer: lexer.peekable(),
+            previous_end: 0,
         }
     }
 }
@@ -71,13 +89,56 @@ where
         if self.try_consume(expected) {
             return Ok(expected);
         }
+        if self.at(T![unterminated string]) {
+            return Err(ParseError::UnterminatedString {
+                position: self.position(),
+            });
+        }
         Err(ParseError::UnexpectedToken {
             found: self.peek(),
             expected: vec![expected],
             position: self.position(),
+            suggestion: None,
         })
     }
 
+    /// Returns [`ParseError::UnexpectedToken`] for the `found` next token. When it is the start of
+    /// an unknown word close to a fact keyword, the error is about the whole word and suggests the
+    /// keyword. When it is an unterminated string, returns [`ParseError::UnterminatedString`].
+    pub(crate) fn unexpected_token(
+        &mut self,
+        found: TokenKind,
+        expected: Vec<TokenKind>,
+    ) -> ParseError {
+        let position = self.position();
+        if found == T![unterminated string] {
+            return ParseError::UnterminatedString { position };
+        }
+        if found == T![error] {
+            let rest = &self.input[position.start as usize..];
+            let len = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            if let Some(suggestion) = closest_keyword(&rest[..len], &FACT_KEYWORDS) {
+                return ParseError::UnexpectedToken {
+                    found,
+                    expected,
+                    position: Span {
+                        start: position.start,
+                        end: position.start + len as u32,
+                    },
+                    suggestion: Some(suggestion),
+                };
+            }
+        }
+        ParseError::UnexpectedToken {
+            found,
+            expected,
+            position,
+            suggestion: None,
+        }
+    }
+
     /// Returns `true` if the next token is of kind `kind`.
     pub(crate) fn at(&mut self, kind: TokenKind) -> bool {
         self.peek() == kind
@@ -85,7 +146,17 @@ where
 
     /// Unconditionally advances the lexer by one token.
     pub(crate) fn bump(&mut self) {
-        self.lexer.next();
+        if let Some(token) = self.lexer.next() {
+            self.previous_end = token.span.end;
+        }
+    }
+
+    /// The span from `start` to the end of the last token consumed.
+    pub(crate) fn span_from(&self, start: u32) -> Span {
+        Span {
+            start,
+            end: self.previous_end,
+        }
     }
 }
 
@@ -99,12 +170,18 @@ where
         let use_of_var_derefs_origin = self.parse_use_of_var_derefs_origin().unwrap_or_default();
         let drop_of_var_derefs_origin = self.parse_drop_of_var_derefs_origin().unwrap_or_default();
         let blocks = self.parse_blocks()?;
+        let expectations = if self.at(T![expect]) {
+            self.parse_expectations()?
+        } else {
+            vec![]
+        };
         Ok(Input::new(
             placeholders,
             known_subsets,
             use_of_var_derefs_origin,
             drop_of_var_derefs_origin,
             blocks,
+            expectations,
         ))
     }
 
@@ -166,7 +243,11 @@ where
 
     pub fn parse_blocks(&mut self) -> Result<Vec<Block>> {
         let mut blocks = Vec::new();
-        while self.try_consume(T![block]) {
+        loop {
+            let start = self.position().start;
+            if !self.try_consume(T![block]) {
+                break;
+            }
             let name = self.parse_parameter(T![Block])?;
             self.consume(T!['{'])?;
             let statements = self.parse_statements()?;
@@ -176,6 +257,7 @@ where
                 name,
                 statements,
                 goto,
+                span: self.span_from(start),
             });
         }
         Ok(blocks)
@@ -188,11 +270,16 @@ where
                 // end of block
                 return Ok(statements);
             }
+            let start = self.position().start;
+            if self.at(T![on]) {
+                statements.push(self.parse_point_effects(start)?);
+                continue;
+            }
             let effects_start = self.parse_effects()?;
             match self.peek() {
                 T![;] => {
                     self.consume(T![;])?;
-                    statements.push(Statement::new(effects_start));
+                    statements.push(Statement::new(effects_start, self.span_from(start)));
                 }
                 T![/] => {
                     self.consume(T![/])?;
@@ -201,29 +288,57 @@ where
                     statements.push(Statement {
                         effects_start,
                         effects,
+                        span: self.span_from(start),
                     });
                 }
-                found => {
-                    return Err(ParseError::UnexpectedToken {
-                        found,
-                        expected: vec![T![;], T![/]],
-                        position: self.position(),
-                    })
-                }
+                found => return Err(self.unexpected_token(found, vec![T![;], T![/]])),
+            }
+        }
+    }
+
+    /// Parses a statement made of `on start { .. } on mid { .. }` clauses, with the effects at its
+    /// Start and Mid points, starting at the `start` byte. Either clause can be omitted or
+    /// repeated.
+    pub fn parse_point_effects(&mut self, start: u32) -> Result<Statement> {
+        let mut effects_start = Vec::new();
+        let mut effects = Vec::new();
+        while self.try_consume(T![on]) {
+            let point_effects = match self.peek() {
+                T![start] => &mut effects_start,
+                T![mid] => &mut effects,
+                found => return Err(self.unexpected_token(found, vec![T![start], T![mid]])),
+            };
+            self.bump();
+            self.consume(T!['{'])?;
+            point_effects.extend(self.parse_effects()?);
+            if !self.try_consume(T!['}']) {
+                let found = self.peek();
+                return Err(self.unexpected_token(found, vec![T!['}']]));
             }
         }
+        self.consume(T![;])?;
+        Ok(Statement {
+            effects_start,
+            effects,
+            span: self.span_from(start),
+        })
     }
 
-    pub fn parse_effects(&mut self) -> Result<Vec<Effect>> {
+    pub fn parse_effects(&mut self) -> Result<Vec<Spanned<Effect>>> {
         let mut effects = Vec::new();
         loop {
-            match self.peek() {
-                T![use] => effects.push(self.parse_use()?),
+            let start = self.position().start;
+            let effect = match self.peek() {
+                T![use] => self.parse_use()?,
                 _ => match self.parse_fact() {
-                    Ok(fact) => effects.push(Effect::Fact(fact)),
+                    Ok(fact) => Effect::Fact(fact),
                     _ => break, // not an error, just the end of the enumeration
                 },
-            }
+            };
+            effects.push(Spanned {
+                node: effect,
+                span: self.span_from(start),
+            });
             if !self.try_consume(T![,]) {
                 break;
             }
@@ -265,6 +380,20 @@ where
                 self.consume(T![')'])?;
                 Ok(Fact::LoanKilledAt { loan })
             }
+            T![loan_shallow_killed_at] => {
+                self.consume(T![loan_shallow_killed_at])?;
+                self.consume(T!['('])?;
+                let loan = self.parse_parameter(T![loan])?;
+                self.consume(T![')'])?;
+                Ok(Fact::LoanShallowKilledAt { loan })
+            }
+            T![loan_activated_at] => {
+                self.consume(T![loan_activated_at])?;
+                self.consume(T!['('])?;
+                let loan = self.parse_parameter(T![loan])?;
+                self.consume(T![')'])?;
+                Ok(Fact::LoanActivatedAt { loan })
+            }
             T![var_used_at] => {
                 self.consume(T![var_used_at])?;
                 self.consume(T!['('])?;
@@ -293,20 +422,7 @@ where
                 self.consume(T![')'])?;
                 Ok(Fact::UseVariable { variable })
             }
-            found => Err(ParseError::UnexpectedToken {
-                found,
-                expected: vec![
-                    T![outlives],
-                    T![loan_issued_at],
-                    T![loan_invalidated_at],
-                    T![loan_killed_at],
-                    T![var_used_at],
-                    T![var_defined_at],
-                    T![origin_live_on_entry],
-                    T![var_dropped_at],
-                ],
-                position: self.position(),
-            }),
+            found => Err(self.unexpected_token(found, FACT_KEYWORDS.to_vec())),
         }
     }
 
@@ -328,6 +444,67 @@ where
         }
     }
 
+    /// Parses the `expect { .. }` section of the errors expected in the program's output, e.g.
+    /// `expect { error(L0, "Mid(B0[1])"), subset_error('b, 'a) }`.
+    pub fn parse_expectations(&mut self) -> Result<Vec<Spanned<Expectation>>> {
+        self.consume(T![expect])?;
+        self.consume(T!['{'])?;
+        let mut expectations = Vec::new();
+        while matches!(self.peek(), T![expect error] | T![subset_error]) {
+            let start = self.position().start;
+            let expectation = self.parse_expectation()?;
+            expectations.push(Spanned {
+                node: expectation,
+                span: self.span_from(start),
+            });
+            if !self.try_consume(T![,]) {
+                break;
+            }
+        }
+        if !self.try_consume(T!['}']) {
+            let found = self.peek();
+            return Err(
+                self.unexpected_token(found, vec![T![expect error], T![subset_error], T!['}']])
+            );
+        }
+        Ok(expectations)
+    }
+
+    pub fn parse_expectation(&mut self) -> Result<Expectation> {
+        let expectation = match self.peek() {
+            T![expect error] => {
+                self.consume(T![expect error])?;
+                self.consume(T!['('])?;
+                let loan = self.parse_parameter(T![loan])?;
+                let point = self.parse_expected_point()?;
+                Expectation::Error { loan, point }
+            }
+            T![subset_error] => {
+                self.consume(T![subset_error])?;
+                self.consume(T!['('])?;
+                let a = self.parse_parameter(T![origin])?;
+                self.consume(T![,])?;
+                let b = self.parse_parameter(T![origin])?;
+                let point = self.parse_expected_point()?;
+                Expectation::SubsetError { a, b, point }
+            }
+            found => {
+                return Err(self.unexpected_token(found, vec![T![expect error], T![subset_error]]))
+            }
+        };
+        self.consume(T![')'])?;
+        Ok(expectation)
+    }
+
+    /// Parses the optional `, "point"` at the end of an expectation, without the quotes.
+    fn parse_expected_point(&mut self) -> Result<Option<String>> {
+        if !self.try_consume(T![,]) {
+            return Ok(None);
+        }
+        let point = self.parse_parameter(T![string])?;
+        Ok(Some(point.trim_matches('"').to_string()))
+    }
+
     pub fn parse_parameter(&mut self, kind: TokenKind) -> Result<String> {
         let text = self.text().to_string();
         self.consume(kind)?;
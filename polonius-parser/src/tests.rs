@@ -1,7 +1,7 @@
 #![cfg(test)]
 
-use crate::ir::{Effect, Fact, KnownSubset, Placeholder};
-use crate::parse_input;
+use crate::ir::{Effect, Expectation, Fact, KnownSubset, Placeholder};
+use crate::{parse_input, render_diagnostic};
 
 #[test]
 fn placeholders() {
@@ -178,6 +178,70 @@ fn effects_start() {
     );
 }
 
+#[test]
+fn on_start_and_mid() {
+    let program = r"
+        placeholders { 'a, 'b }
+        block B0 {
+            on start { loan_invalidated_at(L0), outlives('a: 'b) } on mid { use('a) };
+            on mid { loan_killed_at(L0) } on start { } on mid { var_used_at(V0) };
+            on start { loan_issued_at('a, L1) };
+        }
+    ";
+    let input = parse_input(program).expect("On start and mid");
+    let statements = &input.blocks[0].statements;
+    assert_eq!(statements.len(), 3);
+
+    assert_eq!(
+        statements[0].effects_start,
+        [
+            Effect::Fact(Fact::LoanInvalidatedAt {
+                loan: "L0".to_string()
+            }),
+            Effect::Fact(Fact::Outlives {
+                a: "'a".to_string(),
+                b: "'b".to_string()
+            })
+        ]
+    );
+    assert_eq!(
+        statements[0].effects,
+        [Effect::Use {
+            origins: vec!["'a".to_string()]
+        }]
+    );
+
+    // clauses can be repeated, in any order
+    assert!(statements[1].effects_start.is_empty());
+    assert_eq!(
+        statements[1].effects,
+        [
+            Effect::Fact(Fact::LoanKilledAt {
+                loan: "L0".to_string()
+            }),
+            Effect::Fact(Fact::UseVariable {
+                variable: "V0".to_string()
+            })
+        ]
+    );
+
+    assert_eq!(
+        statements[2].effects_start,
+        [Effect::Fact(Fact::LoanIssuedAt {
+            origin: "'a".to_string(),
+            loan: "L1".to_string()
+        })]
+    );
+    assert!(statements[2].effects.is_empty());
+
+    let program = "placeholders { }\nblock B0 {\n    on entry { use('a) };\n}\n";
+    let error = parse_input(program).expect_err("Unknown point");
+    assert_eq!(
+        error.to_string(),
+        "Unexpected token at 35-40: found '<?>', but expected one of 'start' or 'mid'"
+    );
+}
+
 #[test]
 fn complete_example() {
     let program = r"
@@ -326,3 +390,133 @@ fn known_subsets() {
         ]
     );
 }
+
+#[test]
+fn spans() {
+    let program = "placeholders { }\n\
+                   block B0 {\n    outlives('a: 'b), loan_killed_at(L0);\n    use('a);\n}\n";
+    let input = parse_input(program).expect("Spans");
+    let block = &input.blocks[0];
+    assert_eq!(&program[block.span], &program[17..program.len() - 1]);
+
+    let statement = &block.statements[0];
+    assert_eq!(
+        &program[statement.span],
+        "outlives('a: 'b), loan_killed_at(L0);"
+    );
+    assert_eq!(&program[statement.effects[0].span], "outlives('a: 'b)");
+    assert_eq!(&program[statement.effects[1].span], "loan_killed_at(L0)");
+    assert_eq!(&program[block.statements[1].effects[0].span], "use('a)");
+}
+
+#[test]
+fn render_diagnostics() {
+    let program = "placeholders { }\nblock B0 {\n    loan_invalidated(L0);\n}\n";
+    let error = parse_input(program).expect_err("Unknown fact");
+    let rendered = error.render(program);
+    assert!(rendered.starts_with(
+        "error: Unexpected token at 32-48: found '<?>', but expected one of ';' or '/'"
+    ));
+    assert!(rendered.ends_with(
+        "\n  --> 3:5\n  \
+         |\n\
+         3 |     loan_invalidated(L0);\n  \
+         |     ^^^^^^^^^^^^^^^^\n"
+    ));
+
+    // a span over multiple lines is underlined on each of them
+    let program = program.replace("loan_invalidated", "loan_killed_at");
+    let input = parse_input(&program).expect("Spans");
+    assert_eq!(
+        render_diagnostic(&program, input.blocks[0].span, "block"),
+        "error: block\n  \
+         --> 2:1\n  \
+         |\n\
+         2 | block B0 {\n  \
+         | ^^^^^^^^^^\n\
+         3 |     loan_killed_at(L0);\n  \
+         | ^^^^^^^^^^^^^^^^^^^^^^^\n\
+         4 | }\n  \
+         | ^\n"
+    );
+}
+
+#[test]
+fn suggestions() {
+    let error = |statement: &str| {
+        let program = format!("placeholders {{ }}\nblock B0 {{\n    {};\n}}\n", statement);
+        parse_input(&program)
+            .expect_err("Misspelled fact")
+            .to_string()
+    };
+
+    assert_eq!(
+        error("loan_invalidated(L0)"),
+        "Unexpected token at 32-48: found '<?>', but expected one of ';' or '/'; \
+         did you mean `loan_invalidated_at`?"
+    );
+    assert!(error("outlive('a: 'b)").ends_with("did you mean `outlives`?"));
+
+    // the whole word is suggested for, even when the lexer splits it
+    assert!(error("var_use_at(V0)").starts_with("Unexpected token at 32-42"));
+    assert!(error("var_use_at(V0)").ends_with("did you mean `var_used_at`?"));
+
+    // words too far from any fact are not
+    assert!(!error("frobnicate(L0)").contains("did you mean"));
+}
+
+#[test]
+fn expectations() {
+    let program = r#"
+        placeholders { 'a, 'b }
+        block B0 {
+            loan_invalidated_at(L0);
+        }
+        expect {
+            error(L0, "Mid(B0[0])"),
+            subset_error('b, 'a)
+        }
+    "#;
+    let input = parse_input(program).expect("Expectations");
+    assert_eq!(
+        input.expectations,
+        [
+            Expectation::Error {
+                loan: "L0".to_string(),
+                point: Some("Mid(B0[0])".to_string())
+            },
+            Expectation::SubsetError {
+                a: "'b".to_string(),
+                b: "'a".to_string(),
+                point: None
+            }
+        ]
+    );
+    assert_eq!(
+        &program[input.expectations[0].span],
+        r#"error(L0, "Mid(B0[0])")"#
+    );
+    assert_eq!(
+        input.expectations[0].to_string(),
+        r#"error(L0, "Mid(B0[0])")"#
+    );
+    assert_eq!(input.expectations[1].to_string(), "subset_error('b, 'a)");
+
+    // programs without the section expect no errors
+    let input = parse_input("placeholders { } block B0 { }").expect("No expectations");
+    assert!(input.expectations.is_empty());
+
+    let error = parse_input("placeholders { } expect { error(L0, Mid(B0[0])) }")
+        .expect_err("Unquoted point");
+    assert!(error.to_string().contains("expected '\"String\"'"));
+
+    // a point missing its closing quote is reported where the string starts, instead of lexing
+    // its text as other tokens
+    let program = r#"placeholders { } expect { error(L0, "Mid(B0[0])) }"#;
+    let error = parse_input(program).expect_err("Unterminated point");
+    assert_eq!(
+        error.to_string(),
+        "Unterminated string at 36-50: expected a closing '\"'"
+    );
+    assert_eq!(&program[error.span()], r#""Mid(B0[0])) }"#);
+}
@@ -38,22 +38,34 @@ pub enum TokenKind {
     // CFG keywords
     KwBlock,
     KwGoto,
+    // statement keywords
+    KwOn,
+    KwStart,
+    KwMid,
     // effect keywords - facts
     KwOutlives,
     KwLoanIssuedAt,
     KwLoanInvalidatedAt,
     KwLoanKilledAt,
+    KwLoanShallowKilledAt,
+    KwLoanActivatedAt,
     KwVarUsedAt,
     KwVarDefinedAt,
     KwOriginLiveOnEntry,
     KwVarDroppedAt,
     // effect keywords - use
     KwUse,
+    // expectation keywords
+    KwExpect,
+    KwError,
+    KwSubsetError,
     // parameters
     Origin,
     Block,
     Loan,
     Variable,
+    String,
+    UnterminatedString,
     Comment,
     Whitespace,
     Error,
@@ -105,22 +117,34 @@ macro_rules! T {
     // CFG keywords
     [block] => { $crate::token::TokenKind::KwBlock};
     [goto] => { $crate::token::TokenKind::KwGoto};
+    // statement keywords
+    [on] => { $crate::token::TokenKind::KwOn};
+    [start] => { $crate::token::TokenKind::KwStart};
+    [mid] => { $crate::token::TokenKind::KwMid};
     // effect keywords - facts
     [outlives] => { $crate::token::TokenKind::KwOutlives};
     [loan_issued_at] => { $crate::token::TokenKind::KwLoanIssuedAt};
     [loan_invalidated_at] => { $crate::token::TokenKind::KwLoanInvalidatedAt};
     [loan_killed_at] => { $crate::token::TokenKind::KwLoanKilledAt};
+    [loan_shallow_killed_at] => { $crate::token::TokenKind::KwLoanShallowKilledAt};
+    [loan_activated_at] => { $crate::token::TokenKind::KwLoanActivatedAt};
     [var_used_at] => { $crate::token::TokenKind::KwVarUsedAt};
     [var_defined_at] => { $crate::token::TokenKind::KwVarDefinedAt};
     [origin_live_on_entry] => { $crate::token::TokenKind::KwOriginLiveOnEntry};
     [var_dropped_at] => { $crate::token::TokenKind::KwVarDroppedAt};
     // effect keywords - use
     [use] => { $crate::token::TokenKind::KwUse};
+    // expectation keywords
+    [expect] => { $crate::token::TokenKind::KwExpect};
+    [expect error] => { $crate::token::TokenKind::KwError};
+    [subset_error] => { $crate::token::TokenKind::KwSubsetError};
     // parameters
     [origin] => { $crate::token::TokenKind::Origin};
     [Block] => { $crate::token::TokenKind::Block};
     [loan] => { $crate::token::TokenKind::Loan};
     [variable] => { $crate::token::TokenKind::Variable};
+    [string] => { $crate::token::TokenKind::String};
+    [unterminated string] => { $crate::token::TokenKind::UnterminatedString};
     [comment] => { $crate::token::TokenKind::Comment};
     [ws] => { $crate::token::TokenKind::Whitespace};
     [error] => { $crate::token::TokenKind::Error};
@@ -160,19 +184,29 @@ impl fmt::Display for TokenKind {
             T![known subsets] => write!(f, "known_subsets"),
             T![block] => write!(f, "block"),
             T![goto] => write!(f, "goto"),
+            T![on] => write!(f, "on"),
+            T![start] => write!(f, "start"),
+            T![mid] => write!(f, "mid"),
             T![outlives] => write!(f, "outlives"),
             T![loan_issued_at] => write!(f, "loan_issued_at"),
             T![loan_invalidated_at] => write!(f, "loan_invalidated_at"),
             T![loan_killed_at] => write!(f, "loan_killed_at"),
+            T![loan_shallow_killed_at] => write!(f, "loan_shallow_killed_at"),
+            T![loan_activated_at] => write!(f, "loan_activated_at"),
             T![var_used_at] => write!(f, "var_used_at"),
             T![var_defined_at] => write!(f, "var_defined_at"),
             T![origin_live_on_entry] => write!(f, "origin_live_on_entry"),
             T![var_dropped_at] => write!(f, "var_dropped_at"),
             T![use] => write!(f, "use"),
+            T![expect] => write!(f, "expect"),
+            T![expect error] => write!(f, "error"),
+            T![subset_error] => write!(f, "subset_error"),
             T![origin] => write!(f, "Origin"),
             T![Block] => write!(f, "Block"),
             T![loan] => write!(f, "Loan"),
             T![variable] => write!(f, "Variable"),
+            T![string] => write!(f, "\"String\""),
+            T![unterminated string] => write!(f, "unterminated \"String\""),
             T![comment] => write!(f, "// Comment"),
             T![ws] => write!(f, "<ws>"),
             T![error] => write!(f, "<?>"),
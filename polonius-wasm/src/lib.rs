@@ -0,0 +1,208 @@
+//! WebAssembly bindings to the Polonius engine, e.g. for an in-browser playground.
+//!
+//! The facts are given as a JSON object, mapping the relations' names to their tuples, whose
+//! atoms are strings:
+//!
+//! ```json
+//! {
+//!     "cfg_edge": [["a", "b"]],
+//!     "loan_issued_at": [["'x", "L0", "a"]],
+//!     "universal_region": ["'static"]
+//! }
+//! ```
+//!
+//! The errors are returned in the same format, as the `errors`, `subset_errors` and
+//! `move_errors` relations.
+
+use std::collections::HashMap;
+
+use polonius_engine::{Algorithm, AllFacts, CompactFacts, Diagnostic, Output};
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+/// Computes the errors of the facts given as JSON, with the given variant of the analysis, and
+/// returns them as JSON.
+#[wasm_bindgen]
+pub fn compute(facts: &str, algorithm: &str) -> Result<String, JsValue> {
+    compute_json(facts, algorithm).map_err(|error| JsValue::from_str(&error))
+}
+
+/// The implementation of `compute`, which doesn't need a JavaScript host.
+pub fn compute_json(facts: &str, algorithm: &str) -> Result<String, String> {
+    let algorithm: Algorithm = algorithm.parse()?;
+    let facts: Value =
+        serde_json::from_str(facts).map_err(|error| format!("invalid JSON: {}", error))?;
+    let relations = facts.as_object().ok_or("the facts must be a JSON object")?;
+
+    let mut atoms = Atoms::default();
+    let mut all_facts = AllFacts::default();
+    for (relation, tuples) in relations {
+        let tuples = tuples
+            .as_array()
+            .ok_or_else(|| format!("`{}`: the tuples must be a JSON array", relation))?;
+        load_relation(&mut all_facts, &mut atoms, relation, tuples)
+            .map_err(|error| format!("`{}`: {}", relation, error))?;
+    }
+
+    let output = Output::<CompactFacts>::compute(&all_facts, algorithm, false);
+
+    let mut errors = Vec::new();
+    let mut subset_errors = Vec::new();
+    let mut move_errors = Vec::new();
+    for diagnostic in output.diagnostics() {
+        match diagnostic {
+            Diagnostic::IllegalAccess { loan, point } => errors.push(json!([
+                atoms.loan.name(loan.into()),
+                atoms.point.name(point.into()),
+            ])),
+            Diagnostic::IllegalSubset { sup, sub, point } => subset_errors.push(json!([
+                atoms.origin.name(sub.into()),
+                atoms.origin.name(sup.into()),
+                atoms.point.name(point.into()),
+            ])),
+            Diagnostic::MoveError { path, point } => move_errors.push(json!([
+                atoms.path.name(path.into()),
+                atoms.point.name(point.into()),
+            ])),
+        }
+    }
+
+    let errors = json!({
+        "errors": errors,
+        "subset_errors": subset_errors,
+        "move_errors": move_errors,
+    });
+    Ok(errors.to_string())
+}
+
+/// Maps the atoms' names to their indices, for each kind of atom.
+#[derive(Default)]
+struct Atoms {
+    origin: Interner,
+    loan: Interner,
+    point: Interner,
+    variable: Interner,
+    path: Interner,
+}
+
+#[derive(Default)]
+struct Interner {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl Interner {
+    fn intern(&mut self, value: &Value) -> Result<usize, String> {
+        let name = value
+            .as_str()
+            .ok_or_else(|| format!("the atom `{}` must be a string", value))?;
+        if let Some(&index) = self.indices.get(name) {
+            return Ok(index);
+        }
+
+        let index = self.names.len();
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(), index);
+        Ok(index)
+    }
+
+    fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+}
+
+macro_rules! load_relations {
+    ($facts:expr, $atoms:expr, $relation:expr, $tuples:expr;
+     $($name:ident($($column:ident),+),)*) => {
+        match $relation {
+            $(
+                stringify!($name) => {
+                    let arity = [$(stringify!($column)),+].len();
+                    for tuple in $tuples {
+                        // the tuples of unary relations are single atoms
+                        let columns = match tuple.as_array() {
+                            Some(columns) => columns.as_slice(),
+                            None => std::slice::from_ref(tuple),
+                        };
+                        if columns.len() != arity {
+                            return Err(format!(
+                                "the tuple `{}` doesn't have {} atoms",
+                                tuple, arity
+                            ));
+                        }
+
+                        let mut columns = columns.iter();
+                        $facts.$name.push((
+                            $($atoms.$column.intern(columns.next().unwrap())?.into()),+
+                        ));
+                    }
+                    Ok(())
+                }
+            )*
+            _ => Err("unknown relation".to_string()),
+        }
+    };
+}
+
+fn load_relation(
+    facts: &mut AllFacts<CompactFacts>,
+    atoms: &mut Atoms,
+    relation: &str,
+    tuples: &[Value],
+) -> Result<(), String> {
+    load_relations! {
+        facts, atoms, relation, tuples;
+        loan_issued_at(origin, loan, point),
+        universal_region(origin),
+        universal_region_live_at(origin, point),
+        cfg_edge(point, point),
+        loan_killed_at(loan, point),
+        loan_shallow_killed_at(loan, point),
+        subset_base(origin, origin, point),
+        loan_invalidated_at(loan, point),
+        loan_activated_at(loan, point),
+        var_used_at(variable, point),
+        var_defined_at(variable, point),
+        var_dropped_at(variable, point),
+        use_of_var_derefs_origin(variable, origin),
+        drop_of_var_derefs_origin(variable, origin),
+        origin_live_on_entry(origin, point),
+        child_path(path, path),
+        path_is_var(path, variable),
+        path_assigned_at_base(path, point),
+        path_moved_at_base(path, point),
+        path_accessed_at_base(path, point),
+        known_placeholder_subset(origin, origin),
+        placeholder(origin, loan),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_errors_from_json_facts() {
+        let facts = r#"{
+            "cfg_edge": [["a", "b"], ["b", "c"]],
+            "loan_issued_at": [["'x", "L0", "a"]],
+            "loan_invalidated_at": [["L0", "b"]],
+            "var_used_at": [["v", "c"]],
+            "use_of_var_derefs_origin": [["v", "'x"]]
+        }"#;
+
+        for algorithm in &["Naive", "DatafrogOpt"] {
+            let errors = compute_json(facts, algorithm).unwrap();
+            assert_eq!(
+                errors,
+                r#"{"errors":[["L0","b"]],"move_errors":[],"subset_errors":[]}"#
+            );
+        }
+
+        let error = compute_json(r#"{"cfg_edge": [["a"]]}"#, "Naive").unwrap_err();
+        assert_eq!(
+            error,
+            r#"`cfg_edge`: the tuple `["a"]` doesn't have 2 atoms"#
+        );
+    }
+}
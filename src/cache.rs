@@ -0,0 +1,310 @@
+//! A cache of the computed outputs, keyed by a hash of the facts and of the settings of the
+//! computation, to skip recomputing identical facts, e.g. when debugging the same dataset over
+//! and over.
+//!
+//! Each output is stored in a directory named after its key, with a `.facts` file per relation.
+//! The `subset_error_witnesses`, the `move_error_kinds`, the `relation_sizes`, and the differences
+//! of the `comparison` are not stored, so the outputs which have any of them are not cached: only
+//! whether the variants were compared is. The aborted computations are not cached either.
+
+use crate::dump::{Output, OutputDump};
+use crate::error::Error;
+use crate::facts::{AllFacts, Origin};
+use crate::intern::InternerTables;
+use crate::tab_delim::{load_tab_delimited_file, write_tab_delimited_row, FromTabDelimited};
+use polonius_engine::{ErrorsDiff, OriginKind};
+use rustc_hash::{FxHashMap, FxHasher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The entry of the cache for some facts, computed with some settings.
+pub(crate) struct OutputCache {
+    dir: PathBuf,
+}
+
+macro_rules! cached_relations {
+    ($($relation:ident,)*) => {
+        fn store_relations(dir: &Path, output: &Output, tables: &InternerTables) -> Result<(), Error> {
+            $(store_relation(dir, stringify!($relation), &output.$relation, tables)?;)*
+            Ok(())
+        }
+
+        fn load_relations(
+            dir: &Path,
+            tables: &mut InternerTables,
+            dump_enabled: bool,
+        ) -> Result<Output, Error> {
+            Ok(Output {
+                $($relation: load_relation(dir, stringify!($relation), tables)?,)*
+                move_error_kinds: FxHashMap::default(),
+                dump_enabled,
+                partial: dir.join(PARTIAL_FILE).exists(),
+                aborted: None,
+                relation_sizes: None,
+                subset_error_witnesses: FxHashMap::default(),
+                comparison: dir.join(COMPARED_FILE).exists().then(|| ErrorsDiff {
+                    removed: Vec::new(),
+                    added: Vec::new(),
+                }),
+            })
+        }
+    };
+}
+
+cached_relations! {
+    errors,
+    subset_errors,
+    move_errors,
+    loan_live_at,
+    potential_errors,
+    loan_scopes,
+    origin_live_ranges,
+    loans_out_of_scope_at,
+    origin_contains_loan_at,
+    origin_contains_loan_anywhere,
+    origin_live_on_entry,
+    loan_invalidated_at,
+    subset,
+    subset_anywhere,
+    var_live_on_entry,
+    var_drop_live_on_entry,
+    var_dropped_while_init_at,
+    path_maybe_initialized_on_exit,
+    path_maybe_uninitialized_on_exit,
+    path_definitely_initialized_on_exit,
+    path_moved_at,
+    path_assigned_at,
+    path_accessed_at,
+    known_contains,
+    var_maybe_partly_initialized_on_exit,
+    known_placeholder_subset,
+    origin_kind,
+}
+
+/// The file marking the outputs whose computation stopped after finding move errors.
+const PARTIAL_FILE: &str = "partial";
+
+/// The file marking the outputs where the variants were compared, and found the same errors.
+const COMPARED_FILE: &str = "compared";
+
+impl OutputCache {
+    /// The entry of the cache in `cache_dir` for the `all_facts`, computed with the `settings`:
+    /// e.g. the algorithm and options of the computation, which also change the output.
+    pub(crate) fn new(
+        cache_dir: &Path,
+        all_facts: &AllFacts,
+        tables: &InternerTables,
+        settings: &str,
+    ) -> Self {
+        // unlike `DefaultHasher`'s, `FxHasher`'s hashes don't change across releases of Rust, so
+        // the entries stay valid when the CLI is rebuilt
+        let mut hasher = FxHasher::default();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        settings.hash(&mut hasher);
+        hash_facts(all_facts, tables, &mut hasher);
+        OutputCache {
+            dir: cache_dir.join(format!("{:016x}", hasher.finish())),
+        }
+    }
+
+    /// The output stored in this entry, if any. Its atoms are interned in the `tables` the facts
+    /// were loaded with.
+    pub(crate) fn load(
+        &self,
+        tables: &mut InternerTables,
+        dump_enabled: bool,
+    ) -> Result<Option<Output>, Error> {
+        if !self.dir.is_dir() {
+            return Ok(None);
+        }
+        load_relations(&self.dir, tables, dump_enabled).map(Some)
+    }
+
+    /// Stores the `output` in this entry, unless it has relation sizes, witnesses of subset
+    /// errors, kinds of move errors, or differences between the compared variants, which can't
+    /// be restored, or its computation was aborted.
+    /// The entry is written to a temporary directory first, so that an interrupted run doesn't
+    /// leave an incomplete entry behind.
+    pub(crate) fn store(&self, output: &Output, tables: &InternerTables) -> Result<(), Error> {
+        let variants_differ = output
+            .comparison
+            .as_ref()
+            .is_some_and(|comparison| !comparison.is_empty());
+        if output.relation_sizes.is_some()
+            || !output.subset_error_witnesses.is_empty()
+            || !output.move_error_kinds.is_empty()
+            || output.aborted.is_some()
+            || variants_differ
+            || self.dir.is_dir()
+        {
+            return Ok(());
+        }
+
+        let io_error = |path: &Path| {
+            let path = path.to_owned();
+            move |source| Error::Io { path, source }
+        };
+
+        let temp_dir = self
+            .dir
+            .with_extension(format!("tmp-{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).map_err(io_error(&temp_dir))?;
+        store_relations(&temp_dir, output, tables)?;
+        if output.partial {
+            let partial = temp_dir.join(PARTIAL_FILE);
+            File::create(&partial).map_err(io_error(&partial))?;
+        }
+        if output.comparison.is_some() {
+            let compared = temp_dir.join(COMPARED_FILE);
+            File::create(&compared).map_err(io_error(&compared))?;
+        }
+
+        if fs::rename(&temp_dir, &self.dir).is_err() {
+            // another run stored the same entry in the meantime
+            fs::remove_dir_all(&temp_dir).map_err(io_error(&temp_dir))?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the names of the atoms of the `all_facts`, rather than their indices, which depend on
+/// the order they were interned in.
+fn hash_facts(all_facts: &AllFacts, tables: &InternerTables, hasher: &mut impl Hasher) {
+    macro_rules! hash_relations {
+        ($($relation:ident,)*) => {
+            $(
+                stringify!($relation).hash(hasher);
+                all_facts.$relation.for_each_row(tables, &mut vec![], &mut |row| {
+                    for &(_, name) in row {
+                        name.hash(hasher);
+                    }
+                });
+            )*
+        };
+    }
+
+    hash_relations! {
+        loan_issued_at,
+        universal_region,
+        universal_region_live_at,
+        cfg_edge,
+        loan_killed_at,
+        loan_shallow_killed_at,
+        subset_base,
+        loan_invalidated_at,
+        loan_activated_at,
+        var_defined_at,
+        var_used_at,
+        var_dropped_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        origin_live_on_entry,
+        child_path,
+        path_is_var,
+        path_assigned_at_base,
+        path_moved_at_base,
+        path_accessed_at_base,
+        known_placeholder_subset,
+        placeholder,
+    }
+}
+
+fn store_relation(
+    dir: &Path,
+    name: &str,
+    relation: &impl OutputDump,
+    tables: &InternerTables,
+) -> Result<(), Error> {
+    let path = dir.join(format!("{}.facts", name));
+    let io_error = |source| Error::Io {
+        path: path.clone(),
+        source,
+    };
+
+    let mut file = BufWriter::new(File::create(&path).map_err(io_error)?);
+    let mut names = Vec::new();
+    let mut result = Ok(());
+    relation.for_each_row(tables, &mut vec![], &mut |row| {
+        if result.is_ok() {
+            names.clear();
+            names.extend(row.iter().map(|&(_, name)| name));
+            result = write_tab_delimited_row(&mut file, &names);
+        }
+    });
+    result.and_then(|()| file.flush()).map_err(io_error)
+}
+
+fn load_relation<R: FromRows>(
+    dir: &Path,
+    name: &str,
+    tables: &mut InternerTables,
+) -> Result<R, Error> {
+    let path = dir.join(format!("{}.facts", name));
+    let rows: Vec<R::Row> = load_tab_delimited_file(tables, &path, None, R::Row::ARITY)?;
+    let mut relation = R::default();
+    for row in rows {
+        relation.insert(row);
+    }
+    Ok(relation)
+}
+
+/// A relation of the output, rebuilt from the rows it is dumped as.
+trait FromRows: Default {
+    type Row: for<'input> FromTabDelimited<'input>;
+
+    fn insert(&mut self, row: Self::Row);
+}
+
+impl<K, V> FromRows for FxHashMap<K, Vec<V>>
+where
+    K: Eq + Hash,
+    (K, V): for<'input> FromTabDelimited<'input>,
+{
+    type Row = (K, V);
+
+    fn insert(&mut self, (key, value): Self::Row) {
+        self.entry(key).or_default().push(value);
+    }
+}
+
+impl<K, V> FromRows for FxHashMap<K, BTreeSet<V>>
+where
+    K: Eq + Hash,
+    V: Ord,
+    (K, V): for<'input> FromTabDelimited<'input>,
+{
+    type Row = (K, V);
+
+    fn insert(&mut self, (key, value): Self::Row) {
+        self.entry(key).or_default().insert(value);
+    }
+}
+
+impl<K1, K2, V> FromRows for FxHashMap<K1, BTreeMap<K2, BTreeSet<V>>>
+where
+    K1: Eq + Hash,
+    K2: Ord,
+    V: Ord,
+    (K1, (K2, V)): for<'input> FromTabDelimited<'input>,
+{
+    type Row = (K1, (K2, V));
+
+    fn insert(&mut self, (key1, (key2, value)): Self::Row) {
+        self.entry(key1)
+            .or_default()
+            .entry(key2)
+            .or_default()
+            .insert(value);
+    }
+}
+
+impl FromRows for FxHashMap<Origin, OriginKind> {
+    type Row = (Origin, OriginKind);
+
+    fn insert(&mut self, (origin, kind): Self::Row) {
+        FxHashMap::insert(self, origin, kind);
+    }
+}
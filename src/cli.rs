@@ -1,19 +1,37 @@
 use log::{error, Level, LevelFilter, Metadata, Record, SetLoggerError};
 use pico_args as pico;
-use polonius_engine::Algorithm;
+use polonius_engine::{
+    prune_facts, Algorithm, ComputeOptions, MoveErrorPolicy, PointOrder, PruningStats,
+};
 use std::env;
-use std::error;
+use std::ffi::OsString;
 use std::fmt;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
+use tracing_subscriber::fmt::format::{FmtSpan, Format, Json, JsonFields};
+use tracing_subscriber::fmt::{MakeWriter, Subscriber as FmtSubscriber};
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::EnvFilter;
 
+use crate::cache::OutputCache;
+use crate::diff;
 use crate::dump;
-use crate::dump::Output;
+use crate::dump::{DumpStyle, Focus, Output, RelationSelection};
+pub use crate::error::Error;
 use crate::facts::AllFacts;
+use crate::glob;
+use crate::golden::{self, GOLDEN_FILE};
 use crate::intern;
+use crate::interpret;
+use crate::parity;
+use crate::progress::Progress;
+use crate::rules;
+use crate::synthetic;
 use crate::tab_delim;
+use crate::waivers::Waivers;
 
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -23,23 +41,86 @@ const PKG_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 pub struct Options {
     algorithm: Algorithm,
     show_tuples: bool,
+    numeric_atoms: bool,
     skip_timing: bool,
     verbose: bool,
     graphviz_file: Option<String>,
+    graphviz_depth: Option<usize>,
+    short_names: bool,
     output_directory: Option<String>,
-    fact_dirs: Vec<String>,
+    gzip_output: bool,
+    rustc_layout: bool,
+    dump_style: DumpStyle,
+    focus: Focus,
+    dump_relations: Option<RelationSelection>,
+    pub(crate) fact_dirs: Vec<String>,
+    added_fact_files: Vec<String>,
+    added_facts: Vec<String>,
     liveness_graph_file: Option<String>,
+    constraint_graph_file: Option<String>,
+    prune_facts: bool,
+    stop_on_move_errors: bool,
+    reverse_post_order: bool,
+    two_phase_borrows: bool,
+    progress: bool,
+    log_json: bool,
+    max_rounds: Option<usize>,
+    skip_loan_live_at: bool,
+    scopes: bool,
+    loans_out_of_scope: bool,
+    loan_partitions: Option<usize>,
+    cache_dir: Option<String>,
+    filter_fn: Option<String>,
+    symbols_file: Option<String>,
+    waivers_file: Option<String>,
+    bless: bool,
 }
 
+/// The options of the `diff` subcommand.
 #[derive(Debug)]
-pub struct Error(String);
+pub struct DiffOptions {
+    before: String,
+    after: String,
+}
 
-impl error::Error for Error {}
+/// The options of the `lint-rules` subcommand.
+#[derive(Debug)]
+pub struct LintRulesOptions {
+    files: Vec<String>,
+}
 
-impl fmt::Display for Error {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.write_str(&self.0)
-    }
+/// The options of the `eval-rules` subcommand.
+#[derive(Debug)]
+pub struct EvalRulesOptions {
+    rules: String,
+    fact_dirs: Vec<String>,
+    relations: Vec<String>,
+    filter_fn: Option<String>,
+}
+
+/// The options of the `parity` subcommand.
+#[derive(Debug)]
+pub struct ParityOptions {
+    fact_dirs: Vec<String>,
+    filter_fn: Option<String>,
+    souffle_program: Option<String>,
+    output_file: Option<String>,
+}
+
+/// The options of the `gen-bench` subcommand.
+#[derive(Debug)]
+pub struct GenBenchOptions {
+    parameters: synthetic::Parameters,
+    output_directory: Option<String>,
+    bench_interner: bool,
+}
+
+/// The options of the `explain-rule` subcommand.
+#[derive(Debug)]
+pub struct ExplainRuleOptions {
+    rule: Option<String>,
+    dependencies: bool,
+    dependency_graph: Option<String>,
 }
 
 macro_rules! attempt {
@@ -58,49 +139,563 @@ pub fn main(opt: Options) -> Result<(), Error> {
         .liveness_graph_file
         .as_ref()
         .map(|x| Path::new(x).to_owned());
-    for facts_dir in &opt.fact_dirs {
-        let tables = &mut intern::InternerTables::new();
+    let constraint_graph_file = opt
+        .constraint_graph_file
+        .as_ref()
+        .map(|x| Path::new(x).to_owned());
+    // the atoms of all the inputs are interned in the symbols' tables, when given, to keep their
+    // indices stable across runs
+    let symbols_file = opt.symbols_file.as_ref().map(Path::new);
+    let mut symbols = match symbols_file {
+        Some(path) if path.exists() => Some(intern::InternerTables::load(path)?),
+        Some(_) => Some(intern::InternerTables::new()),
+        None => None,
+    };
+    let waivers = match opt.waivers_file {
+        Some(ref path) => Some(Waivers::load(Path::new(path))?),
+        None => None,
+    };
+    let mut differing_inputs = 0;
+    let mut aborted_inputs = 0;
+    for facts_dir in &facts_inputs(&opt.fact_dirs, opt.filter_fn.as_deref())? {
+        let mut input_tables;
+        let tables = match symbols {
+            Some(ref mut symbols) => symbols,
+            None => {
+                input_tables = intern::InternerTables::new();
+                &mut input_tables
+            }
+        };
 
-        let result: Result<(Duration, AllFacts, Output), Error> = attempt! {
+        if opt.bless {
+            let golden_file = facts_dir.join(GOLDEN_FILE);
+            let blessed: Result<bool, Error> = attempt! {
+                let all_facts = tab_delim::load_facts_from_input(tables, facts_dir)?;
+                golden::bless(facts_dir, &golden::compute_errors(&all_facts, tables))?
+            };
+            match blessed {
+                Ok(true) => println!("Blessed `{}`", golden_file.display()),
+                Ok(false) => println!("`{}` is up to date", golden_file.display()),
+                Err(error) => error!("`{}`: {}", facts_dir.display(), error),
+            }
+            continue;
+        }
+
+        let result: Result<(Duration, bool, AllFacts, Output, Option<PruningStats>), Error> = attempt! {
             let verbose = opt.verbose;
-            let all_facts = tab_delim::load_tab_delimited_facts(tables, &Path::new(&facts_dir))
-                .map_err(|e| Error(e.to_string()))?;
+            let mut all_facts = tab_delim::load_facts_from_input(tables, facts_dir)?;
+            for path in &opt.added_fact_files {
+                let added_facts = tab_delim::load_added_facts(tables, Path::new(path))?;
+                tab_delim::merge_facts(&mut all_facts, added_facts);
+            }
+            for tuple in &opt.added_facts {
+                let added_facts = tab_delim::load_inline_fact(tables, tuple)?;
+                tab_delim::merge_facts(&mut all_facts, added_facts);
+            }
+            let pruning_stats = if opt.prune_facts {
+                let (pruned_facts, stats) = prune_facts(&all_facts);
+                all_facts = pruned_facts;
+                Some(stats)
+            } else {
+                None
+            };
             let algorithm = opt.algorithm;
-            let graphviz_output = graphviz_file.is_some() || liveness_graph_file.is_some();
-            let (duration, output) =
-                timed(|| Output::compute(&all_facts, algorithm, verbose || graphviz_output));
-            (duration, all_facts, output)
+            let graphviz_output = graphviz_file.is_some()
+                || liveness_graph_file.is_some()
+                || constraint_graph_file.is_some();
+            let options = ComputeOptions {
+                dump_enabled: verbose || graphviz_output || opt.dump_relations.is_some(),
+                move_error_policy: if opt.stop_on_move_errors {
+                    MoveErrorPolicy::Stop
+                } else {
+                    MoveErrorPolicy::Continue
+                },
+                point_order: if opt.reverse_post_order {
+                    PointOrder::ReversePostOrder
+                } else {
+                    PointOrder::Input
+                },
+                two_phase_borrows: opt.two_phase_borrows,
+                progress: opt.progress.then(Progress::hook),
+                max_rounds: opt.max_rounds,
+                skip_loan_live_at: opt.skip_loan_live_at,
+                scopes: opt.scopes,
+                loans_out_of_scope: opt.loans_out_of_scope,
+                ..ComputeOptions::default()
+            };
+            let cache = opt.cache_dir.as_ref().map(|cache_dir| {
+                // showing the progress doesn't change the output
+                let options = ComputeOptions {
+                    progress: None,
+                    ..options.clone()
+                };
+                let settings = format!("{:?} {:?} {:?}", algorithm, options, opt.loan_partitions);
+                OutputCache::new(Path::new(cache_dir), &all_facts, tables, &settings)
+            });
+            let cached = match cache {
+                Some(ref cache) => {
+                    let (duration, output) = timed(|| cache.load(tables, options.dump_enabled));
+                    output?.map(|output| (duration, output))
+                }
+                None => None,
+            };
+            match cached {
+                Some((duration, output)) => (duration, true, all_facts, output, pruning_stats),
+                None => {
+                    let (duration, output) = match opt.loan_partitions {
+                        Some(partitions) => timed(|| {
+                            Output::compute_partitioned(&all_facts, algorithm, &options, partitions)
+                        }),
+                        None => {
+                            timed(|| Output::compute_with_options(&all_facts, algorithm, &options))
+                        }
+                    };
+                    if let Some(ref cache) = cache {
+                        cache.store(&output, tables)?;
+                    }
+                    (duration, false, all_facts, output, pruning_stats)
+                }
+            }
         };
 
         match result {
-            Ok((duration, all_facts, output)) => {
+            Ok((duration, cached, all_facts, output, pruning_stats)) => {
                 println!("--------------------------------------------------");
-                println!("Directory: {}", facts_dir);
+                println!("Directory: {}", facts_dir.display());
+                if cached {
+                    println!("Loaded the output from the cache");
+                }
                 if !opt.skip_timing {
                     let seconds = duration.as_secs() as f64;
                     let millis = f64::from(duration.subsec_nanos()) * 0.000_000_001_f64;
                     println!("Time: {:0.3}s", seconds + millis);
                 }
-                if opt.show_tuples {
-                    dump::dump_output(&output, &output_directory, tables)
-                        .expect("Failed to write output");
+                if let Some(stats) = pruning_stats {
+                    println!("Pruned facts:\n{}", stats);
+                }
+                if output.partial {
+                    println!("Stopped after finding move errors");
+                }
+                if let Some(ref aborted) = output.aborted {
+                    println!("Aborted: {}", aborted);
+                    aborted_inputs += 1;
+                }
+                if opt.show_tuples || opt.rustc_layout || opt.dump_relations.is_some() {
+                    let numeric_tables;
+                    let names = if opt.numeric_atoms {
+                        numeric_tables = tables.numeric();
+                        &numeric_tables
+                    } else {
+                        &*tables
+                    };
+                    match output_directory {
+                        Some(ref output_directory) if opt.rustc_layout => dump::dump_rustc_layout(
+                            &output,
+                            &output_directory.join(input_name(facts_dir)),
+                            names,
+                            &opt.focus,
+                            opt.dump_relations.as_ref(),
+                        ),
+                        _ => dump::dump_output(
+                            &output,
+                            &output_directory,
+                            names,
+                            opt.gzip_output,
+                            opt.dump_style,
+                            &opt.focus,
+                            opt.dump_relations.as_ref(),
+                        ),
+                    }
+                    .expect("Failed to write output");
                 }
                 if let Some(ref graphviz_file) = graphviz_file {
-                    dump::graphviz(&output, &all_facts, graphviz_file, tables)
-                        .expect("Failed to write GraphViz");
+                    dump::graphviz(
+                        &output,
+                        &all_facts,
+                        graphviz_file,
+                        tables,
+                        opt.graphviz_depth,
+                        &opt.focus,
+                        opt.short_names,
+                    )
+                    .expect("Failed to write GraphViz");
                 }
                 if let Some(ref liveness_graph_file) = liveness_graph_file {
-                    dump::liveness_graph(&output, &all_facts, liveness_graph_file, tables)
-                        .expect("Failed to write liveness graph");
+                    dump::liveness_graph(
+                        &output,
+                        &all_facts,
+                        liveness_graph_file,
+                        tables,
+                        opt.short_names,
+                    )
+                    .expect("Failed to write liveness graph");
+                }
+                if let Some(ref constraint_graph_file) = constraint_graph_file {
+                    dump::constraint_graph(
+                        &output,
+                        &all_facts,
+                        constraint_graph_file,
+                        tables,
+                        opt.short_names,
+                    )
+                    .expect("Failed to write constraint graph");
+                }
+                if let Some(ref comparison) = output.comparison {
+                    let waived = waivers
+                        .as_ref()
+                        .map(|waivers| waivers.check(facts_dir, comparison, tables));
+                    if !comparison.is_empty() {
+                        let comparison_file =
+                            comparison_file(output_directory.as_deref(), facts_dir);
+                        dump::comparison(comparison, &comparison_file, tables)
+                            .expect("Failed to write the comparison");
+                        match waived {
+                            Some(ref waived) if waived.unexpected == 0 => println!(
+                                "The variants reported the waived differences, see `{}`",
+                                comparison_file.display()
+                            ),
+                            _ => {
+                                println!(
+                                    "The variants reported different errors, see `{}`",
+                                    comparison_file.display()
+                                );
+                                differing_inputs += 1;
+                            }
+                        }
+                    }
+                    if let Some(waived) = waived.filter(|waived| waived.stale > 0) {
+                        println!(
+                            "{} waived difference(s) didn't occur, and can be removed from the waivers",
+                            waived.stale
+                        );
+                    }
                 }
             }
 
             Err(error) => {
-                error!("`{}`: {}", facts_dir, error);
+                error!("`{}`: {}", facts_dir.display(), error);
             }
         }
     }
 
+    if let (Some(path), Some(symbols)) = (symbols_file, symbols) {
+        symbols.save(path)?;
+    }
+
+    if aborted_inputs > 0 {
+        return Err(Error::RoundLimitExceeded {
+            inputs: aborted_inputs,
+        });
+    }
+    if differing_inputs > 0 {
+        return Err(Error::VariantsDiffer {
+            inputs: differing_inputs,
+        });
+    }
+    Ok(())
+}
+
+/// The file where the differences between the compared variants on the facts of `facts_dir` are
+/// written: in the `output_directory` when given, or the current directory, and named after the
+/// facts' directory, to collect them from a whole corpus.
+fn comparison_file(output_directory: Option<&Path>, facts_dir: &Path) -> PathBuf {
+    let file_name = format!("{}.comparison.json", input_name(facts_dir));
+    match output_directory {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// The name of the input `facts_dir`: the name of its function, for rustc's `nll-facts`
+/// directories.
+fn input_name(facts_dir: &Path) -> &str {
+    facts_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("facts")
+}
+
+/// The inputs to analyze: the `fact_dirs`, where the directories of function directories, like
+/// rustc's `nll-facts` directory, are replaced by the directories of facts they contain. When
+/// given, only the directories whose name matches the glob `filter` are analyzed.
+fn facts_inputs(fact_dirs: &[String], filter: Option<&str>) -> Result<Vec<PathBuf>, Error> {
+    let mut inputs = Vec::new();
+    for input in fact_dirs {
+        let path = Path::new(input);
+        if !path.is_dir() {
+            // a bundle, or `-` for stdin
+            inputs.push(path.to_owned());
+            continue;
+        }
+
+        let facts_dirs = glob::find_facts_dirs(path, filter)?;
+        if facts_dirs.is_empty() && filter.is_none() {
+            // loading the directory reports the missing facts
+            inputs.push(path.to_owned());
+        }
+        inputs.extend(facts_dirs);
+    }
+    Ok(inputs)
+}
+
+/// Compares the facts of two inputs, and shows the tuples of each relation which differ.
+pub fn diff(opt: DiffOptions) -> Result<(), Error> {
+    // the atoms of both inputs are interned in the same tables, to align them by name
+    let tables = &mut intern::InternerTables::new();
+    let before = tab_delim::load_facts_from_input(tables, Path::new(&opt.before))?;
+    let after = tab_delim::load_facts_from_input(tables, Path::new(&opt.after))?;
+
+    let stdout = io::stdout();
+    let differences =
+        diff::diff_facts(&before, &after, tables, &mut stdout.lock()).map_err(|source| {
+            Error::Io {
+                path: "<stdout>".into(),
+                source,
+            }
+        })?;
+    if differences == 0 {
+        println!("The facts are identical");
+    }
+    Ok(())
+}
+
+/// Runs the engines on every function of the inputs, and writes the CSV matrix of their results.
+pub fn parity(opt: ParityOptions) -> Result<(), Error> {
+    let output_path = opt.output_file.as_deref().unwrap_or("<stdout>");
+    let io_error = |source| Error::Io {
+        path: output_path.into(),
+        source,
+    };
+    let stdout = io::stdout();
+    let mut stream: Box<dyn io::Write> = match opt.output_file {
+        Some(ref file) => Box::new(io::BufWriter::new(
+            std::fs::File::create(file).map_err(io_error)?,
+        )),
+        None => Box::new(stdout.lock()),
+    };
+
+    parity::write_header(&mut stream).map_err(io_error)?;
+    let mut differing_inputs = 0;
+    for facts_dir in &facts_inputs(&opt.fact_dirs, opt.filter_fn.as_deref())? {
+        let runs: Result<Vec<parity::Run>, Error> = attempt! {
+            let tables = &mut intern::InternerTables::new();
+            let all_facts = tab_delim::load_facts_from_input(tables, facts_dir)?;
+            let (mut runs, reference) = parity::run_engines(&all_facts);
+            if let Some(ref program) = opt.souffle_program {
+                let program = Path::new(program);
+                let souffle = parity::run_souffle(program, facts_dir, &reference, tables)?;
+                runs.push(souffle);
+            }
+            runs
+        };
+        match runs {
+            Ok(runs) => {
+                if runs.iter().any(|run| run.agrees == Some(false)) {
+                    differing_inputs += 1;
+                }
+                parity::write_runs(&mut stream, input_name(facts_dir), &runs).map_err(io_error)?;
+            }
+            Err(error) => eprintln!("`{}`: {}", facts_dir.display(), error),
+        }
+    }
+    stream.flush().map_err(io_error)?;
+
+    if differing_inputs > 0 {
+        return Err(Error::VariantsDiffer {
+            inputs: differing_inputs,
+        });
+    }
+    Ok(())
+}
+
+pub fn gen_bench(opt: GenBenchOptions) -> Result<(), Error> {
+    let facts = synthetic::generate(&opt.parameters);
+    if opt.bench_interner {
+        let names = facts.point_names();
+        println!("Interning {} point names:", names.len());
+        for bench in &intern::bench_interners(&names, 5) {
+            println!(
+                "{:>15}: {} atoms, {:.3}ms, {} bytes",
+                bench.interner,
+                bench.atoms,
+                bench.elapsed.as_secs_f64() * 1000.0,
+                bench.heap_size
+            );
+        }
+        return Ok(());
+    }
+
+    match opt.output_directory {
+        Some(ref dir) => {
+            let dir = Path::new(dir);
+            facts.write_directory(dir).map_err(|source| Error::Io {
+                path: dir.to_owned(),
+                source,
+            })?;
+            println!("Generated {} tuples in `{}`", facts.len(), dir.display());
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut stream = io::BufWriter::new(stdout.lock());
+            facts
+                .write_bundle(&mut stream)
+                .and_then(|()| io::Write::flush(&mut stream))
+                .map_err(|source| Error::Io {
+                    path: "<stdout>".into(),
+                    source,
+                })?;
+        }
+    }
+    Ok(())
+}
+
+pub fn explain_rule(opt: ExplainRuleOptions) -> Result<(), Error> {
+    if let Some(ref rule) = opt.rule {
+        let rules = rules::find_rules(rule)?;
+        for (index, rule) in rules.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            print!("{}", rule);
+        }
+    }
+
+    let spec = rules::spec();
+    if opt.dependencies {
+        let stdout = io::stdout();
+        spec.write_dependencies(&mut stdout.lock())
+            .map_err(|source| Error::Io {
+                path: "<stdout>".into(),
+                source,
+            })?;
+    }
+    if let Some(ref dependency_graph) = opt.dependency_graph {
+        let path = Path::new(dependency_graph);
+        let write = || -> io::Result<()> {
+            let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+            spec.write_dependency_graph(&mut file)?;
+            io::Write::flush(&mut file)
+        };
+        write().map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+pub fn lint_rules(opt: LintRulesOptions) -> Result<(), Error> {
+    // the datalog of each file: the `prolog` blocks of markdown files, like the book's chapters
+    let mut sources: Vec<(String, String)> = Vec::new();
+    if opt.files.is_empty() {
+        for &(path, chapter) in rules::CHAPTERS {
+            sources.push((path.to_string(), chapter.to_string()));
+        }
+    } else {
+        for file in &opt.files {
+            let text = std::fs::read_to_string(file).map_err(|source| Error::Io {
+                path: file.into(),
+                source,
+            })?;
+            sources.push((file.clone(), text));
+        }
+    }
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for (file, text) in sources {
+        match polonius_datalog::parse(&datalog(&file, text)) {
+            Ok(program) => {
+                for lint in polonius_datalog::lint(&program) {
+                    println!("{}: {}", file, lint);
+                    if lint.is_error() {
+                        errors += 1;
+                    } else {
+                        warnings += 1;
+                    }
+                }
+            }
+            Err(error) => {
+                println!("{}: error: {}", file, error);
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        return Err(Error::RuleErrors { errors });
+    }
+    println!("The rules have no errors, and {} warning(s)", warnings);
+    Ok(())
+}
+
+/// The datalog of a `file`'s `text`: the `prolog` blocks of markdown files, like the book's
+/// chapters, or the whole text.
+fn datalog(file: &str, text: String) -> String {
+    if file.ends_with(".md") {
+        rules::prolog_blocks(&text)
+            .into_iter()
+            .map(|(_, block)| block)
+            .collect()
+    } else {
+        text
+    }
+}
+
+/// Evaluates datalog rules with the interpreter on every function of the inputs, and shows the
+/// number of tuples of the relations they derive, or the tuples of the selected relations.
+pub fn eval_rules(opt: EvalRulesOptions) -> Result<(), Error> {
+    let text = std::fs::read_to_string(&opt.rules).map_err(|source| Error::Io {
+        path: opt.rules.clone().into(),
+        source,
+    })?;
+    let invalid_rules = |message: String| Error::InvalidRules {
+        file: opt.rules.clone().into(),
+        message,
+    };
+    let program = polonius_datalog::parse(&datalog(&opt.rules, text))
+        .map_err(|error| invalid_rules(error.to_string()))?;
+
+    let stdout = io::stdout();
+    let mut stream = stdout.lock();
+    for facts_dir in &facts_inputs(&opt.fact_dirs, opt.filter_fn.as_deref())? {
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_facts_from_input(tables, facts_dir)?;
+        let mut database = interpret::facts_database(&all_facts);
+        let (duration, evaluation) = timed(|| polonius_datalog::evaluate(&program, &mut database));
+        evaluation.map_err(|error| invalid_rules(error.to_string()))?;
+
+        let write = |stream: &mut dyn io::Write| -> io::Result<()> {
+            writeln!(
+                stream,
+                "# {}: evaluated in {:.3}s",
+                input_name(facts_dir),
+                duration.as_secs_f64()
+            )?;
+            if opt.relations.is_empty() {
+                let mut derived: Vec<&str> = program
+                    .rules()
+                    .map(|rule| rule.head.relation.as_str())
+                    .collect();
+                derived.dedup();
+                for relation in derived {
+                    writeln!(stream, "{}: {} tuples", relation, database.len(relation))?;
+                }
+            }
+            for relation in &opt.relations {
+                writeln!(stream, "{}: {} tuples", relation, database.len(relation))?;
+                let declaration = program
+                    .declarations()
+                    .find(|declaration| &declaration.relation == relation);
+                interpret::write_relation(&database, relation, declaration, tables, stream)?;
+            }
+            Ok(())
+        };
+        write(&mut stream).map_err(|source| Error::Io {
+            path: "<stdout>".into(),
+            source,
+        })?;
+    }
     Ok(())
 }
 
@@ -113,7 +708,22 @@ fn timed<T>(op: impl FnOnce() -> T) -> (Duration, T) {
 
 // Parses the provided CLI arguments into `Options`
 pub fn options_from_args() -> Result<Options, Error> {
-    let mut args = pico::Arguments::from_env();
+    options_from_vec(env::args_os().skip(1).collect())
+}
+
+/// Parses the arguments of the analysis, without the executable's path, into `Options`. The
+/// arguments after a `--` are all directories of facts, e.g. to analyze a directory named like a
+/// subcommand: `polonius -- diff`.
+pub(crate) fn options_from_vec(mut args: Vec<OsString>) -> Result<Options, Error> {
+    let escaped_fact_dirs = match args.iter().position(|arg| arg == "--") {
+        Some(index) => {
+            let escaped = args.split_off(index + 1);
+            args.pop();
+            escaped
+        }
+        None => Vec::new(),
+    };
+    let mut args = pico::Arguments::from_vec(args);
 
     // 1) print optional information before exiting: help, version
     let show_help = args.contains(["-h", "--help"]);
@@ -128,28 +738,65 @@ pub fn options_from_args() -> Result<Options, Error> {
 {description}
 
 USAGE:
-    polonius [FLAGS] [OPTIONS] <fact_dirs>...
+    polonius [FLAGS] [OPTIONS] [--] <fact_dirs>...
+    polonius diff <before> <after>
+    polonius eval-rules [OPTIONS] <rules> <fact_dirs>...
+    polonius explain-rule <rule>
+    polonius gen-bench [OPTIONS]
+    polonius lint-rules [files]...
+    polonius parity [OPTIONS] <fact_dirs>...
 
 FLAGS:
-    -h, --help           Prints help information
-        --show-tuples    Show output tuples on stdout
-        --skip-timing    Do not display timing results
-    -V, --version        Prints version information
-    -v, --verbose        Show intermediate output tuples and not just errors
+        --bless                  Rewrite the `{golden_file}` files of the directories of facts with the errors of every algorithm
+        --gzip-output            Compress the files written to the output directory with gzip
+    -h, --help                   Prints help information
+        --loans-out-of-scope     Compute the points where each loan goes out of scope, as rustc's `-Zpolonius=next` borrow checker does, dumped as `loans_out_of_scope_at`
+        --log-json               Log the phases and rounds of the computation as JSON lines on stderr, filtered by `RUST_LOG` [default: info]
+        --numeric-atoms          Show the output tuples' atoms as their numeric indices, instead of their names
+        --progress               Show the phases of the computation, and periodically its rounds and an estimate of the remaining time, on stderr
+        --prune-facts            Remove the facts which cannot lead to errors, and show pruning statistics
+        --reverse-post-order     Renumber the points in reverse post-order of the CFG during the computation
+        --rustc-layout           Write the output tuples like rustc's `-Znll-facts` directories: a directory per function in the output directory, with a tab-delimited `.facts` file per relation
+        --short-names            Show the atoms of the graphviz files by short names, like `O3` or `P17`, with a legend of their names
+        --scopes                 Compute the scope of each loan, and the live range of each origin: the points where they are live, dumped as `loan_scopes` and `origin_live_ranges`
+        --show-tuples            Show output tuples on stdout
+        --skip-loan-live-at      Do not compute the loans in scope at each point, only the errors
+        --skip-timing            Do not display timing results
+        --stop-on-move-errors    Do not borrow check the facts when there are move errors
+        --two-phase-borrows      Only report the conflicts with the loans of `loan_activated_at` facts after their activation
+    -V, --version                Prints version information
+    -v, --verbose                Show intermediate output tuples and not just errors
 
 OPTIONS:
     -a <algorithm> [default: Naive]
         [possible values: {variants}]
+        --add-fact <tuple>...                    Add this tuple to the facts of each input, like `cfg_edge "Mid(bb0[1])" "Start(bb3[0])"`: the name of its relation, followed by its values separated by spaces, as in its fact file
+        --add-facts <fact file>...               Add the facts of this file to the facts of each input: a fact file named after its relation, like `cfg_edge.facts`, or a bundle of fact files
+        --filter-fn <glob>                       Only analyze the directories of facts whose name matches this glob, e.g. `*add_defaults*`
         --graphviz-file <graphviz file>          Generate a graphviz file to visualize the computation
+        --graphviz-depth <count>                 Show at most this many input and output relations per point in the graphviz file
         --dump-liveness-graph <graphviz file>    Generate a graphviz file to visualize the liveness information
-    -o, --output <output_directory>              Directory where to output resulting tuples
+        --dump-constraint-graph <graphviz file>  Generate a graphviz file of the subset constraints between origins, and their loans
+        --focus-loan <loan>...                   Only dump and visualize the tuples involving this loan
+        --focus-origin <origin>...               Only dump and visualize the tuples involving this origin
+        --focus-point <point>...                 Only dump and visualize the tuples at this point
+        --dump-relations <relations>             Only dump these comma-separated output relations
+        --dump-style <style>                     Layout of the output tuples [default: tsv]
+        [possible values: aligned, tsv, csv]
+        --loan-partitions <count>                Compute the errors of the loans in this many separate partitions
+        --max-rounds <count>                     Abort the computation when an iteration doesn't converge within this many rounds, showing the sizes of its relations
+        --cache-dir <cache directory>            Reuse the outputs computed for identical facts and options, stored in this directory
+        --symbols <symbols file>                 Intern the atoms of all the inputs in the same tables, loaded from this file when it exists and saved to it afterwards, to keep their numeric indices stable across runs, e.g. to dump them with `--numeric-atoms`
+    -o, --output <output_directory>              Directory where to output resulting tuples, and the differences found by the Compare variant
+        --waivers <waiver file>                  Tolerate the known differences between the variants compared by the Compare variant listed in this JSON file, by directory of facts, in the format of the written differences
 
 ARGS:
-    <fact_dirs>..."#,
+    <fact_dirs>...    Directories of fact files, directories containing them, bundles of fact files, or `-` to read a bundle from stdin. The arguments after `--` are all directories of facts, e.g. to analyze a directory named like a subcommand"#,
             name = PKG_NAME,
             version = PKG_VERSION,
             description = PKG_DESCRIPTION,
-            variants = variants.join(", ")
+            variants = variants.join(", "),
+            golden_file = GOLDEN_FILE,
         );
         exit(0);
     }
@@ -161,16 +808,53 @@ ARGS:
     }
 
     // 2) parse args
-    let options = Options {
+    let mut options = Options {
         algorithm: arg_from_str(&mut args, "-a")?.unwrap_or(Algorithm::Naive),
         show_tuples: args.contains("--show-tuples"),
+        numeric_atoms: args.contains("--numeric-atoms"),
         skip_timing: args.contains("--skip-timing"),
         verbose: args.contains(["-v", "--verbose"]),
         graphviz_file: arg_from_str(&mut args, "--graphviz-file")?,
+        graphviz_depth: arg_from_str(&mut args, "--graphviz-depth")?,
+        short_names: args.contains("--short-names"),
         output_directory: arg_from_str(&mut args, "-o")?.or(arg_from_str(&mut args, "--output")?),
+        gzip_output: args.contains("--gzip-output"),
+        rustc_layout: args.contains("--rustc-layout"),
+        dump_style: arg_from_str(&mut args, "--dump-style")?.unwrap_or_default(),
+        dump_relations: arg_from_str(&mut args, "--dump-relations")?,
+        focus: Focus {
+            loans: args_from_str(&mut args, "--focus-loan")?,
+            origins: args_from_str(&mut args, "--focus-origin")?,
+            points: args_from_str(&mut args, "--focus-point")?,
+        },
+        added_fact_files: args_from_str(&mut args, "--add-facts")?,
+        added_facts: args_from_str(&mut args, "--add-fact")?,
         liveness_graph_file: arg_from_str(&mut args, "--dump-liveness-graph")?,
+        constraint_graph_file: arg_from_str(&mut args, "--dump-constraint-graph")?,
+        prune_facts: args.contains("--prune-facts"),
+        stop_on_move_errors: args.contains("--stop-on-move-errors"),
+        reverse_post_order: args.contains("--reverse-post-order"),
+        two_phase_borrows: args.contains("--two-phase-borrows"),
+        progress: args.contains("--progress"),
+        log_json: args.contains("--log-json"),
+        max_rounds: arg_from_str(&mut args, "--max-rounds")?,
+        skip_loan_live_at: args.contains("--skip-loan-live-at"),
+        scopes: args.contains("--scopes"),
+        loans_out_of_scope: args.contains("--loans-out-of-scope"),
+        loan_partitions: arg_from_str(&mut args, "--loan-partitions")?,
+        cache_dir: arg_from_str(&mut args, "--cache-dir")?,
+        filter_fn: arg_from_str(&mut args, "--filter-fn")?,
+        symbols_file: arg_from_str(&mut args, "--symbols")?,
+        waivers_file: arg_from_str(&mut args, "--waivers")?,
+        bless: args.contains("--bless"),
         fact_dirs: args.free().map_err(readable_pico_error)?,
     };
+    for fact_dir in escaped_fact_dirs {
+        let fact_dir = fact_dir
+            .into_string()
+            .map_err(|_| readable_pico_error(pico::Error::NonUtf8Argument))?;
+        options.fact_dirs.push(fact_dir);
+    }
 
     // 3) validate args: a fact directory is required
     if options.fact_dirs.is_empty() {
@@ -186,14 +870,272 @@ For more information try --help"#
         exit(1);
     }
 
+    // the rustc layout is only for the files of an output directory, in the facts' format
+    if options.rustc_layout {
+        if options.output_directory.is_none() {
+            return Err(Error::Arguments(
+                "error: `--rustc-layout` requires an output directory: `-o <output_directory>`"
+                    .to_string(),
+            ));
+        }
+        if options.gzip_output || options.dump_style != DumpStyle::Tsv {
+            return Err(Error::Arguments(
+                "error: `--rustc-layout` writes uncompressed tab-delimited files, and can't be used with `--gzip-output` or `--dump-style`".to_string(),
+            ));
+        }
+    }
+
     // 4) setup logging at the default `Info` level when necessary
-    if env::var("RUST_LOG").is_ok() {
+    if options.log_json {
+        start_json_logging().expect("Initializing logger failed");
+    } else if env::var("RUST_LOG").is_ok() {
         start_logging().expect("Initializing logger failed");
     }
 
     Ok(options)
 }
 
+/// Parses the arguments of the `diff` subcommand: `polonius diff <before> <after>`.
+pub fn diff_options_from_args() -> Result<DiffOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        println!(
+            r#"{name}-diff {version}
+Compares the facts of two inputs, showing the tuples of each relation which differ
+
+USAGE:
+    polonius diff <before> <after>
+
+ARGS:
+    <before>    The facts to compare from: a directory of fact files, a bundle, or `-` for stdin
+    <after>     The facts to compare to"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+        );
+        exit(0);
+    }
+
+    let mut inputs = subcommand_args(args)?;
+    if inputs.len() != 2 {
+        return Err(Error::Arguments(
+            "error: expected the two inputs to compare: polonius diff <before> <after>".to_string(),
+        ));
+    }
+
+    let after = inputs.pop().unwrap();
+    let before = inputs.pop().unwrap();
+    Ok(DiffOptions { before, after })
+}
+
+/// Parses the arguments of the `explain-rule` subcommand: `polonius explain-rule <rule>`.
+pub fn explain_rule_options_from_args() -> Result<ExplainRuleOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        println!(
+            r#"{name}-explain-rule {version}
+Shows a datalog rule of the book, its documentation, the relations it joins and derives, and the function implementing it
+
+USAGE:
+    polonius explain-rule [OPTIONS] [rule]
+
+FLAGS:
+        --dependencies                          Show the rules producing and consuming each relation, as markdown
+
+OPTIONS:
+        --dependency-graph <graphviz file>      Generate a graphviz file of the dependencies between the relations of the rules
+
+ARGS:
+    <rule>    The label of the rule, like `R3`, or the relation it derives, like `subset`"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+        );
+        exit(0);
+    }
+
+    let dependencies = args.contains("--dependencies");
+    let dependency_graph = arg_from_str(&mut args, "--dependency-graph")?;
+    let mut rules = subcommand_args(args)?;
+    let rule = rules.pop();
+    if !rules.is_empty() || (rule.is_none() && !dependencies && dependency_graph.is_none()) {
+        return Err(Error::Arguments(
+            "error: expected the rule to explain: polonius explain-rule <rule>".to_string(),
+        ));
+    }
+
+    Ok(ExplainRuleOptions {
+        rule,
+        dependencies,
+        dependency_graph,
+    })
+}
+
+/// Parses the arguments of the `eval-rules` subcommand:
+/// `polonius eval-rules <rules> <fact_dirs>...`.
+pub fn eval_rules_options_from_args() -> Result<EvalRulesOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        println!(
+            r#"{name}-eval-rules {version}
+Evaluates datalog rules with an interpreter on every function of the inputs, and shows the number of tuples of the relations they derive
+
+The rules can join the relations of the facts, and `origin_live_on_entry`, `placeholder_origin` and `placeholder_loan`, like the book's rules
+
+USAGE:
+    polonius eval-rules [OPTIONS] <rules> <fact_dirs>...
+
+OPTIONS:
+        --filter-fn <glob>       Only analyze the directories of facts whose name matches this glob, e.g. `*add_defaults*`
+        --relation <name>...     Show the tuples of this relation, named after the types of its columns' declaration
+
+ARGS:
+    <rules>           A datalog file, or a markdown file whose `prolog` blocks are evaluated
+    <fact_dirs>...    Directories of fact files, directories containing them, or bundles of fact files"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+        );
+        exit(0);
+    }
+
+    let filter_fn = arg_from_str(&mut args, "--filter-fn")?;
+    let relations = args_from_str(&mut args, "--relation")?;
+    let mut inputs = subcommand_args(args)?.into_iter();
+    let (rules, fact_dirs) = match inputs.next() {
+        Some(rules) if inputs.len() > 0 => (rules, inputs.collect()),
+        _ => {
+            return Err(Error::Arguments(
+                "error: expected the rules and the facts to evaluate them on: polonius eval-rules <rules> <fact_dirs>..."
+                    .to_string(),
+            ))
+        }
+    };
+    Ok(EvalRulesOptions {
+        rules,
+        fact_dirs,
+        relations,
+        filter_fn,
+    })
+}
+
+/// Parses the arguments of the `parity` subcommand: `polonius parity <fact_dirs>...`.
+pub fn parity_options_from_args() -> Result<ParityOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        let engines: Vec<_> = parity::ENGINES
+            .iter()
+            .map(|engine| format!("{:?}", engine))
+            .collect();
+        println!(
+            r#"{name}-parity {version}
+Runs the {engines} engines on every function of the inputs, and writes a CSV matrix of their error counts, `loan_live_at` tuple counts and timings, and whether they agree with the first engine
+
+USAGE:
+    polonius parity [OPTIONS] <fact_dirs>...
+
+OPTIONS:
+        --filter-fn <glob>              Only analyze the directories of facts whose name matches this glob, e.g. `*add_defaults*`
+        --souffle <datalog file>        Also run this datalog program with Souffle's interpreter, on each directory of facts, and count the tuples of its `errors`, `subset_errors`, `move_errors` and `loan_live_at` output relations
+    -o, --output <csv file>             Write the matrix to this file, instead of stdout
+
+ARGS:
+    <fact_dirs>...    Directories of fact files, directories containing them, or bundles of fact files"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+            engines = engines.join(", "),
+        );
+        exit(0);
+    }
+
+    let options = ParityOptions {
+        filter_fn: arg_from_str(&mut args, "--filter-fn")?,
+        souffle_program: arg_from_str(&mut args, "--souffle")?,
+        output_file: arg_from_str(&mut args, "-o")?.or(arg_from_str(&mut args, "--output")?),
+        fact_dirs: subcommand_args(args)?,
+    };
+    if options.fact_dirs.is_empty() {
+        return Err(Error::Arguments(
+            "error: expected the facts to analyze: polonius parity <fact_dirs>...".to_string(),
+        ));
+    }
+    Ok(options)
+}
+
+/// Parses the arguments of the `gen-bench` subcommand: `polonius gen-bench [OPTIONS]`.
+pub fn gen_bench_options_from_args() -> Result<GenBenchOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        println!(
+            r#"{name}-gen-bench {version}
+Generates synthetic facts, deterministic for a given seed, to benchmark the engines on reproducible workloads of any size
+
+USAGE:
+    polonius gen-bench [FLAGS] [OPTIONS]
+
+FLAGS:
+        --bench-interner                 Instead of writing the facts, intern the names of their points with the CLI's interner, and with the owned strings one it replaced, and show their fastest time of 5 runs and their heap size
+
+OPTIONS:
+        --points <count>                 The number of points of the CFG [default: 1000]
+        --origins <count>                The number of origins, two of which are placeholders [default: 100]
+        --loans <count>                  The number of loans [default: 50]
+        --variables <count>              The number of variables [default: 50]
+        --density <percent>              The percentage of statements with each kind of fact: subsets, invalidations, uses and definitions of variables [default: 10]
+        --shape <shape>                  The shape of the CFG [default: line]
+        [possible values: line, diamonds, loops]
+        --seed <seed>                    The seed of the pseudo-random facts [default: 0]
+    -o, --output <output_directory>      Write the facts to this directory, instead of a bundle of fact files on stdout"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+        );
+        exit(0);
+    }
+
+    let parameters = synthetic::Parameters {
+        points: arg_from_str(&mut args, "--points")?.unwrap_or(1000),
+        origins: arg_from_str(&mut args, "--origins")?.unwrap_or(100),
+        loans: arg_from_str(&mut args, "--loans")?.unwrap_or(50),
+        variables: arg_from_str(&mut args, "--variables")?.unwrap_or(50),
+        density: arg_from_str(&mut args, "--density")?.unwrap_or(10),
+        shape: arg_from_str(&mut args, "--shape")?.unwrap_or_default(),
+        seed: arg_from_str(&mut args, "--seed")?.unwrap_or(0),
+    };
+    let options = GenBenchOptions {
+        parameters,
+        output_directory: arg_from_str(&mut args, "-o")?.or(arg_from_str(&mut args, "--output")?),
+        bench_interner: args.contains("--bench-interner"),
+    };
+    let unexpected = subcommand_args(args)?;
+    if !unexpected.is_empty() {
+        return Err(Error::Arguments(format!(
+            "error: unexpected arguments: {}",
+            unexpected.join(" ")
+        )));
+    }
+    Ok(options)
+}
+
+/// Parses the arguments of the `lint-rules` subcommand: `polonius lint-rules [files]...`.
+pub fn lint_rules_options_from_args() -> Result<LintRulesOptions, Error> {
+    let mut args = pico::Arguments::from_env();
+    if args.contains(["-h", "--help"]) {
+        println!(
+            r#"{name}-lint-rules {version}
+Checks datalog rules for unbound variables, non-stratified negations, unused declarations, and joins datafrog can't start with
+
+USAGE:
+    polonius lint-rules [files]...
+
+ARGS:
+    <files>...    Datalog files, or markdown files whose `prolog` blocks are checked [default: the book's chapters]"#,
+            name = PKG_NAME,
+            version = PKG_VERSION,
+        );
+        exit(0);
+    }
+
+    let files = subcommand_args(args)?;
+    Ok(LintRulesOptions { files })
+}
+
 // Read an argument from the CLI, parse it, but with a readable error message if it fails
 pub fn arg_from_str<T>(args: &mut pico::Arguments, key: &'static str) -> Result<Option<T>, Error>
 where
@@ -201,7 +1143,7 @@ where
     <T as FromStr>::Err: fmt::Display,
 {
     args.value_from_str(key).map_err(|e| {
-        Error(format!(
+        Error::Arguments(format!(
             "error parsing argument '{}': {}",
             key,
             readable_pico_error(e)
@@ -209,19 +1151,39 @@ where
     })
 }
 
+// Read all the occurrences of a repeatable argument from the CLI
+fn args_from_str<T>(args: &mut pico::Arguments, key: &'static str) -> Result<Vec<T>, Error>
+where
+    T: FromStr,
+    <T as FromStr>::Err: fmt::Display,
+{
+    let mut values = Vec::new();
+    while let Some(value) = arg_from_str(args, key)? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+// The free arguments of a subcommand, after its name
+fn subcommand_args(mut args: pico::Arguments) -> Result<Vec<String>, Error> {
+    args.free_from_str::<String>()
+        .map_err(readable_pico_error)?;
+    args.free().map_err(readable_pico_error)
+}
+
 // Make a pico_args error a bit more readable than just its `Debug` output
 fn readable_pico_error(error: pico::Error) -> Error {
-    use pico::Error;
-    Error(match error {
-        Error::ArgumentParsingFailed { cause } => format!("failed to parse ({})", cause),
-        Error::Utf8ArgumentParsingFailed { value, cause } => {
+    use pico::Error as PicoError;
+    Error::Arguments(match error {
+        PicoError::ArgumentParsingFailed { cause } => format!("failed to parse ({})", cause),
+        PicoError::Utf8ArgumentParsingFailed { value, cause } => {
             format!("'{}' isn't a valid value ({})", value, cause)
         }
-        Error::OptionWithoutAValue(_) => "missing value".to_string(),
-        Error::UnusedArgsLeft(left) => {
+        PicoError::OptionWithoutAValue(_) => "missing value".to_string(),
+        PicoError::UnusedArgsLeft(left) => {
             format!("error, unrecognized arguments: {}", left.join(", "))
         }
-        Error::NonUtf8Argument => "not a valid utf8 value".to_string(),
+        PicoError::NonUtf8Argument => "not a valid utf8 value".to_string(),
     })
 }
 
@@ -229,7 +1191,9 @@ struct Logger;
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Info
+        // the engine's spans are only shown in the JSON logs: their entries and exits would
+        // drown the events
+        metadata.level() <= Level::Info && !metadata.target().starts_with("tracing::span")
     }
 
     fn log(&self, record: &Record) {
@@ -246,3 +1210,29 @@ static LOGGER: Logger = Logger;
 fn start_logging() -> Result<(), SetLoggerError> {
     log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Info))
 }
+
+/// Logs the events of the engine and of the CLI as JSON lines on stderr, filtered by the
+/// `RUST_LOG` directives, e.g. `RUST_LOG=debug` to also log the rounds of the computation, and at
+/// the default `Info` level otherwise.
+fn start_json_logging() -> Result<(), TryInitError> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    json_logger(filter, io::stderr).try_init()
+}
+
+/// The subscriber writing the events passing the `filter` as JSON lines, with the engine's spans
+/// of the phases and rounds of the computation, and their durations when they close.
+pub(crate) fn json_logger<W>(
+    filter: EnvFilter,
+    make_writer: W,
+) -> FmtSubscriber<JsonFields, Format<Json>, EnvFilter, W>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_current_span(true)
+        .with_writer(make_writer)
+        .finish()
+}
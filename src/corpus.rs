@@ -0,0 +1,414 @@
+#![cfg(test)]
+
+//! Regression tests over the whole `inputs` corpus: the errors computed by every variant, on
+//! every directory of facts, are compared to the ones recorded in the `errors.expected` golden
+//! file next to the facts.
+//!
+//! When errors change intentionally, the golden files can be updated by running the tests with
+//! the `POLONIUS_BLESS` environment variable set, or the CLI with `--bless`, and the differences
+//! reviewed in the diff. The
+//! `POLONIUS_FILTER_FN` environment variable restricts the tests to the directories whose name
+//! matches its glob pattern, e.g. `*move_error*`.
+//!
+//! The `.polonius` programs in `inputs/programs` are self-contained tests: the errors computed by
+//! every variant are compared to the ones the program expects in its `expect { .. }` section.
+//!
+//! The rules of the book are also evaluated with the datalog interpreter on all these inputs, and
+//! must find the errors of the hand-optimized `DatafrogOpt` variant, so that they don't drift
+//! apart.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use polonius_datalog::Tuple;
+use polonius_engine::{Algorithm, Atom};
+use polonius_parser::ir::Expectation;
+use polonius_parser::parse_input;
+
+use crate::dump::Output;
+use crate::facts::AllFacts;
+use crate::glob;
+use crate::golden::{self, GOLDEN_FILE};
+use crate::intern::InternerTables;
+use crate::interpret;
+use crate::program::parse_from_program;
+use crate::rules;
+use crate::tab_delim;
+
+/// The `clap` dataset is a benchmark, too slow to be computed with the `Naive` rules in tests.
+const EXCLUDED_DIRS: &[&str] = &["clap-rs"];
+
+/// The directories of facts under `dir`, outside of the `EXCLUDED_DIRS`.
+fn find_facts_dirs(dir: &Path, filter: Option<&str>) -> Vec<PathBuf> {
+    glob::find_facts_dirs(dir, filter)
+        .unwrap_or_else(|e| panic!("cannot read {:?}: {}", dir, e))
+        .into_iter()
+        .filter(|path| {
+            !path.components().any(|component| {
+                EXCLUDED_DIRS
+                    .iter()
+                    .any(|&dir| component.as_os_str() == dir)
+            })
+        })
+        .collect()
+}
+
+/// Computes the errors of every variant on the facts in `facts_dir`, in the golden files' format.
+fn compute_errors(facts_dir: &Path) -> String {
+    let mut tables = InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(&mut tables, facts_dir)
+        .unwrap_or_else(|e| panic!("cannot load {:?}: {}", facts_dir, e));
+    golden::compute_errors(&all_facts, &tables)
+}
+
+/// The variants computing the errors at precise points, which must find exactly the expected
+/// errors. The `LocationInsensitive` variant only needs to find them, as it over-approximates.
+const LOCATION_SENSITIVE: &[Algorithm] = &[
+    Algorithm::Naive,
+    Algorithm::DatafrogOpt,
+    Algorithm::Hybrid,
+    Algorithm::ParallelNaive,
+    Algorithm::Compare,
+];
+
+/// The errors of the `output`, as the expectations they'd match, at their points.
+fn computed_errors(output: &Output, tables: &InternerTables) -> Vec<Expectation> {
+    let point = |point| Some(tables.points.untern(point).trim_matches('"').to_string());
+    let mut errors = Vec::new();
+    for (&at, loans) in &output.errors {
+        errors.extend(loans.iter().map(|&loan| Expectation::Error {
+            loan: tables.loans.untern(loan).to_string(),
+            point: point(at),
+        }));
+    }
+    for (&at, origins) in &output.subset_errors {
+        errors.extend(
+            origins
+                .iter()
+                .map(|&(origin1, origin2)| Expectation::SubsetError {
+                    a: tables.origins.untern(origin1).to_string(),
+                    b: tables.origins.untern(origin2).to_string(),
+                    point: point(at),
+                }),
+        );
+    }
+    errors
+}
+
+/// Whether the `computed` error is the `expected` one, at the expected point if both have one.
+fn matches(expected: &Expectation, computed: &Expectation) -> bool {
+    let point_matches = match (expected.point(), computed.point()) {
+        (Some(expected), Some(computed)) => expected == computed,
+        _ => true,
+    };
+    match (expected, computed) {
+        (Expectation::Error { loan: l1, .. }, Expectation::Error { loan: l2, .. }) => {
+            l1 == l2 && point_matches
+        }
+        (
+            Expectation::SubsetError { a: a1, b: b1, .. },
+            Expectation::SubsetError { a: a2, b: b2, .. },
+        ) => a1 == a2 && b1 == b2 && point_matches,
+        _ => false,
+    }
+}
+
+/// Checks the errors computed by every variant on the `program` against its expectations,
+/// describing the errors missing from, or added to, the output of each variant.
+fn check_expectations(program: &str) -> String {
+    let input = parse_input(program).unwrap_or_else(|e| panic!("{}", e.render(program)));
+    let mut tables = InternerTables::new();
+    let all_facts = parse_from_program(program, &mut tables).unwrap();
+
+    let mut diff = String::new();
+    let mut check = |algorithm: Algorithm, exact: bool| {
+        let output = Output::compute(&all_facts, algorithm, false);
+        let mut computed = computed_errors(&output, &tables);
+        if matches!(algorithm, Algorithm::LocationInsensitive) {
+            // the potential subset errors are only at a representative point, and match anywhere
+            for error in &mut computed {
+                if let Expectation::SubsetError { point, .. } = error {
+                    *point = None;
+                }
+            }
+        }
+
+        for expected in &input.expectations {
+            let found = computed.iter().any(|computed| matches(expected, computed));
+            if !found {
+                writeln!(diff, "  [{:?}] - {}", algorithm, expected.node).unwrap();
+            }
+        }
+        if exact {
+            for computed in &computed {
+                if !input
+                    .expectations
+                    .iter()
+                    .any(|expected| matches(expected, computed))
+                {
+                    writeln!(diff, "  [{:?}] + {}", algorithm, computed).unwrap();
+                }
+            }
+        }
+    };
+
+    for &algorithm in LOCATION_SENSITIVE {
+        check(algorithm, true);
+    }
+    check(Algorithm::LocationInsensitive, false);
+    diff
+}
+
+/// Describes the lines missing from, and added to, the `expected` errors.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let expected_lines: BTreeSet<_> = expected.lines().collect();
+    let actual_lines: BTreeSet<_> = actual.lines().collect();
+
+    let mut diff = String::new();
+    for line in expected_lines.difference(&actual_lines) {
+        writeln!(diff, "  - {}", line).unwrap();
+    }
+    for line in actual_lines.difference(&expected_lines) {
+        writeln!(diff, "  + {}", line).unwrap();
+    }
+    diff
+}
+
+#[test]
+fn corpus_errors_match_golden_files() {
+    let bless = env::var_os("POLONIUS_BLESS").is_some();
+
+    let filter = env::var("POLONIUS_FILTER_FN").ok();
+
+    let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs");
+    let facts_dirs = find_facts_dirs(&inputs, filter.as_deref());
+    assert!(!facts_dirs.is_empty(), "no facts found in {:?}", inputs);
+
+    let mut failures = Vec::new();
+    for facts_dir in &facts_dirs {
+        let actual = compute_errors(facts_dir);
+        let golden_path = facts_dir.join(GOLDEN_FILE);
+
+        if bless {
+            golden::bless(facts_dir, &actual).unwrap_or_else(|e| panic!("{}", e));
+            continue;
+        }
+
+        match fs::read_to_string(&golden_path) {
+            Ok(ref expected) if *expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{:?}: errors differ\n{}",
+                facts_dir,
+                diff_lines(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!("{:?}: missing golden file", facts_dir)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} fact directories don't match their golden files, \
+         run the tests with `POLONIUS_BLESS=1` to update them:\n{}",
+        failures.len(),
+        facts_dirs.len(),
+        failures.join("\n")
+    );
+}
+
+/// Each directory of facts is analyzed this many times by the concurrent test, to interleave the
+/// computations of the same facts as well as of different ones.
+const CONCURRENT_ROUNDS: usize = 2;
+
+/// The engine has no shared state: the corpus, analyzed concurrently on a pool of threads, must
+/// have the same errors as when analyzed sequentially.
+#[test]
+fn corpus_errors_are_computed_concurrently() {
+    let filter = env::var("POLONIUS_FILTER_FN").ok();
+    let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs");
+    let facts_dirs = find_facts_dirs(&inputs, filter.as_deref());
+    let expected: Vec<_> = facts_dirs
+        .iter()
+        .map(|facts_dir| fs::read_to_string(facts_dir.join(GOLDEN_FILE)).ok())
+        .collect();
+
+    let jobs: Vec<_> = (0..CONCURRENT_ROUNDS)
+        .flat_map(|_| 0..facts_dirs.len())
+        .collect();
+    let next_job = AtomicUsize::new(0);
+    let failures = Mutex::new(BTreeSet::new());
+    let threads = thread::available_parallelism().map_or(4, |threads| threads.get().clamp(2, 8));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while let Some(&index) = jobs.get(next_job.fetch_add(1, Ordering::Relaxed)) {
+                    // the golden files are checked by `corpus_errors_match_golden_files`
+                    let Some(ref expected) = expected[index] else {
+                        continue;
+                    };
+                    let actual = compute_errors(&facts_dirs[index]);
+                    if actual != *expected {
+                        failures.lock().unwrap().insert(format!(
+                            "{:?}: errors differ\n{}",
+                            facts_dirs[index],
+                            diff_lines(expected, &actual)
+                        ));
+                    }
+                }
+            });
+        }
+    });
+
+    let failures = failures.into_inner().unwrap();
+    assert!(
+        failures.is_empty(),
+        "{} fact directories have different errors when analyzed concurrently:\n{}",
+        failures.len(),
+        failures.into_iter().collect::<Vec<_>>().join("\n")
+    );
+}
+
+#[test]
+fn programs_match_expectations() {
+    let programs = find_programs();
+    assert!(!programs.is_empty(), "no programs found in inputs/programs");
+
+    let mut failures = Vec::new();
+    for path in &programs {
+        let program =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("cannot read {:?}: {}", path, e));
+        let diff = check_expectations(&program);
+        if !diff.is_empty() {
+            failures.push(format!("{:?}: errors differ\n{}", path, diff));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} programs don't have the errors they expect:\n{}",
+        failures.len(),
+        programs.len(),
+        failures.join("\n")
+    );
+}
+
+/// The programs in `inputs/programs`, in order.
+fn find_programs() -> Vec<PathBuf> {
+    let programs_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs/programs");
+    let mut programs: Vec<_> = fs::read_dir(&programs_dir)
+        .unwrap_or_else(|e| panic!("cannot read {:?}: {}", programs_dir, e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "polonius")
+        })
+        .collect();
+    programs.sort();
+    programs
+}
+
+/// Describes the differences between the `errors` and `subset_errors` derived by the
+/// location-sensitive rules of the book, and the ones computed by `DatafrogOpt`.
+fn compare_book_rules(all_facts: &AllFacts) -> String {
+    let chapter = rules::CHAPTERS
+        .iter()
+        .find(|&&(path, _)| path == "book/src/rules/loans.md")
+        .unwrap()
+        .1;
+    let datalog: String = rules::prolog_blocks(chapter)
+        .into_iter()
+        .filter(|(section, _)| !section.starts_with("Location Insensitive"))
+        .map(|(_, block)| block)
+        .collect();
+    let program = polonius_datalog::parse(&datalog).unwrap();
+    let mut database = interpret::facts_database(all_facts);
+    polonius_datalog::evaluate(&program, &mut database).unwrap();
+
+    let output = Output::compute(all_facts, Algorithm::DatafrogOpt, false);
+    let errors: BTreeSet<Tuple> = output
+        .errors
+        .iter()
+        .flat_map(|(&point, loans)| {
+            loans
+                .iter()
+                .map(move |&loan| vec![loan.index() as u32, point.index() as u32])
+        })
+        .collect();
+    let subset_errors: BTreeSet<Tuple> = output
+        .subset_errors
+        .iter()
+        .flat_map(|(&point, origins)| {
+            origins.iter().map(move |&(origin1, origin2)| {
+                vec![
+                    origin1.index() as u32,
+                    origin2.index() as u32,
+                    point.index() as u32,
+                ]
+            })
+        })
+        .collect();
+
+    let mut diff = String::new();
+    for (relation, computed) in &[("errors", errors), ("subset_errors", subset_errors)] {
+        // The engine removes the reflexive subset errors of the origins on a cycle of subsets,
+        // but the rules can't: the datalog dialect has no `Origin1 != Origin2` constraint.
+        let derived: BTreeSet<Tuple> = database
+            .tuples(relation)
+            .filter(|tuple| *relation != "subset_errors" || tuple[0] != tuple[1])
+            .cloned()
+            .collect();
+        for tuple in derived.difference(computed) {
+            writeln!(
+                diff,
+                "  {}: only derived by the rules: {:?}",
+                relation, tuple
+            )
+            .unwrap();
+        }
+        for tuple in computed.difference(&derived) {
+            writeln!(
+                diff,
+                "  {}: only computed by DatafrogOpt: {:?}",
+                relation, tuple
+            )
+            .unwrap();
+        }
+    }
+    diff
+}
+
+#[test]
+fn book_rules_match_datafrog_opt() {
+    let inputs_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs");
+    let mut failures = Vec::new();
+    for facts_dir in find_facts_dirs(&inputs_dir, None) {
+        let mut tables = InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(&mut tables, &facts_dir)
+            .unwrap_or_else(|e| panic!("cannot load {:?}: {}", facts_dir, e));
+        let diff = compare_book_rules(&all_facts);
+        if !diff.is_empty() {
+            failures.push(format!("{:?}:\n{}", facts_dir, diff));
+        }
+    }
+    for path in find_programs() {
+        let program =
+            fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {:?}: {}", path, e));
+        let all_facts = parse_from_program(&program, &mut InternerTables::new()).unwrap();
+        let diff = compare_book_rules(&all_facts);
+        if !diff.is_empty() {
+            failures.push(format!("{:?}:\n{}", path, diff));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "the book's rules and `DatafrogOpt` have different errors on {} input(s):\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
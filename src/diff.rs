@@ -0,0 +1,80 @@
+//! Compares the facts of two inputs, e.g. dumped by two rustc versions for the same function, to
+//! debug why the analysis' results changed.
+
+use crate::dump::{write_rows, DumpStyle, OutputDump};
+use crate::facts::AllFacts;
+use crate::intern::InternerTables;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Writes the tuples of each relation only present in the `before` facts, prefixed with `-`, and
+/// the ones only present in the `after` facts, prefixed with `+`. Both inputs must have been
+/// loaded with the same `tables`, so that their atoms are aligned by name. Returns the number of
+/// tuples which differ.
+pub(crate) fn diff_facts(
+    before: &AllFacts,
+    after: &AllFacts,
+    tables: &InternerTables,
+    stream: &mut dyn Write,
+) -> io::Result<usize> {
+    macro_rules! diff_relations {
+        ($($relation:ident,)*) => {{
+            let mut differences = 0;
+            $({
+                let before: BTreeSet<_> = before.$relation.iter().collect();
+                let after: BTreeSet<_> = after.$relation.iter().collect();
+                let removed: Vec<_> = before.difference(&after).map(|&&tuple| tuple).collect();
+                let added: Vec<_> = after.difference(&before).map(|&&tuple| tuple).collect();
+                if !removed.is_empty() || !added.is_empty() {
+                    writeln!(
+                        stream,
+                        "# {}: {} removed, {} added",
+                        stringify!($relation),
+                        removed.len(),
+                        added.len()
+                    )?;
+                    write_diff_rows("-", stream, tables, &removed)?;
+                    write_diff_rows("+", stream, tables, &added)?;
+                    differences += removed.len() + added.len();
+                }
+            })*
+            differences
+        }};
+    }
+
+    Ok(diff_relations! {
+        loan_issued_at,
+        universal_region,
+        universal_region_live_at,
+        cfg_edge,
+        loan_killed_at,
+        loan_shallow_killed_at,
+        subset_base,
+        loan_invalidated_at,
+        loan_activated_at,
+        var_defined_at,
+        var_used_at,
+        var_dropped_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        origin_live_on_entry,
+        child_path,
+        path_is_var,
+        path_assigned_at_base,
+        path_moved_at_base,
+        path_accessed_at_base,
+        known_placeholder_subset,
+        placeholder,
+    })
+}
+
+fn write_diff_rows(
+    sign: &str,
+    stream: &mut dyn Write,
+    tables: &InternerTables,
+    tuples: &impl OutputDump,
+) -> io::Result<()> {
+    let mut rows = Vec::new();
+    tuples.push_all(tables, &mut rows);
+    write_rows(Some(sign), stream, DumpStyle::Aligned, &rows)
+}
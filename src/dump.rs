@@ -1,151 +1,611 @@
 use crate::facts::*;
 use crate::intern::InternerTables;
 use crate::intern::*;
+use crate::manifest::{write_manifest, Manifest};
+use crate::schema::AtomKind;
+use crate::tab_delim::write_tab_delimited_row;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::info;
 use petgraph::stable_graph::StableGraph;
 use petgraph::visit::{Dfs, EdgeRef, IntoEdgeReferences, IntoNodeReferences, NodeIndexable};
 use petgraph::{Incoming, Outgoing};
-use polonius_engine::{Atom as PoloniusEngineAtom, Output as PoloniusEngineOutput};
+use polonius_engine::{
+    Atom as PoloniusEngineAtom, AtomNames, Diagnostic, ErrorsDiff, OriginKind,
+    Output as PoloniusEngineOutput,
+};
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::fs::File;
+use std::fs::{self, File};
 use std::hash::Hash;
-use std::io::{self, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
 
 pub(crate) type Output = PoloniusEngineOutput<LocalFacts>;
 
+macro_rules! output_relations {
+    (
+        errors { $($error:ident,)* }
+        outputs { $($output:ident,)* }
+        optional { $($optional:ident,)* }
+        dumped { $($dumped:ident,)* }
+    ) => {
+        /// The names of the relations of the output which can be dumped, followed by the
+        /// statistics of the computation.
+        pub(crate) const OUTPUT_RELATIONS: &[&str] = &[
+            $(stringify!($error),)*
+            $(stringify!($output),)*
+            $(stringify!($optional),)*
+            $(stringify!($dumped),)*
+            "relation_peak_sizes",
+            "rounds_to_fixpoint",
+        ];
+
+        /// The relations of the `output`: the errors and the other outputs computed by default,
+        /// followed by the optional outputs and the intermediate relations when they were
+        /// computed.
+        fn output_relations(output: &Output) -> Vec<(&'static str, &(dyn OutputDump + Sync))> {
+            let mut relations: Vec<(&'static str, &(dyn OutputDump + Sync))> = vec![
+                $((stringify!($error), &output.$error),)*
+                $((stringify!($output), &output.$output),)*
+            ];
+            $(
+                if !output.$optional.is_empty() {
+                    relations.push((stringify!($optional), &output.$optional));
+                }
+            )*
+            if output.dump_enabled {
+                $(relations.push((stringify!($dumped), &output.$dumped));)*
+            }
+            relations
+        }
+    };
+}
+
+output_relations! {
+    errors {
+        errors,
+        move_errors,
+        subset_errors,
+    } outputs {
+        loan_live_at,
+    } optional {
+        potential_errors,
+        loan_scopes,
+        origin_live_ranges,
+        loans_out_of_scope_at,
+    } dumped {
+        origin_contains_loan_at,
+        origin_contains_loan_anywhere,
+        origin_live_on_entry,
+        loan_invalidated_at,
+        subset_anywhere,
+        known_contains,
+        known_placeholder_subset,
+        origin_kind,
+        var_live_on_entry,
+        var_drop_live_on_entry,
+        var_dropped_while_init_at,
+        path_maybe_initialized_on_exit,
+        path_maybe_uninitialized_on_exit,
+        path_definitely_initialized_on_exit,
+        path_moved_at,
+        path_assigned_at,
+        path_accessed_at,
+        var_maybe_partly_initialized_on_exit,
+    }
+}
+
+/// The relations of the output selected by name, e.g. with `--dump-relations subset,errors`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RelationSelection(Vec<&'static str>);
+
+impl RelationSelection {
+    pub(crate) fn includes(&self, relation: &str) -> bool {
+        self.0.contains(&relation)
+    }
+}
+
+impl FromStr for RelationSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let relations = s
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                OUTPUT_RELATIONS
+                    .iter()
+                    .find(|&&relation| relation == name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown relation `{}`, expected one of: {}",
+                            name,
+                            OUTPUT_RELATIONS.join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if relations.is_empty() {
+            return Err("expected the names of the relations to dump".to_string());
+        }
+        Ok(RelationSelection(relations))
+    }
+}
+
+/// Writes the relations of the `output`, or the ones in the `selection` when given, to stdout or
+/// to the files of the `output_dir`.
 pub(crate) fn dump_output(
     output: &Output,
     output_dir: &Option<PathBuf>,
     intern: &InternerTables,
+    gzip: bool,
+    style: DumpStyle,
+    focus: &Focus,
+    selection: Option<&RelationSelection>,
 ) -> io::Result<()> {
-    macro_rules! dump_output_fields {
-        ( $($field:ident),+ ) => {
-            $({
-                let (name, mut write) = writer_for(output_dir, stringify!($field))?;
-                dump_rows(
-                    name,
-                    &mut write,
-                    intern,
-                    &output.$field,
-                )?;
-            })+
-        };
-    }
-
-    dump_output_fields![errors, move_errors];
-
-    let (name, mut write) = writer_for(output_dir, "subset_errors")?;
-    dump_rows(name, &mut write, intern, &output.subset_errors)?;
-
-    if output.dump_enabled {
-        dump_output_fields![
-            origin_contains_loan_at,
-            origin_contains_loan_anywhere,
-            origin_live_on_entry,
-            loan_invalidated_at,
-            loan_live_at,
-            subset_anywhere,
-            known_contains,
-            var_live_on_entry,
-            var_drop_live_on_entry,
-            path_maybe_initialized_on_exit,
-            path_maybe_uninitialized_on_exit,
-            var_maybe_partly_initialized_on_exit
-        ];
+    let selected = |name: &str| selection.is_none_or(|selection| selection.includes(name));
+    let relations: Vec<_> = output_relations(output)
+        .into_iter()
+        .filter(|&(name, _)| selected(name))
+        .collect();
+
+    // the peak number of tuples of each relation, and the round where it was reached
+    let mut statistics: Vec<(&str, Vec<Vec<String>>)> = Vec::new();
+    if let Some(ref sizes) = output.relation_sizes {
+        let peaks = sizes
+            .peaks
+            .iter()
+            .map(|(&relation, peak)| {
+                vec![
+                    relation.to_string(),
+                    peak.tuples.to_string(),
+                    peak.round.to_string(),
+                ]
+            })
+            .collect();
+        statistics.push(("relation_peak_sizes", peaks));
+        statistics.push(("rounds_to_fixpoint", vec![vec![sizes.rounds.to_string()]]));
     }
-    return Ok(());
-
-    fn writer_for(
-        out_dir: &Option<PathBuf>,
-        name: &str,
-    ) -> io::Result<(Option<String>, Box<dyn Write>)> {
-        // create a writer for the provided output.
-        // If we have an output directory use that, otherwise just dump to stdout
-        use std::fs;
-
-        Ok(match out_dir {
-            Some(dir) => {
-                fs::create_dir_all(&dir)?;
-                let mut of = dir.join(name);
-                of.set_extension("facts");
-                (None, Box::new(fs::File::create(of)?))
+    statistics.retain(|&(name, _)| selected(name));
+
+    let dir = match output_dir {
+        Some(dir) => dir,
+        None => {
+            // dump to stdout, one relation after the other
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            for (name, relation) in relations {
+                writeln!(stdout, "# {}", name)?;
+                dump_rows(Some(name), &mut stdout, style, focus, intern, relation)?;
             }
-            None => {
-                let mut stdout = io::stdout();
-                write!(&mut stdout, "# {}\n", name)?;
-                (Some(name.to_string()), Box::new(stdout))
+            for (name, rows) in &statistics {
+                writeln!(stdout, "# {}", name)?;
+                write_rows(Some(name), &mut stdout, style, &owned_rows(rows))?;
             }
-        })
+            return Ok(());
+        }
+    };
+
+    fs::create_dir_all(dir)?;
+    let dumped: Vec<&str> = relations
+        .iter()
+        .map(|&(name, _)| name)
+        .chain(statistics.iter().map(|&(name, _)| name))
+        .collect();
+    let manifest = Manifest::new(&dumped, intern);
+    write_relation_files(dir, relations, gzip, style, focus, intern)?;
+    for (name, rows) in &statistics {
+        write_file(dir, name, gzip, |stream| {
+            write_rows(None, stream, style, &owned_rows(rows))
+        })?;
     }
+    write_manifest(dir, &manifest)
 }
 
-trait OutputDump {
-    fn push_all<'a>(
+/// Writes the relations of the `output`, or the ones in the `selection` when given, in the layout
+/// of rustc's `-Znll-facts` directories: a tab-delimited `.facts` file per relation in the
+/// function's `dir`, without the manifest nor the statistics of the computation, so that they can
+/// be used as the expectations of rustc's tests.
+pub(crate) fn dump_rustc_layout(
+    output: &Output,
+    dir: &std::path::Path,
+    intern: &InternerTables,
+    focus: &Focus,
+    selection: Option<&RelationSelection>,
+) -> io::Result<()> {
+    let relations: Vec<_> = output_relations(output)
+        .into_iter()
+        .filter(|&(name, _)| selection.is_none_or(|selection| selection.includes(name)))
+        .collect();
+    fs::create_dir_all(dir)?;
+    write_relation_files(dir, relations, false, DumpStyle::Tsv, focus, intern)
+}
+
+/// Writes each of the `relations` to its own file in the `dir`, in parallel: their contents don't
+/// depend on the order the files are written in.
+fn write_relation_files(
+    dir: &std::path::Path,
+    relations: Vec<(&'static str, &(dyn OutputDump + Sync))>,
+    gzip: bool,
+    style: DumpStyle,
+    focus: &Focus,
+    intern: &InternerTables,
+) -> io::Result<()> {
+    let results: Vec<io::Result<()>> = thread::scope(|scope| {
+        let relations: Vec<_> = relations
+            .into_iter()
+            .map(|(name, relation)| {
+                scope.spawn(move || {
+                    write_file(dir, name, gzip, |stream| {
+                        dump_rows(None, stream, style, focus, intern, relation)
+                    })
+                })
+            })
+            .collect();
+        relations
+            .into_iter()
+            .map(|writer| writer.join().expect("the dump writer panicked"))
+            .collect()
+    });
+    for result in results {
+        result?;
+    }
+    Ok(())
+}
+
+/// Writes the file of the relation `name` in the `dir`: a `.facts` file, or a `.facts.gz` file
+/// when `gzip` is set.
+fn write_file(
+    dir: &std::path::Path,
+    name: &str,
+    gzip: bool,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let file_name = if gzip {
+        format!("{}.facts.gz", name)
+    } else {
+        format!("{}.facts", name)
+    };
+    let mut file = BufWriter::new(File::create(dir.join(file_name))?);
+    if gzip {
+        // the gzip header has no timestamp, so that the files are reproducible
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        write(&mut encoder)?;
+        encoder.finish()?.flush()
+    } else {
+        write(&mut file)?;
+        file.flush()
+    }
+}
+
+fn owned_rows(rows: &[Vec<String>]) -> Vec<Vec<&str>> {
+    rows.iter()
+        .map(|row| row.iter().map(String::as_str).collect())
+        .collect()
+}
+
+/// A column of a dumped row: the name of an atom, and its kind.
+pub(crate) type Column<'a> = (AtomKind, &'a str);
+
+pub(crate) trait OutputDump {
+    /// Calls `row` with each of the rows of the tuples, after the columns of the `prefix`, in
+    /// the order of their atoms.
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     );
+
+    fn push_all<'a>(&'a self, intern: &'a InternerTables, output: &mut Vec<Vec<&'a str>>) {
+        self.for_each_row(intern, &mut vec![], &mut |row| {
+            output.push(row.iter().map(|&(_, name)| name).collect())
+        });
+    }
+}
+
+/// The atoms selected to focus on: when any is given, only the rows involving them are dumped
+/// or visualized.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Focus {
+    pub(crate) loans: Vec<String>,
+    pub(crate) origins: Vec<String>,
+    pub(crate) points: Vec<String>,
 }
 
+impl Focus {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.loans.is_empty() && self.origins.is_empty() && self.points.is_empty()
+    }
+
+    /// Whether the `row` is in focus: it has an atom of a kind being focused on, and each of
+    /// these atoms is one of the selected ones. Any row is in focus without a selection.
+    pub(crate) fn includes(&self, row: &[Column<'_>]) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let mut focused = false;
+        for &(kind, name) in row {
+            let selected = match kind {
+                AtomKind::Loan => &self.loans,
+                AtomKind::Origin => &self.origins,
+                AtomKind::Point => &self.points,
+                AtomKind::Variable | AtomKind::Path | AtomKind::OriginKind => continue,
+            };
+            if selected.is_empty() {
+                continue;
+            }
+            if !selected
+                .iter()
+                .any(|selected| unquote(selected) == unquote(name))
+            {
+                return false;
+            }
+            focused = true;
+        }
+        focused
+    }
+}
+
+/// The name of an atom without its surrounding quotes, and with its quotes unescaped, so that
+/// `"'_#5r"` on the command line matches the interned `"\'_#5r"`.
+pub(crate) fn unquote(name: &str) -> Cow<'_, str> {
+    let name = name.trim_matches('"');
+    if name.contains("\\'") {
+        Cow::Owned(name.replace("\\'", "'"))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// The short names of the atoms in the visual outputs, with `--short-names`: rustc's names, like
+/// `'_#123r` or `"Mid(bb12[3])"`, make the graphs' nodes and labels wide. The atoms are named after
+/// their kind and index instead, e.g. `O3` or `P17`, and a legend maps them back to their names.
+pub(crate) struct ShortNames<'a> {
+    names: &'a InternerTables,
+    tables: InternerTables,
+}
+
+impl<'a> ShortNames<'a> {
+    pub(crate) fn new(names: &'a InternerTables) -> Self {
+        ShortNames {
+            names,
+            tables: names.short_names(),
+        }
+    }
+
+    /// The `focus`, selecting the atoms by their short names.
+    fn focus(&self, focus: &Focus) -> Focus {
+        fn shorten<T: Atom>(selected: &[String], short_names: &ShortNames<'_>) -> Vec<String> {
+            let table = T::table(short_names.names);
+            selected
+                .iter()
+                .map(|name| {
+                    match (0..table.len())
+                        .map(T::from)
+                        .find(|&atom| unquote(table.untern(atom)) == unquote(name))
+                    {
+                        Some(atom) => T::table(&short_names.tables).untern(atom).to_string(),
+                        None => name.clone(),
+                    }
+                })
+                .collect()
+        }
+
+        Focus {
+            loans: shorten::<Loan>(&focus.loans, self),
+            origins: shorten::<Origin>(&focus.origins, self),
+            points: shorten::<Point>(&focus.points, self),
+        }
+    }
+
+    /// A graphviz node showing the legend of the short names: a table of the atoms of each kind,
+    /// and their names.
+    fn graphviz_legend(&self) -> String {
+        fn rows<T: Atom>(short_names: &ShortNames<'_>, legend: &mut String) {
+            let table = T::table(short_names.names);
+            for atom in (0..table.len()).map(T::from) {
+                legend.push_str(&format!(
+                    "<tr><td align=\"left\">{}</td><td align=\"left\">{}</td></tr>",
+                    T::table(&short_names.tables).untern(atom),
+                    escape_for_html(&unquote(table.untern(atom)))
+                ));
+            }
+        }
+
+        let mut legend = String::new();
+        rows::<Origin>(self, &mut legend);
+        rows::<Loan>(self, &mut legend);
+        rows::<Point>(self, &mut legend);
+        rows::<Variable>(self, &mut legend);
+        rows::<Path>(self, &mut legend);
+        format!(
+            "legend [\n  shape = \"plaintext\"\n  label = <<table border=\"0\" cellborder=\"1\" cellspacing=\"0\">{}</table>>\n];\n",
+            legend
+        )
+    }
+}
+
+fn escape_for_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// How the rows of the dumped relations are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum DumpStyle {
+    /// Columns padded to the width of their widest value, to be read by humans.
+    Aligned,
+
+    /// Tab-separated values, in the format of the facts files.
+    #[default]
+    Tsv,
+
+    /// Comma-separated values, quoted when needed.
+    Csv,
+}
+
+impl FromStr for DumpStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "aligned" => Ok(DumpStyle::Aligned),
+            "tsv" => Ok(DumpStyle::Tsv),
+            "csv" => Ok(DumpStyle::Csv),
+            _ => Err(format!(
+                "unknown dump style `{}`, expected one of: aligned, tsv, csv",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes the rows of the tuples in `focus` as they are visited, without collecting them. The
+/// aligned style visits them twice: once to find the width of the columns, and once to write
+/// them.
 fn dump_rows(
-    name: Option<String>,
+    name: Option<&str>,
     stream: &mut dyn Write,
+    style: DumpStyle,
+    focus: &Focus,
     intern: &InternerTables,
-    value: &impl OutputDump,
+    value: &(impl OutputDump + ?Sized),
 ) -> io::Result<()> {
-    let mut rows = Vec::new();
-    OutputDump::push_all(value, intern, &mut vec![], &mut rows);
-    let col_width: usize = rows
-        .iter()
-        .map(|cols| cols.iter().map(|s| s.len()).max().unwrap_or(0))
-        .max()
-        .unwrap_or(0);
-    for row in &rows {
-        let mut string = String::new();
-
-        let (last, not_last) = row.split_last().unwrap();
-        for col in not_last {
-            string.push_str(col);
-
-            let padding = col_width - col.len();
-            for _ in 0..=padding {
-                string.push(' ');
+    let mut names = Vec::new();
+    let mut widths = Vec::new();
+    if style == DumpStyle::Aligned {
+        value.for_each_row(intern, &mut vec![], &mut |row| {
+            if focus.includes(row) {
+                names.clear();
+                names.extend(row.iter().map(|&(_, name)| name));
+                update_widths(&mut widths, &names)
             }
+        });
+    }
+
+    let mut result = Ok(());
+    value.for_each_row(intern, &mut vec![], &mut |row| {
+        if result.is_ok() && focus.includes(row) {
+            names.clear();
+            names.extend(row.iter().map(|&(_, name)| name));
+            result = write_row(name, stream, style, &widths, &names);
         }
-        string.push_str(last);
+    });
+    result
+}
 
-        if let Some(ref name) = name {
-            write!(stream, "{} ", name)?;
+pub(crate) fn write_rows(
+    name: Option<&str>,
+    stream: &mut dyn Write,
+    style: DumpStyle,
+    rows: &[Vec<&str>],
+) -> io::Result<()> {
+    let mut widths = Vec::new();
+    if style == DumpStyle::Aligned {
+        for row in rows {
+            update_widths(&mut widths, row);
         }
-        writeln!(stream, "{}", string)?;
     }
-
+    for row in rows {
+        write_row(name, stream, style, &widths, row)?;
+    }
     Ok(())
 }
 
+/// Widens the `widths` of the columns to fit the values of the `row`.
+fn update_widths(widths: &mut Vec<usize>, row: &[&str]) {
+    if widths.len() < row.len() {
+        widths.resize(row.len(), 0);
+    }
+    for (width, value) in widths.iter_mut().zip(row) {
+        *width = (*width).max(value.len());
+    }
+}
+
+/// Writes the `row` in the `style`, after the `name` of its relation if any. In the aligned
+/// style, the columns are padded to their `widths`.
+fn write_row(
+    name: Option<&str>,
+    stream: &mut dyn Write,
+    style: DumpStyle,
+    widths: &[usize],
+    row: &[&str],
+) -> io::Result<()> {
+    match style {
+        DumpStyle::Aligned => {
+            let mut string = String::new();
+
+            let (last, not_last) = row.split_last().unwrap();
+            for (col, width) in not_last.iter().zip(widths) {
+                string.push_str(col);
+
+                let padding = width - col.len();
+                for _ in 0..=padding {
+                    string.push(' ');
+                }
+            }
+            string.push_str(last);
+
+            if let Some(name) = name {
+                write!(stream, "{} ", name)?;
+            }
+            writeln!(stream, "{}", string)
+        }
+        DumpStyle::Tsv => {
+            let values: Vec<&str> = name.into_iter().chain(row.iter().cloned()).collect();
+            write_tab_delimited_row(stream, &values)
+        }
+        DumpStyle::Csv => {
+            let values: Vec<_> = name
+                .into_iter()
+                .chain(row.iter().cloned())
+                .map(csv_field)
+                .collect();
+            writeln!(stream, "{}", values.join(","))
+        }
+    }
+}
+
+/// Quotes the `value` as a CSV field, if it contains a separator, a quote, or a line break.
+pub(crate) fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
 impl<K, V> OutputDump for FxHashMap<K, V>
 where
     K: Atom + Eq + Hash + Ord,
     V: OutputDump,
 {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         let table = K::table(intern);
         let mut keys: Vec<_> = self.keys().collect();
         keys.sort();
         for key in keys {
             preserve(prefix, |prefix| {
-                prefix.push(table.untern(*key));
+                prefix.push((K::KIND, table.untern(*key)));
 
                 let value = &self[key];
-                value.push_all(intern, prefix, output);
+                value.for_each_row(intern, prefix, row);
             });
         }
     }
@@ -156,21 +616,21 @@ where
     K: Atom + Eq + Hash + Ord,
     V: OutputDump,
 {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         let table = K::table(intern);
         let mut keys: Vec<_> = self.keys().collect();
         keys.sort();
         for key in keys {
             preserve(prefix, |prefix| {
-                prefix.push(table.untern(*key));
+                prefix.push((K::KIND, table.untern(*key)));
 
                 let value = &self[key];
-                value.push_all(intern, prefix, output);
+                value.for_each_row(intern, prefix, row);
             });
         }
     }
@@ -180,14 +640,14 @@ impl<K> OutputDump for BTreeSet<K>
 where
     K: OutputDump,
 {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         for key in self {
-            key.push_all(intern, prefix, output);
+            key.for_each_row(intern, prefix, row);
         }
     }
 }
@@ -196,57 +656,75 @@ impl<V> OutputDump for Vec<V>
 where
     V: OutputDump,
 {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         for value in self {
-            value.push_all(intern, prefix, output);
+            value.for_each_row(intern, prefix, row);
         }
     }
 }
 
 impl<T: Atom> OutputDump for T {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         let table = T::table(intern);
         let text = table.untern(*self);
         preserve(prefix, |prefix| {
-            prefix.push(text);
-            output.push(prefix.clone());
+            prefix.push((T::KIND, text));
+            row(prefix);
+        });
+    }
+}
+
+impl OutputDump for OriginKind {
+    fn for_each_row<'a>(
+        &'a self,
+        _intern: &'a InternerTables,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
+    ) {
+        let text = match self {
+            OriginKind::Placeholder => "placeholder",
+            OriginKind::Existential => "existential",
+        };
+        preserve(prefix, |prefix| {
+            prefix.push((AtomKind::OriginKind, text));
+            row(prefix);
         });
     }
 }
 
 impl<T1: Atom> OutputDump for (T1,) {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         let (ref a1,) = self;
         let t1_table = T1::table(intern);
         let a1_text = t1_table.untern(*a1);
         preserve(prefix, |prefix| {
-            prefix.push(a1_text);
-            output.push(prefix.clone());
+            prefix.push((T1::KIND, a1_text));
+            row(prefix);
         });
     }
 }
 
 impl<T1: Atom, T2: Atom> OutputDump for (T1, T2) {
-    fn push_all<'a>(
+    fn for_each_row<'a>(
         &'a self,
         intern: &'a InternerTables,
-        prefix: &mut Vec<&'a str>,
-        output: &mut Vec<Vec<&'a str>>,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
     ) {
         let (ref a1, ref a2) = self;
         let t1_table = T1::table(intern);
@@ -254,48 +732,80 @@ impl<T1: Atom, T2: Atom> OutputDump for (T1, T2) {
         let a1_text = t1_table.untern(*a1);
         let a2_text = t2_table.untern(*a2);
         preserve(prefix, |prefix| {
-            prefix.push(a1_text);
-            prefix.push(a2_text);
-            output.push(prefix.clone());
+            prefix.push((T1::KIND, a1_text));
+            prefix.push((T2::KIND, a2_text));
+            row(prefix);
         });
     }
 }
 
-fn preserve<'a>(s: &mut Vec<&'a str>, op: impl FnOnce(&mut Vec<&'a str>)) {
+impl<T1: Atom, T2: Atom, T3: Atom> OutputDump for (T1, T2, T3) {
+    fn for_each_row<'a>(
+        &'a self,
+        intern: &'a InternerTables,
+        prefix: &mut Vec<Column<'a>>,
+        row: &mut dyn FnMut(&[Column<'a>]),
+    ) {
+        let (ref a1, ref a2, ref a3) = self;
+        let a1_text = T1::table(intern).untern(*a1);
+        let a2_text = T2::table(intern).untern(*a2);
+        let a3_text = T3::table(intern).untern(*a3);
+        preserve(prefix, |prefix| {
+            prefix.push((T1::KIND, a1_text));
+            prefix.push((T2::KIND, a2_text));
+            prefix.push((T3::KIND, a3_text));
+            row(prefix);
+        });
+    }
+}
+
+fn preserve<'a>(s: &mut Vec<Column<'a>>, op: impl FnOnce(&mut Vec<Column<'a>>)) {
     let len = s.len();
     op(s);
     s.truncate(len);
 }
 
-pub(crate) trait Atom: Copy + From<usize> + Into<usize> {
+pub(crate) trait Atom: PoloniusEngineAtom {
+    const KIND: AtomKind;
+
     fn table(intern: &InternerTables) -> &Interner<Self>;
 }
 
 impl Atom for Origin {
+    const KIND: AtomKind = AtomKind::Origin;
+
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.origins
     }
 }
 
 impl Atom for Point {
+    const KIND: AtomKind = AtomKind::Point;
+
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.points
     }
 }
 
 impl Atom for Loan {
+    const KIND: AtomKind = AtomKind::Loan;
+
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.loans
     }
 }
 
 impl Atom for Variable {
+    const KIND: AtomKind = AtomKind::Variable;
+
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.variables
     }
 }
 
 impl Atom for Path {
+    const KIND: AtomKind = AtomKind::Path;
+
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.paths
     }
@@ -306,6 +816,7 @@ fn facts_by_point<F: Clone, Out: OutputDump>(
     point: impl Fn(F) -> (Point, Out),
     name: String,
     point_pos: usize,
+    focus: &Focus,
     intern: &InternerTables,
 ) -> HashMap<Point, String> {
     let mut by_point: HashMap<Point, Vec<Out>> = HashMap::new();
@@ -315,13 +826,25 @@ fn facts_by_point<F: Clone, Out: OutputDump>(
     }
     by_point
         .into_iter()
-        .map(|(point, o)| {
+        .filter_map(|(point, o)| {
+            // the rows in focus, with the point in its column
+            let point_name = Point::table(intern).untern(point);
             let mut rows: Vec<Vec<&str>> = Vec::new();
-            OutputDump::push_all(&o, intern, &mut vec![], &mut rows);
+            OutputDump::for_each_row(&o, intern, &mut vec![], &mut |row| {
+                let mut row = row.to_vec();
+                row.insert(point_pos, (AtomKind::Point, point_name));
+                if focus.includes(&row) {
+                    rows.push(row.into_iter().map(|(_, name)| name).collect());
+                }
+            });
+            if rows.is_empty() {
+                return None;
+            }
+
             let s = rows
                 .into_iter()
                 .map(|mut vals| {
-                    vals.insert(point_pos, "_");
+                    vals[point_pos] = "_";
                     escape_for_graphviz(
                         format!(
                             "{}({})",
@@ -338,13 +861,14 @@ fn facts_by_point<F: Clone, Out: OutputDump>(
                 .join("\\l")
                 + "\\l";
             // in graphviz, \l is a \n that left-aligns
-            (point, s)
+            Some((point, s))
         })
         .collect()
 }
 
 fn build_inputs_by_point_for_visualization(
     all_facts: &AllFacts,
+    focus: &Focus,
     intern: &InternerTables,
 ) -> Vec<HashMap<Point, String>> {
     vec![
@@ -353,6 +877,7 @@ fn build_inputs_by_point_for_visualization(
             |(origin, loan, point)| (point, (origin, loan)),
             "loan_issued_at".to_string(),
             2,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -360,6 +885,7 @@ fn build_inputs_by_point_for_visualization(
             |(loan, point)| (point, (loan,)),
             "loan_killed_at".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -367,13 +893,15 @@ fn build_inputs_by_point_for_visualization(
             |(origin1, origin2, point)| (point, (origin1, origin2)),
             "subset_base".to_string(),
             2,
+            focus,
             intern,
         ),
         facts_by_point(
             all_facts.loan_invalidated_at.iter().cloned(),
-            |(point, loan)| (point, (loan,)),
+            |(loan, point)| (point, (loan,)),
             "loan_invalidated_at".to_string(),
             0,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -381,6 +909,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "var_used_at".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -388,6 +917,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "var_defined_at".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -395,6 +925,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "var_dropped_at".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -402,6 +933,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "path_assigned_at_base".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -409,6 +941,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "path_moved_at_base".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -416,6 +949,7 @@ fn build_inputs_by_point_for_visualization(
             |(var, point)| (point, (var,)),
             "path_accessed_at_base".to_string(),
             1,
+            focus,
             intern,
         ),
     ]
@@ -423,6 +957,7 @@ fn build_inputs_by_point_for_visualization(
 
 fn build_outputs_by_point_for_visualization(
     output: &Output,
+    focus: &Focus,
     intern: &InternerTables,
 ) -> Vec<HashMap<Point, String>> {
     vec![
@@ -431,6 +966,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, loans)| (*point, loans.clone()),
             "loan_live_at".to_string(),
             0,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -438,6 +974,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, origin_to_loans)| (*point, origin_to_loans.clone()),
             "origin_contains_loan_at".to_string(),
             0,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -445,6 +982,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, loans)| (*point, loans.clone()),
             "loan_invalidated_at".to_string(),
             0,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -452,6 +990,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, origin_to_origins)| (*point, origin_to_origins.clone()),
             "subset".to_string(),
             0,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -459,6 +998,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, var)| (*point, var.clone()),
             "var_live_on_entry".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -466,6 +1006,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, var)| (*point, var.clone()),
             "var_drop_live_on_entry".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -473,6 +1014,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, origin)| (*point, origin.clone()),
             "origin_live_on_entry".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -480,6 +1022,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, var)| (*point, var.clone()),
             "var_maybe_partly_initialized_on_exit".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -487,6 +1030,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, path)| (*point, path.clone()),
             "path_maybe_initialized_on_exit".to_string(),
             1,
+            focus,
             intern,
         ),
         facts_by_point(
@@ -494,6 +1038,7 @@ fn build_outputs_by_point_for_visualization(
             |(point, path)| (*point, path.clone()),
             "move_errors".to_string(),
             1,
+            focus,
             intern,
         ),
     ]
@@ -504,99 +1049,150 @@ pub(crate) fn graphviz(
     all_facts: &AllFacts,
     output_file: &PathBuf,
     intern: &InternerTables,
+    depth: Option<usize>,
+    focus: &Focus,
+    short_names: bool,
 ) -> io::Result<()> {
     let mut file = File::create(output_file)?;
-    let mut output_fragments: Vec<String> = Vec::new();
-    let mut seen_nodes = BTreeSet::new();
+    let graphviz = render_graphviz(output, all_facts, intern, depth, focus, short_names);
+    file.write_all(graphviz.as_bytes())
+}
 
-    let inputs_by_point = build_inputs_by_point_for_visualization(all_facts, intern);
-    let outputs_by_point = build_outputs_by_point_for_visualization(output, intern);
+/// Renders the CFG as a graphviz digraph, with a record node per point showing the input and
+/// output tuples at this point: at most `depth` relations of each, when given. The points of a
+/// basic block are grouped into a cluster, in the order of their statements. Only the tuples in
+/// `focus` are shown. With `short_names`, the atoms are shown by their short names, and a legend.
+pub(crate) fn render_graphviz(
+    output: &Output,
+    all_facts: &AllFacts,
+    intern: &InternerTables,
+    depth: Option<usize>,
+    focus: &Focus,
+    short_names: bool,
+) -> String {
+    let short_names = short_names.then(|| ShortNames::new(intern));
+    let (names, focus) = match short_names {
+        Some(ref short_names) => (&short_names.tables, Cow::Owned(short_names.focus(focus))),
+        None => (intern, Cow::Borrowed(focus)),
+    };
+    let inputs_by_point = build_inputs_by_point_for_visualization(all_facts, &focus, names);
+    let outputs_by_point = build_outputs_by_point_for_visualization(output, &focus, names);
+
+    // the points of each block, and those which aren't named after one, in order
+    let mut points: Vec<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .flat_map(|&(point1, point2)| vec![point1, point2])
+        .collect();
+    points.sort();
+    points.dedup();
+    let mut blocks: BTreeMap<&str, Vec<(usize, usize, Point)>> = BTreeMap::new();
+    let mut other_points = Vec::new();
+    for point in points {
+        match block_location(Point::table(intern).untern(point)) {
+            Some((block, statement, kind)) => blocks
+                .entry(block)
+                .or_default()
+                .push((statement, kind, point)),
+            None => other_points.push(point),
+        }
+    }
 
-    output_fragments.push("digraph g {\n  graph [\n  rankdir = \"TD\"\n];\n".to_string());
-    for (idx, &(point1, point2)) in all_facts.cfg_edge.iter().enumerate() {
-        let graphviz_code = graphviz_for_edge(
-            point1,
-            point2,
-            idx,
-            &mut seen_nodes,
+    let mut graphviz = "digraph g {\n  graph [\n  rankdir = \"TD\"\n];\n".to_string();
+    for (block, mut points) in blocks {
+        points.sort();
+        graphviz.push_str(&format!(
+            "subgraph \"cluster_{0}\" {{\n  label = \"{0}\"\n",
+            escape_for_graphviz(block)
+        ));
+        for (_, _, point) in points {
+            graphviz.push_str(&render_point(
+                point,
+                &inputs_by_point,
+                &outputs_by_point,
+                depth,
+                names,
+            ));
+        }
+        graphviz.push_str("}\n");
+    }
+    for point in other_points {
+        graphviz.push_str(&render_point(
+            point,
             &inputs_by_point,
             &outputs_by_point,
-            intern,
-        )
-        .into_iter();
-        output_fragments.extend(graphviz_code);
+            depth,
+            names,
+        ));
     }
-    output_fragments.push("}".to_string()); // close digraph
-    let output_bytes = output_fragments.join("").bytes().collect::<Vec<_>>();
-    file.write_all(&output_bytes)?;
-    Ok(())
+
+    for (idx, &(point1, point2)) in all_facts.cfg_edge.iter().enumerate() {
+        // the edges within a block are weighted to keep its points in a straight column
+        let block1 = block_location(Point::table(intern).untern(point1));
+        let block2 = block_location(Point::table(intern).untern(point2));
+        let weight = match (block1, block2) {
+            (Some((block1, ..)), Some((block2, ..))) if block1 == block2 => "\n  weight = 10",
+            _ => "",
+        };
+        graphviz.push_str(&format!(
+            "\"node{0}\" -> \"node{1}\":f0 [\n  id = {2}{3}\n];\n",
+            point1.index(),
+            point2.index(),
+            idx,
+            weight
+        ));
+    }
+    if let Some(ref short_names) = short_names {
+        graphviz.push_str(&short_names.graphviz_legend());
+    }
+    graphviz.push('}'); // close digraph
+    graphviz
 }
 
-fn graphviz_for_edge(
-    point1: Point,
-    point2: Point,
-    edge_index: usize,
-    seen_points: &mut BTreeSet<usize>,
-    inputs_by_point: &[HashMap<Point, String>],
-    outputs_by_point: &[HashMap<Point, String>],
-    intern: &InternerTables,
-) -> Vec<String> {
-    let mut ret = Vec::new();
-    maybe_render_point(
-        point1,
-        seen_points,
-        inputs_by_point,
-        outputs_by_point,
-        &mut ret,
-        intern,
-    );
-    maybe_render_point(
-        point2,
-        seen_points,
-        inputs_by_point,
-        outputs_by_point,
-        &mut ret,
-        intern,
-    );
-    ret.push(format!(
-        "\"node{0}\" -> \"node{1}\":f0 [\n  id = {2}\n];\n",
-        point1.index(),
-        point2.index(),
-        edge_index
-    ));
-    ret
+/// The basic block, statement index, and kind (0 for `Start`, 1 for `Mid`) of a point named
+/// like rustc's points, e.g. `"Mid(bb3[2])"`.
+fn block_location(point: &str) -> Option<(&str, usize, usize)> {
+    let point = point.trim_matches('"');
+    let (kind, location) = if let Some(location) = point.strip_prefix("Start(") {
+        (0, location)
+    } else if let Some(location) = point.strip_prefix("Mid(") {
+        (1, location)
+    } else {
+        return None;
+    };
+    let location = location.strip_suffix("])")?;
+    let (block, statement) = location.split_once('[')?;
+    Some((block, statement.parse().ok()?, kind))
 }
 
-fn maybe_render_point(
+fn render_point(
     point: Point,
-    seen_points: &mut BTreeSet<usize>,
     inputs_by_point: &[HashMap<Point, String>],
     outputs_by_point: &[HashMap<Point, String>],
-    render_vec: &mut Vec<String>,
+    depth: Option<usize>,
     intern: &InternerTables,
-) {
-    if seen_points.contains(&point.index()) {
-        return;
-    }
-    seen_points.insert(point.index());
-
-    let input_tuples = inputs_by_point
-        .iter()
-        .filter_map(|inp| inp.get(&point).map(ToString::to_string))
-        .collect::<Vec<_>>()
-        .join(" | ");
-
-    let output_tuples = outputs_by_point
-        .iter()
-        .filter_map(|outp| outp.get(&point).map(ToString::to_string))
-        .collect::<Vec<_>>()
-        .join(" | ");
-
-    render_vec.push(format!("\"node{0}\" [\n  label = \"{{ <f0> {1} | INPUTS | {2} | OUTPUTS | {3} }}\"\n  shape = \"record\"\n];\n",
-                     point.index(),
-                     escape_for_graphviz(Point::table(intern).untern(point)),
-                     &input_tuples,
-                     &output_tuples));
+) -> String {
+    let tuples = |relations: &[HashMap<Point, String>]| {
+        let mut tuples: Vec<_> = relations
+            .iter()
+            .filter_map(|relation| relation.get(&point).map(ToString::to_string))
+            .collect();
+        if let Some(depth) = depth {
+            if tuples.len() > depth {
+                let omitted = tuples.len() - depth;
+                tuples.truncate(depth);
+                let plural = if omitted == 1 { "" } else { "s" };
+                tuples.push(format!("{} more relation{}\\l", omitted, plural));
+            }
+        }
+        tuples.join(" | ")
+    };
+
+    format!("\"node{0}\" [\n  label = \"{{ <f0> {1} | INPUTS | {2} | OUTPUTS | {3} }}\"\n  shape = \"record\"\n];\n",
+            point.index(),
+            escape_for_graphviz(Point::table(intern).untern(point)),
+            tuples(inputs_by_point),
+            tuples(outputs_by_point))
 }
 
 fn escape_for_graphviz(s: &str) -> String {
@@ -729,8 +1325,13 @@ pub(crate) fn liveness_graph(
     all_facts: &AllFacts,
     output_file: &PathBuf,
     intern: &InternerTables,
+    short_names: bool,
 ) -> io::Result<()> {
     info!("Generating liveness graph");
+    let short_names = short_names.then(|| ShortNames::new(intern));
+    let names = short_names
+        .as_ref()
+        .map_or(intern, |short_names| &short_names.tables);
     let mut file = File::create(output_file)?;
     let mut output_fragments: Vec<String> = Vec::new();
     let mut cfg = StableGraph::<Liveness, ()>::new();
@@ -791,7 +1392,7 @@ pub(crate) fn liveness_graph(
                 format!(
                     "{} [shape=\"record\" label=\"{}\"]",
                     cfg.to_index(node_idx),
-                    render_cfg_label(node_data, intern)
+                    render_cfg_label(node_data, names)
                 )
             })
             .collect::<Vec<_>>()
@@ -839,7 +1440,7 @@ pub(crate) fn liveness_graph(
                 "{} -> {} [label=\" {} {}\", color=\"{}\", penwidth = 2 arrowhead = none]",
                 cfg.to_index(edge.target()),
                 cfg.to_index(edge.source()),
-                intern.variables.untern(var).replace("\"", ""),
+                names.variables.untern(var).replace("\"", ""),
                 liveness_status,
                 colour_palette[var.index() % colour_palette.len()],
             ));
@@ -853,9 +1454,206 @@ pub(crate) fn liveness_graph(
     }
 
     output_fragments.push(edge_fragments.join("\n"));
+    if let Some(ref short_names) = short_names {
+        output_fragments.push("\n".to_string());
+        output_fragments.push(short_names.graphviz_legend());
+    }
 
     output_fragments.push("\n}".to_string()); // close digraph
     let output_bytes = output_fragments.join("").bytes().collect::<Vec<_>>();
     file.write_all(&output_bytes)?;
     Ok(())
 }
+
+/// Generates a graphviz file of the origins' constraint graph, see `render_constraint_graph`.
+pub(crate) fn constraint_graph(
+    output: &Output,
+    all_facts: &AllFacts,
+    output_file: &PathBuf,
+    intern: &InternerTables,
+    short_names: bool,
+) -> io::Result<()> {
+    info!("Generating constraint graph");
+    let mut file = File::create(output_file)?;
+    file.write_all(render_constraint_graph(output, all_facts, intern, short_names).as_bytes())
+}
+
+/// Renders the `subset_base` constraints as a graphviz digraph, close to the region graphs of
+/// rustc's NLL region inference: a node per origin, listing the loans issued in it, and an edge
+/// from each origin to the origins it flows into, labeled with the points where the constraint
+/// holds. The placeholders are highlighted, when the `output` has the origins' kinds. With
+/// `short_names`, the atoms are shown by their short names, and a legend.
+pub(crate) fn render_constraint_graph(
+    output: &Output,
+    all_facts: &AllFacts,
+    intern: &InternerTables,
+    short_names: bool,
+) -> String {
+    fn escape(s: &str) -> String {
+        s.replace('\\', r"\\").replace('"', "\\\"")
+    }
+
+    /// The escaped names of the `points`, or their range when there are many.
+    fn points_label(points: &[Point], intern: &InternerTables) -> String {
+        let name = |point: &Point| escape(&unquote(intern.points.untern(*point)));
+        if points.len() <= 3 {
+            points.iter().map(name).collect::<Vec<_>>().join("\\n")
+        } else {
+            format!(
+                "{}–{} ({} points)",
+                name(&points[0]),
+                name(&points[points.len() - 1]),
+                points.len()
+            )
+        }
+    }
+
+    let short_names = short_names.then(|| ShortNames::new(intern));
+    let names = short_names
+        .as_ref()
+        .map_or(intern, |short_names| &short_names.tables);
+
+    let mut loans: BTreeMap<Origin, Vec<(Loan, Point)>> = BTreeMap::new();
+    for &(origin, loan, point) in &all_facts.loan_issued_at {
+        loans.entry(origin).or_default().push((loan, point));
+    }
+    let mut constraints: BTreeMap<(Origin, Origin), Vec<Point>> = BTreeMap::new();
+    for &(origin1, origin2, point) in &all_facts.subset_base {
+        constraints
+            .entry((origin1, origin2))
+            .or_default()
+            .push(point);
+    }
+
+    let origins: BTreeSet<Origin> = all_facts
+        .universal_region
+        .iter()
+        .copied()
+        .chain(loans.keys().copied())
+        .chain(
+            constraints
+                .keys()
+                .flat_map(|&(origin1, origin2)| [origin1, origin2]),
+        )
+        .collect();
+
+    let mut fragments = vec!["digraph constraints {\n  node [shape=box];\n".to_string()];
+    for &origin in &origins {
+        let mut label = escape(&unquote(names.origins.untern(origin)));
+        for &(loan, point) in loans.get(&origin).into_iter().flatten() {
+            label.push_str(&format!(
+                "\\n{} at {}",
+                escape(&unquote(names.loans.untern(loan))),
+                escape(&unquote(names.points.untern(point)))
+            ));
+        }
+        let style = if output.origin_kind.get(&origin) == Some(&OriginKind::Placeholder) {
+            " style=filled fillcolor=\"#c9c9ff\""
+        } else {
+            ""
+        };
+        fragments.push(format!(
+            "  {} [label=\"{}\"{}];\n",
+            origin.index(),
+            label,
+            style
+        ));
+    }
+    for (&(origin1, origin2), points) in &mut constraints {
+        points.sort();
+        points.dedup();
+        fragments.push(format!(
+            "  {} -> {} [label=\"{}\"];\n",
+            origin1.index(),
+            origin2.index(),
+            points_label(points, names)
+        ));
+    }
+    if let Some(ref short_names) = short_names {
+        fragments.push(short_names.graphviz_legend());
+    }
+    fragments.push("}\n".to_string());
+    fragments.concat()
+}
+
+/// Writes the differences between the errors of the variants compared by `Algorithm::Compare`
+/// to the `output_file`, see `render_comparison`.
+pub(crate) fn comparison(
+    comparison: &ErrorsDiff<LocalFacts>,
+    output_file: &PathBuf,
+    intern: &InternerTables,
+) -> io::Result<()> {
+    if let Some(dir) = output_file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(output_file)?;
+    file.write_all(render_comparison(comparison, intern).as_bytes())
+}
+
+/// The differences between the errors of the variants compared by `Algorithm::Compare`, as they
+/// are written in the `<function>.comparison.json` files: for each error relation, the tuples only
+/// found by each variant.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Comparison {
+    pub(crate) errors: VariantTuples,
+    pub(crate) subset_errors: VariantTuples,
+}
+
+/// The tuples of an error relation only found by the `naive` variant, and the ones only found by
+/// the `datafrog_opt` variant, in the columns of the relation's dump.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct VariantTuples {
+    pub(crate) naive: Vec<Vec<String>>,
+    pub(crate) datafrog_opt: Vec<Vec<String>>,
+}
+
+impl Comparison {
+    pub(crate) fn new(comparison: &ErrorsDiff<LocalFacts>, intern: &InternerTables) -> Self {
+        let tuples = |diagnostics: &[Diagnostic<LocalFacts>], subsets: bool| -> Vec<Vec<String>> {
+            diagnostics
+                .iter()
+                .filter_map(|diagnostic| {
+                    let columns = match *diagnostic {
+                        Diagnostic::IllegalAccess { loan, point } if !subsets => {
+                            vec![intern.point_name(point), intern.loan_name(loan)]
+                        }
+                        Diagnostic::IllegalSubset { sup, sub, point } if subsets => vec![
+                            intern.point_name(point),
+                            intern.origin_name(sub),
+                            intern.origin_name(sup),
+                        ],
+                        _ => return None,
+                    };
+                    Some(
+                        columns
+                            .iter()
+                            .map(|name| unquote(name).into_owned())
+                            .collect(),
+                    )
+                })
+                .collect()
+        };
+        let variants = |subsets: bool| VariantTuples {
+            naive: tuples(&comparison.removed, subsets),
+            datafrog_opt: tuples(&comparison.added, subsets),
+        };
+        Comparison {
+            errors: variants(false),
+            subset_errors: variants(true),
+        }
+    }
+}
+
+/// Renders the differences between the errors of the variants compared by `Algorithm::Compare`
+/// as a JSON object, see `Comparison`.
+pub(crate) fn render_comparison(
+    comparison: &ErrorsDiff<LocalFacts>,
+    intern: &InternerTables,
+) -> String {
+    let comparison = Comparison::new(comparison, intern);
+    let mut json = serde_json::to_string_pretty(&comparison).expect("comparisons are valid JSON");
+    json.push('\n');
+    json
+}
@@ -0,0 +1,118 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The errors which can happen when loading facts, or running the CLI.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the file at `path` failed.
+    Io { path: PathBuf, source: io::Error },
+
+    /// The input is malformed at the given `line` and `column`, both starting at 1. The `file` is
+    /// missing when the input doesn't come from a file, e.g. a program parsed from a string.
+    Parse {
+        file: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// The `file` doesn't contain the facts of any known relation.
+    UnknownRelation { file: PathBuf },
+
+    /// There are more atoms of the given `kind` than their `u32` indices can represent, or a
+    /// numeric atom has an index above the maximum number of numeric atoms.
+    InternerOverflow { kind: &'static str },
+
+    /// The atoms of the given `kind` are given both as numeric indices, and as names.
+    MixedAtomForms { kind: &'static str },
+
+    /// The command-line arguments are invalid.
+    Arguments(String),
+
+    /// The variants compared by `Algorithm::Compare` reported different errors on this number
+    /// of `inputs`.
+    VariantsDiffer { inputs: usize },
+
+    /// The computation was aborted on this number of `inputs`, because one of its iterations
+    /// didn't converge within the maximum number of rounds.
+    RoundLimitExceeded { inputs: usize },
+
+    /// The precomputed `origin_live_on_entry` facts lack the origins of the variables used at
+    /// this number of `points`, starting with the `first` one.
+    UncoveredLiveness { points: usize, first: String },
+
+    /// No rule of the book has this label, or derives this relation.
+    UnknownRule { name: String },
+
+    /// The linted rules have this number of `errors`.
+    RuleErrors { errors: usize },
+
+    /// The rules of the `file` can't be parsed, or evaluated.
+    InvalidRules { file: PathBuf, message: String },
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => {
+                write!(fmt, "error reading `{}`: {}", path.display(), source)
+            }
+            Error::Parse {
+                file,
+                line,
+                column,
+                message,
+            } => {
+                if let Some(file) = file {
+                    write!(fmt, "{}:", file.display())?;
+                }
+                write!(fmt, "{}:{}: {}", line, column, message)
+            }
+            Error::UnknownRelation { file } => {
+                write!(fmt, "`{}` is not a known relation", file.display())
+            }
+            Error::InternerOverflow { kind } => write!(fmt, "too many {} to intern", kind),
+            Error::MixedAtomForms { kind } => {
+                write!(fmt, "the {} are both numeric and named", kind)
+            }
+            Error::Arguments(message) => fmt.write_str(message),
+            Error::VariantsDiffer { inputs } => write!(
+                fmt,
+                "the compared variants reported different errors on {} input(s)",
+                inputs
+            ),
+            Error::RoundLimitExceeded { inputs } => write!(
+                fmt,
+                "the computation exceeded the maximum number of rounds on {} input(s)",
+                inputs
+            ),
+            Error::UncoveredLiveness { points, first } => write!(
+                fmt,
+                "the precomputed `origin_live_on_entry` facts don't cover {} point(s), like `{}`",
+                points, first
+            ),
+            Error::RuleErrors { errors } => {
+                write!(fmt, "the rules have {} error(s)", errors)
+            }
+            Error::InvalidRules { file, message } => {
+                write!(fmt, "`{}`: {}", file.display(), message)
+            }
+            Error::UnknownRule { name } => write!(
+                fmt,
+                "unknown rule `{}`: expected a rule label like `R3`, or a relation derived by the rules",
+                name
+            ),
+        }
+    }
+}
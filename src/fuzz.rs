@@ -0,0 +1,214 @@
+#![cfg(test)]
+
+//! Differential fuzzing of the analysis variants: small random programs are generated, and the
+//! `Naive`, `DatafrogOpt`, `ParallelNaive` and `LocationInsensitive` variants are checked to
+//! agree on them.
+//!
+//! The number of programs to generate, and the initial seed, can be chosen with the
+//! `POLONIUS_FUZZ_ITERATIONS` and `POLONIUS_FUZZ_SEED` environment variables. A failure
+//! prints the seed and the program which triggered it, to be turned into a regular test.
+
+use std::env;
+use std::fmt::Write;
+
+use polonius_engine::Algorithm;
+
+use crate::dump::Output;
+use crate::intern::InternerTables;
+use crate::program::parse_from_program;
+use crate::synthetic::Rng;
+
+/// Generates a random, syntactically valid, program: a random CFG, whose statements issue,
+/// invalidate and kill loans, create chains of `outlives` constraints between origins, and
+/// use or define the variables whose types mention these origins.
+pub(crate) fn generate_program(rng: &mut Rng) -> String {
+    let placeholder_count = rng.below(3);
+    let origin_count = placeholder_count + 1 + rng.below(4);
+    let loan_count = 1 + rng.below(3);
+    let variable_count = 1 + rng.below(3);
+    let block_count = 1 + rng.below(4);
+
+    let origin = |idx: usize| {
+        if idx < placeholder_count {
+            format!("'p{}", idx)
+        } else {
+            format!("'o{}", idx)
+        }
+    };
+
+    let mut program = String::new();
+
+    let placeholders: Vec<_> = (0..placeholder_count).map(origin).collect();
+    writeln!(program, "placeholders {{ {} }}", placeholders.join(", ")).unwrap();
+
+    if placeholder_count > 1 && rng.one_in(2) {
+        writeln!(program, "known_subsets {{ {}: {} }}", origin(0), origin(1)).unwrap();
+    }
+
+    // each variable's type mentions one origin, for uses and drops
+    let var_origins: Vec<_> = (0..variable_count)
+        .map(|var| format!("(V{}, {})", var, origin(rng.below(origin_count))))
+        .collect();
+    writeln!(
+        program,
+        "use_of_var_derefs_origin {{ {} }}",
+        var_origins.join(", ")
+    )
+    .unwrap();
+    let mut var_origins = Vec::new();
+    for var in 0..variable_count {
+        if rng.one_in(2) {
+            var_origins.push(format!("(V{}, {})", var, origin(rng.below(origin_count))));
+        }
+    }
+    writeln!(
+        program,
+        "drop_of_var_derefs_origin {{ {} }}",
+        var_origins.join(", ")
+    )
+    .unwrap();
+
+    for block in 0..block_count {
+        writeln!(program, "block B{} {{", block).unwrap();
+
+        let statement_count = 1 + rng.below(4);
+        for _ in 0..statement_count {
+            let mut effects = Vec::new();
+            for _ in 0..1 + rng.below(3) {
+                let effect = match rng.below(7) {
+                    0 => format!(
+                        "loan_issued_at({}, L{})",
+                        origin(rng.below(origin_count)),
+                        rng.below(loan_count)
+                    ),
+                    1 | 2 => format!(
+                        "outlives({}: {})",
+                        origin(rng.below(origin_count)),
+                        origin(rng.below(origin_count))
+                    ),
+                    3 => format!("loan_invalidated_at(L{})", rng.below(loan_count)),
+                    4 if rng.one_in(3) => {
+                        format!("loan_shallow_killed_at(L{})", rng.below(loan_count))
+                    }
+                    4 => format!("loan_killed_at(L{})", rng.below(loan_count)),
+                    5 => format!("var_used_at(V{})", rng.below(variable_count)),
+                    _ => format!("var_defined_at(V{})", rng.below(variable_count)),
+                };
+                effects.push(effect);
+            }
+
+            // some effects happen on the statement's start point
+            if effects.len() > 1 && rng.one_in(3) {
+                let mid_effects = effects.split_off(1);
+                writeln!(program, "    {} / {};", effects[0], mid_effects.join(", ")).unwrap();
+            } else {
+                writeln!(program, "    {};", effects.join(", ")).unwrap();
+            }
+        }
+
+        // successors, possibly looping back to earlier blocks
+        let successors: Vec<_> = (0..block_count)
+            .filter(|&successor| {
+                (successor == block + 1 && !rng.one_in(4)) || rng.one_in(block_count + 2)
+            })
+            .map(|successor| format!("B{}", successor))
+            .collect();
+        if !successors.is_empty() {
+            writeln!(program, "    goto {};", successors.join(", ")).unwrap();
+        }
+
+        writeln!(program, "}}").unwrap();
+    }
+
+    program
+}
+
+/// Checks that the `Naive`, `DatafrogOpt` and `ParallelNaive` variants compute the same errors
+/// on the given `program`, and that the `LocationInsensitive` errors are a superset of them.
+pub(crate) fn check_variants_agree(program: &str) -> Result<(), String> {
+    let mut tables = InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).map_err(|error| error.to_string())?;
+
+    let naive = Output::compute(&facts, Algorithm::Naive, false);
+    let opt = Output::compute(&facts, Algorithm::DatafrogOpt, false);
+    let parallel = Output::compute(&facts, Algorithm::ParallelNaive, false);
+    let insensitive = Output::compute(&facts, Algorithm::LocationInsensitive, false);
+
+    let mut naive_errors: Vec<_> = naive.diagnostics();
+    let mut opt_errors: Vec<_> = opt.diagnostics();
+    let mut parallel_errors: Vec<_> = parallel.diagnostics();
+    naive_errors.dedup();
+    opt_errors.dedup();
+    parallel_errors.dedup();
+    if naive_errors != opt_errors {
+        return Err(format!(
+            "`Naive` and `DatafrogOpt` errors differ:\n  naive: {:?}\n  opt: {:?}",
+            naive_errors, opt_errors
+        ));
+    }
+    if naive_errors != parallel_errors {
+        return Err(format!(
+            "`Naive` and `ParallelNaive` errors differ:\n  naive: {:?}\n  parallel: {:?}",
+            naive_errors, parallel_errors
+        ));
+    }
+
+    for (point, loans) in &naive.errors {
+        let insensitive_loans = insensitive.errors_at(*point);
+        if let Some(loan) = loans.iter().find(|loan| !insensitive_loans.contains(loan)) {
+            return Err(format!(
+                "`LocationInsensitive` is missing the error for {} at {}",
+                tables.loans.untern(*loan),
+                tables.points.untern(*point),
+            ));
+        }
+    }
+
+    for origins in naive.subset_errors.values() {
+        for &(origin1, origin2) in origins {
+            let found = insensitive
+                .subset_errors
+                .values()
+                .any(|insensitive_origins| insensitive_origins.contains(&(origin1, origin2)));
+            if !found {
+                return Err(format!(
+                    "`LocationInsensitive` is missing the subset error {}: {}",
+                    tables.origins.untern(origin1),
+                    tables.origins.untern(origin2),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+fn fuzz_variants_agree_on_random_programs() {
+    let iterations: u64 = env_or("POLONIUS_FUZZ_ITERATIONS", 200);
+    let seed: u64 = env_or("POLONIUS_FUZZ_SEED", 0);
+
+    for seed in seed..seed + iterations {
+        let program = generate_program(&mut Rng::new(seed));
+        if let Err(error) = check_variants_agree(&program) {
+            panic!(
+                "fuzzing failed for seed {}: {}\nprogram:\n{}",
+                seed, error, program
+            );
+        }
+    }
+}
+
+#[test]
+fn generated_programs_are_deterministic() {
+    let program1 = generate_program(&mut Rng::new(42));
+    let program2 = generate_program(&mut Rng::new(42));
+    assert_eq!(program1, program2);
+}
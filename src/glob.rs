@@ -0,0 +1,75 @@
+//! Matching the names of the directories of facts, e.g. one per function in rustc's `nll-facts`
+//! directory, against glob patterns, to select the functions to analyze.
+
+use crate::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether the whole `text` matches the glob `pattern`, where `*` matches any sequence of
+/// characters, and `?` any single character.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // the positions after the last `*`, and in the text where it started matching, to backtrack
+    // to when the rest of the pattern doesn't match
+    let mut star = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether the directory at `path` contains facts, i.e. their CFG edges.
+fn is_facts_dir(path: &Path) -> bool {
+    path.join("cfg_edge.facts").exists()
+}
+
+/// Finds the directories of facts under `dir`, or `dir` itself, whose name matches the glob
+/// `filter` when given, in order.
+pub(crate) fn find_facts_dirs(dir: &Path, filter: Option<&str>) -> Result<Vec<PathBuf>, Error> {
+    fn visit(dir: &Path, filter: Option<&str>, facts_dirs: &mut Vec<PathBuf>) -> Result<(), Error> {
+        if is_facts_dir(dir) {
+            let name = dir.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            if filter.is_none_or(|filter| glob_matches(filter, name)) {
+                facts_dirs.push(dir.to_owned());
+            }
+            return Ok(());
+        }
+
+        let io_error = |source| Error::Io {
+            path: dir.to_owned(),
+            source,
+        };
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.is_dir() {
+                visit(&path, filter, facts_dirs)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut facts_dirs = Vec::new();
+    visit(dir, filter, &mut facts_dirs)?;
+    facts_dirs.sort();
+    Ok(facts_dirs)
+}
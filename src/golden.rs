@@ -0,0 +1,43 @@
+//! The golden files of the errors expected on a directory of facts, recorded next to the facts:
+//! the regression tests over the `inputs` corpus compare them to the errors computed by every
+//! variant, and the CLI's `--bless` flag rewrites them from the current results, to review the
+//! intentional changes in a single diff.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use polonius_engine::Algorithm;
+
+use crate::dump::Output;
+use crate::error::Error;
+use crate::facts::AllFacts;
+use crate::intern::InternerTables;
+
+/// The name of the golden file, in each directory of facts.
+pub(crate) const GOLDEN_FILE: &str = "errors.expected";
+
+/// Computes the errors of every variant on the `all_facts`, in the golden files' format: one
+/// error per line, prefixed by the name of the variant which found it.
+pub(crate) fn compute_errors(all_facts: &AllFacts, tables: &InternerTables) -> String {
+    let mut errors = String::new();
+    for name in Algorithm::variants().iter() {
+        let algorithm: Algorithm = name.parse().unwrap();
+        let output = Output::compute(all_facts, algorithm, false);
+        for diagnostic in output.diagnostics() {
+            writeln!(errors, "[{}] {}", name, diagnostic.display(tables)).unwrap();
+        }
+    }
+    errors
+}
+
+/// Rewrites the golden file of the `facts_dir` with the `errors`, and returns whether they
+/// differ from the ones it recorded.
+pub(crate) fn bless(facts_dir: &Path, errors: &str) -> Result<bool, Error> {
+    let path = facts_dir.join(GOLDEN_FILE);
+    if fs::read_to_string(&path).is_ok_and(|expected| expected == errors) {
+        return Ok(false);
+    }
+    fs::write(&path, errors).map_err(|source| Error::Io { path, source })?;
+    Ok(true)
+}
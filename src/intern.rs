@@ -1,47 +1,361 @@
+use crate::error::Error;
 use crate::facts::*;
-use rustc_hash::FxHashMap;
-use std::collections::HashMap;
+use polonius_engine::{Atom, AtomNames};
+use rustc_hash::{FxHashMap, FxHasher};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::str;
+use std::time::{Duration, Instant};
+
+/// Strings of at most this many bytes are stored inline in their entry, instead of in the
+/// arena. Most point names, like `"Mid(bb12[3])"`, fit.
+const INLINE_CAPACITY: usize = 14;
+
+/// The maximum number of numeric atoms of each kind. Their names are stored up to the largest
+/// index, so a single large index, most likely a corrupted value, would otherwise allocate
+/// gigabytes: rustc's largest functions have far fewer atoms.
+const MAX_NUMERIC_ATOMS: usize = 1 << 22;
+
+/// Where the bytes of an interned string are stored.
+#[derive(Clone, Copy)]
+enum Entry {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Arena {
+        start: u32,
+        len: u32,
+    },
+}
 
 /// When we load facts out of the table, they are essentially random
 /// strings. We create an intern table to map those to small integers.
+///
+/// Fact files can have millions of lines, so the strings are not owned individually: the short
+/// ones are stored inline in their entry, and the others are appended to a single arena. The
+/// strings are looked up with an open-addressing table of entry indices, to avoid storing them a
+/// second time as keys.
 pub(crate) struct Interner<TargetType: From<usize> + Copy> {
-    strings: FxHashMap<String, TargetType>,
-    rev_strings: Vec<String>,
+    arena: String,
+    entries: Vec<Entry>,
+
+    /// The index of each entry plus one, at the position its hash probes to: 0 is an empty
+    /// bucket. The number of buckets is a power of two.
+    buckets: Vec<u32>,
+
+    /// What the interned strings are, for error messages, e.g. "points".
+    kind: &'static str,
+
+    /// Whether the atoms are given as their numeric indices, instead of names to intern.
+    numeric: bool,
+
+    _target: PhantomData<TargetType>,
 }
 
 impl<TargetType> Interner<TargetType>
 where
-    TargetType: From<usize> + Into<usize> + Copy,
+    TargetType: Atom,
 {
-    fn new() -> Self {
+    fn new(kind: &'static str) -> Self {
         Self {
-            strings: HashMap::default(),
-            rev_strings: vec![],
+            arena: String::new(),
+            entries: Vec::new(),
+            buckets: vec![0; 16],
+            kind,
+            numeric: false,
+            _target: PhantomData,
         }
     }
 
     pub(crate) fn untern(&self, data: TargetType) -> &str {
         let data: usize = data.into();
-        &self.rev_strings[data]
+        self.entry_str(&self.entries[data])
     }
 
     #[cfg(test)]
     pub(crate) fn untern_vec(&self, data: &[TargetType]) -> Vec<&str> {
-        data.into_iter().map(|d| self.untern(*d)).collect()
+        data.iter().map(|d| self.untern(*d)).collect()
     }
 
+    /// Interns `data`, panicking if there are more atoms than `u32` indices can represent.
     pub(crate) fn intern(&mut self, data: &str) -> TargetType {
+        self.try_intern(data)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Interns `data`, unless there are more strings, or bytes in the arena, than `u32`
+    /// indices can represent.
+    pub(crate) fn try_intern(&mut self, data: &str) -> Result<TargetType, Error> {
+        if self.numeric {
+            return Err(Error::MixedAtomForms { kind: self.kind });
+        }
+
+        let bucket = match self.find(data) {
+            Ok(interned) => return Ok(interned),
+            Err(bucket) => bucket,
+        };
+
+        // check for overflows before modifying the table, which is left as is on errors
+        let index = self.entries.len();
+        let kind = self.kind;
+        let overflow = || Error::InternerOverflow { kind };
+        let index_plus_one = checked_u32(index + 1).ok_or_else(overflow)?;
+        let interned = TargetType::try_from_index(index).map_err(|_| overflow())?;
+        let entry = if data.len() <= INLINE_CAPACITY {
+            let mut bytes = [0; INLINE_CAPACITY];
+            bytes[..data.len()].copy_from_slice(data.as_bytes());
+            Entry::Inline {
+                len: data.len() as u8,
+                bytes,
+            }
+        } else {
+            let start = self.arena.len();
+            checked_u32(start + data.len()).ok_or_else(overflow)?;
+            self.arena.push_str(data);
+            Entry::Arena {
+                start: start as u32,
+                len: data.len() as u32,
+            }
+        };
+        self.entries.push(entry);
+        self.buckets[bucket] = index_plus_one;
+
+        // keep the table at most half full, so that probe sequences stay short
+        if self.entries.len() * 2 > self.buckets.len() {
+            self.grow();
+        }
+
+        Ok(interned)
+    }
+
+    /// Interns a value of a fact file: an unquoted integer is the index of a numeric atom, used
+    /// as is, and any other value is the name of an atom to intern. The two forms can't be mixed.
+    pub(crate) fn intern_value(&mut self, data: &str) -> Result<TargetType, Error> {
+        if data.is_empty() || !data.bytes().all(|byte| byte.is_ascii_digit()) {
+            return self.try_intern(data);
+        }
+
+        let kind = self.kind;
+        if !self.numeric && !self.entries.is_empty() {
+            return Err(Error::MixedAtomForms { kind });
+        }
+
+        // the numeric atoms are named after their index, to be dumped in the same form
+        let index: usize = data.parse().map_err(|_| Error::InternerOverflow { kind })?;
+        if index >= MAX_NUMERIC_ATOMS {
+            return Err(Error::InternerOverflow { kind });
+        }
+        let atom =
+            TargetType::try_from_index(index).map_err(|_| Error::InternerOverflow { kind })?;
+        self.numeric = true;
+        while self.entries.len() <= index {
+            let name = self.entries.len().to_string();
+            let mut bytes = [0; INLINE_CAPACITY];
+            bytes[..name.len()].copy_from_slice(name.as_bytes());
+            self.entries.push(Entry::Inline {
+                len: name.len() as u8,
+                bytes,
+            });
+        }
+        Ok(atom)
+    }
+
+    /// Returns the atom `data` was interned to, without interning it if it wasn't.
+    #[cfg(test)]
+    pub(crate) fn get(&self, data: &str) -> Option<TargetType> {
+        self.find(data).ok()
+    }
+
+    /// The number of interned strings.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The number of buckets of the open-addressing table.
+    #[cfg(test)]
+    pub(crate) fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// The number of bytes allocated on the heap to store the interned strings.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.arena.capacity()
+            + self.entries.capacity() * mem::size_of::<Entry>()
+            + self.buckets.capacity() * mem::size_of::<u32>()
+    }
+
+    fn entry_bytes<'a>(&'a self, entry: &'a Entry) -> &'a [u8] {
+        match *entry {
+            Entry::Inline { len, ref bytes } => &bytes[..len as usize],
+            Entry::Arena { start, len } => {
+                let start = start as usize;
+                &self.arena.as_bytes()[start..start + len as usize]
+            }
+        }
+    }
+
+    fn entry_str<'a>(&'a self, entry: &'a Entry) -> &'a str {
+        match *entry {
+            Entry::Inline { len, ref bytes } => {
+                str::from_utf8(&bytes[..len as usize]).expect("interned strings are valid utf8")
+            }
+            Entry::Arena { start, len } => {
+                let start = start as usize;
+                &self.arena[start..start + len as usize]
+            }
+        }
+    }
+
+    /// Looks for `data` in the table: returns its atom if it was interned, and otherwise the
+    /// empty bucket where it would be inserted.
+    fn find(&self, data: &str) -> Result<TargetType, usize> {
+        let mask = self.buckets.len() - 1;
+        let mut bucket = bucket_of(data, self.buckets.len());
+        loop {
+            match self.buckets[bucket] {
+                0 => return Err(bucket),
+                index => {
+                    let index = index as usize - 1;
+                    if self.entry_bytes(&self.entries[index]) == data.as_bytes() {
+                        return Ok(TargetType::from(index));
+                    }
+                }
+            }
+            bucket = (bucket + 1) & mask;
+        }
+    }
+
+    fn grow(&mut self) {
+        let mut buckets = vec![0; self.buckets.len() * 2];
+        let mask = buckets.len() - 1;
+        for (index, entry) in self.entries.iter().enumerate() {
+            let mut bucket = bucket_of(self.entry_str(entry), buckets.len());
+            while buckets[bucket] != 0 {
+                bucket = (bucket + 1) & mask;
+            }
+            buckets[bucket] = index as u32 + 1;
+        }
+        self.buckets = buckets;
+    }
+}
+
+/// The bucket where the probe sequence of `data` starts, in a table of `bucket_count` buckets. It
+/// is taken from the high bits of the hash, as the low bits of `FxHasher`'s are the least mixed.
+pub(crate) fn bucket_of(data: &str, bucket_count: usize) -> usize {
+    let mut hasher = FxHasher::default();
+    data.hash(&mut hasher);
+    let bits = bucket_count.trailing_zeros();
+    (hasher.finish() >> (64 - bits)) as usize
+}
+
+/// The interner the CLI used before the arena: each string is owned twice, as a key of the hash
+/// map and in the vector of names. It's only kept as the baseline of `bench_interners`.
+#[derive(Default)]
+struct OwnedInterner {
+    strings: FxHashMap<String, usize>,
+    rev_strings: Vec<String>,
+}
+
+impl OwnedInterner {
+    fn intern(&mut self, data: &str) -> usize {
         if let Some(&interned) = self.strings.get(data) {
             return interned;
         }
 
-        let index = TargetType::from(self.strings.len());
+        let index = self.strings.len();
         self.rev_strings.push(data.to_string());
         *self.strings.entry(data.to_string()).or_insert(index)
     }
+
+    /// The number of bytes allocated on the heap, approximately: the hash map's allocation is
+    /// estimated from its capacity.
+    fn heap_size(&self) -> usize {
+        let strings: usize = self.rev_strings.iter().map(String::capacity).sum::<usize>()
+            + self.strings.keys().map(String::capacity).sum::<usize>();
+        strings
+            + self.rev_strings.capacity() * mem::size_of::<String>()
+            + self.strings.capacity() * (mem::size_of::<(String, usize)>() + 1)
+    }
+}
+
+/// The time and memory an interner took to intern a list of names.
+#[derive(Debug)]
+pub(crate) struct InternerBench {
+    pub(crate) interner: &'static str,
+    pub(crate) atoms: usize,
+
+    /// The fastest of the runs.
+    pub(crate) elapsed: Duration,
+
+    pub(crate) heap_size: usize,
+}
+
+/// Interns the `names`, in order and with their repetitions, like the values of fact files, with
+/// the owned strings interner the CLI used before, and with the arena interner. Each interner
+/// takes the fastest of `runs` runs.
+pub(crate) fn bench_interners(names: &[&str], runs: usize) -> [InternerBench; 2] {
+    fn bench<I>(
+        interner: &'static str,
+        runs: usize,
+        new: impl Fn() -> I,
+        intern: impl Fn(&mut I),
+        stats: impl Fn(&I) -> (usize, usize),
+    ) -> InternerBench {
+        let mut elapsed = Duration::MAX;
+        let mut sizes = (0, 0);
+        for _ in 0..runs.max(1) {
+            let mut interned = new();
+            let start = Instant::now();
+            intern(&mut interned);
+            elapsed = elapsed.min(start.elapsed());
+            sizes = stats(&interned);
+        }
+        InternerBench {
+            interner,
+            atoms: sizes.0,
+            elapsed,
+            heap_size: sizes.1,
+        }
+    }
+
+    [
+        bench(
+            "owned strings",
+            runs,
+            OwnedInterner::default,
+            |interner| {
+                for name in names {
+                    interner.intern(name);
+                }
+            },
+            |interner| (interner.rev_strings.len(), interner.heap_size()),
+        ),
+        bench(
+            "arena",
+            runs,
+            || Interner::<Point>::new("points"),
+            |interner| {
+                for name in names {
+                    interner.intern(name);
+                }
+            },
+            |interner| (interner.len(), interner.heap_size()),
+        ),
+    ]
 }
 
-pub(crate) struct InternerTables {
+fn checked_u32(value: usize) -> Option<u32> {
+    u32::try_from(value).ok()
+}
+
+/// The names of the atoms of a set of facts, which they were interned from.
+pub struct InternerTables {
     pub(crate) origins: Interner<Origin>,
     pub(crate) loans: Interner<Loan>,
     pub(crate) points: Interner<Point>,
@@ -50,26 +364,161 @@ pub(crate) struct InternerTables {
 }
 
 impl InternerTables {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
+        Self {
+            origins: Interner::new("origins"),
+            loans: Interner::new("loans"),
+            points: Interner::new("points"),
+            variables: Interner::new("variables"),
+            paths: Interner::new("paths"),
+        }
+    }
+
+    /// Tables naming each atom of these tables after its numeric index, e.g. to dump the atoms
+    /// in this form.
+    pub(crate) fn numeric(&self) -> Self {
+        fn numeric<T>(interner: &Interner<T>) -> Interner<T>
+        where
+            T: Atom,
+        {
+            let mut numeric = Interner::new(interner.kind);
+            if let Some(last) = interner.len().checked_sub(1) {
+                numeric
+                    .intern_value(&last.to_string())
+                    .expect("the tables' atoms have `u32` indices");
+            }
+            numeric
+        }
+
+        Self {
+            origins: numeric(&self.origins),
+            loans: numeric(&self.loans),
+            points: numeric(&self.points),
+            variables: numeric(&self.variables),
+            paths: numeric(&self.paths),
+        }
+    }
+
+    /// Tables naming each atom of these tables after its kind and numeric index, like `O3` for an
+    /// origin or `P17` for a point, to shorten the visual outputs.
+    pub(crate) fn short_names(&self) -> Self {
+        fn short_names<T>(interner: &Interner<T>, prefix: &str) -> Interner<T>
+        where
+            T: Atom,
+        {
+            let mut short_names = Interner::new(interner.kind);
+            for index in 0..interner.len() {
+                short_names.intern(&format!("{}{}", prefix, index));
+            }
+            short_names
+        }
+
         Self {
-            origins: Interner::new(),
-            loans: Interner::new(),
-            points: Interner::new(),
-            variables: Interner::new(),
-            paths: Interner::new(),
+            origins: short_names(&self.origins, "O"),
+            loans: short_names(&self.loans, "L"),
+            points: short_names(&self.points, "P"),
+            variables: short_names(&self.variables, "V"),
+            paths: short_names(&self.paths, "M"),
+        }
+    }
+
+    /// Writes the names of the atoms to a symbols file at `path`: a line per atom, in the order
+    /// of their indices, with the kind of atom, a tab, and its name until the end of the line, as
+    /// quoted names can contain tabs. Reloading the
+    /// file with `InternerTables::load` interns the atoms to the same indices, e.g. to resolve
+    /// the atoms dumped with their numeric indices by another process.
+    pub fn save(&self, path: &std::path::Path) -> Result<(), Error> {
+        fn write_symbols<T: Atom>(interner: &Interner<T>, symbols: &mut String) {
+            for index in 0..interner.len() {
+                let name = interner.untern(T::from(index));
+                writeln!(symbols, "{}\t{}", interner.kind, name).unwrap();
+            }
+        }
+
+        let mut symbols = String::new();
+        write_symbols(&self.origins, &mut symbols);
+        write_symbols(&self.loans, &mut symbols);
+        write_symbols(&self.points, &mut symbols);
+        write_symbols(&self.variables, &mut symbols);
+        write_symbols(&self.paths, &mut symbols);
+        fs::write(path, symbols).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Loads the tables saved to the symbols file at `path` by `InternerTables::save`.
+    pub fn load(path: &std::path::Path) -> Result<Self, Error> {
+        fn load_symbol<T: Atom>(interner: &mut Interner<T>, name: &str) -> Result<(), String> {
+            let len = interner.len();
+            interner.intern_value(name).map_err(|e| e.to_string())?;
+            if interner.len() != len + 1 {
+                return Err(format!("duplicate symbol `{}`", name));
+            }
+            Ok(())
         }
+
+        let symbols = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mut tables = Self::new();
+        for (index, line) in symbols.lines().enumerate() {
+            let loaded = match line.split_once('\t') {
+                Some(("origins", name)) => load_symbol(&mut tables.origins, name),
+                Some(("loans", name)) => load_symbol(&mut tables.loans, name),
+                Some(("points", name)) => load_symbol(&mut tables.points, name),
+                Some(("variables", name)) => load_symbol(&mut tables.variables, name),
+                Some(("paths", name)) => load_symbol(&mut tables.paths, name),
+                _ => Err(format!(
+                    "expected the kind of atom and its name, separated by a tab, but found `{}`",
+                    line
+                )),
+            };
+            loaded.map_err(|message| Error::Parse {
+                file: Some(path.to_owned()),
+                line: index + 1,
+                column: 1,
+                message,
+            })?;
+        }
+        Ok(tables)
+    }
+}
+
+impl Default for InternerTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtomNames<LocalFacts> for InternerTables {
+    fn origin_name(&self, origin: Origin) -> Cow<'_, str> {
+        self.origins.untern(origin).into()
+    }
+
+    fn loan_name(&self, loan: Loan) -> Cow<'_, str> {
+        self.loans.untern(loan).into()
+    }
+
+    fn point_name(&self, point: Point) -> Cow<'_, str> {
+        self.points.untern(point).into()
+    }
+
+    fn path_name(&self, path: Path) -> Cow<'_, str> {
+        self.paths.untern(path).into()
     }
 }
 
 pub(crate) trait InternTo<To> {
-    fn intern(tables: &mut InternerTables, input: Self) -> To;
+    fn intern(tables: &mut InternerTables, input: Self) -> Result<To, Error>;
 }
 
 macro_rules! intern_impl {
     ($t:ident, $field:ident) => {
         impl InternTo<$t> for &str {
-            fn intern(tables: &mut InternerTables, input: &str) -> $t {
-                tables.$field.intern(input)
+            fn intern(tables: &mut InternerTables, input: &str) -> Result<$t, Error> {
+                tables.$field.intern_value(input)
             }
         }
     };
@@ -86,9 +535,12 @@ where
     FromA: InternTo<A>,
     FromB: InternTo<B>,
 {
-    fn intern(tables: &mut InternerTables, input: (FromA, FromB)) -> (A, B) {
+    fn intern(tables: &mut InternerTables, input: (FromA, FromB)) -> Result<(A, B), Error> {
         let (from_a, from_b) = input;
-        (FromA::intern(tables, from_a), FromB::intern(tables, from_b))
+        Ok((
+            FromA::intern(tables, from_a)?,
+            FromB::intern(tables, from_b)?,
+        ))
     }
 }
 
@@ -98,13 +550,16 @@ where
     FromB: InternTo<B>,
     FromC: InternTo<C>,
 {
-    fn intern(tables: &mut InternerTables, input: (FromA, FromB, FromC)) -> (A, B, C) {
+    fn intern(
+        tables: &mut InternerTables,
+        input: (FromA, FromB, FromC),
+    ) -> Result<(A, B, C), Error> {
         let (from_a, from_b, from_c) = input;
-        (
-            FromA::intern(tables, from_a),
-            FromB::intern(tables, from_b),
-            FromC::intern(tables, from_c),
-        )
+        Ok((
+            FromA::intern(tables, from_a)?,
+            FromB::intern(tables, from_b)?,
+            FromC::intern(tables, from_c)?,
+        ))
     }
 }
 
@@ -115,13 +570,16 @@ where
     FromC: InternTo<C>,
     FromD: InternTo<D>,
 {
-    fn intern(tables: &mut InternerTables, input: (FromA, FromB, FromC, FromD)) -> (A, B, C, D) {
+    fn intern(
+        tables: &mut InternerTables,
+        input: (FromA, FromB, FromC, FromD),
+    ) -> Result<(A, B, C, D), Error> {
         let (from_a, from_b, from_c, from_d) = input;
-        (
-            FromA::intern(tables, from_a),
-            FromB::intern(tables, from_b),
-            FromC::intern(tables, from_c),
-            FromD::intern(tables, from_d),
-        )
+        Ok((
+            FromA::intern(tables, from_a)?,
+            FromB::intern(tables, from_b)?,
+            FromC::intern(tables, from_c)?,
+            FromD::intern(tables, from_d)?,
+        ))
     }
 }
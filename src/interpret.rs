@@ -0,0 +1,147 @@
+//! Evaluates datalog rules against the facts of an input, with the interpreter of
+//! `polonius-datalog`, to prototype rules without implementing them with datafrog.
+
+use crate::facts::{AllFacts, Loan, Origin, Path, Point, Variable};
+use crate::intern::InternerTables;
+use polonius_datalog::{ast, Database, Tuple};
+use polonius_engine::{compute_origin_liveness, Atom};
+use std::io::{self, Write};
+
+/// A tuple of facts, as a tuple of the interpreter.
+trait Columns {
+    fn columns(&self) -> Tuple;
+}
+
+impl<A: Atom, B: Atom> Columns for (A, B) {
+    fn columns(&self) -> Tuple {
+        vec![column(self.0), column(self.1)]
+    }
+}
+
+impl<A: Atom, B: Atom, C: Atom> Columns for (A, B, C) {
+    fn columns(&self) -> Tuple {
+        vec![column(self.0), column(self.1), column(self.2)]
+    }
+}
+
+fn column(atom: impl Atom) -> u32 {
+    atom.index() as u32
+}
+
+/// The database of the relations of the `all_facts`, named after their fields, and of the other
+/// inputs of the book's rules:
+/// - `origin_live_on_entry`, computed by the liveness analysis when the facts don't have it, with
+///   the placeholders live everywhere.
+/// - `placeholder_origin`, the `universal_region`s.
+/// - `placeholder_loan`, the origins and loans of the `placeholder` facts.
+pub(crate) fn facts_database(all_facts: &AllFacts) -> Database {
+    let mut database = Database::new();
+    macro_rules! relations {
+        ($($relation:ident,)*) => {
+            $(
+                database.extend(
+                    stringify!($relation),
+                    all_facts.$relation.iter().map(Columns::columns),
+                );
+            )*
+        };
+    }
+
+    relations! {
+        loan_issued_at,
+        universal_region_live_at,
+        cfg_edge,
+        loan_killed_at,
+        loan_shallow_killed_at,
+        subset_base,
+        loan_invalidated_at,
+        loan_activated_at,
+        var_defined_at,
+        var_used_at,
+        var_dropped_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at_base,
+        path_moved_at_base,
+        path_accessed_at_base,
+        known_placeholder_subset,
+        placeholder,
+    }
+
+    let universal_regions = || {
+        all_facts
+            .universal_region
+            .iter()
+            .map(|&origin| vec![column(origin)])
+    };
+    database.extend("universal_region", universal_regions());
+    database.extend("placeholder_origin", universal_regions());
+    database.extend(
+        "placeholder_loan",
+        all_facts.placeholder.iter().map(Columns::columns),
+    );
+    database.extend(
+        "origin_live_on_entry",
+        compute_origin_liveness(all_facts)
+            .iter()
+            .map(Columns::columns),
+    );
+    database
+}
+
+/// Writes the tuples of the `relation`, tab-separated like fact files: their atoms are named after
+/// the `tables` when the `declaration` of the relation gives the types of its columns, and are
+/// shown as indices otherwise.
+pub(crate) fn write_relation(
+    database: &Database,
+    relation: &str,
+    declaration: Option<&ast::Declaration>,
+    tables: &InternerTables,
+    stream: &mut dyn Write,
+) -> io::Result<()> {
+    for tuple in database.tuples(relation) {
+        let columns: Vec<String> = tuple
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let ty = declaration
+                    .and_then(|declaration| declaration.columns.get(index))
+                    .map(|column| column.ty.as_str());
+                atom_name(tables, ty, value as usize)
+            })
+            .collect();
+        writeln!(stream, "{}", columns.join("\t"))?;
+    }
+    Ok(())
+}
+
+/// The name of the atom of this `index` and column type, in the `tables`.
+fn atom_name(tables: &InternerTables, ty: Option<&str>, index: usize) -> String {
+    let interned = |len: usize, name: &dyn Fn() -> String| {
+        if index < len {
+            name()
+        } else {
+            index.to_string()
+        }
+    };
+    match ty {
+        Some("origin") => interned(tables.origins.len(), &|| {
+            tables.origins.untern(Origin::from(index)).to_string()
+        }),
+        Some("loan") => interned(tables.loans.len(), &|| {
+            tables.loans.untern(Loan::from(index)).to_string()
+        }),
+        Some("point") => interned(tables.points.len(), &|| {
+            tables.points.untern(Point::from(index)).to_string()
+        }),
+        Some("variable") => interned(tables.variables.len(), &|| {
+            tables.variables.untern(Variable::from(index)).to_string()
+        }),
+        Some("path") => interned(tables.paths.len(), &|| {
+            tables.paths.untern(Path::from(index)).to_string()
+        }),
+        _ => index.to_string(),
+    }
+}
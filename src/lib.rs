@@ -1,9 +1,29 @@
+mod cache;
+mod corpus;
+mod diff;
 mod dump;
+mod error;
 mod facts;
+mod fuzz;
+mod glob;
+mod golden;
 mod intern;
+mod interpret;
+mod manifest;
+mod parity;
 mod program;
+mod progress;
+mod properties;
+mod rules;
+mod schema;
+mod synthetic;
 mod tab_delim;
 mod test;
 mod test_util;
+mod waivers;
 
 pub mod cli;
+
+pub use crate::error::Error;
+pub use crate::intern::InternerTables;
+pub use crate::program::{analyze_program, AnalysisResult};
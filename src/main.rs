@@ -1,13 +1,44 @@
 use polonius::cli;
+use std::env;
 use std::process::exit;
 
 fn main() -> Result<(), cli::Error> {
-    match cli::options_from_args() {
-        Ok(options) => cli::main(options),
-        Err(e) => {
-            // override default `Termination` error printing
-            eprintln!("{}\n\nFor more information try --help", e);
-            exit(1);
-        }
+    // the subcommands are only recognized as the first argument: a directory of facts named like
+    // one is analyzed with `polonius -- <fact_dirs>...`
+    match env::args().nth(1).as_deref() {
+        Some("diff") => match cli::diff_options_from_args() {
+            Ok(options) => cli::diff(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        Some("eval-rules") => match cli::eval_rules_options_from_args() {
+            Ok(options) => cli::eval_rules(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        Some("explain-rule") => match cli::explain_rule_options_from_args() {
+            Ok(options) => cli::explain_rule(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        Some("gen-bench") => match cli::gen_bench_options_from_args() {
+            Ok(options) => cli::gen_bench(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        Some("lint-rules") => match cli::lint_rules_options_from_args() {
+            Ok(options) => cli::lint_rules(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        Some("parity") => match cli::parity_options_from_args() {
+            Ok(options) => cli::parity(options),
+            Err(e) => exit_with_usage_error(e),
+        },
+        _ => match cli::options_from_args() {
+            Ok(options) => cli::main(options),
+            Err(e) => exit_with_usage_error(e),
+        },
     }
 }
+
+fn exit_with_usage_error(e: cli::Error) -> ! {
+    // override default `Termination` error printing
+    eprintln!("{}\n\nFor more information try --help", e);
+    exit(1);
+}
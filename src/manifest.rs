@@ -0,0 +1,175 @@
+//! The manifest written alongside dumped facts, in a `manifest.json` file: the program which
+//! produced them, the version of the facts format, the relations, and the number of atoms of each
+//! kind.
+//!
+//! The relations keep evolving, e.g. `borrow_region` was renamed to `loan_issued_at`, so the
+//! loader reads the manifest of a directory of facts when there is one, and warns when its
+//! version of the format differs from the one it expects, instead of silently misinterpreting
+//! the facts.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use log::warn;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::intern::InternerTables;
+
+/// The name of the manifest, in a directory of facts.
+pub(crate) const MANIFEST_FILE: &str = "manifest.json";
+
+/// The semantic version of the facts format: the major version changes when relations are
+/// renamed, removed, or have their columns reordered, and the minor version when relations are
+/// added.
+pub(crate) const FORMAT_VERSION: Version = Version {
+    major: 1,
+    minor: 0,
+    patch: 0,
+};
+
+/// A semantic version, `major.minor.patch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Version {
+    pub(crate) major: u32,
+    pub(crate) minor: u32,
+    pub(crate) patch: u32,
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid version `{}`, expected `major.minor.patch`", s);
+        let parts: Vec<_> = s.split('.').collect();
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        let part = |index: usize| parts[index].parse::<u32>().map_err(|_| invalid());
+        Ok(Version {
+            major: part(0)?,
+            minor: part(1)?,
+            patch: part(2)?,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Versions are serialized as their `major.minor.patch` string.
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        version.parse().map_err(de::Error::custom)
+    }
+}
+
+/// The contents of a `manifest.json` file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// The program which dumped the facts, and its version, e.g. `polonius 0.7.0`.
+    pub(crate) producer: String,
+
+    /// The version of the facts format the facts were dumped in.
+    pub(crate) format_version: Version,
+
+    /// The names of the dumped relations.
+    pub(crate) relations: Vec<String>,
+
+    /// The number of atoms of each kind, by the name of their kind, e.g. `points`.
+    #[serde(default)]
+    pub(crate) atoms: BTreeMap<String, usize>,
+}
+
+impl Manifest {
+    /// The manifest of the `relations` dumped by this program, whose atoms are interned in the
+    /// `tables`.
+    pub(crate) fn new(relations: &[&str], tables: &InternerTables) -> Self {
+        Manifest {
+            producer: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            format_version: FORMAT_VERSION,
+            relations: relations.iter().map(|name| name.to_string()).collect(),
+            atoms: [
+                ("origins", tables.origins.len()),
+                ("loans", tables.loans.len()),
+                ("points", tables.points.len()),
+                ("variables", tables.variables.len()),
+                ("paths", tables.paths.len()),
+            ]
+            .iter()
+            .map(|&(kind, count)| (kind.to_string(), count))
+            .collect(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("manifests are valid JSON");
+        json.push('\n');
+        json
+    }
+
+    pub(crate) fn from_json(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|error| error.to_string())
+    }
+
+    /// Why the facts of this manifest may be misinterpreted by this program, if their format's
+    /// version is not compatible with `FORMAT_VERSION`.
+    pub(crate) fn version_mismatch(&self) -> Option<String> {
+        let version = self.format_version;
+        if version.major != FORMAT_VERSION.major {
+            Some(format!(
+                "the facts were dumped by {} in version {} of the facts format, incompatible with \
+                 version {}: their relations may be misinterpreted",
+                self.producer, version, FORMAT_VERSION
+            ))
+        } else if version.minor > FORMAT_VERSION.minor {
+            Some(format!(
+                "the facts were dumped by {} in version {} of the facts format, newer than version \
+                 {}: some of their relations may be ignored",
+                self.producer, version, FORMAT_VERSION
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes the `manifest` in the `dir`.
+pub(crate) fn write_manifest(dir: &Path, manifest: &Manifest) -> io::Result<()> {
+    fs::write(dir.join(MANIFEST_FILE), manifest.to_json())
+}
+
+/// Reads the manifest of the `facts_dir`, if it has one, and warns when its facts may be
+/// misinterpreted. A manifest which can't be read is only warned about: the facts themselves may
+/// still be readable.
+pub(crate) fn check_manifest(facts_dir: &Path) -> Option<Manifest> {
+    let path = facts_dir.join(MANIFEST_FILE);
+    let text = fs::read_to_string(&path).ok()?;
+    match Manifest::from_json(&text) {
+        Ok(manifest) => {
+            if let Some(mismatch) = manifest.version_mismatch() {
+                warn!("`{}`: {}", path.display(), mismatch);
+            }
+            Some(manifest)
+        }
+        Err(message) => {
+            warn!("ignoring the malformed `{}`: {}", path.display(), message);
+            None
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! The parity report between the engines: the errors, tuples and timings of each engine on each
+//! function of a corpus, as a CSV matrix, to track which engines agree and how they scale as the
+//! rules evolve.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path as FsPath;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use polonius_engine::Algorithm;
+
+use crate::dump::{csv_field, unquote, Output};
+use crate::error::Error;
+use crate::facts::{AllFacts, Loan, Origin, Path, Point};
+use crate::intern::InternerTables;
+
+/// The engines compared on each function, in the order of the report. The first one is the
+/// reference the others must agree with.
+pub(crate) const ENGINES: &[Algorithm] = &[
+    Algorithm::Naive,
+    Algorithm::DatafrogOpt,
+    Algorithm::LocationInsensitive,
+];
+
+/// The columns of the report.
+const HEADER: &[&str] = &[
+    "function",
+    "engine",
+    "errors",
+    "subset_errors",
+    "move_errors",
+    "loan_live_at",
+    "time_ms",
+    "agrees",
+];
+
+/// The results of an engine on a function.
+#[derive(Debug)]
+pub(crate) struct Run {
+    pub(crate) engine: String,
+    pub(crate) errors: usize,
+    pub(crate) subset_errors: usize,
+    pub(crate) move_errors: usize,
+
+    /// The number of `loan_live_at` tuples: the loans in scope at each point.
+    pub(crate) loan_live_at: usize,
+
+    pub(crate) duration: Duration,
+
+    /// Whether the engine found the same errors as the reference engine: the
+    /// `LocationInsensitive` engine only needs to find them, anywhere, as it over-approximates.
+    /// It's unknown when Soufflé's errors can't be compared.
+    pub(crate) agrees: Option<bool>,
+}
+
+/// The errors of an output, with their points when they are compared at precise locations.
+#[derive(PartialEq, Eq)]
+struct Errors {
+    loans: BTreeSet<(Option<Point>, Loan)>,
+    subsets: BTreeSet<(Option<Point>, Origin, Origin)>,
+    moves: BTreeSet<(Point, Path)>,
+}
+
+impl Errors {
+    fn new(output: &Output, located: bool) -> Self {
+        let at = |point: Point| if located { Some(point) } else { None };
+        Errors {
+            loans: output
+                .errors
+                .iter()
+                .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (at(point), loan)))
+                .collect(),
+            subsets: output
+                .subset_errors
+                .iter()
+                .flat_map(|(&point, subsets)| {
+                    subsets
+                        .iter()
+                        .map(move |&(origin1, origin2)| (at(point), origin1, origin2))
+                })
+                .collect(),
+            moves: output
+                .move_errors
+                .iter()
+                .flat_map(|(&point, paths)| paths.iter().map(move |&path| (point, path)))
+                .collect(),
+        }
+    }
+
+    fn is_subset(&self, other: &Errors) -> bool {
+        self.loans.is_subset(&other.loans)
+            && self.subsets.is_subset(&other.subsets)
+            && self.moves == other.moves
+    }
+}
+
+/// Runs the `ENGINES` on the `all_facts`, and compares their errors to the reference engine's,
+/// whose output is returned with the runs.
+pub(crate) fn run_engines(all_facts: &AllFacts) -> (Vec<Run>, Output) {
+    let mut runs = Vec::new();
+    let mut reference: Option<Output> = None;
+    for &algorithm in ENGINES {
+        let start = Instant::now();
+        let output = Output::compute(all_facts, algorithm, false);
+        let duration = start.elapsed();
+
+        let agrees = match reference {
+            None => true,
+            Some(ref reference) if matches!(algorithm, Algorithm::LocationInsensitive) => {
+                Errors::new(reference, false).is_subset(&Errors::new(&output, false))
+            }
+            Some(ref reference) => Errors::new(reference, true) == Errors::new(&output, true),
+        };
+        runs.push(Run {
+            engine: format!("{:?}", algorithm),
+            errors: output.errors.values().map(Vec::len).sum(),
+            subset_errors: output.subset_errors.values().map(BTreeSet::len).sum(),
+            move_errors: output.move_errors.values().map(Vec::len).sum(),
+            loan_live_at: output.loan_live_at.values().map(Vec::len).sum(),
+            duration,
+            agrees: Some(agrees),
+        });
+        if reference.is_none() {
+            reference = Some(output);
+        }
+    }
+    (runs, reference.expect("no engine was run"))
+}
+
+/// Runs Soufflé's interpreter on the datalog `program`, with the facts of `facts_dir` as its
+/// input files, and counts the tuples of the `errors`, `subset_errors`, `move_errors` and
+/// `loan_live_at` relations it outputs, when the program has them. Its errors are compared with
+/// the `reference` output's by name, see `souffle_agrees`.
+pub(crate) fn run_souffle(
+    program: &FsPath,
+    facts_dir: &FsPath,
+    reference: &Output,
+    tables: &InternerTables,
+) -> Result<Run, Error> {
+    let output_dir = std::env::temp_dir().join(format!(
+        "polonius-parity-{}-{}",
+        std::process::id(),
+        facts_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("facts")
+    ));
+    fs::create_dir_all(&output_dir).map_err(|source| Error::Io {
+        path: output_dir.clone(),
+        source,
+    })?;
+
+    let start = Instant::now();
+    let status = Command::new("souffle")
+        .arg("-F")
+        .arg(facts_dir)
+        .arg("-D")
+        .arg(&output_dir)
+        .arg(program)
+        .status();
+    let duration = start.elapsed();
+    let souffle_error = |source| Error::Io {
+        path: program.to_owned(),
+        source,
+    };
+    let status = status.map_err(souffle_error)?;
+    if !status.success() {
+        return Err(souffle_error(io::Error::other(format!(
+            "souffle failed with {}",
+            status
+        ))));
+    }
+
+    let tuples = |relation: &str| {
+        fs::read_to_string(output_dir.join(format!("{}.csv", relation)))
+            .map(|tuples| tuples.lines().count())
+            .unwrap_or(0)
+    };
+    let run = Run {
+        engine: "Souffle".to_string(),
+        errors: tuples("errors"),
+        subset_errors: tuples("subset_errors"),
+        move_errors: tuples("move_errors"),
+        loan_live_at: tuples("loan_live_at"),
+        duration,
+        agrees: souffle_agrees(&output_dir, reference, tables),
+    };
+    let _ = fs::remove_dir_all(&output_dir);
+    Ok(run)
+}
+
+/// Whether the error relations Soufflé wrote in `output_dir` hold the same tuples as the
+/// `reference` output. Its atoms are not interned like the engines', so the tuples are compared
+/// by the names of their atoms, without their quotes, in the columns of the book's rules: the
+/// point last. The relations the program doesn't output are not compared, and the agreement is
+/// unknown when none is, or when a relation's tuples don't have the expected columns.
+pub(crate) fn souffle_agrees(
+    output_dir: &FsPath,
+    reference: &Output,
+    tables: &InternerTables,
+) -> Option<bool> {
+    let mut agrees = None;
+    for (relation, arity, expected) in error_tuples(reference, tables) {
+        let contents = match fs::read_to_string(output_dir.join(format!("{}.csv", relation))) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let mut tuples = BTreeSet::new();
+        for line in contents.lines() {
+            let tuple: Vec<String> = line
+                .split('\t')
+                .map(|field| unquote(field).into_owned())
+                .collect();
+            if tuple.len() != arity {
+                return None;
+            }
+            tuples.insert(tuple);
+        }
+        agrees = Some(agrees.unwrap_or(true) && tuples == expected);
+    }
+    agrees
+}
+
+/// The sorted tuples of the error relations of the `output`, with their arities, as the names of
+/// their atoms in the columns of the book's rules.
+fn error_tuples(
+    output: &Output,
+    tables: &InternerTables,
+) -> Vec<(&'static str, usize, BTreeSet<Vec<String>>)> {
+    let name = |name: &str| unquote(name).into_owned();
+    let errors = output
+        .errors
+        .iter()
+        .flat_map(|(&point, loans)| {
+            loans.iter().map(move |&loan| {
+                vec![
+                    name(tables.loans.untern(loan)),
+                    name(tables.points.untern(point)),
+                ]
+            })
+        })
+        .collect();
+    let subset_errors = output
+        .subset_errors
+        .iter()
+        .flat_map(|(&point, subsets)| {
+            subsets.iter().map(move |&(origin1, origin2)| {
+                vec![
+                    name(tables.origins.untern(origin1)),
+                    name(tables.origins.untern(origin2)),
+                    name(tables.points.untern(point)),
+                ]
+            })
+        })
+        .collect();
+    let move_errors = output
+        .move_errors
+        .iter()
+        .flat_map(|(&point, paths)| {
+            paths.iter().map(move |&path| {
+                vec![
+                    name(tables.paths.untern(path)),
+                    name(tables.points.untern(point)),
+                ]
+            })
+        })
+        .collect();
+    vec![
+        ("errors", 2, errors),
+        ("subset_errors", 3, subset_errors),
+        ("move_errors", 2, move_errors),
+    ]
+}
+
+/// Writes the header of the report.
+pub(crate) fn write_header(stream: &mut dyn Write) -> io::Result<()> {
+    writeln!(stream, "{}", HEADER.join(","))
+}
+
+/// Writes a row of the report per engine `run` on the `function`.
+pub(crate) fn write_runs(stream: &mut dyn Write, function: &str, runs: &[Run]) -> io::Result<()> {
+    for run in runs {
+        writeln!(
+            stream,
+            "{},{},{},{},{},{},{:.3},{}",
+            csv_field(function),
+            run.engine,
+            run.errors,
+            run.subset_errors,
+            run.move_errors,
+            run.loan_live_at,
+            run.duration.as_secs_f64() * 1000.0,
+            run.agrees
+                .map_or(String::new(), |agrees| agrees.to_string())
+        )?;
+    }
+    Ok(())
+}
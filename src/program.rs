@@ -1,12 +1,14 @@
-#![cfg(test)]
-
 use std::collections::BTreeSet;
 
 use polonius_parser::{
-    ir::{Effect, Fact, KnownSubset, Placeholder},
-    parse_input,
+    ir::{Effect, Fact, KnownSubset},
+    parse_input, ParseError,
 };
 
+use polonius_engine::{Algorithm, ComputeOptions};
+
+use crate::dump::Output;
+use crate::error::Error;
 use crate::facts::{AllFacts, Loan, Origin, Path, Point, Variable};
 use crate::intern::InternerTables;
 
@@ -15,10 +17,13 @@ use crate::intern::InternerTables;
 struct Facts {
     loan_issued_at: BTreeSet<(Origin, Loan, Point)>,
     universal_region: BTreeSet<Origin>,
+    universal_region_live_at: BTreeSet<(Origin, Point)>,
     cfg_edge: BTreeSet<(Point, Point)>,
     loan_killed_at: BTreeSet<(Loan, Point)>,
+    loan_shallow_killed_at: BTreeSet<(Loan, Point)>,
     subset_base: BTreeSet<(Origin, Origin, Point)>,
-    loan_invalidated_at: BTreeSet<(Point, Loan)>,
+    loan_invalidated_at: BTreeSet<(Loan, Point)>,
+    loan_activated_at: BTreeSet<(Loan, Point)>,
     known_placeholder_subset: BTreeSet<(Origin, Origin)>,
     placeholder: BTreeSet<(Origin, Loan)>,
     var_defined_at: BTreeSet<(Variable, Point)>,
@@ -26,6 +31,7 @@ struct Facts {
     var_dropped_at: BTreeSet<(Variable, Point)>,
     use_of_var_derefs_origin: BTreeSet<(Variable, Origin)>,
     drop_of_var_derefs_origin: BTreeSet<(Variable, Origin)>,
+    origin_live_on_entry: BTreeSet<(Origin, Point)>,
     child_path: BTreeSet<(Path, Path)>,
     path_is_var: BTreeSet<(Path, Variable)>,
     path_assigned_at_base: BTreeSet<(Path, Point)>,
@@ -38,15 +44,19 @@ impl From<Facts> for AllFacts {
         Self {
             loan_issued_at: facts.loan_issued_at.into_iter().collect(),
             universal_region: facts.universal_region.into_iter().collect(),
+            universal_region_live_at: facts.universal_region_live_at.into_iter().collect(),
             cfg_edge: facts.cfg_edge.into_iter().collect(),
             loan_killed_at: facts.loan_killed_at.into_iter().collect(),
+            loan_shallow_killed_at: facts.loan_shallow_killed_at.into_iter().collect(),
             subset_base: facts.subset_base.into_iter().collect(),
             loan_invalidated_at: facts.loan_invalidated_at.into_iter().collect(),
+            loan_activated_at: facts.loan_activated_at.into_iter().collect(),
             var_defined_at: facts.var_defined_at.into_iter().collect(),
             var_used_at: facts.var_used_at.into_iter().collect(),
             var_dropped_at: facts.var_dropped_at.into_iter().collect(),
             use_of_var_derefs_origin: facts.use_of_var_derefs_origin.into_iter().collect(),
             drop_of_var_derefs_origin: facts.drop_of_var_derefs_origin.into_iter().collect(),
+            origin_live_on_entry: facts.origin_live_on_entry.into_iter().collect(),
             child_path: facts.child_path.into_iter().collect(),
             path_is_var: facts.path_is_var.into_iter().collect(),
             path_assigned_at_base: facts.path_assigned_at_base.into_iter().collect(),
@@ -58,12 +68,27 @@ impl From<Facts> for AllFacts {
     }
 }
 
+/// Converts the parser's error, at an offset in the `program`, into an error at a line and
+/// column.
+fn parse_error(program: &str, error: ParseError) -> Error {
+    let position = error.span();
+    let before = &program[..position.start as usize];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+    Error::Parse {
+        file: None,
+        line,
+        column,
+        message: error.to_string(),
+    }
+}
+
 /// Parses an input program into a set of its facts, into the same format `rustc` outputs.
 pub(crate) fn parse_from_program(
     program: &str,
     tables: &mut InternerTables,
-) -> Result<AllFacts, String> {
-    let input = parse_input(program)?;
+) -> Result<AllFacts, Error> {
+    let input = parse_input(program).map_err(|error| parse_error(program, error))?;
 
     let mut facts: Facts = Default::default();
 
@@ -178,20 +203,17 @@ pub(crate) fn parse_from_program(
 
             // the most common statement effects: mid point effects
             for effect in &statement.effects {
-                match effect {
-                    // TODO: once the parser is revamped for liveness etc, make
-                    // sure to catch the new inputs here!
-                    Effect::Fact(ref fact) => {
-                        // Manually specified facts
-                        emit_fact(&mut facts, fact, mid, tables)
-                    }
-                    _ => {}
-                };
+                // TODO: once the parser is revamped for liveness etc, make
+                // sure to catch the new inputs here!
+                if let Effect::Fact(ref fact) = effect.node {
+                    // Manually specified facts
+                    emit_fact(&mut facts, fact, mid, tables)
+                }
             }
 
             // commonly used to emit manual `loan_invalidated_at` at Start points, like some rustc features do
             for effect in &statement.effects_start {
-                if let Effect::Fact(ref fact) = effect {
+                if let Effect::Fact(ref fact) = effect.node {
                     emit_fact(&mut facts, fact, start, tables);
                 }
             }
@@ -201,6 +223,36 @@ pub(crate) fn parse_from_program(
     Ok(facts.into())
 }
 
+/// The results of `analyze_program`.
+pub struct AnalysisResult {
+    /// The facts of the program.
+    pub facts: AllFacts,
+
+    /// The errors, and intermediate relations, computed from the facts.
+    pub output: Output,
+
+    /// The names of the atoms in the facts and the output, e.g. to display the
+    /// output's `diagnostics()`.
+    pub tables: InternerTables,
+}
+
+/// Parses a program in the `polonius-parser` syntax, and analyzes its facts with the given
+/// `algorithm`. The intermediate relations are computed as well, as if in verbose mode.
+pub fn analyze_program(program: &str, algorithm: Algorithm) -> Result<AnalysisResult, Error> {
+    let mut tables = InternerTables::new();
+    let facts = parse_from_program(program, &mut tables)?;
+    let options = ComputeOptions {
+        dump_enabled: true,
+        ..Default::default()
+    };
+    let output = Output::compute_with_options(&facts, algorithm, &options);
+    Ok(AnalysisResult {
+        facts,
+        output,
+        tables,
+    })
+}
+
 fn emit_fact(facts: &mut Facts, fact: &Fact, point: Point, tables: &mut InternerTables) {
     match fact {
         // facts: loan_issued_at(Origin, Loan, Point)
@@ -231,11 +283,25 @@ fn emit_fact(facts: &mut Facts, fact: &Fact, point: Point, tables: &mut Interner
             facts.loan_killed_at.insert((loan, point));
         }
 
-        // facts: loan_invalidated_at(Point, Loan)
+        // facts: loan_shallow_killed_at(Loan, Point)
+        Fact::LoanShallowKilledAt { ref loan } => {
+            // loan_shallow_killed_at: a loan is shallowly killed on Mid points
+            let loan = tables.loans.intern(loan);
+            facts.loan_shallow_killed_at.insert((loan, point));
+        }
+
+        // facts: loan_invalidated_at(Loan, Point)
         Fact::LoanInvalidatedAt { ref loan } => {
             let loan = tables.loans.intern(loan);
             // loan_invalidated_at: a loan can be invalidated on both Start and Mid points
-            facts.loan_invalidated_at.insert((point, loan));
+            facts.loan_invalidated_at.insert((loan, point));
+        }
+
+        // facts: loan_activated_at(Loan, Point)
+        Fact::LoanActivatedAt { ref loan } => {
+            // loan_activated_at: a two-phase borrow is activated on Mid points
+            let loan = tables.loans.intern(loan);
+            facts.loan_activated_at.insert((loan, point));
         }
 
         // facts: var_defined_at(Variable, Point)
@@ -260,6 +326,7 @@ fn emit_fact(facts: &mut Facts, fact: &Fact, point: Point, tables: &mut Interner
 mod tests {
     use super::*;
     use crate::intern::InternerTables;
+    use polonius_parser::ir::Placeholder;
 
     #[test]
     fn complete_program() {
@@ -332,8 +399,8 @@ mod tests {
         assert_eq!(facts.loan_invalidated_at.len(), 2);
         {
             // regular mid point `loan_invalidated_at`
-            let point = tables.points.untern(facts.loan_invalidated_at[0].0);
-            let loan = tables.loans.untern(facts.loan_invalidated_at[0].1);
+            let point = tables.points.untern(facts.loan_invalidated_at[0].1);
+            let loan = tables.loans.untern(facts.loan_invalidated_at[0].0);
 
             assert_eq!(point, "\"Mid(B0[0])\"");
             assert_eq!(loan, "L0");
@@ -341,8 +408,8 @@ mod tests {
 
         {
             // uncommon start point `loan_invalidated_at`
-            let point = tables.points.untern(facts.loan_invalidated_at[1].0);
-            let loan = tables.loans.untern(facts.loan_invalidated_at[1].1);
+            let point = tables.points.untern(facts.loan_invalidated_at[1].1);
+            let loan = tables.loans.untern(facts.loan_invalidated_at[1].0);
 
             assert_eq!(point, "\"Start(B0[1])\"");
             assert_eq!(loan, "L1");
@@ -412,4 +479,25 @@ mod tests {
             .cfg_edge
             .contains(&make_edge("\"Mid(B0[1])\"", "\"Start(B1[0])\"")));
     }
+
+    #[test]
+    fn facts_on_start_and_mid_points() {
+        let program = r"
+            placeholders { 'a, 'b }
+            block B0 {
+                on start { outlives('a: 'b), loan_issued_at('a, L0) } on mid { loan_killed_at(L0) };
+                on start { var_defined_at(V0), var_used_at(V1) };
+            }
+        ";
+
+        let mut tables = InternerTables::new();
+        let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+        let point = |point: Point| tables.points.untern(point);
+
+        assert_eq!(point(facts.subset_base[0].2), "\"Start(B0[0])\"");
+        assert_eq!(point(facts.loan_issued_at[0].2), "\"Start(B0[0])\"");
+        assert_eq!(point(facts.loan_killed_at[0].1), "\"Mid(B0[0])\"");
+        assert_eq!(point(facts.var_defined_at[0].1), "\"Start(B0[1])\"");
+        assert_eq!(point(facts.var_used_at[0].1), "\"Start(B0[1])\"");
+    }
 }
@@ -0,0 +1,128 @@
+//! The progress of long computations, shown by the CLI's `--progress` flag: the phases of the
+//! computation as they start, and periodically, the rounds of the fixpoint iterations with their
+//! number of tuples, and an estimate of the remaining time, to tell a computation still
+//! converging from a hung one.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use polonius_engine::{ProgressEvent, ProgressHook};
+
+/// The minimum delay between two reports of the rounds of a phase.
+const ROUND_REPORT_PERIOD: Duration = Duration::from_secs(1);
+
+/// Follows the events of a computation, to describe its progress.
+#[derive(Debug)]
+pub(crate) struct Progress {
+    /// The start of the current phase, and its name.
+    phase: Option<(&'static str, Instant)>,
+
+    /// The number of tuples at the end of the last round of the current phase.
+    tuples: usize,
+
+    /// The number of new tuples of the last round, if any.
+    delta: Option<usize>,
+
+    /// When the rounds of the current phase were last reported, with the round, and its number
+    /// of new tuples.
+    last_report: Option<(Instant, usize, usize)>,
+}
+
+impl Progress {
+    pub(crate) fn new() -> Self {
+        Progress {
+            phase: None,
+            tuples: 0,
+            delta: None,
+            last_report: None,
+        }
+    }
+
+    /// The hook reporting the progress of a computation on stderr.
+    pub(crate) fn hook() -> ProgressHook {
+        let progress = Mutex::new(Progress::new());
+        ProgressHook::new(move |event| {
+            let mut progress = progress.lock().unwrap();
+            if let Some(line) = progress.update(event, Instant::now()) {
+                eprintln!("[progress] {}", line);
+            }
+        })
+    }
+
+    /// Updates the progress with the `event` received at `now`, and describes it when it should
+    /// be reported: the phases are always reported, and their rounds at most once per
+    /// `ROUND_REPORT_PERIOD`.
+    pub(crate) fn update(&mut self, event: ProgressEvent, now: Instant) -> Option<String> {
+        match event {
+            ProgressEvent::Phase(name) => {
+                let previous = self.phase;
+                *self = Progress {
+                    phase: Some((name, now)),
+                    ..Progress::new()
+                };
+                Some(match previous {
+                    Some((previous, start)) => format!(
+                        "{} (after {} in {:.1}s)",
+                        name,
+                        previous,
+                        now.saturating_duration_since(start).as_secs_f64()
+                    ),
+                    None => name.to_string(),
+                })
+            }
+            ProgressEvent::Round { round, tuples } => {
+                let delta = tuples.saturating_sub(self.tuples);
+                let previous_delta = self.delta.replace(delta);
+                self.tuples = tuples;
+
+                let (name, start) = self.phase?;
+                let last_report = self.last_report.map_or(start, |(at, _, _)| at);
+                if now.saturating_duration_since(last_report) < ROUND_REPORT_PERIOD {
+                    return None;
+                }
+
+                // the decrease of the new tuples is measured since the last report, or else
+                // since the previous round
+                let since = match self.last_report {
+                    Some((_, reported_round, reported_delta)) => {
+                        Some((round - reported_round, reported_delta))
+                    }
+                    None => previous_delta.map(|previous_delta| (1, previous_delta)),
+                };
+                self.last_report = Some((now, round, delta));
+
+                let elapsed = now.saturating_duration_since(start).as_secs_f64();
+                let estimate = match since {
+                    _ if delta == 0 => "converging".to_string(),
+                    Some((rounds, previous)) => match remaining_rounds(previous, delta, rounds) {
+                        Some(rounds) => {
+                            let seconds = rounds * elapsed / round as f64;
+                            format!("ETA ~{:.0}s", seconds)
+                        }
+                        None => "still growing, no ETA".to_string(),
+                    },
+                    None => "no ETA yet".to_string(),
+                };
+                Some(format!(
+                    "{}: round {}, {} tuples (+{}), {:.1}s, {}",
+                    name, round, tuples, delta, elapsed, estimate
+                ))
+            }
+        }
+    }
+}
+
+/// Estimates the number of rounds until a fixpoint, assuming the number of new tuples per round
+/// keeps decreasing at the same rate as from `previous` to `delta`, over the last `rounds`: until
+/// less than one new tuple is found. There is no estimate while the number of new tuples doesn't
+/// decrease.
+pub(crate) fn remaining_rounds(previous: usize, delta: usize, rounds: usize) -> Option<f64> {
+    if delta == 0 {
+        return Some(0.0);
+    }
+    if delta >= previous || rounds == 0 {
+        return None;
+    }
+    let rate = (delta as f64 / previous as f64).powf(1.0 / rounds as f64);
+    Some((delta as f64).ln() / -rate.ln())
+}
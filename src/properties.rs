@@ -0,0 +1,148 @@
+#![cfg(test)]
+
+//! Property-based tests checking that the analysis is monotonic on arbitrary inputs: adding or
+//! removing some kinds of facts can only add, or only remove, errors.
+
+use polonius_engine::{Algorithm, Diagnostic};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::dump::Output;
+use crate::facts::{AllFacts, LocalFacts};
+
+const POINTS: usize = 8;
+const ORIGINS: usize = 5;
+const LOANS: usize = 3;
+const VARIABLES: usize = 3;
+
+/// The first origins are placeholders, each containing its own placeholder loan, numbered after
+/// the regular loans.
+const PLACEHOLDERS: usize = 2;
+
+prop_compose! {
+    fn arb_facts()(
+        cfg_edge in vec((0..POINTS, 0..POINTS), 1..12),
+        loan_issued_at in vec((0..ORIGINS, 0..LOANS, 0..POINTS), 0..4),
+        subset_base in vec((0..ORIGINS, 0..ORIGINS, 0..POINTS), 0..8),
+        loan_invalidated_at in vec((0..LOANS, 0..POINTS), 0..4),
+        loan_killed_at in vec((0..LOANS, 0..POINTS), 0..3),
+        var_used_at in vec((0..VARIABLES, 0..POINTS), 0..4),
+        var_defined_at in vec((0..VARIABLES, 0..POINTS), 0..3),
+        use_of_var_derefs_origin in vec((0..VARIABLES, 0..ORIGINS), 0..4),
+        known_placeholder_subset in vec((0..PLACEHOLDERS, 0..PLACEHOLDERS), 0..2),
+    ) -> AllFacts {
+        AllFacts {
+            cfg_edge: atoms2(cfg_edge),
+            loan_issued_at: loan_issued_at
+                .into_iter()
+                .map(|(origin, loan, point)| (origin.into(), loan.into(), point.into()))
+                .collect(),
+            subset_base: subset_base
+                .into_iter()
+                .map(|(origin1, origin2, point)| (origin1.into(), origin2.into(), point.into()))
+                .collect(),
+            loan_invalidated_at: atoms2(loan_invalidated_at),
+            loan_killed_at: atoms2(loan_killed_at),
+            var_used_at: atoms2(var_used_at),
+            var_defined_at: atoms2(var_defined_at),
+            use_of_var_derefs_origin: atoms2(use_of_var_derefs_origin),
+            known_placeholder_subset: atoms2(known_placeholder_subset),
+            universal_region: (0..PLACEHOLDERS).map(Into::into).collect(),
+            placeholder: (0..PLACEHOLDERS)
+                .map(|origin| (origin.into(), (LOANS + origin).into()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+fn atoms2<A: From<usize>, B: From<usize>>(tuples: Vec<(usize, usize)>) -> Vec<(A, B)> {
+    tuples
+        .into_iter()
+        .map(|(a, b)| (a.into(), b.into()))
+        .collect()
+}
+
+fn compute(facts: &AllFacts) -> Output {
+    Output::compute(facts, Algorithm::Naive, false)
+}
+
+fn is_subset_error(diagnostic: &Diagnostic<LocalFacts>) -> bool {
+    matches!(diagnostic, Diagnostic::IllegalSubset { .. })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn adding_a_cfg_edge_never_removes_errors(
+        facts in arb_facts(),
+        edge in (0..POINTS, 0..POINTS),
+    ) {
+        let mut perturbed = facts.clone();
+        perturbed.cfg_edge.push((edge.0.into(), edge.1.into()));
+
+        let diff = compute(&facts).diff_errors(&compute(&perturbed));
+        prop_assert!(diff.removed.is_empty(), "errors removed: {:?}", diff.removed);
+    }
+
+    #[test]
+    fn adding_a_subset_never_removes_errors(
+        facts in arb_facts(),
+        subset in (0..ORIGINS, 0..ORIGINS, 0..POINTS),
+    ) {
+        let mut perturbed = facts.clone();
+        perturbed.subset_base.push((subset.0.into(), subset.1.into(), subset.2.into()));
+
+        let diff = compute(&facts).diff_errors(&compute(&perturbed));
+        prop_assert!(diff.removed.is_empty(), "errors removed: {:?}", diff.removed);
+    }
+
+    #[test]
+    fn adding_a_known_placeholder_subset_never_adds_subset_errors(
+        facts in arb_facts(),
+        known_subset in (0..PLACEHOLDERS, 0..PLACEHOLDERS),
+    ) {
+        let mut perturbed = facts.clone();
+        perturbed
+            .known_placeholder_subset
+            .push((known_subset.0.into(), known_subset.1.into()));
+
+        let diff = compute(&facts).diff_errors(&compute(&perturbed));
+        let added: Vec<_> = diff.added.into_iter().filter(is_subset_error).collect();
+        prop_assert!(added.is_empty(), "subset errors added: {:?}", added);
+    }
+
+    #[test]
+    fn removing_an_invalidation_never_adds_errors(
+        facts in arb_facts(),
+        idx in any::<prop::sample::Index>(),
+    ) {
+        prop_assume!(!facts.loan_invalidated_at.is_empty());
+
+        let mut perturbed = facts.clone();
+        perturbed.loan_invalidated_at.remove(idx.index(facts.loan_invalidated_at.len()));
+
+        let diff = compute(&facts).diff_errors(&compute(&perturbed));
+        prop_assert!(diff.added.is_empty(), "errors added: {:?}", diff.added);
+    }
+
+    #[test]
+    fn adding_a_kill_never_adds_errors(
+        facts in arb_facts(),
+        kill in (0..LOANS, 0..POINTS),
+    ) {
+        let mut perturbed = facts.clone();
+        perturbed.loan_killed_at.push((kill.0.into(), kill.1.into()));
+
+        let diff = compute(&facts).diff_errors(&compute(&perturbed));
+        prop_assert!(diff.added.is_empty(), "errors added: {:?}", diff.added);
+    }
+
+    #[test]
+    fn optimized_variant_matches_naive(facts in arb_facts()) {
+        let opt = Output::compute(&facts, Algorithm::DatafrogOpt, false);
+        let diff = compute(&facts).diff_errors(&opt);
+        prop_assert!(diff.is_empty(), "errors differ: {:?}", diff);
+    }
+}
@@ -0,0 +1,378 @@
+//! The datalog rules of the analysis, parsed from the `prolog` blocks of the book's chapters with
+//! `polonius-datalog`, to navigate between the rules and the code implementing them, and between
+//! the rules producing and consuming each relation.
+
+use crate::error::Error;
+use polonius_datalog::ast;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// The book's chapters describing the rules, and their paths.
+pub(crate) const CHAPTERS: &[(&str, &str)] = &[
+    (
+        "book/src/rules/loans.md",
+        include_str!("../book/src/rules/loans.md"),
+    ),
+    (
+        "book/src/rules/liveness.md",
+        include_str!("../book/src/rules/liveness.md"),
+    ),
+    (
+        "book/src/rules/initialization.md",
+        include_str!("../book/src/rules/initialization.md"),
+    ),
+];
+
+const REPOSITORY: &str = "https://github.com/rust-lang/polonius/blob/master";
+
+/// The rules and declarations of the book's chapters.
+#[derive(Debug, Default)]
+pub(crate) struct Spec {
+    pub(crate) rules: Vec<Rule>,
+    pub(crate) declarations: Vec<Declaration>,
+}
+
+/// A `.decl` of a relation, in a section of the book.
+#[derive(Debug)]
+pub(crate) struct Declaration {
+    pub(crate) name: String,
+
+    /// Whether the relation is an `.input` of the analysis.
+    pub(crate) input: bool,
+}
+
+/// A rule of the book, and its documentation.
+#[derive(Debug)]
+pub(crate) struct Rule {
+    /// The heading of the book's section describing the rule.
+    pub(crate) section: String,
+
+    /// The label of the rule in its section, like `R3`, when it has one.
+    pub(crate) label: Option<String>,
+
+    /// The comment preceding the rule, without its label.
+    pub(crate) documentation: Vec<String>,
+
+    /// The lines of the rule.
+    pub(crate) text: Vec<String>,
+
+    /// The relation derived by the rule.
+    pub(crate) output: String,
+
+    /// The relations joined by the rule, in order, and whether they're negated.
+    pub(crate) inputs: Vec<(String, bool)>,
+}
+
+impl Rule {
+    /// The engine's function implementing the rule, and its file.
+    pub(crate) fn implementation(&self) -> (&'static str, &'static str) {
+        if self.output == "placeholder_known_to_contain" {
+            (
+                "Output::compute_known_contains",
+                "polonius-engine/src/output/mod.rs",
+            )
+        } else if self.section.starts_with("Location Insensitive") {
+            (
+                "location_insensitive::compute",
+                "polonius-engine/src/output/location_insensitive.rs",
+            )
+        } else {
+            ("naive::evaluate", "polonius-engine/src/output/naive.rs")
+        }
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.output == name
+            || self
+                .label
+                .as_ref()
+                .is_some_and(|label| label.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label {
+            Some(ref label) => writeln!(fmt, "{}, in \"{}\":", label, self.section)?,
+            None => writeln!(fmt, "In \"{}\":", self.section)?,
+        }
+        for line in &self.documentation {
+            writeln!(fmt, "  {}", line)?;
+        }
+        writeln!(fmt)?;
+        for line in &self.text {
+            writeln!(fmt, "  {}", line)?;
+        }
+        writeln!(fmt)?;
+
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|(relation, negated)| {
+                if *negated {
+                    format!("!{}", relation)
+                } else {
+                    relation.clone()
+                }
+            })
+            .collect();
+        writeln!(fmt, "  Output: {}", self.output)?;
+        writeln!(fmt, "  Inputs: {}", inputs.join(", "))?;
+        let (function, file) = self.implementation();
+        writeln!(
+            fmt,
+            "  Implemented by `{}`: {}/{}",
+            function, REPOSITORY, file
+        )
+    }
+}
+
+/// The rules and declarations of all the book's chapters, in order.
+pub(crate) fn spec() -> Spec {
+    let mut spec = Spec::default();
+    for (_, chapter) in CHAPTERS {
+        parse_chapter(chapter, &mut spec);
+    }
+    spec
+}
+
+/// The rules of all the book's chapters, in order.
+pub(crate) fn rules() -> Vec<Rule> {
+    spec().rules
+}
+
+/// The rules with the given label, like `R3`, or deriving the given relation.
+pub(crate) fn find_rules(name: &str) -> Result<Vec<Rule>, Error> {
+    let rules: Vec<Rule> = rules()
+        .into_iter()
+        .filter(|rule| rule.is_named(name))
+        .collect();
+    if rules.is_empty() {
+        return Err(Error::UnknownRule {
+            name: name.to_string(),
+        });
+    }
+    Ok(rules)
+}
+
+/// The `prolog` blocks of a markdown `chapter`, and the headings of the sections they're in.
+pub(crate) fn prolog_blocks(chapter: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut section = String::new();
+    let mut block: Option<String> = None;
+    for line in chapter.lines() {
+        match block {
+            None if line.starts_with('#') => {
+                section = line.trim_start_matches('#').trim().to_string();
+            }
+            None if line.trim() == "```prolog" => block = Some(String::new()),
+            None => {}
+            Some(_) if line.trim() == "```" => {
+                blocks.push((section.clone(), block.take().unwrap()));
+            }
+            Some(ref mut text) => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    blocks
+}
+
+/// Parses the `prolog` blocks of the `chapter`, in the sections they're in.
+fn parse_chapter(chapter: &str, spec: &mut Spec) {
+    for (section, text) in prolog_blocks(chapter) {
+        let program = polonius_datalog::parse(&text)
+            .unwrap_or_else(|error| panic!("invalid rules in section \"{}\": {}", section, error));
+        add_program(&section, &program, spec);
+    }
+}
+
+fn add_program(section: &str, program: &ast::Program, spec: &mut Spec) {
+    for declaration in program.declarations() {
+        spec.declarations.push(Declaration {
+            name: declaration.relation.clone(),
+            input: program.is_input(&declaration.relation),
+        });
+    }
+
+    for rule in program.rules() {
+        let mut inputs: Vec<(String, bool)> = Vec::new();
+        for (atom, negated) in rule.atoms() {
+            let input = (atom.relation.clone(), negated);
+            if !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+
+        spec.rules.push(Rule {
+            section: section.to_string(),
+            label: rule.label().map(str::to_string),
+            documentation: rule
+                .documentation()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            text: rule
+                .to_string()
+                .lines()
+                .skip(rule.comments.len())
+                .map(str::to_string)
+                .collect(),
+            output: rule.head.relation.clone(),
+            inputs,
+        });
+    }
+}
+
+/// The relations of the rules implemented by a function of the engine, and the rules producing and
+/// consuming each of them.
+#[derive(Debug)]
+pub(crate) struct Dependencies<'a> {
+    pub(crate) implementation: &'static str,
+    pub(crate) relations: BTreeMap<&'a str, RelationUses<'a>>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RelationUses<'a> {
+    /// The rules deriving the relation.
+    pub(crate) produced_by: Vec<&'a Rule>,
+
+    /// The rules joining the relation, and whether they negate it.
+    pub(crate) consumed_by: Vec<(&'a Rule, bool)>,
+}
+
+impl Spec {
+    /// The dependencies between the relations of the rules, grouped by the function implementing
+    /// them: the variants use the same names for different relations.
+    pub(crate) fn dependencies(&self) -> Vec<Dependencies<'_>> {
+        let mut dependencies: Vec<Dependencies<'_>> = Vec::new();
+        for rule in &self.rules {
+            let implementation = rule.implementation().0;
+            let index = match dependencies
+                .iter()
+                .position(|group| group.implementation == implementation)
+            {
+                Some(index) => index,
+                None => {
+                    dependencies.push(Dependencies {
+                        implementation,
+                        relations: BTreeMap::new(),
+                    });
+                    dependencies.len() - 1
+                }
+            };
+
+            let relations = &mut dependencies[index].relations;
+            relations
+                .entry(rule.output.as_str())
+                .or_default()
+                .produced_by
+                .push(rule);
+            for (input, negated) in &rule.inputs {
+                relations
+                    .entry(input.as_str())
+                    .or_default()
+                    .consumed_by
+                    .push((rule, *negated));
+            }
+        }
+        dependencies
+    }
+
+    fn is_input(&self, relation: &str) -> bool {
+        self.declarations
+            .iter()
+            .any(|declaration| declaration.input && declaration.name == relation)
+    }
+
+    /// Writes a markdown section per implementation, with a table of the rules producing and
+    /// consuming each relation.
+    pub(crate) fn write_dependencies(&self, stream: &mut dyn Write) -> io::Result<()> {
+        writeln!(stream, "# Relation dependencies")?;
+        for group in self.dependencies() {
+            writeln!(stream)?;
+            writeln!(stream, "## `{}`", group.implementation)?;
+            writeln!(stream)?;
+            writeln!(stream, "| Relation | Produced by | Consumed by |")?;
+            writeln!(stream, "|---|---|---|")?;
+            for (relation, uses) in &group.relations {
+                let produced_by = if !uses.produced_by.is_empty() {
+                    rule_names(uses.produced_by.iter().map(|&rule| (rule, false)))
+                } else if self.is_input(relation) {
+                    "input".to_string()
+                } else {
+                    "-".to_string()
+                };
+                let consumed_by = if uses.consumed_by.is_empty() {
+                    "-".to_string()
+                } else {
+                    rule_names(uses.consumed_by.iter().copied())
+                };
+                writeln!(
+                    stream,
+                    "| `{}` | {} | {} |",
+                    relation, produced_by, consumed_by
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the graphviz graph of the relations, with an edge from each input relation of a
+    /// rule to its output, in a cluster per implementation. The negated inputs are dashed.
+    pub(crate) fn write_dependency_graph(&self, stream: &mut dyn Write) -> io::Result<()> {
+        writeln!(stream, "digraph rules {{")?;
+        for (index, group) in self.dependencies().iter().enumerate() {
+            writeln!(stream, "    subgraph cluster_{} {{", index)?;
+            writeln!(stream, "        label=\"{}\";", group.implementation)?;
+            for &relation in group.relations.keys() {
+                let shape = if self.is_input(relation) {
+                    "box"
+                } else {
+                    "ellipse"
+                };
+                writeln!(
+                    stream,
+                    "        \"{}:{}\" [label=\"{}\", shape={}];",
+                    index, relation, relation, shape
+                )?;
+            }
+            for (relation, uses) in &group.relations {
+                for &(rule, negated) in &uses.consumed_by {
+                    let style = if negated { ", style=dashed" } else { "" };
+                    writeln!(
+                        stream,
+                        "        \"{}:{}\" -> \"{}:{}\" [label=\"{}\"{}];",
+                        index,
+                        relation,
+                        index,
+                        rule.output,
+                        rule.label.as_deref().unwrap_or(""),
+                        style
+                    )?;
+                }
+            }
+            writeln!(stream, "    }}")?;
+        }
+        writeln!(stream, "}}")
+    }
+}
+
+/// The labels of the `rules`, or the relation they derive when they have none, with the negated
+/// uses marked with `!`.
+fn rule_names<'a>(rules: impl Iterator<Item = (&'a Rule, bool)>) -> String {
+    let mut names: Vec<String> = Vec::new();
+    for (rule, negated) in rules {
+        let name = format!(
+            "{}{}",
+            if negated { "!" } else { "" },
+            rule.label.as_deref().unwrap_or(&rule.output)
+        );
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names.join(", ")
+}
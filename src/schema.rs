@@ -0,0 +1,142 @@
+//! The schemes used to name the fact files, and order their columns, over time: rustc and
+//! polonius renamed most relations, e.g. `borrow_region` to `loan_issued_at`, and existing dumps
+//! can use either scheme. The files of each version are recognized here, to be converted to the
+//! canonical in-memory relations of `AllFacts` when loading them.
+//!
+//! The schemas of these canonical relations, their names and the kinds of their atoms, are
+//! listed in `RELATIONS`, to be looked up by name at runtime.
+
+use crate::tab_delim::RELATIONS;
+
+/// The kinds of atoms of the relations' columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum AtomKind {
+    Origin,
+    Loan,
+    Point,
+    Variable,
+    Path,
+
+    /// Not an interned atom, but the kind of an origin in the `origin_kind` output:
+    /// `placeholder` or `existential`.
+    OriginKind,
+}
+
+/// The schema of a relation of `AllFacts`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RelationSchema {
+    pub(crate) name: &'static str,
+
+    /// The kinds of the relation's atoms, in the order of its arguments.
+    pub(crate) atoms: &'static [AtomKind],
+
+    /// Whether the relation's fact file must be present in a directory of facts.
+    pub(crate) required: bool,
+}
+
+impl RelationSchema {
+    pub(crate) fn arity(&self) -> usize {
+        self.atoms.len()
+    }
+}
+
+/// The schema of the relation of `AllFacts` named `name`, if any.
+pub(crate) fn relation_schema(name: &str) -> Option<&'static RelationSchema> {
+    RELATIONS.iter().find(|relation| relation.name == name)
+}
+
+/// A version of the scheme used to name the fact files, and order their columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SchemaVersion {
+    /// The initial names, like `borrow_region(origin, loan, point)` or
+    /// `invalidates(point, loan)`.
+    Legacy,
+
+    /// The current names, like `loan_issued_at(origin, loan, point)`, used by rustc. It still
+    /// stores `loan_invalidated_at(point, loan)` in the opposite order of the canonical
+    /// `loan_invalidated_at(loan, point)` relation.
+    Current,
+}
+
+/// How a relation is stored in the fact files of a schema version.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RelationFile {
+    /// The name of the relation in `AllFacts`.
+    pub(crate) relation: &'static str,
+
+    /// The name of the file, without its `.facts` extension.
+    pub(crate) name: &'static str,
+
+    /// For each argument of the relation, the column of the file it is stored in. `None` when
+    /// the columns are in the same order as the arguments.
+    pub(crate) columns: Option<&'static [usize]>,
+}
+
+const fn renamed(relation: &'static str, name: &'static str) -> RelationFile {
+    RelationFile {
+        relation,
+        name,
+        columns: None,
+    }
+}
+
+/// The relations stored differently in the `Legacy` version than in the canonical form.
+const LEGACY_FILES: &[RelationFile] = &[
+    renamed("loan_issued_at", "borrow_region"),
+    renamed("loan_killed_at", "killed"),
+    renamed("subset_base", "outlives"),
+    RelationFile {
+        relation: "loan_invalidated_at",
+        name: "invalidates",
+        columns: Some(&[1, 0]),
+    },
+    renamed("var_defined_at", "var_defined"),
+    renamed("var_used_at", "var_used"),
+    renamed("var_dropped_at", "var_drop_used"),
+    renamed("use_of_var_derefs_origin", "var_uses_region"),
+    renamed("drop_of_var_derefs_origin", "var_drops_region"),
+    renamed("child_path", "child"),
+    renamed("path_is_var", "path_belongs_to_var"),
+    renamed("path_assigned_at_base", "initialized_at"),
+    renamed("path_moved_at_base", "moved_out_at"),
+    renamed("path_accessed_at_base", "path_accessed_at"),
+    renamed("known_placeholder_subset", "known_subset"),
+];
+
+/// The relations stored differently in the `Current` version than in the canonical form.
+const CURRENT_FILES: &[RelationFile] = &[RelationFile {
+    relation: "loan_invalidated_at",
+    name: "loan_invalidated_at",
+    columns: Some(&[1, 0]),
+}];
+
+impl SchemaVersion {
+    /// The relations stored differently in this version than in the canonical form.
+    fn files(self) -> &'static [RelationFile] {
+        match self {
+            SchemaVersion::Legacy => LEGACY_FILES,
+            SchemaVersion::Current => CURRENT_FILES,
+        }
+    }
+
+    /// How the `relation` is stored in this version.
+    pub(crate) fn relation_file(self, relation: &'static str) -> RelationFile {
+        self.files()
+            .iter()
+            .find(|file| file.relation == relation)
+            .cloned()
+            .unwrap_or_else(|| renamed(relation, relation))
+    }
+
+    /// Recognizes the file of one of the `relations` named `name` in this version.
+    pub(crate) fn recognize(
+        self,
+        name: &str,
+        relations: &[RelationSchema],
+    ) -> Option<RelationFile> {
+        relations
+            .iter()
+            .map(|relation| self.relation_file(relation.name))
+            .find(|file| file.name == name)
+    }
+}
@@ -0,0 +1,346 @@
+//! Synthetic facts for benchmarking: pseudo-random facts, deterministic for a given seed, on CFGs
+//! of tunable shapes and sizes, so that the performance of the engines can be measured on
+//! reproducible workloads of any size, beyond the few functions of the `inputs`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::schema::{AtomKind, RelationFile, RelationSchema, SchemaVersion};
+use crate::tab_delim::{write_tab_delimited_row, RELATIONS};
+
+/// A small xorshift pseudo-random number generator: the generated facts only need to be
+/// deterministic for a given seed, so that they can be reproduced.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // xorshift can't leave the all-zeroes state
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number in `0..bound`.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Returns `true` with a probability of `1 / n`.
+    pub(crate) fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    /// Returns `true` with a probability of `percent / 100`.
+    fn percent(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// The shape of the CFG of the synthetic facts, whose blocks are straight lines of statements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum CfgShape {
+    /// A line of blocks.
+    #[default]
+    Line,
+
+    /// A chain of diamonds: a block branches to two blocks, which both jump to the next one.
+    Diamonds,
+
+    /// Nested loops: the last blocks loop back to the first ones, the innermost loop being in
+    /// the middle of the CFG.
+    Loops,
+}
+
+impl FromStr for CfgShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "line" => Ok(CfgShape::Line),
+            "diamonds" => Ok(CfgShape::Diamonds),
+            "loops" => Ok(CfgShape::Loops),
+            _ => Err(format!(
+                "unknown CFG shape `{}`, expected one of: line, diamonds, loops",
+                s
+            )),
+        }
+    }
+}
+
+/// The sizes and densities of the synthetic facts.
+#[derive(Clone, Debug)]
+pub(crate) struct Parameters {
+    /// The number of points of the CFG, rounded up to an even number: each statement has a start
+    /// and a mid point.
+    pub(crate) points: usize,
+
+    /// The number of origins, two of which are placeholders when there are enough of them.
+    pub(crate) origins: usize,
+
+    /// The number of loans, each issued once, in addition to the loans of the placeholders.
+    pub(crate) loans: usize,
+
+    pub(crate) variables: usize,
+
+    /// The percentage of the statements creating a subset between two origins, invalidating a
+    /// loan, using a variable, and defining one. Half as many kill a loan, and a quarter drop a
+    /// variable.
+    pub(crate) density: usize,
+
+    pub(crate) shape: CfgShape,
+
+    pub(crate) seed: u64,
+}
+
+/// The number of statements of the blocks, except the last one, which can be shorter.
+const BLOCK_LEN: usize = 4;
+
+/// The number of placeholder origins, at most.
+const PLACEHOLDERS: usize = 2;
+
+/// The tuples of the synthetic facts, by relation, with the names of their atoms in the style of
+/// rustc's fact files.
+#[derive(Default)]
+pub(crate) struct SyntheticFacts {
+    /// The rows of each relation, in the order of its arguments.
+    relations: BTreeMap<&'static str, Vec<Vec<String>>>,
+}
+
+/// Generates the facts described by the `parameters`.
+pub(crate) fn generate(parameters: &Parameters) -> SyntheticFacts {
+    let mut rng = Rng::new(parameters.seed);
+    let mut facts = SyntheticFacts::default();
+
+    let statements = parameters.points.div_ceil(2).max(1);
+    let blocks = statements.div_ceil(BLOCK_LEN);
+    let block_len = |block: usize| BLOCK_LEN.min(statements - block * BLOCK_LEN);
+    let point = |kind: &str, statement: usize| {
+        format!(
+            "\"{}(bb{}[{}])\"",
+            kind,
+            statement / BLOCK_LEN,
+            statement % BLOCK_LEN
+        )
+    };
+    let origin = |index: usize| format!("\"\\'_#{}r\"", index);
+    let loan = |index: usize| format!("\"bw{}\"", index);
+    let variable = |index: usize| format!("\"_{}\"", index);
+    let path = |index: usize| format!("\"mp{}\"", index);
+
+    // the CFG: the statements of each block, and the edges between blocks
+    for statement in 0..statements {
+        facts.push(
+            "cfg_edge",
+            vec![point("Start", statement), point("Mid", statement)],
+        );
+        if (statement + 1) % BLOCK_LEN != 0 && statement + 1 < statements {
+            facts.push(
+                "cfg_edge",
+                vec![point("Mid", statement), point("Start", statement + 1)],
+            );
+        }
+    }
+    for block in 0..blocks {
+        let successors = match parameters.shape {
+            CfgShape::Line => vec![block + 1],
+            CfgShape::Diamonds => match block % 3 {
+                0 => vec![block + 1, block + 2],
+                1 => vec![block + 2],
+                _ => vec![block + 1],
+            },
+            CfgShape::Loops if blocks - 1 - block < block => {
+                vec![block + 1, blocks - 1 - block]
+            }
+            CfgShape::Loops => vec![block + 1],
+        };
+        let last_statement = block * BLOCK_LEN + block_len(block) - 1;
+        for successor in successors.into_iter().filter(|&block| block < blocks) {
+            facts.push(
+                "cfg_edge",
+                vec![
+                    point("Mid", last_statement),
+                    point("Start", successor * BLOCK_LEN),
+                ],
+            );
+        }
+    }
+
+    // the placeholders, and their loans
+    let origins = parameters.origins.max(1);
+    let placeholders = PLACEHOLDERS.min(origins - 1);
+    for placeholder in 0..placeholders {
+        facts.push("universal_region", vec![origin(placeholder)]);
+        facts.push(
+            "placeholder",
+            vec![origin(placeholder), loan(parameters.loans + placeholder)],
+        );
+    }
+
+    // the loans, issued into the other origins
+    for index in 0..parameters.loans {
+        let issuing_origin = placeholders + rng.below(origins - placeholders);
+        facts.push(
+            "loan_issued_at",
+            vec![
+                origin(issuing_origin),
+                loan(index),
+                point("Mid", rng.below(statements)),
+            ],
+        );
+    }
+
+    // the variables, and the origins of their types
+    for index in 0..parameters.variables {
+        facts.push(
+            "use_of_var_derefs_origin",
+            vec![variable(index), origin(rng.below(origins))],
+        );
+        if rng.one_in(2) {
+            facts.push(
+                "drop_of_var_derefs_origin",
+                vec![variable(index), origin(rng.below(origins))],
+            );
+        }
+        facts.push("path_is_var", vec![path(index), variable(index)]);
+    }
+
+    // the effects of the statements
+    let density = parameters.density;
+    for statement in 0..statements {
+        let (start, mid) = (point("Start", statement), point("Mid", statement));
+        if rng.percent(density) {
+            facts.push(
+                "subset_base",
+                vec![
+                    origin(rng.below(origins)),
+                    origin(rng.below(origins)),
+                    mid.clone(),
+                ],
+            );
+        }
+        if parameters.loans > 0 {
+            if rng.percent(density) {
+                let invalidated = loan(rng.below(parameters.loans));
+                facts.push("loan_invalidated_at", vec![invalidated, start.clone()]);
+            }
+            if rng.percent(density / 2) {
+                let killed = loan(rng.below(parameters.loans));
+                facts.push("loan_killed_at", vec![killed, mid.clone()]);
+            }
+        }
+        if parameters.variables > 0 {
+            if rng.percent(density) {
+                let used = rng.below(parameters.variables);
+                facts.push("var_used_at", vec![variable(used), mid.clone()]);
+                facts.push("path_accessed_at_base", vec![path(used), mid.clone()]);
+            }
+            if rng.percent(density) {
+                let defined = rng.below(parameters.variables);
+                facts.push("var_defined_at", vec![variable(defined), mid.clone()]);
+                facts.push("path_assigned_at_base", vec![path(defined), mid.clone()]);
+            }
+            if rng.percent(density / 4) {
+                let dropped = rng.below(parameters.variables);
+                facts.push("var_dropped_at", vec![variable(dropped), mid]);
+            }
+        }
+    }
+
+    facts
+}
+
+impl SyntheticFacts {
+    fn push(&mut self, relation: &'static str, row: Vec<String>) {
+        self.relations.entry(relation).or_default().push(row);
+    }
+
+    /// The number of tuples of all the relations.
+    pub(crate) fn len(&self) -> usize {
+        self.relations.values().map(Vec::len).sum()
+    }
+
+    /// The names of the points of all the tuples, in the order they're interned when loading the
+    /// facts, with their repetitions.
+    pub(crate) fn point_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for relation in RELATIONS {
+            let rows = self
+                .relations
+                .get(relation.name)
+                .map_or(&[][..], Vec::as_slice);
+            for row in rows {
+                for (value, &kind) in row.iter().zip(relation.atoms) {
+                    if kind == AtomKind::Point {
+                        names.push(value.as_str());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Writes a fact file per relation in the `dir`, named after the relations, like rustc's. The
+    /// optional relations are only written when they have tuples.
+    pub(crate) fn write_directory(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for relation in RELATIONS {
+            let (file, rows) = self.file_rows(relation);
+            if !relation.required && rows.is_empty() {
+                continue;
+            }
+
+            let mut stream =
+                io::BufWriter::new(fs::File::create(dir.join(format!("{}.facts", file.name)))?);
+            for row in rows {
+                write_tab_delimited_row(&mut stream, &row)?;
+            }
+            stream.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the facts as a bundle: the contents of the fact files which have tuples, each
+    /// preceded by a `# <file name>` header line.
+    pub(crate) fn write_bundle(&self, stream: &mut dyn Write) -> io::Result<()> {
+        for relation in RELATIONS {
+            let (file, rows) = self.file_rows(relation);
+            if rows.is_empty() {
+                continue;
+            }
+
+            writeln!(stream, "# {}", file.name)?;
+            for row in rows {
+                write_tab_delimited_row(stream, &row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The file of the `relation`, and its rows, with their values in the columns of the file.
+    fn file_rows(&self, relation: &RelationSchema) -> (RelationFile, Vec<Vec<&str>>) {
+        let file = SchemaVersion::Current.relation_file(relation.name);
+        let rows = self
+            .relations
+            .get(relation.name)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|row| {
+                let mut values = vec![""; row.len()];
+                for (argument, value) in row.iter().enumerate() {
+                    let column = file.columns.map_or(argument, |columns| columns[argument]);
+                    values[column] = value.as_str();
+                }
+                values
+            })
+            .collect();
+        (file, rows)
+    }
+}
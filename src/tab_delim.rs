@@ -1,105 +1,758 @@
+use crate::error::Error;
 use crate::facts::AllFacts;
 use crate::intern::{InternTo, InternerTables};
-use log::error;
-use std::fs::File;
+use crate::manifest::check_manifest;
+use crate::schema::{relation_schema, AtomKind, RelationFile, RelationSchema, SchemaVersion};
+use log::{info, warn};
+use polonius_engine::OriginKind;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
 use std::io::{self, prelude::*};
-use std::path::Path;
-use std::process;
+use std::path::{Path, PathBuf};
+use std::vec;
 
-trait FromTabDelimited<'input>: Sized {
-    fn parse(
+pub(crate) trait FromTabDelimited<'input>: Sized {
+    /// The number of values of the rows.
+    const ARITY: usize;
+
+    fn parse(tables: &mut InternerTables, inputs: &mut Columns<'input, '_>) -> Result<Self, Error>;
+}
+
+/// The tab-separated columns of a line of a facts file, keeping track of where they are in the
+/// file to report errors.
+pub(crate) struct Columns<'input, 'path> {
+    /// The values of the line, in the order of the relation's arguments, with the column where
+    /// they start, counted in bytes from 1.
+    values: vec::IntoIter<(usize, Cow<'input, str>)>,
+    path: &'path Path,
+    line: usize,
+
+    /// The column at the end of the line, where missing values are reported.
+    end: usize,
+
+    /// The number of values expected on the line, to report missing or extra values.
+    arity: usize,
+}
+
+impl<'input, 'path> Columns<'input, 'path> {
+    /// Splits the `line`, and reorders its values according to the file's `columns`.
+    fn new(
+        line: &'input str,
+        columns: Option<&[usize]>,
+        arity: usize,
+        path: &'path Path,
+        line_index: usize,
+    ) -> Result<Self, Error> {
+        let (mut values, end) = split_values(line).map_err(|(column, message)| Error::Parse {
+            file: Some(path.to_owned()),
+            line: line_index + 1,
+            column,
+            message,
+        })?;
+
+        if let Some(columns) = columns {
+            // missing values are reported after the reordering
+            if values.len() >= columns.len() {
+                let mut reordered: Vec<_> = columns
+                    .iter()
+                    .map(|&column| values[column].clone())
+                    .collect();
+                reordered.extend_from_slice(&values[columns.len()..]);
+                values = reordered;
+            }
+        }
+
+        Ok(Columns {
+            values: values.into_iter(),
+            path,
+            line: line_index + 1,
+            end,
+            arity,
+        })
+    }
+
+    fn next(&mut self) -> Result<Cow<'input, str>, Error> {
+        self.next_with_column().map(|(_, value)| value)
+    }
+
+    /// The next value, with the column where it starts.
+    fn next_with_column(&mut self) -> Result<(usize, Cow<'input, str>), Error> {
+        match self.values.next() {
+            Some(value) => Ok(value),
+            None => Err(self.error(self.end, "missing value")),
+        }
+    }
+
+    /// Checks that all the values have been parsed.
+    fn finish(mut self) -> Result<(), Error> {
+        match self.values.next() {
+            Some((column, _)) => Err(self.error(column, "extra data")),
+            None => Ok(()),
+        }
+    }
+
+    fn error(&self, column: usize, message: &str) -> Error {
+        Error::Parse {
+            file: Some(self.path.to_owned()),
+            line: self.line,
+            column,
+            message: format!("{}, expected {} values", message, self.arity),
+        }
+    }
+}
+
+/// The values of a line, with the column where they start.
+type Values<'input> = Vec<(usize, Cow<'input, str>)>;
+
+/// The values of a tab-separated `line`, with the column where they start, counted in bytes from
+/// 1, and the column at the end of the line. A value can be quoted, to contain tabs: it then ends
+/// at the next unescaped `"`, and can contain the escape sequences of Rust's strings, like `\"`
+/// or `\t`. The quoted values keep their quotes, which are part of the atoms' names, but their
+/// escape sequences are decoded. The errors are the column where the line is malformed, and why.
+fn split_values(line: &str) -> Result<(Values<'_>, usize), (usize, String)> {
+    let bytes = line.as_bytes();
+    let mut values = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = start;
+        let mut unescaped: Option<String> = None;
+        if bytes.get(start) == Some(&b'"') {
+            // the end of the part of the value already copied to `unescaped`, if any
+            let mut copied = start;
+            end += 1;
+            loop {
+                match bytes.get(end) {
+                    Some(b'"') => break,
+                    Some(b'\\') => match escaped_char(&bytes[end + 1..]) {
+                        Some((c, len)) => {
+                            let unescaped = unescaped.get_or_insert_with(String::new);
+                            unescaped.push_str(&line[copied..end]);
+                            unescaped.push(c);
+                            end += 1 + len;
+                            copied = end;
+                        }
+                        None => {
+                            let sequence = line[end..].chars().take(2).collect::<String>();
+                            return Err((
+                                end + 1,
+                                format!("invalid escape sequence `{}`", sequence),
+                            ));
+                        }
+                    },
+                    Some(_) => end += 1,
+                    None => return Err((start + 1, "unterminated quoted value".to_string())),
+                }
+            }
+            end += 1;
+            if let Some(ref mut unescaped) = unescaped {
+                unescaped.push_str(&line[copied..end]);
+            }
+
+            if end < bytes.len() && bytes[end] != b'\t' {
+                return Err((end + 1, "expected a tab after the quoted value".to_string()));
+            }
+        } else {
+            while end < bytes.len() && bytes[end] != b'\t' {
+                if bytes[end] == b'"' {
+                    return Err((end + 1, "unexpected quote in an unquoted value".to_string()));
+                }
+                end += 1;
+            }
+        }
+
+        let value = match unescaped {
+            Some(unescaped) => Cow::Owned(unescaped),
+            None => Cow::Borrowed(&line[start..end]),
+        };
+        values.push((start + 1, value));
+        if end == bytes.len() {
+            return Ok((values, end + 2));
+        }
+        start = end + 1;
+    }
+}
+
+/// The character of the escape sequence starting at the `bytes` after a backslash, and the
+/// sequence's length, if it's one of the escape sequences of Rust's strings, which are used by
+/// rustc to dump the facts.
+fn escaped_char(bytes: &[u8]) -> Option<(char, usize)> {
+    let c = match bytes.first()? {
+        b'\\' => '\\',
+        b'"' => '"',
+        b'\'' => '\'',
+        b't' => '\t',
+        b'n' => '\n',
+        b'r' => '\r',
+        b'0' => '\0',
+        b'u' if bytes.get(1) == Some(&b'{') => {
+            let digits = bytes[2..]
+                .iter()
+                .take_while(|byte| byte.is_ascii_hexdigit())
+                .count();
+            if !(1..=6).contains(&digits) || bytes.get(2 + digits) != Some(&b'}') {
+                return None;
+            }
+            let code = std::str::from_utf8(&bytes[2..2 + digits]).ok()?;
+            let c = char::from_u32(u32::from_str_radix(code, 16).ok()?)?;
+            return Some((c, 3 + digits));
+        }
+        _ => return None,
+    };
+    Some((c, 1))
+}
+
+/// Quotes the `value` if it can't be read back as is from a facts file. A value between quotes,
+/// like the atoms' names dumped by rustc, is read back as is once its tabs, quotes, backslashes
+/// and line breaks are escaped. Any other value containing tabs, quotes or line breaks is put
+/// between quotes, and is read back with them.
+pub(crate) fn quote_value(value: &str) -> Cow<'_, str> {
+    let needs_quotes = |c: char| matches!(c, '\t' | '"' | '\n' | '\r');
+    match value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Some(inner) if inner.contains(|c| c == '\\' || needs_quotes(c)) => {
+            Cow::Owned(escape_quoted(inner))
+        }
+        Some(_) => Cow::Borrowed(value),
+        None if value.contains(needs_quotes) => Cow::Owned(escape_quoted(value)),
+        None => Cow::Borrowed(value),
+    }
+}
+
+/// The `value` between quotes, with the characters which can't appear in a quoted value escaped.
+fn escape_quoted(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a row of a facts file: its `values`, quoted if needed, separated by tabs.
+pub(crate) fn write_tab_delimited_row(stream: &mut dyn Write, values: &[&str]) -> io::Result<()> {
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            stream.write_all(b"\t")?;
+        }
+        stream.write_all(quote_value(value).as_bytes())?;
+    }
+    stream.write_all(b"\n")
+}
+
+macro_rules! relations {
+    (load AllFacts {
+        $($t:ident($($t_atom:ident),+),)*
+    } optional {
+        $($optional:ident($($optional_atom:ident),+),)*
+    }) => {
+        /// The schemas of the relations of `AllFacts`.
+        pub(crate) const RELATIONS: &[RelationSchema] = &[
+            $(RelationSchema {
+                name: stringify!($t),
+                atoms: &[$(AtomKind::$t_atom),+],
+                required: true,
+            },)*
+            $(RelationSchema {
+                name: stringify!($optional),
+                atoms: &[$(AtomKind::$optional_atom),+],
+                required: false,
+            },)*
+        ];
+
+        fn load_facts(tables: &mut InternerTables, source: &Source<'_>) -> Result<AllFacts, Error> {
+            let all_facts = AllFacts {
+                $($t: source.load(tables, relation_schema(stringify!($t)).unwrap())?,)*
+                $($optional: source.load(tables, relation_schema(stringify!($optional)).unwrap())?,)*
+            };
+            check_origin_liveness(tables, &all_facts)?;
+            Ok(all_facts)
+        }
+
+        /// Adds the `added_facts` to the `all_facts`.
+        pub(crate) fn merge_facts(all_facts: &mut AllFacts, added_facts: AllFacts) {
+            $(all_facts.$t.extend(added_facts.$t);)*
+            $(all_facts.$optional.extend(added_facts.$optional);)*
+        }
+    };
+}
+
+relations! {
+    load AllFacts {
+        loan_issued_at(Origin, Loan, Point),
+        universal_region(Origin),
+        cfg_edge(Point, Point),
+        loan_killed_at(Loan, Point),
+        subset_base(Origin, Origin, Point),
+        loan_invalidated_at(Loan, Point),
+        var_defined_at(Variable, Point),
+        var_used_at(Variable, Point),
+        var_dropped_at(Variable, Point),
+        use_of_var_derefs_origin(Variable, Origin),
+        drop_of_var_derefs_origin(Variable, Origin),
+        child_path(Path, Path),
+        path_is_var(Path, Variable),
+        path_assigned_at_base(Path, Point),
+        path_moved_at_base(Path, Point),
+        path_accessed_at_base(Path, Point),
+        known_placeholder_subset(Origin, Origin),
+        placeholder(Origin, Loan),
+    } optional {
+        universal_region_live_at(Origin, Point),
+        loan_activated_at(Loan, Point),
+        loan_shallow_killed_at(Loan, Point),
+        origin_live_on_entry(Origin, Point),
+    }
+}
+
+/// Checks that the precomputed `origin_live_on_entry` facts, if any, include the origins of the
+/// variables used at each point: otherwise, they were likely computed for other facts.
+fn check_origin_liveness(tables: &InternerTables, all_facts: &AllFacts) -> Result<(), Error> {
+    let uncovered = all_facts.uncovered_liveness_points();
+    match uncovered.first() {
+        None => Ok(()),
+        Some(&point) => Err(Error::UncoveredLiveness {
+            points: uncovered.len(),
+            first: tables.points.untern(point).to_string(),
+        }),
+    }
+}
+
+/// Where the facts are loaded from.
+enum Source<'a> {
+    /// The `.facts` files of a directory, as found by `relation_files`.
+    Directory(BTreeMap<&'static str, (PathBuf, RelationFile)>),
+
+    /// The sections of a bundle, as split by `bundle_sections`.
+    Bundle {
+        path: &'a Path,
+        sections: BTreeMap<&'static str, Section>,
+    },
+}
+
+/// The contents of a fact file, in a bundle.
+struct Section {
+    file: RelationFile,
+
+    /// The lines of the file, with their index in the bundle to report errors.
+    lines: Vec<(usize, String)>,
+}
+
+impl Source<'_> {
+    /// Loads the facts of the `relation`. A missing file is an error when the relation is
+    /// required, while a missing section of a bundle is always empty.
+    fn load<Row>(
+        &self,
         tables: &mut InternerTables,
-        inputs: &mut dyn Iterator<Item = &'input str>,
-    ) -> Option<Self>;
+        relation: &RelationSchema,
+    ) -> Result<Vec<Row>, Error>
+    where
+        Row: for<'input> FromTabDelimited<'input>,
+    {
+        match self {
+            Source::Directory(files) => {
+                let (facts_file, file) = &files[relation.name];
+                if relation.required || facts_file.exists() {
+                    load_tab_delimited_file(tables, facts_file, file.columns, relation.arity())
+                } else {
+                    Ok(Vec::default())
+                }
+            }
+            Source::Bundle { path, sections } => match sections.get(relation.name) {
+                Some(section) => load_tab_delimited_lines(
+                    tables,
+                    path,
+                    section.file.columns,
+                    relation.arity(),
+                    section
+                        .lines
+                        .iter()
+                        .map(|(index, line)| Ok((*index, line.as_str()))),
+                ),
+                None => Ok(Vec::default()),
+            },
+        }
+    }
 }
 
 pub(crate) fn load_tab_delimited_facts(
     tables: &mut InternerTables,
     facts_dir: &Path,
-) -> io::Result<AllFacts> {
-    macro_rules! load_facts {
-        (from ($tables:expr, $facts_dir:expr) load AllFacts { $($t:ident,)* }) => {
-            Ok(AllFacts {
-                $(
-                    $t: {
-                        let filename = format!("{}.facts", stringify!($t));
-                        let facts_file = $facts_dir.join(&filename);
-                        load_tab_delimited_file($tables, &facts_file)?
-                    },
-                )*
-            })
+) -> Result<AllFacts, Error> {
+    check_manifest(facts_dir);
+    let files = relation_files(facts_dir, RELATIONS)?;
+    let all_facts = load_facts(tables, &Source::Directory(files))?;
+
+    info!(
+        "interned {} points, using {} bytes",
+        tables.points.len(),
+        tables.points.heap_size()
+    );
+
+    Ok(all_facts)
+}
+
+/// Loads the facts of a bundle: the contents of the fact files concatenated into a single stream,
+/// each preceded by a `# <file name>` header line, e.g. `# cfg_edge`. The files can be named
+/// after the relations' current or legacy names, and the missing files are empty. The `path` is
+/// where the bundle is read from, to report errors.
+pub(crate) fn load_facts_bundle(
+    tables: &mut InternerTables,
+    path: &Path,
+    reader: impl BufRead,
+) -> Result<AllFacts, Error> {
+    let sections = bundle_sections(path, reader)?;
+    load_facts(tables, &Source::Bundle { path, sections })
+}
+
+/// Loads the facts of the `input`: a directory of fact files, a bundle file, or a bundle read
+/// from stdin when the input is `-`.
+pub(crate) fn load_facts_from_input(
+    tables: &mut InternerTables,
+    input: &Path,
+) -> Result<AllFacts, Error> {
+    if input == Path::new("-") {
+        let stdin = io::stdin();
+        let reader = stdin.lock();
+        load_facts_bundle(tables, Path::new("<stdin>"), reader)
+    } else if input.is_file() {
+        let file = File::open(input).map_err(|source| Error::Io {
+            path: input.to_owned(),
+            source,
+        })?;
+        load_facts_bundle(tables, input, io::BufReader::new(file))
+    } else {
+        load_tab_delimited_facts(tables, input)
+    }
+}
+
+/// Loads the facts added to each input by `--add-facts`: a fact file named after its relation,
+/// like `cfg_edge.facts`, or a bundle of fact files.
+pub(crate) fn load_added_facts(
+    tables: &mut InternerTables,
+    path: &Path,
+) -> Result<AllFacts, Error> {
+    let relation_file = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("facts") => path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(recognize_relation_file),
+        _ => None,
+    };
+    let file = match relation_file {
+        Some(file) => file,
+        None => return load_facts_from_input(tables, path),
+    };
+
+    let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    let lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index, line.to_string()))
+        .collect();
+    load_section(tables, path, file, lines)
+}
+
+/// Loads the facts added to each input by `--add-fact`: the name of a relation, followed by the
+/// values of a tuple separated by spaces, e.g. `cfg_edge "Mid(bb0[1])" "Start(bb3[0])"`. The
+/// values are in the order and quoted like in the relation's fact file, and the quoted values can
+/// contain spaces.
+pub(crate) fn load_inline_fact(
+    tables: &mut InternerTables,
+    tuple: &str,
+) -> Result<AllFacts, Error> {
+    let path = Path::new("--add-fact");
+    let tuple = tuple.trim();
+    let (name, values) = tuple.split_once(char::is_whitespace).unwrap_or((tuple, ""));
+    let file = recognize_relation_file(name).ok_or_else(|| Error::Parse {
+        file: Some(path.to_owned()),
+        line: 1,
+        column: 1,
+        message: format!("unknown relation `{}`", name),
+    })?;
+
+    load_section(
+        tables,
+        path,
+        file,
+        vec![(0, tab_separated(values.trim_start()))],
+    )
+}
+
+/// Replaces the spaces separating the `values` with tabs, outside of the quoted values.
+fn tab_separated(values: &str) -> String {
+    let mut line = String::with_capacity(values.len());
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut separated = false;
+    for c in values.chars() {
+        if !quoted && c.is_whitespace() {
+            separated = true;
+            continue;
+        }
+        if separated {
+            line.push('\t');
+            separated = false;
+        }
+
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        }
+        line.push(c);
+    }
+    line
+}
+
+/// Loads the facts of a single relation, from the `lines` of its `file`.
+fn load_section(
+    tables: &mut InternerTables,
+    path: &Path,
+    file: RelationFile,
+    lines: Vec<(usize, String)>,
+) -> Result<AllFacts, Error> {
+    let mut sections = BTreeMap::new();
+    sections.insert(file.relation, Section { file, lines });
+    load_facts(tables, &Source::Bundle { path, sections })
+}
+
+/// The file of a relation named `name`, in the `Current` schema version, or else in the `Legacy`
+/// one.
+fn recognize_relation_file(name: &str) -> Option<RelationFile> {
+    SchemaVersion::Current
+        .recognize(name, RELATIONS)
+        .or_else(|| SchemaVersion::Legacy.recognize(name, RELATIONS))
+}
+
+/// Splits a bundle into the sections of each relation, recognizing the file names of the
+/// `Current` schema version first, and then the `Legacy` ones. Blank lines are ignored.
+fn bundle_sections(
+    path: &Path,
+    reader: impl BufRead,
+) -> Result<BTreeMap<&'static str, Section>, Error> {
+    let error = |line: usize, column: usize, message: String| Error::Parse {
+        file: Some(path.to_owned()),
+        line: line + 1,
+        column,
+        message,
+    };
+
+    let mut sections: BTreeMap<&'static str, Section> = BTreeMap::new();
+    let mut current = None;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("# ") {
+            let name = name.trim();
+            let file = recognize_relation_file(name)
+                .ok_or_else(|| error(index, 3, format!("unknown relation `{}`", name)))?;
+            if sections.contains_key(file.relation) {
+                return Err(error(
+                    index,
+                    3,
+                    format!("the `{}` relation is already present", file.relation),
+                ));
+            }
+
+            sections.insert(
+                file.relation,
+                Section {
+                    file,
+                    lines: Vec::new(),
+                },
+            );
+            current = Some(file.relation);
+            continue;
+        }
+
+        match current {
+            Some(relation) => sections
+                .get_mut(relation)
+                .unwrap()
+                .lines
+                .push((index, line)),
+            None => {
+                return Err(error(
+                    index,
+                    1,
+                    "expected a `# <file name>` header".to_string(),
+                ))
+            }
         }
     }
 
-    load_facts! {
-        from (tables, facts_dir) load AllFacts {
-            loan_issued_at,
-            universal_region,
-            cfg_edge,
-            loan_killed_at,
-            subset_base,
-            loan_invalidated_at,
-            var_defined_at,
-            var_used_at,
-            var_dropped_at,
-            use_of_var_derefs_origin,
-            drop_of_var_derefs_origin,
-            child_path,
-            path_is_var,
-            path_assigned_at_base,
-            path_moved_at_base,
-            path_accessed_at_base,
-            known_placeholder_subset,
-            placeholder,
+    Ok(sections)
+}
+
+/// Finds the file to load each of the `relations` from, in `facts_dir`: the file of the `Current`
+/// schema version, or else the file of the `Legacy` version. A warning lists the `.facts` files
+/// which don't correspond to any relation, as their facts would otherwise be silently ignored.
+fn relation_files(
+    facts_dir: &Path,
+    relations: &[RelationSchema],
+) -> Result<BTreeMap<&'static str, (PathBuf, RelationFile)>, Error> {
+    let io_error = |source| Error::Io {
+        path: facts_dir.to_owned(),
+        source,
+    };
+
+    let path = |file: &RelationFile| facts_dir.join(format!("{}.facts", file.name));
+    let mut files: BTreeMap<_, _> = relations
+        .iter()
+        .map(|relation| {
+            let file = SchemaVersion::Current.relation_file(relation.name);
+            (relation.name, (path(&file), file))
+        })
+        .collect();
+
+    let mut unrecognized = Vec::new();
+    for entry in fs::read_dir(facts_dir).map_err(io_error)? {
+        let file = entry.map_err(io_error)?.path();
+        if file.extension().and_then(|extension| extension.to_str()) != Some("facts") {
+            continue;
+        }
+
+        let name = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+        if SchemaVersion::Current.recognize(name, relations).is_some() {
+            continue;
+        }
+
+        match SchemaVersion::Legacy.recognize(name, relations) {
+            Some(legacy_file) if files[legacy_file.relation].0.exists() => {
+                warn!(
+                    "ignoring `{}`, the legacy name of the `{}` relation, which is also present",
+                    file.display(),
+                    legacy_file.relation
+                );
+            }
+            Some(legacy_file) => {
+                warn!(
+                    "loading `{}` as the `{}` relation, its new name",
+                    file.display(),
+                    legacy_file.relation
+                );
+                files.insert(legacy_file.relation, (file, legacy_file));
+            }
+            None => unrecognized.push(file),
         }
     }
+
+    if !unrecognized.is_empty() {
+        let unrecognized: Vec<_> = unrecognized
+            .iter()
+            .map(|file| format!("`{}`", file.display()))
+            .collect();
+        warn!("unrecognized fact files: {}", unrecognized.join(", "));
+    }
+
+    Ok(files)
 }
 
-fn load_tab_delimited_file<Row>(tables: &mut InternerTables, path: &Path) -> io::Result<Vec<Row>>
+/// Loads the rows of the file at `path`, whose `arity` values are stored in the given `columns`
+/// order.
+pub(crate) fn load_tab_delimited_file<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+    columns: Option<&[usize]>,
+    arity: usize,
+) -> Result<Vec<Row>, Error>
 where
     Row: for<'input> FromTabDelimited<'input>,
 {
-    match File::open(path) {
-        Ok(file) => io::BufReader::new(file)
-            .lines()
-            .enumerate()
-            .map(|(index, line)| {
-                let line = line?;
-                let mut columns = line.split('\t');
-                let row = match FromTabDelimited::parse(tables, &mut columns) {
-                    None => {
-                        error!("error parsing line {} of `{}`", index + 1, path.display());
-                        process::exit(1);
-                    }
-
-                    Some(v) => v,
-                };
-
-                if columns.next().is_some() {
-                    error!("extra data on line {} of `{}`", index + 1, path.display());
-                    process::exit(1);
-                }
+    let io_error = |source| Error::Io {
+        path: path.to_owned(),
+        source,
+    };
 
-                Ok(row)
-            })
-            .collect(),
+    let file = File::open(path).map_err(io_error)?;
+    let lines = io::BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(index, line)| Ok((index, line.map_err(io_error)?)));
+    load_tab_delimited_lines(tables, path, columns, arity, lines)
+}
 
-        Err(e) => {
-            panic!("Error opening file '{}': {}", path.display(), e);
-        }
-    }
+/// Loads the rows of the `lines` read from `path`, each with its index in the input, and whose
+/// `arity` values are stored in the given `columns` order.
+fn load_tab_delimited_lines<Row, Line>(
+    tables: &mut InternerTables,
+    path: &Path,
+    columns: Option<&[usize]>,
+    arity: usize,
+    lines: impl Iterator<Item = Result<(usize, Line), Error>>,
+) -> Result<Vec<Row>, Error>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+    Line: AsRef<str>,
+{
+    lines
+        .map(|line| {
+            let (index, line) = line?;
+            let mut values = Columns::new(line.as_ref(), columns, arity, path, index)?;
+            let row = FromTabDelimited::parse(tables, &mut values)?;
+            values.finish()?;
+            Ok(row)
+        })
+        .collect()
 }
 
 impl<'input, T> FromTabDelimited<'input> for T
 where
-    &'input str: InternTo<T>,
+    for<'a> &'a str: InternTo<T>,
 {
-    fn parse(
-        tables: &mut InternerTables,
-        inputs: &mut dyn Iterator<Item = &'input str>,
-    ) -> Option<Self> {
+    const ARITY: usize = 1;
+
+    fn parse(tables: &mut InternerTables, inputs: &mut Columns<'input, '_>) -> Result<Self, Error> {
         let input = inputs.next()?;
-        Some(InternTo::intern(tables, input))
+        InternTo::intern(tables, &*input)
+    }
+}
+
+/// The kinds of origins are only read from the outputs stored in the cache, as dumped.
+impl<'input> FromTabDelimited<'input> for OriginKind {
+    const ARITY: usize = 1;
+
+    fn parse(
+        _tables: &mut InternerTables,
+        inputs: &mut Columns<'input, '_>,
+    ) -> Result<Self, Error> {
+        let (column, input) = inputs.next_with_column()?;
+        match &*input {
+            "placeholder" => Ok(OriginKind::Placeholder),
+            "existential" => Ok(OriginKind::Existential),
+            _ => Err(Error::Parse {
+                file: Some(inputs.path.to_owned()),
+                line: inputs.line,
+                column,
+                message: format!("unknown origin kind `{}`", input),
+            }),
+        }
     }
 }
 
@@ -108,13 +761,12 @@ where
     A: FromTabDelimited<'input>,
     B: FromTabDelimited<'input>,
 {
-    fn parse(
-        tables: &mut InternerTables,
-        inputs: &mut dyn Iterator<Item = &'input str>,
-    ) -> Option<Self> {
+    const ARITY: usize = A::ARITY + B::ARITY;
+
+    fn parse(tables: &mut InternerTables, inputs: &mut Columns<'input, '_>) -> Result<Self, Error> {
         let a = A::parse(tables, inputs)?;
         let b = B::parse(tables, inputs)?;
-        Some((a, b))
+        Ok((a, b))
     }
 }
 
@@ -124,14 +776,13 @@ where
     B: FromTabDelimited<'input>,
     C: FromTabDelimited<'input>,
 {
-    fn parse(
-        tables: &mut InternerTables,
-        inputs: &mut dyn Iterator<Item = &'input str>,
-    ) -> Option<Self> {
+    const ARITY: usize = A::ARITY + B::ARITY + C::ARITY;
+
+    fn parse(tables: &mut InternerTables, inputs: &mut Columns<'input, '_>) -> Result<Self, Error> {
         let a = A::parse(tables, inputs)?;
         let b = B::parse(tables, inputs)?;
         let c = C::parse(tables, inputs)?;
-        Some((a, b, c))
+        Ok((a, b, c))
     }
 }
 
@@ -142,14 +793,13 @@ where
     C: FromTabDelimited<'input>,
     D: FromTabDelimited<'input>,
 {
-    fn parse(
-        tables: &mut InternerTables,
-        inputs: &mut dyn Iterator<Item = &'input str>,
-    ) -> Option<Self> {
+    const ARITY: usize = A::ARITY + B::ARITY + C::ARITY + D::ARITY;
+
+    fn parse(tables: &mut InternerTables, inputs: &mut Columns<'input, '_>) -> Result<Self, Error> {
         let a = A::parse(tables, inputs)?;
         let b = B::parse(tables, inputs)?;
         let c = C::parse(tables, inputs)?;
         let d = D::parse(tables, inputs)?;
-        Some((a, b, c, d))
+        Ok((a, b, c, d))
     }
 }
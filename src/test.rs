@@ -1,18 +1,43 @@
 #![cfg(test)]
 
+use crate::cache::OutputCache;
+use crate::cli;
+use crate::diff;
+use crate::dump;
 use crate::dump::Output;
+use crate::error;
 use crate::facts::{AllFacts, Loan, Origin, Point};
+use crate::glob;
+use crate::golden;
 use crate::intern;
+use crate::interpret;
+use crate::manifest::{self, Manifest};
+use crate::parity;
 use crate::program::parse_from_program;
+use crate::progress::{self, Progress};
+use crate::rules;
+use crate::schema;
+use crate::synthetic;
 use crate::tab_delim;
 use crate::test_util::{
-    assert_checkers_match, assert_equal, assert_outputs_match, location_insensitive_checker_for,
-    naive_checker_for, opt_checker_for,
+    assert_checkers_match, assert_equal, assert_outputs_match, check_program,
+    location_insensitive_checker_for, naive_checker_for, opt_checker_for,
+};
+use crate::waivers::{WaiverCheck, Waivers};
+use polonius_engine::{
+    compute_origin_liveness, prune_facts, Algorithm, Atom, ComputeOptions, DatafrogJoins,
+    Diagnostic, ErrorsDiff, FactTransform, JoinBackend, KnownSubsets, Leapers, MoveErrorKind,
+    MoveErrorPolicy, OriginKind, PointOrder, ProgressEvent, ProgressHook, Pruning,
 };
-use polonius_engine::Algorithm;
 use rustc_hash::FxHashMap;
+use std::collections::BTreeSet;
 use std::error::Error;
+use std::io;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
 
 fn test_facts(all_facts: &AllFacts, algorithms: &[Algorithm]) {
     let naive = Output::compute(all_facts, Algorithm::Naive, true);
@@ -45,29 +70,18 @@ fn test_facts(all_facts: &AllFacts, algorithms: &[Algorithm]) {
         }
     }
 
-    // Check that the "naive subset errors" are a subset of the "insensitive ones".
+    // Check that the "naive subset errors" are a subset of the "insensitive ones". The
+    // location-insensitive errors are only located at a representative point.
+    let insensitive_subset_errors: BTreeSet<_> =
+        insensitive.subset_errors.values().flatten().collect();
     for (naive_point, naive_origins) in &naive.subset_errors {
-        // Potential location-insensitive errors don't have a meaningful location, and use 0
-        // as a default when debugging.
-        match insensitive.subset_errors.get(&0.into()) {
-            Some(insensitive_origins) => {
-                for &(origin1, origin2) in naive_origins {
-                    if !insensitive_origins.contains(&(origin1, origin2)) {
-                        panic!(
-                            "naive analysis had subset error for `{:?}` <: `{:?}` at `{:?}` \
-                             but insensitive analysis did not \
-                             (origins = {:#?})",
-                            origin1, origin2, naive_point, insensitive_origins,
-                        );
-                    }
-                }
-            }
-
-            None => {
+        for &(origin1, origin2) in naive_origins {
+            if !insensitive_subset_errors.contains(&(origin1, origin2)) {
                 panic!(
-                    "naive analysis had subset errors at `{:?}` but insensitive analysis did not \
+                    "naive analysis had subset error for `{:?}` <: `{:?}` at `{:?}` \
+                     but insensitive analysis did not \
                      (origins = {:#?})",
-                    naive_point, naive_origins,
+                    origin1, origin2, naive_point, insensitive_subset_errors,
                 );
             }
         }
@@ -77,8 +91,7 @@ fn test_facts(all_facts: &AllFacts, algorithms: &[Algorithm]) {
     for &optimized_algorithm in algorithms {
         println!("Algorithm {:?}", optimized_algorithm);
         let opt = Output::compute(all_facts, optimized_algorithm, true);
-        // TMP: until we reach our correctness goals, deactivate some comparisons between variants
-        // assert_equal(&naive.loan_live_at, &opt.loan_live_at);
+        assert_equal(&naive.loan_live_at, &opt.loan_live_at);
         assert_equal(&naive.errors, &opt.errors);
         assert_equal(&naive.subset_errors, &opt.subset_errors);
         assert_equal(&naive.move_errors, &opt.move_errors);
@@ -89,6 +102,193 @@ fn test_facts(all_facts: &AllFacts, algorithms: &[Algorithm]) {
     assert_equal(&naive.errors, &opt.errors);
     assert_equal(&naive.subset_errors, &opt.subset_errors);
     assert_equal(&naive.move_errors, &opt.move_errors);
+
+    // Restricting the computation to the loans with errors finds the same errors
+    let error_loans = naive.errors.values().flatten().cloned();
+    let options = ComputeOptions::default();
+    let sliced =
+        Output::compute_for_loans(all_facts, Algorithm::DatafrogOpt, &options, error_loans);
+    assert_equal(&naive.errors, &sliced.errors);
+    assert_equal(&naive.subset_errors, &sliced.subset_errors);
+
+    // Computing the loans in separate partitions finds the same errors
+    let partitioned = Output::compute_partitioned(all_facts, Algorithm::DatafrogOpt, &options, 3);
+    assert_equal(&naive.errors, &partitioned.errors);
+    assert_equal(&naive.subset_errors, &partitioned.subset_errors);
+    assert_equal(&naive.move_errors, &partitioned.move_errors);
+
+    // and keeps the options, like the dump of the data about the loans
+    let options = ComputeOptions {
+        dump_enabled: true,
+        ..ComputeOptions::default()
+    };
+    let partitioned = Output::compute_partitioned(all_facts, Algorithm::DatafrogOpt, &options, 3);
+    let opt = Output::compute(all_facts, Algorithm::DatafrogOpt, true);
+    let sorted = |loans: &FxHashMap<Point, Vec<Loan>>| -> BTreeSet<(Point, Loan)> {
+        loans
+            .iter()
+            .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+            .collect()
+    };
+    assert_eq!(sorted(&opt.loan_live_at), sorted(&partitioned.loan_live_at));
+    assert_eq!(
+        sorted(&opt.loan_invalidated_at),
+        sorted(&partitioned.loan_invalidated_at)
+    );
+    assert_eq!(
+        opt.origin_contains_loan_at.len(),
+        partitioned.origin_contains_loan_at.len()
+    );
+    for (point, origins) in opt.origin_contains_loan_at.iter() {
+        assert_eq!(
+            Some(origins),
+            partitioned.origin_contains_loan_at.get(point)
+        );
+    }
+
+    // Computing only the errors finds the same errors
+    let errors_only = Output::errors_only(all_facts, Algorithm::DatafrogOpt);
+    assert_equal(&naive.errors, &errors_only.errors);
+    assert_equal(&naive.subset_errors, &errors_only.subset_errors);
+    assert_equal(&naive.move_errors, &errors_only.move_errors);
+
+    // Pruning the facts which can't contribute to errors doesn't change the errors
+    let (pruned_facts, _) = prune_facts(all_facts);
+    let pruned = Output::compute(&pruned_facts, Algorithm::Naive, false);
+    assert_equal(&naive.errors, &pruned.errors);
+    assert_equal(&naive.subset_errors, &pruned.subset_errors);
+    assert_equal(&naive.move_errors, &pruned.move_errors);
+}
+
+/// Pruning finds the placeholders like the variants do, and keeps the subsets reaching them,
+/// including with origins which only have a `placeholder` loan.
+#[test]
+fn pruning_keeps_the_placeholders_subsets() {
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B0 {
+            outlives('c: 'x), outlives('x: 'a);
+            outlives('b: 'y), outlives('y: 'a);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let c = tables.origins.intern("'c");
+    facts.placeholder.push((c, tables.loans.intern("Lc")));
+
+    let (pruned_facts, _) = prune_facts(&facts);
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+        Algorithm::Hybrid,
+    ] {
+        let output = Output::compute(&facts, algorithm, false);
+        let pruned = Output::compute(&pruned_facts, algorithm, false);
+        assert!(!output.subset_errors.is_empty(), "{:?}", algorithm);
+        assert_equal(&output.errors, &pruned.errors);
+        assert_equal(&output.subset_errors, &pruned.subset_errors);
+    }
+}
+
+#[test]
+fn fact_transforms_modify_the_computed_facts() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0);
+            var_used_at(V0);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert!(output.errors.is_empty());
+
+    // a synthetic invalidation of the loans where the variables are used
+    let invalidate_at_uses = |all_facts: &mut AllFacts| {
+        let invalidations: Vec<_> = all_facts
+            .var_used_at
+            .iter()
+            .flat_map(|&(_, point)| {
+                all_facts
+                    .loan_issued_at
+                    .iter()
+                    .map(move |&(_, loan, _)| (loan, point))
+            })
+            .collect();
+        all_facts.loan_invalidated_at.extend(invalidations);
+    };
+    let transforms: &[&dyn FactTransform<_>] = &[&invalidate_at_uses, &Pruning];
+    let output = Output::compute_with_transforms(
+        &facts,
+        Algorithm::Naive,
+        &ComputeOptions::default(),
+        transforms,
+    );
+    let (point, loans) = output.errors.iter().next().unwrap();
+    assert_eq!(tables.points.untern(*point), "\"Mid(B0[1])\"");
+    assert_eq!(loans.len(), 1);
+
+    // the facts themselves are unchanged
+    assert!(facts.loan_invalidated_at.is_empty());
+}
+
+#[test]
+fn added_facts_are_merged_into_the_loaded_facts() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0);
+            var_used_at(V0);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let added_facts =
+        tab_delim::load_inline_fact(&mut tables, "loan_invalidated_at  \"Mid(B0[1])\" L0").unwrap();
+    tab_delim::merge_facts(&mut facts, added_facts);
+    let l0 = tables.loans.intern("L0");
+    let point = tables.points.intern("\"Mid(B0[1])\"");
+    assert_eq!(facts.loan_invalidated_at, [(l0, point)]);
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert_eq!(output.errors[&point], [l0]);
+
+    // the values are in the order of the relation's fact file, quoted values can contain spaces,
+    // and the legacy names of the relations are recognized
+    let added_facts =
+        tab_delim::load_inline_fact(&mut tables, "killed L0 \"Mid(B0 [1])\"").unwrap();
+    let killed_at = tables.points.intern("\"Mid(B0 [1])\"");
+    assert_eq!(added_facts.loan_killed_at, [(l0, killed_at)]);
+
+    let error = tab_delim::load_inline_fact(&mut tables, "cfg_edges a b").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "--add-fact:1:1: unknown relation `cfg_edges`"
+    );
+    let error = tab_delim::load_inline_fact(&mut tables, "cfg_edge a").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "--add-fact:1:3: missing value, expected 2 values"
+    );
+
+    // a fact file named after its relation
+    let path = std::env::temp_dir().join(format!("polonius-added-{}", std::process::id()));
+    std::fs::create_dir_all(&path).unwrap();
+    let facts_file = path.join("cfg_edge.facts");
+    std::fs::write(&facts_file, "\"Mid(B0[1])\"\t\"Start(B1[0])\"\n").unwrap();
+    let added_facts = tab_delim::load_added_facts(&mut tables, &facts_file);
+    std::fs::remove_dir_all(&path).unwrap();
+    let start = tables.points.intern("\"Start(B1[0])\"");
+    assert_eq!(added_facts.unwrap().cfg_edge, [(point, start)]);
 }
 
 fn test_fn(dir_name: &str, fn_name: &str, algorithm: Algorithm) -> Result<(), Box<dyn Error>> {
@@ -214,6 +414,504 @@ fn no_subset_symmetries_exist() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn parallel_naive_computes_the_naive_relations() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    // The components are evaluated separately, but their results are the same as evaluating the
+    // whole CFG at once, including the intermediate relations.
+    let naive = Output::compute(&all_facts, Algorithm::Naive, true);
+    let parallel = Output::compute(&all_facts, Algorithm::ParallelNaive, true);
+    assert_equal(&naive.errors, &parallel.errors);
+    assert_equal(&naive.subset_errors, &parallel.subset_errors);
+    assert_equal(&naive.subset, &parallel.subset);
+    assert_equal(
+        &naive.origin_contains_loan_at,
+        &parallel.origin_contains_loan_at,
+    );
+    assert_equal(&naive.loan_live_at, &parallel.loan_live_at);
+    Ok(())
+}
+
+/// The loans in scope are computed by all the variants, unless they're skipped, without having
+/// to record the intermediate relations.
+#[test]
+fn loan_live_at_is_always_computed() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("vec-push-ref")
+        .join("nll-facts")
+        .join("foo1");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let naive = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert!(!naive.loan_live_at.is_empty());
+    let error_point = *naive.errors.keys().next().unwrap();
+    assert!(naive
+        .loans_live_at(error_point)
+        .contains(&naive.errors[&error_point][0]));
+
+    for &algorithm in &[
+        Algorithm::DatafrogOpt,
+        Algorithm::ParallelNaive,
+        Algorithm::Compare,
+    ] {
+        let output = Output::compute(&all_facts, algorithm, false);
+        assert_equal(&naive.loan_live_at, &output.loan_live_at);
+    }
+
+    // The location-insensitive scopes, also used by the hybrid variant for the loans without
+    // potential errors, are an over-approximation
+    for &algorithm in &[Algorithm::LocationInsensitive, Algorithm::Hybrid] {
+        let output = Output::compute(&all_facts, algorithm, false);
+        for (point, loans) in &naive.loan_live_at {
+            for loan in loans {
+                assert!(output.loans_live_at(*point).contains(loan));
+            }
+        }
+    }
+
+    let options = ComputeOptions {
+        skip_loan_live_at: true,
+        ..ComputeOptions::default()
+    };
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+        Algorithm::ParallelNaive,
+        Algorithm::Hybrid,
+    ] {
+        let output = Output::compute_with_options(&all_facts, algorithm, &options);
+        assert!(output.loan_live_at.is_empty());
+        let expected = Output::compute(&all_facts, algorithm, false);
+        assert_equal(&expected.errors, &output.errors);
+    }
+    Ok(())
+}
+
+/// A join backend without any indexing, to check that the variants' rules only depend on the
+/// joins' results and not on how they're computed.
+struct NestedLoopJoins;
+
+impl JoinBackend for NestedLoopJoins {
+    fn join<K: Ord, V1, V2>(
+        input1: &[(K, V1)],
+        input2: &[(K, V2)],
+        mut result: impl FnMut(&K, &V1, &V2),
+    ) {
+        for (key1, value1) in input1 {
+            for (key2, value2) in input2 {
+                if key1 == key2 {
+                    result(key1, value1, value2);
+                }
+            }
+        }
+    }
+
+    fn antijoin<K: Ord, V>(input1: &[(K, V)], input2: &[K], mut result: impl FnMut(&K, &V)) {
+        for (key, value) in input1 {
+            if !input2.contains(key) {
+                result(key, value);
+            }
+        }
+    }
+
+    fn leapjoin<'leap, Tuple, Val: 'leap>(
+        source: &[Tuple],
+        leapers: impl Leapers<'leap, Tuple, Val>,
+        result: impl FnMut(&Tuple, &Val),
+    ) {
+        DatafrogJoins::leapjoin(source, leapers, result);
+    }
+}
+
+#[test]
+fn compute_with_other_join_backend() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("vec-push-ref")
+        .join("nll-facts")
+        .join("foo1");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let options = ComputeOptions::default();
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+        Algorithm::Hybrid,
+    ] {
+        let datafrog = Output::compute_with_options(&all_facts, algorithm, &options);
+        let nested_loops =
+            Output::compute_with_backend::<NestedLoopJoins>(&all_facts, algorithm, &options);
+        assert!(!datafrog.errors.is_empty());
+        assert_equal(&datafrog.errors, &nested_loops.errors);
+        assert_equal(&datafrog.subset_errors, &nested_loops.subset_errors);
+        assert_equal(&datafrog.loan_live_at, &nested_loops.loan_live_at);
+    }
+    Ok(())
+}
+
+#[test]
+fn parity_report() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("vec-push-ref")
+        .join("nll-facts")
+        .join("foo1");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let (runs, _) = parity::run_engines(&all_facts);
+    let engines: Vec<&str> = runs.iter().map(|run| run.engine.as_str()).collect();
+    assert_eq!(engines, ["Naive", "DatafrogOpt", "LocationInsensitive"]);
+    assert!(runs.iter().all(|run| run.agrees == Some(true)));
+    assert_eq!(runs[0].errors, 1);
+    assert_eq!(runs[0].loan_live_at, runs[1].loan_live_at);
+    assert!(runs[2].loan_live_at >= runs[0].loan_live_at);
+
+    let mut csv = Vec::new();
+    parity::write_header(&mut csv)?;
+    parity::write_runs(&mut csv, "foo,1", &runs)?;
+    let csv = String::from_utf8(csv)?;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "function,engine,errors,subset_errors,move_errors,loan_live_at,time_ms,agrees"
+    );
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("\"foo,1\",Naive,1,0,0,"));
+    assert!(lines[1].ends_with(",true"));
+    Ok(())
+}
+
+#[test]
+fn parity_souffle_tuples() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("vec-push-ref")
+        .join("nll-facts")
+        .join("foo1");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+    let (_, reference) = parity::run_engines(&all_facts);
+    let (&point, loans) = reference.errors.iter().next().unwrap();
+    let error = format!(
+        "{}\t{}\n",
+        tables.loans.untern(loans[0]),
+        tables.points.untern(point)
+    );
+
+    let dir = std::env::temp_dir().join(format!("polonius-souffle-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let agrees = |errors: &str| -> Result<Option<bool>, Box<dyn Error>> {
+        std::fs::write(dir.join("errors.csv"), errors)?;
+        Ok(parity::souffle_agrees(&dir, &reference, tables))
+    };
+    // the same error agrees, but not a count of errors alone, nor tuples without their points
+    let same = agrees(&error);
+    let other = agrees("\"bw1\"\t\"Mid(bb0[0])\"\n");
+    let missing = agrees("");
+    let unnormalized = agrees("\"bw0\"\n");
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(same?, Some(true));
+    assert_eq!(other?, Some(false));
+    assert_eq!(missing?, Some(false));
+    assert_eq!(unnormalized?, None);
+
+    // without any error relation to compare, the agreement is unknown
+    let empty = std::env::temp_dir().join(format!("polonius-souffle-empty-{}", std::process::id()));
+    std::fs::create_dir_all(&empty)?;
+    let unknown = parity::souffle_agrees(&empty, &reference, tables);
+    std::fs::remove_dir_all(&empty)?;
+    assert_eq!(unknown, None);
+
+    let mut csv = Vec::new();
+    let souffle = parity::Run {
+        engine: "Souffle".to_string(),
+        errors: 1,
+        subset_errors: 0,
+        move_errors: 0,
+        loan_live_at: 0,
+        duration: std::time::Duration::default(),
+        agrees: None,
+    };
+    parity::write_runs(&mut csv, "foo", &[souffle])?;
+    assert!(String::from_utf8(csv)?.ends_with(",\n"));
+    Ok(())
+}
+
+#[test]
+fn synthetic_facts() -> Result<(), Box<dyn Error>> {
+    let parameters = synthetic::Parameters {
+        points: 80,
+        origins: 8,
+        loans: 4,
+        variables: 4,
+        density: 30,
+        shape: synthetic::CfgShape::Line,
+        seed: 1,
+    };
+    let bundle = |parameters: &synthetic::Parameters| {
+        let mut bundle = Vec::new();
+        synthetic::generate(parameters)
+            .write_bundle(&mut bundle)
+            .unwrap();
+        bundle
+    };
+
+    // the facts only depend on the seed
+    let line = bundle(&parameters);
+    assert_eq!(line, bundle(&parameters));
+    let other_seed = synthetic::Parameters {
+        seed: 2,
+        ..parameters.clone()
+    };
+    assert_ne!(line, bundle(&other_seed));
+
+    // the directories of facts are loaded like the bundles
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_facts_bundle(tables, Path::new("<bundle>"), &line[..])?;
+    let dir = std::env::temp_dir().join(format!("polonius-synthetic-{}", std::process::id()));
+    synthetic::generate(&parameters).write_directory(&dir)?;
+    let dir_facts = tab_delim::load_tab_delimited_facts(tables, &dir);
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(format!("{:?}", facts), format!("{:?}", dir_facts?));
+
+    // 40 statements in blocks of 4: a line has an edge into every point but the first, and the
+    // loops of the other shape add one edge per block in its second half
+    assert_eq!(tables.points.len(), 80);
+    assert_eq!(facts.cfg_edge.len(), 79);
+    assert_eq!(facts.universal_region.len(), 2);
+    assert_eq!(facts.loan_issued_at.len(), 4);
+    let loops = synthetic::Parameters {
+        shape: synthetic::CfgShape::Loops,
+        ..parameters.clone()
+    };
+    let loops = tab_delim::load_facts_bundle(tables, Path::new("<bundle>"), &bundle(&loops)[..])?;
+    assert_eq!(loops.cfg_edge.len(), 79 + 5);
+
+    for facts in [&facts, &loops] {
+        let naive = Output::compute(facts, Algorithm::Naive, false);
+        let opt = Output::compute(facts, Algorithm::DatafrogOpt, false);
+        assert_equal(&naive.errors, &opt.errors);
+        assert_equal(&naive.subset_errors, &opt.subset_errors);
+    }
+    Ok(())
+}
+
+#[test]
+fn datafrog_opt_records_relation_sizes() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+    assert!(output.relation_sizes.is_none());
+
+    let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, true);
+    let sizes = output.relation_sizes.expect("missing relation sizes");
+    let subset_peak = sizes.peaks["subset_o1p"];
+    assert!(subset_peak.tuples > 0);
+    assert!(0 < subset_peak.round && subset_peak.round <= sizes.rounds);
+    assert_eq!(sizes.peaks["errors"].tuples, 0);
+    Ok(())
+}
+
+#[test]
+fn liveness_only_matches_full_computation() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let naive = Output::compute(&all_facts, Algorithm::Naive, true);
+    let liveness = Output::liveness(&all_facts);
+    assert_equal(&naive.var_live_on_entry, &liveness.var_live_on_entry);
+    assert_equal(
+        &naive.var_drop_live_on_entry,
+        &liveness.var_drop_live_on_entry,
+    );
+    assert_equal(&naive.origin_live_on_entry, &liveness.origin_live_on_entry);
+
+    // The loans are not borrow checked
+    let output = Output::compute(&all_facts, Algorithm::LivenessOnly, false);
+    assert!(output.errors.is_empty() && output.subset_errors.is_empty());
+    assert!(!output.var_live_on_entry.is_empty());
+    Ok(())
+}
+
+#[test]
+fn origin_liveness_matches_full_computation() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let liveness = Output::liveness(&all_facts);
+    let origin_live_on_entry = compute_origin_liveness(&all_facts);
+    assert!(!origin_live_on_entry.is_empty());
+
+    let mut origins_live_at: FxHashMap<Point, Vec<Origin>> = FxHashMap::default();
+    for &(origin, point) in origin_live_on_entry.iter() {
+        origins_live_at.entry(point).or_default().push(origin);
+    }
+    assert_equal(&liveness.origin_live_on_entry, &origins_live_at);
+    Ok(())
+}
+
+/// A precomputed origin liveness replaces the liveness computation, as long as it covers all the
+/// points of the facts.
+#[test]
+fn precomputed_origin_liveness() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("vec-push-ref")
+        .join("nll-facts")
+        .join("foo1");
+    let tables = &mut intern::InternerTables::new();
+    let mut all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+    let expected = Output::compute(&all_facts, Algorithm::Naive, true);
+    assert!(!expected.errors.is_empty());
+    assert!(all_facts.uncovered_liveness_points().is_empty());
+
+    all_facts.origin_live_on_entry = compute_origin_liveness(&all_facts).elements;
+    assert!(all_facts.uncovered_liveness_points().is_empty());
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let output = Output::compute(&all_facts, algorithm, true);
+        assert_equal(&expected.errors, &output.errors);
+        assert_equal(&expected.origin_live_on_entry, &output.origin_live_on_entry);
+
+        // the liveness of variables is not computed
+        assert!(output.var_live_on_entry.is_empty());
+    }
+
+    // No origin needs to be live at the points where no variable is used: the precomputed
+    // liveness can be empty there, and is still used
+    let used_points: BTreeSet<Point> = all_facts
+        .var_used_at
+        .iter()
+        .map(|&(_, point)| point)
+        .collect();
+    let precomputed = all_facts.origin_live_on_entry.clone();
+    all_facts
+        .origin_live_on_entry
+        .retain(|(_, point)| used_points.contains(point));
+    assert!(all_facts
+        .cfg_edge
+        .iter()
+        .any(|&(point, _)| !used_points.contains(&point)));
+    assert!(all_facts.uncovered_liveness_points().is_empty());
+    let output = Output::compute(&all_facts, Algorithm::Naive, true);
+    assert!(output.var_live_on_entry.is_empty());
+
+    // The liveness is computed when the precomputed one lacks the origins of a used variable
+    all_facts.origin_live_on_entry = precomputed;
+    let (var, used_point) = all_facts
+        .var_used_at
+        .iter()
+        .cloned()
+        .find(|&(var, _)| {
+            all_facts
+                .use_of_var_derefs_origin
+                .iter()
+                .any(|&(used_var, _)| used_var == var)
+        })
+        .unwrap();
+    let used_origins: BTreeSet<Origin> = all_facts
+        .use_of_var_derefs_origin
+        .iter()
+        .filter(|&&(used_var, _)| used_var == var)
+        .map(|&(_, origin)| origin)
+        .collect();
+    all_facts
+        .origin_live_on_entry
+        .retain(|&(origin, point)| point != used_point || !used_origins.contains(&origin));
+    assert_eq!(all_facts.uncovered_liveness_points(), vec![used_point]);
+    let output = Output::compute(&all_facts, Algorithm::Naive, true);
+    assert_equal(&expected.errors, &output.errors);
+    assert!(!output.var_live_on_entry.is_empty());
+    Ok(())
+}
+
+/// Loading precomputed `origin_live_on_entry` facts checks that they include the origins of the
+/// variables used at each point, while other points can have no live origins.
+#[test]
+fn load_uncovered_origin_liveness() {
+    let bundle = "# cfg_edge\nP0\tP1\nP1\tP2\nP2\tP3\n\
+                  # loan_issued_at\n'a\tL0\tP0\n\
+                  # loan_invalidated_at\nP1\tL0\n\
+                  # var_used_at\nV0\tP2\n\
+                  # use_of_var_derefs_origin\nV0\t'b\n\
+                  # origin_live_on_entry\n'a\tP0\n'a\tP1\n";
+    let path = Path::new("liveness.bundle");
+    let tables = &mut intern::InternerTables::new();
+    match tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()) {
+        Err(error::Error::UncoveredLiveness { points, first }) => {
+            assert_eq!((points, first.as_str()), (1, "P2"));
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // `P3` has no live origins
+    let bundle = format!("{}'b\tP2\n", bundle);
+    let facts = tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()).unwrap();
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert_eq!(output.errors.len(), 1);
+}
+
+#[test]
+fn initialization_only_matches_full_computation() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("conditional_init");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let naive = Output::compute(&all_facts, Algorithm::Naive, true);
+    let initialization = Output::initialization(&all_facts);
+    assert!(!initialization.move_errors.is_empty());
+    assert_equal(&naive.move_errors, &initialization.move_errors);
+    assert_equal(
+        &naive.path_maybe_initialized_on_exit,
+        &initialization.path_maybe_initialized_on_exit,
+    );
+    assert_equal(
+        &naive.path_maybe_uninitialized_on_exit,
+        &initialization.path_maybe_uninitialized_on_exit,
+    );
+    assert_equal(
+        &naive.var_maybe_partly_initialized_on_exit,
+        &initialization.var_maybe_partly_initialized_on_exit,
+    );
+
+    // Neither liveness nor loans are computed
+    let output = Output::compute(&all_facts, Algorithm::InitializationOnly, false);
+    assert!(output.var_live_on_entry.is_empty() && output.errors.is_empty());
+    Ok(())
+}
+
 // The following 3 tests, `send_is_not_static_std_sync`, `escape_upvar_nested`, and `issue_31567`
 // are extracted from rustc's test suite, and fail because of differences between the Naive
 // and DatafrogOpt variants, on the computation of the transitive closure.
@@ -277,6 +975,37 @@ fn issue_31567() {
     test_facts(&facts, Algorithm::OPTIMIZED);
 }
 
+/// The closure of the subsets at a point doesn't return the reflexive subsets of the origins on a
+/// cycle, like the symmetries removed by the Naive variant's cleanup step.
+#[test]
+fn subset_cycle_is_not_reflexive() {
+    let program = r"
+        placeholders { }
+        block B0 {
+            outlives('a: 'b), outlives('b: 'a), outlives('b: 'c);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    assert_eq!(output.subset.len(), 1);
+    let subsets: Vec<(&str, Vec<&str>)> = output
+        .subset
+        .values()
+        .flat_map(|subsets| subsets.iter())
+        .map(|(&origin, origins)| {
+            let origins = origins.iter().map(|&o| tables.origins.untern(o)).collect();
+            (tables.origins.untern(origin), origins)
+        })
+        .collect();
+    assert_eq!(
+        subsets,
+        [("'a", vec!["'b", "'c"]), ("'b", vec!["'a", "'c"])]
+    );
+}
+
 #[test]
 fn borrowed_local_error() {
     // This test is related to the previous 3: there is still an issuing origin outliving a live origin,
@@ -506,33 +1235,82 @@ fn var_live_in_successor_killed_by_reassignment() {
     );
 }
 
+/// Dropping a variable which was moved out doesn't make it, nor its origins, drop-live.
 #[test]
-fn var_drop_used_simple() {
+fn var_dropped_while_moved_out() {
     let program = r"
         placeholders { }
+        drop_of_var_derefs_origin { (V1, 'a) }
 
         block B0 {
-            loan_invalidated_at(L0); // generate a point
+            var_defined_at(V1);
             goto B1;
         }
 
         block B1 {
-            var_defined_at(V1); // V1 dies
-            loan_invalidated_at(L0);
+            loan_invalidated_at(L0); // generate a point, where V1 is moved out below
             goto B2;
         }
 
         block B2 {
-            loan_invalidated_at(L0);
-            var_dropped_at(V1);
+            loan_invalidated_at(L0); // generate a point, where V1 is dropped below
         }
     ";
 
     let mut tables = intern::InternerTables::new();
-    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let v1 = tables.variables.intern("V1");
+    let a = tables.origins.intern("'a");
+    let mp1 = tables.paths.intern("\"mp1\"");
+    let defined = tables.points.intern("\"Mid(B0[0])\"");
+    let moved = tables.points.intern("\"Mid(B1[0])\"");
+    let dropped = tables.points.intern("\"Mid(B2[0])\"");
+    facts.path_is_var.push((mp1, v1));
+    facts.path_assigned_at_base.push((mp1, defined));
+    facts.var_dropped_at.push((v1, dropped));
+
+    // While `V1` is initialized, its drop makes it and `'a` live before it.
+    let result = Output::compute(&facts, Algorithm::Naive, true);
+    assert_eq!(result.var_dropped_while_init_at[&dropped], vec![v1]);
+    assert!(result.var_drop_live_on_entry[&moved].contains(&v1));
+    assert!(result.origins_live_at(moved).contains(&a));
 
+    // Once it's moved out, the drop is a no-op.
+    facts.path_moved_at_base.push((mp1, moved));
     let result = Output::compute(&facts, Algorithm::Naive, true);
-    println!("result: {:#?}", result);
+    assert!(result.var_dropped_while_init_at.is_empty());
+    assert!(result.var_drop_live_on_entry.is_empty());
+    assert!(!result.origins_live_at(moved).contains(&a));
+}
+
+#[test]
+fn var_drop_used_simple() {
+    let program = r"
+        placeholders { }
+
+        block B0 {
+            loan_invalidated_at(L0); // generate a point
+            goto B1;
+        }
+
+        block B1 {
+            var_defined_at(V1); // V1 dies
+            loan_invalidated_at(L0);
+            goto B2;
+        }
+
+        block B2 {
+            loan_invalidated_at(L0);
+            var_dropped_at(V1);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let result = Output::compute(&facts, Algorithm::Naive, true);
+    println!("result: {:#?}", result);
     let liveness = result.var_drop_live_on_entry;
     println!("CFG: {:#?}", facts.cfg_edge);
     let first_defined: Point = 3.into(); // Mid(B1[0])
@@ -594,14 +1372,308 @@ fn illegal_subset_error() {
     assert_eq!(checker.subset_errors_count(), 1);
     assert!(checker.subset_error_exists("'b", "'a", "\"Mid(B0[0])\""));
 
-    // and in the location-insensitive results as well
-    assert!(location_insensitive_checker_for(program)
-        .location_insensitive_subset_error_exists("'b", "'a"));
+    // and in the location-insensitive results as well, at the point where `'b`'s placeholder
+    // loan flows into `'a`
+    let mut insensitive = location_insensitive_checker_for(program);
+    assert!(insensitive.location_insensitive_subset_error_exists("'b", "'a"));
+    assert!(insensitive.subset_error_exists("'b", "'a", "\"Mid(B0[0])\""));
 
     // and finally the optimized-variant results should be the same as the naive ones
     assert_checkers_match(&checker, &opt_checker_for(program));
 }
 
+/// Errors can be inspected as typed diagnostics, and displayed with the atoms' names.
+#[test]
+fn diagnostics_display_interned_names() {
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('b: 'x),
+              outlives('x: 'a);
+        }
+    ";
+
+    let checker = naive_checker_for(program);
+    let diagnostics = checker.output.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+
+    let displayed = diagnostics[0].display(&checker.tables).to_string();
+    assert_eq!(
+        displayed,
+        "unknown subset requirement 'b <= 'a at \"Mid(B0[0])\""
+    );
+}
+
+/// The `Compare` variant records the differences between the errors of the variants it compares,
+/// which are rendered as JSON for the CLI to write.
+#[test]
+fn compare_records_the_differences_between_variants() {
+    let program = r"
+        placeholders { 'a, 'b }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('b: 'x),
+              outlives('x: 'a);
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+
+    let checker = check_program(program, Algorithm::Compare, false);
+    let comparison = checker.output.comparison.as_ref().unwrap();
+    assert!(comparison.is_empty());
+
+    // an illegal access only found by one variant, and a subset error only found by the other
+    let diagnostics = checker.output.diagnostics();
+    let illegal_access =
+        |diagnostic: &&Diagnostic<_>| matches!(diagnostic, Diagnostic::IllegalAccess { .. });
+    let diff = ErrorsDiff {
+        removed: diagnostics.iter().filter(illegal_access).cloned().collect(),
+        added: diagnostics
+            .iter()
+            .filter(|d| !illegal_access(d))
+            .take(1)
+            .cloned()
+            .collect(),
+    };
+    assert_eq!(
+        dump::render_comparison(&diff, &checker.tables),
+        r#"{
+  "errors": {
+    "naive": [
+      [
+        "Mid(B0[1])",
+        "L0"
+      ]
+    ],
+    "datafrog_opt": []
+  },
+  "subset_errors": {
+    "naive": [],
+    "datafrog_opt": [
+      [
+        "Mid(B0[0])",
+        "'b",
+        "'a"
+      ]
+    ]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn waivers_tolerate_the_known_differences() {
+    let program = r"
+        placeholders { 'a, 'b }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('b: 'x),
+              outlives('x: 'a);
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+
+    // an illegal access only found by the naive variant
+    let checker = check_program(program, Algorithm::Naive, false);
+    let diff = ErrorsDiff {
+        removed: checker
+            .output
+            .diagnostics()
+            .into_iter()
+            .filter(|diagnostic| matches!(diagnostic, Diagnostic::IllegalAccess { .. }))
+            .collect(),
+        added: Vec::new(),
+    };
+    let facts_dir = Path::new("inputs/example/nll-facts/main");
+
+    let waivers = Waivers::from_json(
+        r#"{
+  "example/nll-facts/main": {
+    "errors": { "naive": [["Mid(B0[1])", "L0"]], "datafrog_opt": [] }
+  },
+  "other": {
+    "subset_errors": { "naive": [["Mid(B0[0])", "'b", "'a"]] }
+  }
+}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        waivers.check(facts_dir, &diff, &checker.tables),
+        WaiverCheck::default()
+    );
+
+    // the differences of a directory without a waiver are unexpected, and a waived difference
+    // which doesn't occur is stale
+    assert_eq!(
+        waivers.check(
+            Path::new("inputs/other/nll-facts/main"),
+            &diff,
+            &checker.tables
+        ),
+        WaiverCheck {
+            unexpected: 1,
+            stale: 0,
+        }
+    );
+    let no_diff = ErrorsDiff {
+        removed: Vec::new(),
+        added: Vec::new(),
+    };
+    assert_eq!(
+        waivers.check(facts_dir, &no_diff, &checker.tables),
+        WaiverCheck {
+            unexpected: 0,
+            stale: 1,
+        }
+    );
+
+    let error = Waivers::from_json(r#"{ "main": { "errors": { "naive": [[0]] } } }"#)
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "invalid type: integer `0`, expected a string at line 1 column 36"
+    );
+
+    // misspelled variants are errors, instead of waiving nothing
+    let error = Waivers::from_json(r#"{ "main": { "errors": { "navie": [] } } }"#)
+        .err()
+        .unwrap();
+    assert!(error.to_string().starts_with("unknown field `navie`"));
+}
+
+#[test]
+fn analyze_program_end_to_end() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0);
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+
+    let result = crate::analyze_program(program, Algorithm::DatafrogOpt).unwrap();
+    let diagnostics: Vec<_> = result
+        .output
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.display(&result.tables).to_string())
+        .collect();
+    assert_eq!(
+        diagnostics,
+        ["loan L0 is invalidated at \"Mid(B0[1])\" while still live"]
+    );
+    assert_eq!(result.facts.loan_issued_at.len(), 1);
+
+    match crate::analyze_program("block B0 { goto }", Algorithm::Naive) {
+        Err(error::Error::Parse { line: 1, .. }) => {}
+        _ => panic!("expected a parse error"),
+    }
+}
+
+/// The placeholders are told apart from the existential origins once, for all the variants, and
+/// their kinds are dumped.
+#[test]
+fn origin_kinds() {
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('b: 'x),
+              outlives('x: 'a);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let [a, b, x] = ["'a", "'b", "'x"].map(|name| tables.origins.intern(name));
+
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+    ] {
+        let output = Output::compute(&facts, algorithm, true);
+        assert_eq!(output.origin_kind.len(), 3);
+        assert_eq!(output.origin_kind[&a], OriginKind::Placeholder);
+        assert_eq!(output.origin_kind[&b], OriginKind::Placeholder);
+        assert_eq!(output.origin_kind[&x], OriginKind::Existential);
+        assert_eq!(output.subset_errors.values().flatten().count(), 1);
+    }
+
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    let mut rows = Vec::new();
+    dump::OutputDump::push_all(&output.origin_kind, &tables, &mut rows);
+    assert_eq!(
+        rows,
+        [
+            ["'a", "placeholder"],
+            ["'b", "placeholder"],
+            ["'x", "existential"]
+        ]
+    );
+
+    // without dumps, the kinds are only used by the computation
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert!(output.origin_kind.is_empty());
+}
+
+/// The placeholders are the `universal_region`s: an origin with a `placeholder` loan, which
+/// isn't one of them, is existential, and its subsets to the placeholders are not errors.
+#[test]
+fn placeholder_loan_origins_are_not_placeholders() {
+    let program = r"
+        placeholders { 'a }
+
+        block B0 {
+            outlives('c: 'a);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let c = tables.origins.intern("'c");
+    facts.placeholder.push((c, tables.loans.intern("Lc")));
+
+    // (`LocationInsensitive` follows all the `placeholder` loans, and over-approximates this)
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+        let output = Output::compute(&facts, algorithm, true);
+        assert_eq!(output.origin_kind[&c], OriginKind::Existential);
+        assert!(output.subset_errors.is_empty(), "{:?}", algorithm);
+    }
+
+    // as a `universal_region`, the origin is a placeholder, and its subset is an error
+    facts.universal_region.push(c);
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+        Algorithm::Hybrid,
+    ] {
+        let output = Output::compute(&facts, algorithm, true);
+        assert_eq!(output.origin_kind[&c], OriginKind::Placeholder);
+        assert_eq!(
+            output.subset_errors.values().flatten().count(),
+            1,
+            "{:?}",
+            algorithm
+        );
+    }
+}
+
 /// This is the same test as the `illegal_subset_error` one, but specifies the `'b: 'a` subset
 /// relation as being "known", making this program valid.
 #[test]
@@ -700,11 +1772,194 @@ fn transitive_illegal_subset_error() {
     // The optimized analysis results should be the same as the naive one's.
     assert_checkers_match(&checker, &opt_checker_for(program));
 
-    // And the location-insensitive analysis should have the same errors, without a location.
+    // And the location-insensitive analysis should have the same errors, at a representative
+    // location.
     let mut checker = location_insensitive_checker_for(program);
     assert_eq!(checker.subset_errors_count(), 2);
     assert!(checker.location_insensitive_subset_error_exists("'b", "'c"));
     assert!(checker.location_insensitive_subset_error_exists("'a", "'c"));
+    assert!(checker.subset_error_exists("'b", "'c", "\"Mid(B0[1])\""));
+    assert!(checker.subset_error_exists("'a", "'c", "\"Mid(B0[1])\""));
+}
+
+/// The location-insensitive subset errors are located at the earliest point in the CFG where the
+/// placeholder flows, even when the points are numbered in another order.
+#[test]
+fn location_insensitive_representative_point() {
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B1 {
+            outlives('a: 'b);
+        }
+
+        block B0 {
+            outlives('a: 'b);
+            goto B1;
+        }
+    ";
+
+    let mut checker = location_insensitive_checker_for(program);
+    assert_eq!(checker.subset_errors_count(), 1);
+    assert!(checker.subset_error_exists("'a", "'b", "\"Mid(B0[0])\""));
+
+    // a placeholder loan issued in the second origin flows into it where it's issued
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B0 {
+            loan_issued_at('b, L0);
+        }
+    ";
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let placeholder_loan = facts
+        .placeholder
+        .iter()
+        .find(|&&(origin, _)| tables.origins.untern(origin) == "'a")
+        .unwrap()
+        .1;
+    facts.loan_issued_at[0].1 = placeholder_loan;
+    let output = Output::compute(&facts, Algorithm::LocationInsensitive, false);
+    let points: Vec<&str> = output
+        .subset_errors
+        .keys()
+        .map(|&point| tables.points.untern(point))
+        .collect();
+    assert_eq!(points, ["\"Mid(B0[0])\""]);
+}
+
+/// Each subset error has a witness: the chain of `subset_base` facts from its first origin to
+/// its second, and the loans issued in the chain's origins.
+#[test]
+fn subset_error_witnesses() {
+    let program = r"
+        placeholders { 'a, 'b, 'c }
+        known_subsets { 'a: 'b }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('a: 'x),
+              outlives('x: 'b);
+
+            loan_issued_at('y, L1),
+              outlives('b: 'y),
+              outlives('y: 'c);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let [a, b, c, x, y] = ["'a", "'b", "'c", "'x", "'y"].map(|name| tables.origins.intern(name));
+    let l0 = tables.loans.intern("L0");
+    let l1 = tables.loans.intern("L1");
+    let first = tables.points.intern("\"Mid(B0[0])\"");
+    let second = tables.points.intern("\"Mid(B0[1])\"");
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let result = Output::compute(&facts, algorithm, false);
+        let witnesses = &result.subset_error_witnesses[&second];
+        assert_eq!(witnesses.len(), 2);
+
+        let witness = &witnesses[&(b, c)];
+        assert_eq!(witness.subsets, [(b, y, second), (y, c, second)]);
+        assert_eq!(witness.loans, [(y, l1, second)]);
+
+        let witness = &witnesses[&(a, c)];
+        assert_eq!(
+            witness.subsets,
+            [(a, x, first), (x, b, first), (b, y, second), (y, c, second)]
+        );
+        assert_eq!(witness.loans, [(x, l0, first), (y, l1, second)]);
+    }
+}
+
+/// The known subsets can be checked as given, without their transitive closure, which is
+/// available in the output.
+#[test]
+fn known_subsets_as_given() {
+    let program = r"
+        placeholders { 'a, 'b, 'c }
+        known_subsets { 'a: 'b, 'b: 'c }
+
+        block B0 {
+            // requires the transitive `'a: 'c` subset
+            loan_issued_at('x, L0),
+              outlives('a: 'x),
+              outlives('x: 'c);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let a = tables.origins.intern("'a");
+    let b = tables.origins.intern("'b");
+    let c = tables.origins.intern("'c");
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let result = Output::compute(&facts, algorithm, false);
+        assert!(result.subset_errors.is_empty());
+        let known: Vec<_> = result.known_placeholder_subset[&a]
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(known, [b, c]);
+
+        let options = ComputeOptions {
+            known_subsets: KnownSubsets::AsGiven,
+            ..ComputeOptions::default()
+        };
+        let result = Output::compute_with_options(&facts, algorithm, &options);
+        let subset_errors: Vec<_> = result.subset_errors.values().flatten().cloned().collect();
+        assert_eq!(subset_errors, [(a, c)]);
+        let known: Vec<_> = result.known_placeholder_subset[&a]
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(known, [b]);
+    }
+}
+
+/// The closure of the known subsets filters out the subset errors which are consequences of
+/// another subset error, like in `transitive_illegal_subset_error`.
+#[test]
+fn root_subset_errors() {
+    let program = r"
+        placeholders { 'a, 'b, 'c }
+        known_subsets { 'a: 'b }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('a: 'x),
+              outlives('x: 'b);
+
+            loan_issued_at('y, L1),
+              outlives('b: 'y),
+              outlives('y: 'c);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let result = Output::compute(&facts, Algorithm::Naive, false);
+    let subset_errors: BTreeSet<_> = result.subset_errors.values().flatten().cloned().collect();
+    assert_eq!(subset_errors.len(), 2);
+
+    // `'a: 'c` is missing because `'b: 'c` is, and `'a: 'b` is known.
+    let root_errors: Vec<_> = subset_errors
+        .iter()
+        .filter(|(origin1, origin3)| {
+            !result
+                .known_placeholder_subset
+                .get(origin1)
+                .into_iter()
+                .flatten()
+                .any(|&origin2| subset_errors.contains(&(origin2, *origin3)))
+        })
+        .collect();
+    let b = tables.origins.intern("'b");
+    let c = tables.origins.intern("'c");
+    assert_eq!(root_errors, [&(b, c)]);
 }
 
 #[test]
@@ -788,36 +2043,87 @@ fn errors_in_subset_relations_dataset() {
 
 // There's only a single successful test in the dataset for now, but the structure of this test
 // will allow to add others, similarly to subset errors tests.
+/// The `Hybrid` variant only checks the placeholders of the pre-pass' potential subset errors,
+/// and finds the same errors as the `Naive` one.
 #[test]
-fn successes_in_move_errors_dataset() {
-    let successes = ["move_reinitialize_ok"];
-
-    // these tests have no illegal access errors, no subset errors, and no move errors
-    for test_fn in &successes {
-        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
-            .join("inputs")
-            .join("smoke-test")
-            .join("nll-facts")
-            .join(test_fn);
+fn hybrid_prunes_subset_errors() {
+    let subset_relations = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("subset-relations")
+        .join("nll-facts");
+    for test_fn in &["missing_subset", "valid_subset", "implied_bounds_subset"] {
+        let facts_dir = subset_relations.join(test_fn);
         let tables = &mut intern::InternerTables::new();
         let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).expect("facts");
 
-        let naive = Output::compute(&facts, Algorithm::Naive, true);
-        assert!(naive.errors.is_empty());
-        assert!(naive.subset_errors.is_empty());
-        assert!(naive.move_errors.is_empty());
+        let naive = Output::compute(&facts, Algorithm::Naive, false);
+        let hybrid = Output::compute(&facts, Algorithm::Hybrid, false);
+        assert_outputs_match(&naive, &hybrid);
+    }
 
-        let insensitive = Output::compute(&facts, Algorithm::LocationInsensitive, true);
-        assert!(insensitive.errors.is_empty());
-        assert!(insensitive.subset_errors.is_empty());
-        assert!(insensitive.move_errors.is_empty());
+    let facts_dir = subset_relations.join("missing_subset");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).expect("facts");
+    let opt = Output::compute(&facts, Algorithm::DatafrogOpt, true);
+    let hybrid = Output::compute(&facts, Algorithm::Hybrid, true);
+    assert_equal(&opt.subset_errors, &hybrid.subset_errors);
 
-        let opt = Output::compute(&facts, Algorithm::DatafrogOpt, true);
-        assert!(opt.errors.is_empty());
-        assert!(opt.subset_errors.is_empty());
-        assert!(opt.move_errors.is_empty());
-    }
-}
+    let peak =
+        |output: &Output| output.relation_sizes.as_ref().unwrap().peaks["subset_placeholder"];
+    assert!(peak(&hybrid).tuples < peak(&opt).tuples);
+
+    let program = r"
+        placeholders { 'a, 'b, 'c }
+        known_subsets { 'a: 'b }
+
+        block B0 {
+            loan_issued_at('x, L0),
+              outlives('a: 'x),
+              outlives('x: 'b);
+
+            loan_issued_at('y, L1),
+              outlives('b: 'y),
+              outlives('y: 'c);
+        }
+    ";
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let naive = Output::compute(&facts, Algorithm::Naive, false);
+    let hybrid = Output::compute(&facts, Algorithm::Hybrid, false);
+    assert_eq!(naive.subset_errors.values().flatten().count(), 2);
+    assert_outputs_match(&naive, &hybrid);
+}
+
+#[test]
+fn successes_in_move_errors_dataset() {
+    let successes = ["move_reinitialize_ok"];
+
+    // these tests have no illegal access errors, no subset errors, and no move errors
+    for test_fn in &successes {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("smoke-test")
+            .join("nll-facts")
+            .join(test_fn);
+        let tables = &mut intern::InternerTables::new();
+        let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).expect("facts");
+
+        let naive = Output::compute(&facts, Algorithm::Naive, true);
+        assert!(naive.errors.is_empty());
+        assert!(naive.subset_errors.is_empty());
+        assert!(naive.move_errors.is_empty());
+
+        let insensitive = Output::compute(&facts, Algorithm::LocationInsensitive, true);
+        assert!(insensitive.errors.is_empty());
+        assert!(insensitive.subset_errors.is_empty());
+        assert!(insensitive.move_errors.is_empty());
+
+        let opt = Output::compute(&facts, Algorithm::DatafrogOpt, true);
+        assert!(opt.errors.is_empty());
+        assert!(opt.subset_errors.is_empty());
+        assert!(opt.move_errors.is_empty());
+    }
+}
 
 #[test]
 fn basic_move_error() {
@@ -843,6 +2149,644 @@ fn basic_move_error() {
     assert_eq!(error_path, move_errors[0]);
 }
 
+/// Renumbering the points in reverse post-order of the CFG doesn't change the results.
+#[test]
+fn reverse_post_order_computes_the_same_results() -> Result<(), Box<dyn Error>> {
+    let inputs = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs");
+    for facts_dir in &[
+        inputs.join("issue-47680").join("nll-facts").join("main"),
+        inputs.join("vec-push-ref").join("nll-facts").join("foo3"),
+    ] {
+        let tables = &mut intern::InternerTables::new();
+        let facts = tab_delim::load_tab_delimited_facts(tables, facts_dir)?;
+        let options = ComputeOptions {
+            dump_enabled: true,
+            point_order: PointOrder::ReversePostOrder,
+            ..ComputeOptions::default()
+        };
+
+        for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+            let expected = Output::compute(&facts, algorithm, true);
+            let renumbered = Output::compute_with_options(&facts, algorithm, &options);
+
+            // The maps iterate in the order of the points' numbering, so their debug text differs.
+            assert_eq!(&expected.errors, &renumbered.errors);
+            assert_eq!(&expected.subset_errors, &renumbered.subset_errors);
+            assert_eq!(&expected.loan_live_at, &renumbered.loan_live_at);
+            assert_eq!(
+                &expected.origin_contains_loan_at,
+                &renumbered.origin_contains_loan_at,
+            );
+            assert_eq!(&expected.var_live_on_entry, &renumbered.var_live_on_entry);
+        }
+    }
+    Ok(())
+}
+
+/// The scope of a loan is the points where it's in scope, and the live range of an origin is the
+/// points where it's live on entry, also when the points are renumbered.
+#[test]
+fn loan_scopes_and_origin_live_ranges() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    assert!(output.loan_scopes.is_empty());
+    assert!(output.origin_live_ranges.is_empty());
+
+    let options = ComputeOptions {
+        dump_enabled: true,
+        scopes: true,
+        ..ComputeOptions::default()
+    };
+    let output = Output::compute_with_options(&facts, Algorithm::Naive, &options);
+    assert!(!output.loan_scopes.is_empty());
+    let mut loan_live_at: BTreeSet<(Point, Loan)> = BTreeSet::new();
+    for (&loan, points) in &output.loan_scopes {
+        loan_live_at.extend(points.iter().map(|&point| (point, loan)));
+    }
+    let expected: BTreeSet<(Point, Loan)> = output
+        .loan_live_at
+        .iter()
+        .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+        .collect();
+    assert_eq!(loan_live_at, expected);
+
+    let mut origin_live_on_entry: BTreeSet<(Point, Origin)> = BTreeSet::new();
+    for (&origin, points) in &output.origin_live_ranges {
+        origin_live_on_entry.extend(points.iter().map(|&point| (point, origin)));
+    }
+    let expected: BTreeSet<(Point, Origin)> = output
+        .origin_live_on_entry
+        .iter()
+        .flat_map(|(&point, origins)| origins.iter().map(move |&origin| (point, origin)))
+        .collect();
+    assert_eq!(origin_live_on_entry, expected);
+
+    let renumbered = Output::compute_with_options(
+        &facts,
+        Algorithm::DatafrogOpt,
+        &ComputeOptions {
+            point_order: PointOrder::ReversePostOrder,
+            ..options.clone()
+        },
+    );
+    assert_eq!(output.loan_scopes, renumbered.loan_scopes);
+    assert_eq!(output.origin_live_ranges, renumbered.origin_live_ranges);
+
+    let liveness = Output::compute_with_options(&facts, Algorithm::LivenessOnly, &options);
+    assert_eq!(output.origin_live_ranges, liveness.origin_live_ranges);
+    Ok(())
+}
+
+/// The location-insensitive analysis records the points where each loan may be an error, the
+/// earliest first.
+#[test]
+fn potential_errors_by_loan() -> Result<(), Box<dyn Error>> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+    let insensitive = Output::compute(&facts, Algorithm::LocationInsensitive, false);
+    assert!(!insensitive.potential_errors.is_empty());
+    let mut errors: BTreeSet<(Loan, Point)> = BTreeSet::new();
+    for (&loan, points) in &insensitive.potential_errors {
+        assert!(points.windows(2).all(|points| points[0] < points[1]));
+        errors.extend(points.iter().map(|&point| (loan, point)));
+    }
+    let expected: BTreeSet<(Loan, Point)> = insensitive
+        .errors
+        .iter()
+        .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (loan, point)))
+        .collect();
+    assert_eq!(errors, expected);
+
+    // the pre-pass of the `Hybrid` variant finds the same potential errors, which contain the
+    // errors of the location-sensitive variants
+    let hybrid = Output::compute(&facts, Algorithm::Hybrid, false);
+    assert_eq!(hybrid.potential_errors, insensitive.potential_errors);
+    for (point, loans) in &hybrid.errors {
+        for loan in loans {
+            assert!(hybrid.potential_errors[loan].contains(point));
+        }
+    }
+
+    let naive = Output::compute(&facts, Algorithm::Naive, false);
+    assert!(naive.potential_errors.is_empty());
+    Ok(())
+}
+
+/// A loan goes out of scope at the first points where it isn't in scope anymore, unless it's
+/// killed before.
+#[test]
+fn loans_out_of_scope_at() {
+    let program = |first_effect| {
+        format!(
+            r"
+            placeholders {{ }}
+            use_of_var_derefs_origin {{ (V0, 'x) }}
+
+            block B0 {{
+                loan_issued_at('x, L0);
+                {}var_used_at(V0);
+                var_defined_at(V0);
+            }}
+            ",
+            first_effect
+        )
+    };
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(&program(""), &mut tables).expect("Parsing failure");
+    let options = ComputeOptions {
+        loans_out_of_scope: true,
+        ..ComputeOptions::default()
+    };
+    let output = Output::compute_with_options(&facts, Algorithm::Naive, &options);
+    let l0 = tables.loans.intern("L0");
+    let out_of_scope_at = tables.points.intern("\"Start(B0[2])\"");
+    assert_eq!(
+        output.loans_out_of_scope_at.into_iter().collect::<Vec<_>>(),
+        [(out_of_scope_at, vec![l0])]
+    );
+
+    let renumbered = Output::compute_with_options(
+        &facts,
+        Algorithm::DatafrogOpt,
+        &ComputeOptions {
+            point_order: PointOrder::ReversePostOrder,
+            ..options.clone()
+        },
+    );
+    assert_eq!(
+        renumbered
+            .loans_out_of_scope_at
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [(out_of_scope_at, vec![l0])]
+    );
+
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert!(output.loans_out_of_scope_at.is_empty());
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(&program("loan_killed_at(L0) / "), &mut tables)
+        .expect("Parsing failure");
+    let output = Output::compute_with_options(&facts, Algorithm::Naive, &options);
+    assert!(output.loans_out_of_scope_at.is_empty());
+}
+
+#[test]
+fn stop_on_move_errors() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).expect("facts");
+
+    let options = ComputeOptions {
+        dump_enabled: true,
+        move_error_policy: MoveErrorPolicy::Stop,
+        ..ComputeOptions::default()
+    };
+    let result = Output::compute_with_options(&facts, Algorithm::Naive, &options);
+
+    // the move errors are found, but the computation stops before liveness and borrowck
+    assert!(result.partial);
+    assert_eq!(result.move_errors.len(), 1);
+    assert!(result.var_live_on_entry.is_empty());
+    assert!(result.loan_live_at.is_empty());
+
+    // and it continues by default
+    let result = Output::compute(&facts, Algorithm::Naive, true);
+    assert!(!result.partial);
+    assert!(!result.var_live_on_entry.is_empty());
+}
+
+/// With the `two_phase_borrows` option, a loan with activations is only reserved until then: its
+/// earlier invalidations are not errors. Without the option, the activations are ignored.
+#[test]
+fn two_phase_borrows_conflict_after_activation() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'a) }
+
+        block B0 {
+            loan_issued_at('a, L0);
+            loan_invalidated_at(L0);
+            loan_activated_at(L0);
+            goto B1;
+        }
+
+        block B1 {
+            loan_invalidated_at(L0);
+            loan_issued_at('a, L0);
+            goto B2;
+        }
+
+        block B2 {
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    assert_eq!(facts.loan_activated_at.len(), 1);
+
+    let error_points = |result: &Output| {
+        let mut points: Vec<_> = result
+            .errors
+            .keys()
+            .map(|&point| tables.points.untern(point).to_string())
+            .collect();
+        points.sort();
+        points
+    };
+    let options = ComputeOptions {
+        two_phase_borrows: true,
+        ..ComputeOptions::default()
+    };
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::Hybrid,
+        Algorithm::ParallelNaive,
+    ] {
+        let result = Output::compute(&facts, algorithm, false);
+        assert_eq!(
+            error_points(&result),
+            ["\"Mid(B0[1])\"", "\"Mid(B1[0])\"", "\"Mid(B2[0])\""]
+        );
+
+        // the reservation doesn't conflict, and neither does the new one issued in `B1`
+        let result = Output::compute_with_options(&facts, algorithm, &options);
+        assert_eq!(error_points(&result), ["\"Mid(B1[0])\""]);
+    }
+}
+
+/// The progress hook receives the phases of the computation, and the rounds of each phase, in
+/// order.
+#[test]
+fn progress_hook_receives_phases_and_rounds() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0);
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let options = ComputeOptions {
+        progress: Some(ProgressHook::new(move |event| {
+            recorded.lock().unwrap().push(event)
+        })),
+        ..ComputeOptions::default()
+    };
+    let result = Output::compute_with_options(&facts, Algorithm::Hybrid, &options);
+    assert_eq!(result.errors.len(), 1);
+
+    let events = events.lock().unwrap();
+    let phases: Vec<_> = events
+        .iter()
+        .filter_map(|event| match *event {
+            ProgressEvent::Phase(name) => Some(name),
+            ProgressEvent::Round { .. } => None,
+        })
+        .collect();
+    assert_eq!(
+        phases,
+        [
+            "initialization",
+            "liveness",
+            "LocationInsensitive",
+            "DatafrogOpt"
+        ]
+    );
+
+    // each phase's rounds are numbered from 1, and the tuples of a fixpoint only grow
+    let opt_rounds: Vec<_> = events
+        .iter()
+        .skip_while(|&&event| event != ProgressEvent::Phase("DatafrogOpt"))
+        .filter_map(|event| match *event {
+            ProgressEvent::Round { round, tuples } => Some((round, tuples)),
+            ProgressEvent::Phase(_) => None,
+        })
+        .collect();
+    assert!(!opt_rounds.is_empty());
+    for (index, window) in opt_rounds.windows(2).enumerate() {
+        assert_eq!(window[0].0, index + 1);
+        assert!(window[0].1 <= window[1].1);
+    }
+}
+
+/// A computation is aborted when one of its iterations doesn't converge within the maximum number
+/// of rounds, with the sizes of the iteration's variables.
+#[test]
+fn max_rounds_abort_the_computation() {
+    // a loan flowing along a chain of blocks, where its origin is live everywhere: borrow
+    // checking needs many more rounds than initialization and liveness
+    let mut program = String::from("placeholders { }\nuse_of_var_derefs_origin { (V0, 'x) }\n");
+    for block in 0..10 {
+        let statement = match block {
+            0 => "loan_issued_at('x, L0), var_used_at(V0);",
+            9 => "loan_invalidated_at(L0), var_used_at(V0);",
+            _ => "var_used_at(V0);",
+        };
+        let goto = match block {
+            9 => String::new(),
+            _ => format!("goto B{};", block + 1),
+        };
+        program += &format!("block B{} {{ {} {} }}\n", block, statement, goto);
+    }
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(&program, &mut tables).expect("Parsing failure");
+
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::LocationInsensitive,
+        Algorithm::Hybrid,
+        Algorithm::ParallelNaive,
+    ] {
+        // the number of rounds of each phase, when they're not limited
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let options = ComputeOptions {
+            progress: Some(ProgressHook::new(move |event| {
+                recorded.lock().unwrap().push(event)
+            })),
+            ..ComputeOptions::default()
+        };
+        let unlimited = Output::compute_with_options(&facts, algorithm, &options);
+        assert_eq!(unlimited.errors.len(), 1, "{:?}", algorithm);
+        assert!(unlimited.aborted.is_none());
+
+        let mut phases: Vec<(&str, usize)> = Vec::new();
+        for event in events.lock().unwrap().iter() {
+            match *event {
+                ProgressEvent::Phase(name) => phases.push((name, 0)),
+                ProgressEvent::Round { round, .. } => phases.last_mut().unwrap().1 = round,
+            }
+        }
+        let max_rounds = phases[..2].iter().map(|&(_, rounds)| rounds).max().unwrap();
+        let aborted_phase = phases[2..]
+            .iter()
+            .find(|&&(_, rounds)| rounds > max_rounds)
+            .map(|&(name, _)| name);
+
+        let options = ComputeOptions {
+            max_rounds: Some(max_rounds),
+            ..ComputeOptions::default()
+        };
+        let limited = Output::compute_with_options(&facts, algorithm, &options);
+        match aborted_phase {
+            Some(phase) => {
+                let aborted = limited.aborted.expect("the computation wasn't aborted");
+                assert_eq!(aborted.phase, phase);
+                assert_eq!(aborted.max_rounds, max_rounds);
+                assert!(aborted.variables.iter().any(|variable| variable.delta > 0));
+                assert!(limited.errors.is_empty());
+            }
+            None => {
+                assert!(limited.aborted.is_none());
+                assert_eq!(limited.errors, unlimited.errors);
+            }
+        }
+    }
+
+    // the computation can also be aborted before borrow checking: there are no paths to
+    // initialize, but liveness needs more than one round
+    let options = ComputeOptions {
+        max_rounds: Some(1),
+        ..ComputeOptions::default()
+    };
+    let output = Output::compute_with_options(&facts, Algorithm::Naive, &options);
+    let aborted = output.aborted.expect("the computation wasn't aborted");
+    assert_eq!(aborted.phase, "liveness");
+    assert!(aborted.to_string().starts_with(
+        "the liveness iteration didn't converge within 1 rounds\n    var_live_on_entry: "
+    ));
+}
+
+/// The JSON logs of a computation have the spans of its phases and rounds, with the number of
+/// tuples of each round.
+#[test]
+fn json_logs_record_phases_and_rounds() {
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'x) }
+
+        block B0 {
+            loan_issued_at('x, L0);
+            loan_invalidated_at(L0);
+            var_used_at(V0);
+        }
+    ";
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let logs = Logs::default();
+    let writer = logs.clone();
+    let logger = cli::json_logger(EnvFilter::new("polonius_engine=debug"), move || {
+        writer.clone()
+    });
+    let result = {
+        let _logger = logger.set_default();
+        Output::compute(&facts, Algorithm::DatafrogOpt, false)
+    };
+    assert_eq!(result.errors.len(), 1);
+
+    // the closed spans: the phases, then the rounds, by their fields
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let closed: Vec<_> = logs
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("invalid JSON log"))
+        .filter(|log| {
+            let message = log.get("fields").and_then(|fields| fields.get("message"));
+            message.and_then(serde_json::Value::as_str) == Some("close")
+        })
+        .map(|log| log.get("span").unwrap().clone())
+        .collect();
+    let phases: Vec<_> = closed
+        .iter()
+        .filter_map(|span| span.get("phase").and_then(serde_json::Value::as_str))
+        .collect();
+    assert_eq!(phases, ["initialization", "liveness", "DatafrogOpt"]);
+
+    let rounds: Vec<_> = closed
+        .iter()
+        .filter(|span| span.get("name").and_then(serde_json::Value::as_str) == Some("round"))
+        .collect();
+    assert!(!rounds.is_empty());
+    for span in rounds {
+        assert!(
+            span.get("round")
+                .and_then(serde_json::Value::as_u64)
+                .unwrap()
+                > 0
+        );
+        assert!(span
+            .get("tuples")
+            .and_then(serde_json::Value::as_u64)
+            .is_some());
+    }
+}
+
+/// The progress shows the phases as they start, and their rounds at most once per second, with
+/// an estimate of the remaining time.
+#[test]
+fn progress_reports() {
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    let mut progress = Progress::new();
+
+    let phase = ProgressEvent::Phase;
+    let round = |round, tuples| ProgressEvent::Round { round, tuples };
+    assert_eq!(
+        progress.update(phase("liveness"), at(0)).unwrap(),
+        "liveness"
+    );
+    assert_eq!(
+        progress.update(phase("Naive"), at(500)).unwrap(),
+        "Naive (after liveness in 0.5s)"
+    );
+    assert_eq!(progress.update(round(1, 1000), at(1000)), None);
+    assert_eq!(
+        progress.update(round(2, 1100), at(1500)).unwrap(),
+        "Naive: round 2, 1100 tuples (+100), 1.0s, ETA ~1s"
+    );
+    // the decrease is measured since the last report
+    assert_eq!(progress.update(round(3, 1110), at(2000)), None);
+    assert_eq!(
+        progress.update(round(4, 1300), at(2500)).unwrap(),
+        "Naive: round 4, 1300 tuples (+190), 2.0s, still growing, no ETA"
+    );
+    assert_eq!(
+        progress.update(round(5, 1300), at(3500)).unwrap(),
+        "Naive: round 5, 1300 tuples (+0), 3.0s, converging"
+    );
+
+    // the new tuples decrease tenfold per round: they'll be less than one in 3 rounds
+    let rounds = progress::remaining_rounds(100_000, 1000, 2).unwrap();
+    assert!((rounds - 3.0).abs() < 1e-9);
+    assert_eq!(progress::remaining_rounds(10, 0, 1), Some(0.0));
+    assert_eq!(progress::remaining_rounds(10, 10, 1), None);
+}
+
+/// A shallow kill only kills the loan in the origin it was issued with: the reborrows it flowed
+/// into keep it, unlike with a deep kill.
+#[test]
+fn shallow_kills_keep_reborrowed_loans() {
+    let program = r"
+        placeholders { }
+        use_of_var_derefs_origin { (V0, 'a), (V1, 'b) }
+
+        block B0 {
+            loan_issued_at('a, L0);
+            outlives('a: 'b);
+            loan_shallow_killed_at(L0);
+            loan_invalidated_at(L0), var_used_at(V0), var_used_at(V1);
+        }
+    ";
+
+    let error_count = |program: &str, algorithm| {
+        let mut tables = intern::InternerTables::new();
+        let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+        let result = Output::compute(&facts, algorithm, false);
+        result
+            .errors
+            .values()
+            .map(|loans| loans.len())
+            .sum::<usize>()
+    };
+    for &algorithm in &[
+        Algorithm::Naive,
+        Algorithm::DatafrogOpt,
+        Algorithm::Hybrid,
+        Algorithm::ParallelNaive,
+    ] {
+        // the reborrow `'b` still contains the loan...
+        assert_eq!(error_count(program, algorithm), 1);
+
+        // ...unless the kill is deep, or `'b` is dead
+        let deep = program.replace("loan_shallow_killed_at", "loan_killed_at");
+        assert_eq!(error_count(&deep, algorithm), 0);
+        let dead_reborrow = program.replace(", var_used_at(V1)", "");
+        assert_eq!(error_count(&dead_reborrow, algorithm), 0);
+    }
+}
+
+/// Universal regions are live at every point, unless the `universal_region_live_at` relation
+/// restricts their liveness to some specific points.
+#[test]
+fn universal_region_live_at_restricts_liveness() {
+    let program = r"
+        placeholders { 'a, 'b }
+
+        block B0 {
+            outlives('a: 'b);
+            goto B1;
+        }
+
+        block B1 {
+            outlives('b: 'a);
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let a = tables.origins.intern("'a");
+    let b = tables.origins.intern("'b");
+    let start = tables.points.intern("\"Start(B0[0])\"");
+    let end = tables.points.intern("\"Mid(B1[0])\"");
+    facts.universal_region_live_at.push((a, start));
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let result = Output::compute(&facts, algorithm, true);
+
+        // `'a` is only live where it was requested...
+        assert!(result.origins_live_at(start).contains(&a));
+        assert!(!result.origins_live_at(end).contains(&a));
+
+        // ...while `'b` is still live everywhere
+        assert!(result.origins_live_at(start).contains(&b));
+        assert!(result.origins_live_at(end).contains(&b));
+    }
+}
+
 #[test]
 fn conditional_init() {
     let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -863,4 +2807,1334 @@ fn conditional_init() {
     let move_errors = result.move_errors.get(&error_point).unwrap();
     assert_eq!(move_errors.len(), 1);
     assert_eq!(move_errors[0], tables.paths.intern("\"mp1\""));
+
+    // `mp1` starts uninitialized, is initialized in only one of the branches, and is then
+    // only maybe initialized when accessed at the error point.
+    let mp1 = tables.paths.intern("\"mp1\"");
+    let start = tables.points.intern("\"Start(bb0[0])\"");
+    let assigned_point = tables.points.intern("\"Mid(bb5[0])\"");
+    assert!(result.path_moved_at[&start].contains(&mp1));
+    assert!(result.path_assigned_at[&assigned_point].contains(&mp1));
+    assert!(result.path_definitely_initialized_on_exit[&assigned_point].contains(&mp1));
+    assert!(result.path_maybe_initialized_on_exit[&error_point].contains(&mp1));
+    assert!(!result
+        .path_definitely_initialized_on_exit
+        .get(&error_point)
+        .into_iter()
+        .any(|paths| paths.contains(&mp1)));
+
+    // The same initialization is available through the output's accessors.
+    assert!(result
+        .paths_maybe_initialized_at(error_point)
+        .contains(&mp1));
+    assert!(result.paths_maybe_moved_at(error_point).contains(&mp1));
+    assert!(!result.paths_maybe_moved_at(assigned_point).contains(&mp1));
+
+    // It's never initialized on the other branch.
+    assert!(matches!(
+        result.move_error_kinds[&error_point][&mp1],
+        MoveErrorKind::Uninitialized
+    ));
+}
+
+/// A move error on a path which was initialized, and then moved out, points at the move.
+#[test]
+fn moved_out_move_error() {
+    let program = r"
+        placeholders { }
+
+        block B0 {
+            loan_invalidated_at(L0); // generate a point, where mp1 is initialized below
+            goto B1, B2;
+        }
+
+        block B1 {
+            loan_invalidated_at(L0); // generate a point, where mp1 is moved out below
+            goto B2;
+        }
+
+        block B2 {
+            loan_invalidated_at(L0); // generate a point, where mp1 is accessed below
+        }
+    ";
+
+    let mut tables = intern::InternerTables::new();
+    let mut facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+
+    let mp1 = tables.paths.intern("\"mp1\"");
+    let start = tables.points.intern("\"Start(B0[0])\"");
+    let assigned = tables.points.intern("\"Mid(B0[0])\"");
+    let moved = tables.points.intern("\"Mid(B1[0])\"");
+    let accessed = tables.points.intern("\"Mid(B2[0])\"");
+    facts.path_moved_at_base.push((mp1, start));
+    facts.path_assigned_at_base.push((mp1, assigned));
+    facts.path_moved_at_base.push((mp1, moved));
+    facts.path_accessed_at_base.push((mp1, accessed));
+
+    let result = Output::compute(&facts, Algorithm::Naive, false);
+    assert_eq!(result.move_errors[&accessed], [mp1]);
+    match &result.move_error_kinds[&accessed][&mp1] {
+        MoveErrorKind::MovedOut { moved_at } => assert_eq!(moved_at, &[moved]),
+        kind => panic!("unexpected move error kind: {:?}", kind),
+    }
+}
+
+#[test]
+fn interner_roundtrip() {
+    let mut tables = intern::InternerTables::new();
+    let names: Vec<_> = (0..1000)
+        .map(|idx| format!("\"Mid(bb{}[{}])\"", idx, idx % 7))
+        .collect();
+
+    // short names are stored inline, long ones in the arena: both are interned and
+    // looked up the same way
+    let points: Vec<Point> = names
+        .iter()
+        .map(|name| tables.points.intern(name))
+        .collect();
+    for (name, &point) in names.iter().zip(&points) {
+        assert_eq!(tables.points.intern(name), point);
+        assert_eq!(tables.points.get(name), Some(point));
+        assert_eq!(tables.points.untern(point), name);
+    }
+
+    assert_eq!(tables.points.len(), names.len());
+    assert_eq!(tables.points.get("\"Start(bb0[0])\""), None);
+    assert_eq!(tables.points.untern_vec(&points[..2]), &names[..2]);
+}
+
+/// The open-addressing table doubles when it's more than half full, and the strings are found at
+/// the same atoms after each growth.
+#[test]
+fn interner_table_grows() {
+    let mut tables = intern::InternerTables::new();
+    assert_eq!(tables.points.bucket_count(), 16);
+
+    let names: Vec<_> = (0..100)
+        .map(|idx| format!("\"Mid(bb{}[0])\"", idx))
+        .collect();
+    for (idx, name) in names.iter().enumerate() {
+        let point = tables.points.intern(name);
+        assert_eq!(point, idx.into());
+
+        let buckets = tables.points.bucket_count();
+        assert!(buckets.is_power_of_two());
+        assert!(
+            (idx + 1) * 2 <= buckets,
+            "{} atoms in {} buckets",
+            idx + 1,
+            buckets
+        );
+        for (idx, name) in names[..=idx].iter().enumerate() {
+            assert_eq!(tables.points.get(name), Some(idx.into()));
+        }
+    }
+    assert_eq!(tables.points.bucket_count(), 256);
+
+    // interning the strings again doesn't grow the table
+    for name in &names {
+        tables.points.intern(name);
+    }
+    assert_eq!(tables.points.len(), names.len());
+    assert_eq!(tables.points.bucket_count(), 256);
+}
+
+/// The strings hashing to the same bucket are probed in the next ones, wrapping around the end
+/// of the table.
+#[test]
+fn interner_table_collisions() {
+    // names hashing to the last of the 16 buckets, some of them too long to be stored inline
+    let colliding: Vec<String> = (0..)
+        .map(|idx| format!("\"Mid(bb{}[{}])\"", idx, "0".repeat(idx % 3 * 5)))
+        .filter(|name| intern::bucket_of(name, 16) == 15)
+        .take(5)
+        .collect();
+
+    let mut tables = intern::InternerTables::new();
+    let points: Vec<Point> = colliding[..4]
+        .iter()
+        .map(|name| tables.points.intern(name))
+        .collect();
+    assert_eq!(tables.points.bucket_count(), 16);
+    assert_eq!(points, [0.into(), 1.into(), 2.into(), 3.into()]);
+    for (name, &point) in colliding.iter().zip(&points) {
+        assert_eq!(tables.points.get(name), Some(point));
+        assert_eq!(tables.points.intern(name), point);
+        assert_eq!(tables.points.untern(point), name);
+    }
+
+    // a colliding string which wasn't interned is not found at the end of the probe sequence
+    assert_eq!(tables.points.get(&colliding[4]), None);
+    assert_eq!(tables.points.intern(&colliding[4]), 4.into());
+    assert_eq!(tables.points.len(), 5);
+}
+
+/// The interners' benchmark interns the same atoms with the owned strings interner and the arena
+/// one.
+#[test]
+fn interner_bench() {
+    let names = ["\"Mid(bb0[0])\"", "\"Start(bb0[1])\"", "\"Mid(bb0[0])\""];
+    let [owned, arena] = intern::bench_interners(&names, 1);
+    assert_eq!(owned.atoms, 2);
+    assert_eq!(arena.atoms, 2);
+    assert!(owned.heap_size > 0 && arena.heap_size > 0);
+}
+
+/// Atoms can be given as their numeric indices instead of names to intern, but not both.
+#[test]
+fn load_numeric_atoms() {
+    let bundle = "# cfg_edge\n0\t1\n1\t2\n\
+                  # loan_issued_at\n'a\tL0\t0\n\
+                  # loan_invalidated_at\n1\tL0\n\
+                  # var_used_at\nv\t2\n\
+                  # use_of_var_derefs_origin\nv\t'a\n";
+    let path = Path::new("numeric.bundle");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()).unwrap();
+    assert_eq!(facts.cfg_edge, [(0.into(), 1.into()), (1.into(), 2.into())]);
+    assert_eq!(tables.points.untern(2.into()), "2");
+    assert_eq!(tables.loans.untern(0.into()), "L0");
+
+    let output = Output::compute(&facts, Algorithm::Naive, false);
+    assert_eq!(output.errors[&1.into()], [0.into()]);
+
+    // the names of the atoms can be replaced by their indices
+    let numeric = tables.numeric();
+    assert_eq!(numeric.loans.untern(0.into()), "0");
+    assert_eq!(numeric.points.len(), tables.points.len());
+
+    let bundle = "# cfg_edge\n0\t1\n\"Mid(bb0[0])\"\t1\n";
+    let tables = &mut intern::InternerTables::new();
+    match tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()) {
+        Err(error::Error::MixedAtomForms { kind }) => assert_eq!(kind, "points"),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // a huge index is rejected, instead of allocating the names of the atoms up to it
+    let tables = &mut intern::InternerTables::new();
+    match tables.points.intern_value("4000000000") {
+        Err(error::Error::InternerOverflow { kind }) => assert_eq!(kind, "points"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(tables.points.len(), 0);
+    assert_eq!(tables.points.intern("\"Mid(bb0[0])\""), 0.into());
+}
+
+/// The tables saved to a symbols file are reloaded with the same indices for the same atoms.
+#[test]
+fn save_and_load_symbols() -> Result<(), Box<dyn Error>> {
+    let bundle = "# cfg_edge\n\"Start(bb0[0])\"\t\"a\tb\"\n\
+                  # loan_issued_at\n'a\tL0\t\"a\tb\"\n\
+                  # var_used_at\nv\t\"a\tb\"\n";
+    let tables = &mut intern::InternerTables::new();
+    tab_delim::load_facts_bundle(tables, Path::new("symbols.bundle"), bundle.as_bytes())?;
+
+    let path = std::env::temp_dir().join(format!("polonius-symbols-{}", std::process::id()));
+    tables.save(&path)?;
+    let mut loaded = intern::InternerTables::load(&path)?;
+    for name in &["\"Start(bb0[0])\"", "\"a\tb\""] {
+        assert_eq!(loaded.points.get(name), tables.points.get(name));
+    }
+    assert_eq!(loaded.loans.get("L0"), Some(0.into()));
+    assert_eq!(loaded.origins.untern(0.into()), "'a");
+    assert_eq!(loaded.variables.untern(0.into()), "v");
+
+    // the new atoms are interned after the loaded ones
+    assert_eq!(loaded.points.intern("\"Mid(bb0[0])\""), 2.into());
+
+    // as are the numeric atoms
+    let numeric = tables.numeric();
+    numeric.save(&path)?;
+    let mut loaded = intern::InternerTables::load(&path)?;
+    assert_eq!(loaded.points.len(), 2);
+    assert_eq!(loaded.points.intern_value("2")?, 2.into());
+
+    std::fs::write(&path, "points\tA\nloans\n")?;
+    match intern::InternerTables::load(&path) {
+        Err(error::Error::Parse { line, message, .. }) => {
+            assert_eq!(line, 2);
+            assert_eq!(
+                message,
+                "expected the kind of atom and its name, separated by a tab, but found `loans`"
+            );
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+    std::fs::write(&path, "points\tA\npoints\tA\n")?;
+    assert!(intern::InternerTables::load(&path).is_err());
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Quoted values can contain tabs and escaped quotes, which are decoded, and malformed values are
+/// reported where they are.
+#[test]
+fn load_quoted_values() {
+    let bundle = "# cfg_edge\n\"a\tb\"\t\"say \\\"hi\\\" \\u{e9}\"\n";
+    let path = Path::new("quoted.bundle");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()).unwrap();
+    let (point1, point2) = facts.cfg_edge[0];
+    assert_eq!(tables.points.untern(point1), "\"a\tb\"");
+    assert_eq!(tables.points.untern(point2), "\"say \"hi\" \u{e9}\"");
+
+    for &(bundle, column, message) in &[
+        (
+            "# cfg_edge\na\"b\t\"c\"\n",
+            2,
+            "unexpected quote in an unquoted value",
+        ),
+        ("# cfg_edge\n\"a\"\t\"b\n", 5, "unterminated quoted value"),
+        (
+            "# cfg_edge\n\"a\"b\t\"c\"\n",
+            4,
+            "expected a tab after the quoted value",
+        ),
+        (
+            "# cfg_edge\n\"a\\x\"\t\"b\"\n",
+            3,
+            "invalid escape sequence `\\x`",
+        ),
+    ] {
+        let tables = &mut intern::InternerTables::new();
+        match tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()) {
+            Err(error::Error::Parse {
+                file,
+                line,
+                column: error_column,
+                message: error_message,
+            }) => {
+                assert_eq!(file.as_deref(), Some(path));
+                assert_eq!((line, error_column), (2, column), "{}", bundle);
+                assert_eq!(error_message, message);
+            }
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+}
+
+/// The values written in a facts file, quoted when needed, are read back as they were.
+#[test]
+fn write_quoted_values() {
+    let names = [
+        "\"Mid(bb0[0])\"",
+        "plain",
+        "\"with\ttab\"",
+        "\"say \"hi\"\"",
+        "\"back\\slash\"",
+        "back\\slash",
+        "\"line\nbreak\"",
+    ];
+    let mut bundle = b"# cfg_edge\n".to_vec();
+    for pair in names.windows(2) {
+        tab_delim::write_tab_delimited_row(&mut bundle, pair).unwrap();
+    }
+
+    let path = Path::new("written.bundle");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_facts_bundle(tables, path, &bundle[..]).unwrap();
+    let read: Vec<_> = facts
+        .cfg_edge
+        .iter()
+        .map(|&(point1, _)| tables.points.untern(point1))
+        .chain(
+            facts
+                .cfg_edge
+                .last()
+                .map(|&(_, point2)| tables.points.untern(point2)),
+        )
+        .collect();
+    assert_eq!(read, names);
+
+    // the names which were already valid values are unchanged, the others are escaped
+    assert_eq!(tab_delim::quote_value(names[0]), names[0]);
+    assert_eq!(tab_delim::quote_value(names[1]), names[1]);
+    assert_eq!(tab_delim::quote_value(names[2]), "\"with\\ttab\"");
+    assert_eq!(tab_delim::quote_value(names[5]), names[5]);
+
+    // the values which aren't between quotes are read back between quotes
+    assert_eq!(tab_delim::quote_value("with\ttab"), "\"with\\ttab\"");
+}
+
+/// Copies the facts of the `basic_move_error` test into a fresh temporary directory, to be
+/// modified by the test `name`.
+fn copy_facts_to_temp_dir(name: &str) -> std::path::PathBuf {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let temp_dir = std::env::temp_dir().join(format!("polonius-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    for entry in std::fs::read_dir(&facts_dir).unwrap() {
+        let file = entry.unwrap().path();
+        std::fs::copy(&file, temp_dir.join(file.file_name().unwrap())).unwrap();
+    }
+    temp_dir
+}
+
+/// The arguments after `--` are all directories of facts, so that the ones named like a
+/// subcommand, or like a flag, can be analyzed.
+#[test]
+fn fact_dirs_named_like_subcommands() {
+    let args =
+        |args: &[&str]| -> Vec<std::ffi::OsString> { args.iter().map(|&arg| arg.into()).collect() };
+    let options = cli::options_from_vec(args(&["--skip-timing", "--", "diff", "-v"])).unwrap();
+    assert_eq!(options.fact_dirs, ["diff", "-v"]);
+    let options = cli::options_from_vec(args(&["eval-rules", "--", "parity"])).unwrap();
+    assert_eq!(options.fact_dirs, ["eval-rules", "parity"]);
+
+    // a directory of facts named `diff`, in a directory of its own
+    let temp_dir = copy_facts_to_temp_dir("subcommand-names");
+    let facts_dir = temp_dir.join("diff");
+    std::fs::create_dir(&facts_dir).unwrap();
+    for entry in std::fs::read_dir(&temp_dir).unwrap() {
+        let file = entry.unwrap().path();
+        if file.is_file() {
+            std::fs::rename(&file, facts_dir.join(file.file_name().unwrap())).unwrap();
+        }
+    }
+    let options =
+        cli::options_from_vec(args(&["--skip-timing", "--", facts_dir.to_str().unwrap()]));
+    let result = cli::main(options.unwrap());
+    std::fs::remove_dir_all(&temp_dir).unwrap();
+    assert!(result.is_ok());
+}
+
+/// Fact files can be named after the relations' legacy names, and unrecognized files are ignored.
+#[test]
+fn load_legacy_fact_files() {
+    let facts_dir = copy_facts_to_temp_dir("legacy-fact-files");
+    let tables = &mut intern::InternerTables::new();
+    let expected = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+
+    for &(legacy_name, relation) in &[
+        ("borrow_region", "loan_issued_at"),
+        ("invalidates", "loan_invalidated_at"),
+        ("var_uses_region", "use_of_var_derefs_origin"),
+    ] {
+        std::fs::rename(
+            facts_dir.join(format!("{}.facts", relation)),
+            facts_dir.join(format!("{}.facts", legacy_name)),
+        )
+        .unwrap();
+    }
+    std::fs::write(facts_dir.join("region_live_at.facts"), "").unwrap();
+
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    std::fs::remove_dir_all(&facts_dir).unwrap();
+
+    assert!(!facts.loan_issued_at.is_empty());
+    assert_eq!(facts.loan_issued_at, expected.loan_issued_at);
+    assert_eq!(facts.loan_invalidated_at, expected.loan_invalidated_at);
+    assert_eq!(
+        facts.use_of_var_derefs_origin,
+        expected.use_of_var_derefs_origin
+    );
+}
+
+/// The fact files can be concatenated into a single bundle, with a header naming each file.
+#[test]
+fn load_facts_bundle() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let expected = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+
+    // the files can have their legacy names, and be separated by blank lines
+    let mut bundle = String::new();
+    for entry in std::fs::read_dir(&facts_dir).unwrap() {
+        let file = entry.unwrap().path();
+        if file.extension().unwrap() != "facts" {
+            continue;
+        }
+
+        let name = match file.file_stem().unwrap().to_str().unwrap() {
+            "path_moved_at_base" => "moved_out_at",
+            name => name,
+        };
+        let facts = std::fs::read_to_string(&file).unwrap();
+        bundle += &format!("# {}\n{}\n", name, facts);
+    }
+
+    let path = Path::new("facts.bundle");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()).unwrap();
+    assert!(!facts.cfg_edge.is_empty());
+    assert_eq!(facts.loan_issued_at, expected.loan_issued_at);
+    assert_eq!(facts.cfg_edge, expected.cfg_edge);
+    assert_eq!(facts.loan_invalidated_at, expected.loan_invalidated_at);
+    assert_eq!(facts.path_moved_at_base, expected.path_moved_at_base);
+
+    // unknown relations, and facts without a header, are errors
+    for &(bundle, expected_line) in &[
+        ("# cfg_edge\n\"a\"\t\"b\"\n\n# cfg_edges\n", 4),
+        ("\"a\"\t\"b\"\n", 1),
+        ("# cfg_edge\n# cfg_edge\n", 2),
+    ] {
+        match tab_delim::load_facts_bundle(tables, path, bundle.as_bytes()) {
+            Err(error::Error::Parse { file, line, .. }) => {
+                assert_eq!(file.as_deref(), Some(path));
+                assert_eq!(line, expected_line);
+            }
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+}
+
+/// The facts of two inputs are compared per relation, with their atoms aligned by name.
+#[test]
+fn diff_fact_directories() {
+    let facts_dir = copy_facts_to_temp_dir("diff");
+    let tables = &mut intern::InternerTables::new();
+    let before = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+
+    let mut output = Vec::new();
+    let differences = diff::diff_facts(&before, &before, tables, &mut output).unwrap();
+    assert_eq!(differences, 0);
+    assert!(output.is_empty());
+
+    // replace the first CFG edge, and add a new point
+    let cfg_edge = facts_dir.join("cfg_edge.facts");
+    let facts = std::fs::read_to_string(&cfg_edge).unwrap();
+    let mut lines: Vec<_> = facts.lines().collect();
+    let removed = lines.remove(0);
+    lines.push("\"Start(bb0[0])\"\t\"Mid(bb99[0])\"");
+    std::fs::write(&cfg_edge, lines.join("\n")).unwrap();
+
+    let after = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    std::fs::remove_dir_all(&facts_dir).unwrap();
+
+    let mut output = Vec::new();
+    let differences = diff::diff_facts(&before, &after, tables, &mut output).unwrap();
+    assert_eq!(differences, 2);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!(
+            "# cfg_edge: 1 removed, 1 added\n\
+             - {}\n\
+             + \"Start(bb0[0])\" \"Mid(bb99[0])\"\n",
+            removed.replace('\t', " ")
+        )
+    );
+}
+
+#[test]
+fn loader_errors() {
+    let tables = &mut intern::InternerTables::new();
+
+    // missing files
+    let missing_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("missing");
+    match tab_delim::load_tab_delimited_facts(tables, &missing_dir) {
+        Err(error::Error::Io { path, .. }) => assert_eq!(path, missing_dir),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // malformed facts
+    let facts_dir = copy_facts_to_temp_dir("loader-errors");
+    let cfg_edge = facts_dir.join("cfg_edge.facts");
+    std::fs::write(
+        &cfg_edge,
+        "\"Start(bb0[0])\"\t\"Mid(bb0[0])\"\n\"Mid(bb0[0])\"\n",
+    )
+    .unwrap();
+    match tab_delim::load_tab_delimited_facts(tables, &facts_dir) {
+        Err(error::Error::Parse {
+            file, line, column, ..
+        }) => {
+            assert_eq!(file, Some(cfg_edge));
+            assert_eq!((line, column), (2, 15));
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    std::fs::remove_dir_all(&facts_dir).unwrap();
+
+    // and syntax errors in programs
+    let program = "placeholders { 'a }\nblock B0 {\n    outlives('a 'a);\n}";
+    match parse_from_program(program, tables) {
+        Err(error::Error::Parse {
+            file, line, column, ..
+        }) => {
+            assert_eq!(file, None);
+            assert_eq!((line, column), (3, 17));
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+/// The output files have the same contents, in rows sorted by atoms, whether they are compressed
+/// or not.
+#[test]
+fn dump_output_files() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+
+    let output_dir = std::env::temp_dir().join(format!("polonius-dump-{}", std::process::id()));
+    let gzip_dir = output_dir.join("gzip");
+    let style = dump::DumpStyle::Tsv;
+    let focus = dump::Focus::default();
+    dump::dump_output(
+        &output,
+        &Some(output_dir.clone()),
+        tables,
+        false,
+        style,
+        &focus,
+        None,
+    )
+    .unwrap();
+    dump::dump_output(
+        &output,
+        &Some(gzip_dir.clone()),
+        tables,
+        true,
+        style,
+        &focus,
+        None,
+    )
+    .unwrap();
+
+    let read = |name: &str| std::fs::read_to_string(output_dir.join(name)).unwrap();
+    let read_gzip = |name: &str| {
+        let file = std::fs::File::open(gzip_dir.join(name)).unwrap();
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(file), &mut contents)
+            .unwrap();
+        contents
+    };
+
+    let move_errors = read("move_errors.facts");
+    let var_live_on_entry = read("var_live_on_entry.facts");
+    assert_eq!(move_errors, "\"Mid(bb9[20])\"\t\"mp1\"\n");
+    assert!(var_live_on_entry.lines().count() > 1);
+    assert_eq!(read_gzip("move_errors.facts.gz"), move_errors);
+    assert_eq!(read_gzip("var_live_on_entry.facts.gz"), var_live_on_entry);
+
+    // the manifest lists the dumped relations, and the number of atoms of each kind
+    let manifest = Manifest::from_json(&read(manifest::MANIFEST_FILE)).unwrap();
+    assert_eq!(manifest.format_version, manifest::FORMAT_VERSION);
+    assert!(manifest.relations.iter().any(|name| name == "move_errors"));
+    assert_eq!(manifest.atoms["points"], tables.points.len());
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+}
+
+/// In rustc's layout, the relations are the only files of the function's directory, with the
+/// same contents as the output files.
+#[test]
+fn dump_rustc_layout_files() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let mut output = Output::compute(&facts, Algorithm::Naive, true);
+    output.relation_sizes = Some(Default::default());
+
+    let output_dir = std::env::temp_dir().join(format!("polonius-rustc-{}", std::process::id()));
+    let function_dir = output_dir.join("basic_move_error");
+    let focus = dump::Focus::default();
+    dump::dump_rustc_layout(&output, &function_dir, tables, &focus, None).unwrap();
+    dump::dump_output(
+        &output,
+        &Some(output_dir.clone()),
+        tables,
+        false,
+        dump::DumpStyle::Tsv,
+        &focus,
+        None,
+    )
+    .unwrap();
+
+    let mut files: Vec<String> = std::fs::read_dir(&function_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert!(files.iter().all(|file| file.ends_with(".facts")));
+    assert!(files.contains(&"move_errors.facts".to_string()));
+    assert!(!files.contains(&"rounds_to_fixpoint.facts".to_string()));
+    for file in &files {
+        assert_eq!(
+            std::fs::read_to_string(function_dir.join(file)).unwrap(),
+            std::fs::read_to_string(output_dir.join(file)).unwrap(),
+        );
+    }
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+}
+
+/// The manifest of a directory of facts is read back, and its version of the facts format is
+/// checked against the current one.
+#[test]
+fn manifest_versions() {
+    let tables = intern::InternerTables::new();
+    let mut manifest = Manifest::new(&["cfg_edge", "loan_issued_at"], &tables);
+    assert_eq!(
+        Manifest::from_json(&manifest.to_json()),
+        Ok(manifest.clone())
+    );
+    assert_eq!(manifest.version_mismatch(), None);
+
+    manifest.format_version.patch += 1;
+    assert_eq!(manifest.version_mismatch(), None);
+    manifest.format_version.minor += 1;
+    assert!(manifest.version_mismatch().unwrap().contains("newer"));
+    manifest.format_version.major += 1;
+    assert!(manifest
+        .version_mismatch()
+        .unwrap()
+        .contains("incompatible"));
+
+    // the facts are still loaded when their manifest doesn't match, or is malformed
+    let facts_dir = copy_facts_to_temp_dir("manifest-versions");
+    manifest::write_manifest(&facts_dir, &manifest).unwrap();
+    let read_back = manifest::check_manifest(&facts_dir);
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir);
+    std::fs::write(
+        facts_dir.join(manifest::MANIFEST_FILE),
+        "{ \"producer\": 1 }",
+    )
+    .unwrap();
+    let malformed = manifest::check_manifest(&facts_dir);
+    std::fs::remove_dir_all(&facts_dir).unwrap();
+
+    assert_eq!(read_back, Some(manifest));
+    assert!(!facts.unwrap().cfg_edge.is_empty());
+    assert_eq!(malformed, None);
+    assert_eq!(
+        Manifest::from_json("{ \"producer\": \"rustc\", \"format_version\": \"1.x\" }"),
+        Err("invalid version `1.x`, expected `major.minor.patch` at line 1 column 48".to_string())
+    );
+}
+
+/// Each column is aligned to its own width, and the separated values are quoted as needed.
+#[test]
+fn dump_styles() {
+    let rows = vec![
+        vec!["\"Mid(bb0[0])\"", "'a", "L0"],
+        vec!["\"Start(bb10[2])\"", "'_#10r", "L1"],
+    ];
+    let write = |style| {
+        let mut output = Vec::new();
+        dump::write_rows(Some("relation"), &mut output, style, &rows).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        write(dump::DumpStyle::Aligned),
+        "relation \"Mid(bb0[0])\"    'a     L0\n\
+         relation \"Start(bb10[2])\" '_#10r L1\n"
+    );
+    assert_eq!(
+        write(dump::DumpStyle::Tsv),
+        "relation\t\"Mid(bb0[0])\"\t'a\tL0\n\
+         relation\t\"Start(bb10[2])\"\t'_#10r\tL1\n"
+    );
+    assert_eq!(
+        write(dump::DumpStyle::Csv),
+        "relation,\"\"\"Mid(bb0[0])\"\"\",'a,L0\n\
+         relation,\"\"\"Start(bb10[2])\"\"\",'_#10r,L1\n"
+    );
+
+    assert_eq!("CSV".parse(), Ok(dump::DumpStyle::Csv));
+    assert!("json".parse::<dump::DumpStyle>().is_err());
+}
+
+/// The points of each basic block are grouped into a cluster of the graph.
+#[test]
+fn graphviz_clusters() {
+    let program = r"
+        placeholders { 'a }
+        block B0 {
+            loan_issued_at('a, L0), outlives('a: 'b), var_used_at(V0);
+            loan_invalidated_at(L0);
+            goto B1;
+        }
+        block B1 {
+            use('a);
+        }
+    ";
+    let result = crate::analyze_program(program, Algorithm::Naive).unwrap();
+    let focus = dump::Focus::default();
+    let graph = dump::render_graphviz(
+        &result.output,
+        &result.facts,
+        &result.tables,
+        None,
+        &focus,
+        false,
+    );
+
+    let cluster = graph.find("subgraph \"cluster_B0\"").unwrap();
+    let end = cluster + graph[cluster..].find("}\n").unwrap();
+    let nodes: Vec<_> = graph[cluster..end]
+        .lines()
+        .filter_map(|line| line.strip_prefix("  label = \"{ <f0> "))
+        .map(|label| &label[..label.find(" |").unwrap()])
+        .collect();
+    assert_eq!(
+        nodes,
+        [
+            r#"\"Start\(B0[0]\)\""#,
+            r#"\"Mid\(B0[0]\)\""#,
+            r#"\"Start\(B0[1]\)\""#,
+            r#"\"Mid\(B0[1]\)\""#,
+        ]
+    );
+    assert!(graph.contains("subgraph \"cluster_B1\""));
+
+    // the edges between blocks are not weighted
+    let edges = graph.matches(" -> ").count();
+    assert_eq!(edges, 5);
+    assert_eq!(graph.matches("weight = 10").count(), 4);
+
+    // the tuples of the first relation of the inputs at a point, and the count of the others
+    let graph = dump::render_graphviz(
+        &result.output,
+        &result.facts,
+        &result.tables,
+        Some(1),
+        &focus,
+        false,
+    );
+    assert!(graph.contains(r"loan_issued_at\('a, L0, _\)\l | 2 more relations\l | OUTPUTS"));
+}
+
+/// The constraint graph has a node per origin, with the loans issued in it, and an edge per
+/// `subset_base` constraint, with the points where it holds.
+#[test]
+fn constraint_graph() {
+    let program = r"
+        placeholders { 'a }
+        block B0 {
+            loan_issued_at('x, L0), outlives('x: 'a);
+            outlives('x: 'a), outlives('a: 'y);
+        }
+    ";
+    let mut tables = intern::InternerTables::new();
+    let facts = parse_from_program(program, &mut tables).expect("Parsing failure");
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    let graph = dump::render_constraint_graph(&output, &facts, &tables, false);
+    let [a, x, y] = ["'a", "'x", "'y"].map(|name| tables.origins.intern(name).index());
+
+    assert!(graph.starts_with("digraph constraints {"));
+    assert!(graph.contains(&format!(
+        "  {} [label=\"'a\" style=filled fillcolor=\"#c9c9ff\"];",
+        a
+    )));
+    assert!(graph.contains(&format!("  {} [label=\"'x\\nL0 at Mid(B0[0])\"];", x)));
+    assert!(graph.contains(&format!(
+        "  {} -> {} [label=\"Mid(B0[0])\\nMid(B0[1])\"];",
+        x, a
+    )));
+    assert!(graph.contains(&format!("  {} -> {} [label=\"Mid(B0[1])\"];", a, y)));
+    assert_eq!(graph.matches(" -> ").count(), 2);
+}
+
+/// With short names, the atoms are shown by their kind and index in the graphs, with a legend of
+/// their names, and the focus still selects them by name.
+#[test]
+fn graphviz_short_names() {
+    let program = r"
+        placeholders { 'a }
+        block B0 {
+            loan_issued_at('a, L0), outlives('a: 'b);
+            loan_issued_at('b, L1);
+            loan_invalidated_at(L0);
+        }
+    ";
+    let result = crate::analyze_program(program, Algorithm::Naive).unwrap();
+    let tables = &result.tables;
+    let [a, b] = ["'a", "'b"].map(|name| tables.origins.get(name).unwrap().index());
+    let [l0, l1] = ["L0", "L1"].map(|name| tables.loans.get(name).unwrap().index());
+    let mid = tables.points.get("\"Mid(B0[0])\"").unwrap().index();
+
+    let focus = dump::Focus {
+        loans: vec!["L0".to_string()],
+        ..Default::default()
+    };
+    let graph = dump::render_graphviz(&result.output, &result.facts, tables, None, &focus, true);
+    assert!(graph.contains(&format!(r"loan_issued_at\(O{}, L{}, _\)", a, l0)));
+    assert!(!graph.contains(&format!("L{}, _", l1)));
+    assert!(graph.contains("subgraph \"cluster_B0\""));
+    assert!(graph.contains(&format!(
+        "<tr><td align=\"left\">P{}</td><td align=\"left\">Mid(B0[0])</td></tr>",
+        mid
+    )));
+    assert!(graph.ends_with("</table>>\n];\n}"));
+
+    let graph = dump::render_constraint_graph(&result.output, &result.facts, tables, true);
+    assert!(graph.contains(&format!("  {} -> {} [label=\"P{}\"];", a, b, mid)));
+    assert!(graph.contains(&format!(
+        "<tr><td align=\"left\">O{}</td><td align=\"left\">'a</td></tr>",
+        a
+    )));
+}
+
+/// Only the tuples involving the atoms in focus are dumped and visualized.
+#[test]
+fn focus_filters() {
+    let program = r"
+        placeholders { 'a }
+        block B0 {
+            loan_issued_at('a, L0), outlives('a: 'b), var_used_at(V0);
+            loan_issued_at('b, L1);
+            loan_invalidated_at(L0);
+        }
+    ";
+    let result = crate::analyze_program(program, Algorithm::Naive).unwrap();
+    let focus = dump::Focus {
+        loans: vec!["L0".to_string()],
+        ..Default::default()
+    };
+    let graph = dump::render_graphviz(
+        &result.output,
+        &result.facts,
+        &result.tables,
+        None,
+        &focus,
+        false,
+    );
+    assert!(graph.contains(r"loan_issued_at\('a, L0, _\)"));
+    assert!(graph.contains(r"loan_invalidated_at\(_, L0\)"));
+    assert!(!graph.contains("L1"));
+    assert!(!graph.contains("subset_base"));
+    assert!(!graph.contains("var_used_at"));
+
+    // the focus on a point and a loan keeps the tuples involving both
+    let output_dir = std::env::temp_dir().join(format!("polonius-focus-{}", std::process::id()));
+    let focus = dump::Focus {
+        loans: vec!["L1".to_string()],
+        points: vec!["Mid(B0[1])".to_string()],
+        ..Default::default()
+    };
+    let output = &result.output;
+    let tables = &result.tables;
+    dump::dump_output(
+        output,
+        &Some(output_dir.clone()),
+        tables,
+        false,
+        Default::default(),
+        &focus,
+        None,
+    )
+    .unwrap();
+    let read = |name: &str| std::fs::read_to_string(output_dir.join(name)).unwrap();
+    assert_eq!(
+        read("origin_contains_loan_at.facts"),
+        "\"Mid(B0[1])\"\t'b\tL1\n"
+    );
+    assert_eq!(read("known_placeholder_subset.facts"), "");
+    std::fs::remove_dir_all(&output_dir).unwrap();
+
+    // the quotes of rustc's atoms are optional, and don't need escaping
+    let focus = dump::Focus {
+        origins: vec!["'_#5r".to_string()],
+        ..Default::default()
+    };
+    assert!(focus.includes(&[(schema::AtomKind::Origin, "\"\\'_#5r\"")]));
+    assert!(!focus.includes(&[(schema::AtomKind::Origin, "\"\\'_#6r\"")]));
+    assert!(!focus.includes(&[(schema::AtomKind::Variable, "\"_1\"")]));
+}
+
+/// The schemas of the relations can be looked up by name, and match the columns of the facts.
+#[test]
+fn relation_schemas() {
+    let schema = schema::relation_schema("subset_base").unwrap();
+    assert_eq!(schema.arity(), 3);
+    assert_eq!(
+        schema.atoms,
+        [
+            schema::AtomKind::Origin,
+            schema::AtomKind::Origin,
+            schema::AtomKind::Point
+        ]
+    );
+    assert!(schema.required);
+    assert!(
+        !schema::relation_schema("universal_region_live_at")
+            .unwrap()
+            .required
+    );
+    assert_eq!(schema::relation_schema("outlives"), None);
+
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    for relation in tab_delim::RELATIONS {
+        let facts_file = facts_dir.join(format!("{}.facts", relation.name));
+        let Ok(contents) = std::fs::read_to_string(&facts_file) else {
+            continue;
+        };
+        for line in contents.lines() {
+            assert_eq!(
+                line.split('\t').count(),
+                relation.arity(),
+                "unexpected columns in {}",
+                facts_file.display()
+            );
+        }
+    }
+}
+
+/// Only the selected relations are dumped, and unknown relations are rejected.
+#[test]
+fn dump_selected_relations() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+
+    let output_dir = std::env::temp_dir().join(format!("polonius-select-{}", std::process::id()));
+    let selection: dump::RelationSelection = "move_errors, var_live_on_entry".parse().unwrap();
+    dump::dump_output(
+        &output,
+        &Some(output_dir.clone()),
+        tables,
+        false,
+        dump::DumpStyle::Tsv,
+        &dump::Focus::default(),
+        Some(&selection),
+    )
+    .unwrap();
+
+    let mut files: Vec<_> = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            manifest::MANIFEST_FILE,
+            "move_errors.facts",
+            "var_live_on_entry.facts"
+        ]
+    );
+    std::fs::remove_dir_all(&output_dir).unwrap();
+
+    let error = "errors,subset"
+        .parse::<dump::RelationSelection>()
+        .unwrap_err();
+    assert!(error.starts_with("unknown relation `subset`, expected one of: errors, "));
+    assert!(",".parse::<dump::RelationSelection>().is_err());
+}
+
+/// The cached outputs are restored with the same relations, for the same facts and settings.
+#[test]
+fn cached_outputs() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("move_reinitialize_ok");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    assert!(!output.origin_kind.is_empty());
+
+    let cache_dir = std::env::temp_dir().join(format!("polonius-cache-{}", std::process::id()));
+    let cache = OutputCache::new(&cache_dir, &facts, tables, "Naive");
+    assert!(cache.load(tables, true).unwrap().is_none());
+    cache.store(&output, tables).unwrap();
+
+    let cached = cache.load(tables, true).unwrap().unwrap();
+    assert_eq!(cached.errors, output.errors);
+    assert_eq!(cached.var_live_on_entry, output.var_live_on_entry);
+    assert_eq!(
+        cached.origin_contains_loan_at,
+        output.origin_contains_loan_at
+    );
+    assert_eq!(cached.subset, output.subset);
+    assert_eq!(cached.origin_kind, output.origin_kind);
+    assert!(cached.comparison.is_none());
+    assert_eq!(cached.partial, output.partial);
+
+    // the outputs where the variants found the same errors are restored as such
+    let output = Output::compute(&facts, Algorithm::Compare, false);
+    assert!(output.comparison.is_some());
+    let compared = OutputCache::new(&cache_dir, &facts, tables, "Compare");
+    compared.store(&output, tables).unwrap();
+    let cached = compared.load(tables, false).unwrap().unwrap();
+    assert!(cached
+        .comparison
+        .is_some_and(|comparison| comparison.is_empty()));
+
+    // other settings, or other facts, are stored in another entry
+    let other_settings = OutputCache::new(&cache_dir, &facts, tables, "Hybrid");
+    assert!(other_settings.load(tables, true).unwrap().is_none());
+    let mut other_facts = facts.clone();
+    other_facts.cfg_edge.pop();
+    let other_facts = OutputCache::new(&cache_dir, &other_facts, tables, "Naive");
+    assert!(other_facts.load(tables, true).unwrap().is_none());
+
+    // the kinds of move errors can't be restored, so the outputs with move errors aren't stored
+    let facts_dir = facts_dir.with_file_name("basic_move_error");
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let output = Output::compute(&facts, Algorithm::Naive, true);
+    assert!(!output.move_error_kinds.is_empty());
+    let move_errors = OutputCache::new(&cache_dir, &facts, tables, "Naive");
+    move_errors.store(&output, tables).unwrap();
+    assert!(move_errors.load(tables, true).unwrap().is_none());
+
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+/// Blessing a directory of facts records the errors of every variant in its golden file, and
+/// only rewrites it when they change.
+#[test]
+fn bless_golden_files() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts")
+        .join("basic_move_error");
+    let tables = &mut intern::InternerTables::new();
+    let facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap();
+    let errors = golden::compute_errors(&facts, tables);
+    assert!(errors.contains("[Naive] "));
+    assert!(errors.contains("[DatafrogOpt] "));
+
+    let blessed_dir = std::env::temp_dir().join(format!("polonius-bless-{}", std::process::id()));
+    std::fs::create_dir_all(&blessed_dir).unwrap();
+    assert!(golden::bless(&blessed_dir, &errors).unwrap());
+    assert_eq!(
+        std::fs::read_to_string(blessed_dir.join(golden::GOLDEN_FILE)).unwrap(),
+        errors
+    );
+    assert!(!golden::bless(&blessed_dir, &errors).unwrap());
+    assert!(golden::bless(&blessed_dir, "").unwrap());
+
+    std::fs::remove_dir_all(&blessed_dir).unwrap();
+}
+
+/// The directories of facts are found under a directory of functions, and selected by glob.
+#[test]
+fn filter_facts_dirs() {
+    assert!(glob::glob_matches(
+        "*add_defaults*",
+        "_ZN3foo12add_defaults17h"
+    ));
+    assert!(glob::glob_matches("main", "main"));
+    assert!(glob::glob_matches("ma?n*", "main"));
+    assert!(glob::glob_matches("*a*b", "aaab"));
+    assert!(!glob::glob_matches("*a*b", "aaba"));
+    assert!(!glob::glob_matches("main", "main2"));
+    assert!(!glob::glob_matches("?", ""));
+
+    let nll_facts = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("smoke-test")
+        .join("nll-facts");
+    let all = glob::find_facts_dirs(&nll_facts, None).unwrap();
+    assert!(all.len() > 1);
+
+    let filtered = glob::find_facts_dirs(&nll_facts, Some("*move_error*")).unwrap();
+    assert_eq!(filtered, [nll_facts.join("basic_move_error")]);
+
+    // a directory of facts is its own only directory of facts
+    let facts_dir = nll_facts.join("basic_move_error");
+    let found = glob::find_facts_dirs(&facts_dir, None).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0], facts_dir);
+    assert!(glob::find_facts_dirs(&facts_dir, Some("other"))
+        .unwrap()
+        .is_empty());
+}
+
+/// The rules of the book are found by label, in every section using it, or by the relation they
+/// derive, with the relations they join.
+#[test]
+fn explain_book_rules() {
+    let rules = rules::find_rules("r6").unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].output, "origin_contains_loan_on_entry");
+    assert_eq!(
+        rules[0].documentation,
+        ["propagate loans along the CFG, according to liveness"]
+    );
+    let inputs: Vec<_> = rules[0]
+        .inputs
+        .iter()
+        .map(|(relation, negated)| (relation.as_str(), *negated))
+        .collect();
+    assert_eq!(
+        inputs,
+        [
+            ("origin_contains_loan_on_entry", false),
+            ("loan_killed_at", true),
+            ("cfg_edge", false),
+            ("origin_live_on_entry", false),
+            ("placeholder", false),
+        ]
+    );
+    assert_eq!(rules[0].implementation().0, "naive::evaluate");
+    assert_eq!(rules[1].output, "potential_subset_errors");
+    assert_eq!(rules[1].implementation().0, "location_insensitive::compute");
+
+    let errors = rules::find_rules("errors").unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].label.as_deref(), Some("R8"));
+    assert!(errors[0]
+        .to_string()
+        .contains("loan_invalidated_at(Loan, Point),"));
+
+    // every rule is labeled, except the ones computing an input
+    assert!(rules::rules()
+        .iter()
+        .all(|rule| rule.label.is_some() || rule.output == "placeholder_known_to_contain"));
+
+    assert!(matches!(
+        rules::find_rules("R42"),
+        Err(error::Error::UnknownRule { .. })
+    ));
+}
+
+/// The relations of each variant's rules are produced and consumed by the rules of that variant
+/// only, and the dependencies are written as markdown and graphviz.
+#[test]
+fn book_rules_dependencies() {
+    let spec = rules::spec();
+    assert!(spec
+        .declarations
+        .iter()
+        .any(|declaration| declaration.name == "loan_killed_at" && declaration.input));
+
+    let dependencies = spec.dependencies();
+    let naive = &dependencies[0];
+    assert_eq!(naive.implementation, "naive::evaluate");
+    let labels = |rules: Vec<&rules::Rule>| -> Vec<String> {
+        rules
+            .iter()
+            .map(|rule| rule.label.clone().unwrap())
+            .collect()
+    };
+    let subset = &naive.relations["subset"];
+    assert_eq!(labels(subset.produced_by.clone()), ["R1", "R2", "R3"]);
+    assert_eq!(
+        labels(subset.consumed_by.iter().map(|&(rule, _)| rule).collect()),
+        ["R2", "R3", "R5", "R9"]
+    );
+    let loan_killed_at = &naive.relations["loan_killed_at"];
+    assert!(loan_killed_at.produced_by.is_empty());
+    assert_eq!(loan_killed_at.consumed_by.len(), 1);
+    assert!(loan_killed_at.consumed_by[0].1);
+
+    // the location-insensitive `subset` is another relation
+    let location_insensitive = &dependencies[1];
+    assert_eq!(
+        labels(location_insensitive.relations["subset"].produced_by.clone()),
+        ["R1"]
+    );
+
+    let mut markdown = Vec::new();
+    spec.write_dependencies(&mut markdown).unwrap();
+    let markdown = String::from_utf8(markdown).unwrap();
+    assert!(markdown.contains("| `subset` | R1, R2, R3 | R2, R3, R5, R9 |"));
+    assert!(markdown.contains("| `loan_killed_at` | input | !R6 |"));
+
+    let mut graph = Vec::new();
+    spec.write_dependency_graph(&mut graph).unwrap();
+    let graph = String::from_utf8(graph).unwrap();
+    assert!(graph.contains(
+        "\"0:loan_killed_at\" -> \"0:origin_contains_loan_on_entry\" [label=\"R6\", style=dashed];"
+    ));
+}
+
+/// The rules of the book are valid datalog, without unbound variables nor non-stratified
+/// negations.
+#[test]
+fn book_rules_have_no_lint_errors() {
+    for &(path, chapter) in rules::CHAPTERS {
+        let datalog: String = rules::prolog_blocks(chapter)
+            .into_iter()
+            .map(|(_, block)| block)
+            .collect();
+        let program = polonius_datalog::parse(&datalog).unwrap();
+        let errors: Vec<_> = polonius_datalog::lint(&program)
+            .into_iter()
+            .filter(|lint| lint.is_error())
+            .collect();
+        assert!(errors.is_empty(), "{}: {:?}", path, errors);
+    }
+}
+
+/// The interpreter evaluates rules joining the relations of the facts, and the liveness computed
+/// by the engine.
+#[test]
+fn interpreted_rules_join_the_facts() -> Result<(), Box<dyn Error>> {
+    let program = polonius_datalog::parse(
+        r"
+        .decl flows_into_live_origin(Loan:loan, Point:point)
+
+        flows_into_live_origin(Loan, Point) :-
+          loan_issued_at(Origin1, Loan, _),
+          subset_base(Origin1, Origin2, _),
+          origin_live_on_entry(Origin2, Point),
+          !loan_killed_at(Loan, Point).
+        ",
+    )?;
+
+    let facts_dir = Path::new("inputs/issue-47680/nll-facts/main");
+    let tables = &mut intern::InternerTables::new();
+    let all_facts = tab_delim::load_tab_delimited_facts(tables, facts_dir)?;
+    let mut database = interpret::facts_database(&all_facts);
+    polonius_datalog::evaluate(&program, &mut database)?;
+
+    let origin_live_on_entry = compute_origin_liveness(&all_facts);
+    let mut expected = BTreeSet::new();
+    for &(origin1, loan, _) in &all_facts.loan_issued_at {
+        for &(_, origin2, _) in all_facts
+            .subset_base
+            .iter()
+            .filter(|subset| subset.0 == origin1)
+        {
+            for &(live, point) in origin_live_on_entry.iter() {
+                if live == origin2 && !all_facts.loan_killed_at.contains(&(loan, point)) {
+                    expected.insert(vec![loan.index() as u32, point.index() as u32]);
+                }
+            }
+        }
+    }
+    let tuples: BTreeSet<_> = database.tuples("flows_into_live_origin").cloned().collect();
+    assert_eq!(tuples, expected);
+    assert!(!tuples.is_empty());
+
+    // the atoms are named after the types of the declared columns
+    let mut output = Vec::new();
+    interpret::write_relation(
+        &database,
+        "flows_into_live_origin",
+        program.declarations().next(),
+        tables,
+        &mut output,
+    )?;
+    let output = String::from_utf8(output)?;
+    assert_eq!(output.lines().count(), tuples.len());
+    assert!(output.starts_with("\"bw"), "{}", output);
+    Ok(())
 }
@@ -0,0 +1,123 @@
+//! The waivers of the known differences between the variants compared by `Algorithm::Compare`,
+//! like the divergences documented by the `issue_31567` test, so that differential CI jobs stay
+//! green on them while new differences still fail.
+//!
+//! A waiver file is a JSON object mapping directories of facts to the differences expected on
+//! them, in the format of the `<function>.comparison.json` files written by the CLI, which can be
+//! copied as is:
+//!
+//! ```json
+//! {
+//!   "issue-47680/nll-facts/main": {
+//!     "errors": {
+//!       "naive": [["Mid(bb3[2])", "bw0"]],
+//!       "datafrog_opt": []
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! A directory of facts is waived by the entry whose key ends its path, e.g. the name of its
+//! function. The relations and variants which aren't listed are expected to have no differences.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use polonius_engine::ErrorsDiff;
+
+use crate::dump::Comparison;
+use crate::error::Error;
+use crate::facts::LocalFacts;
+use crate::intern::InternerTables;
+
+/// The differing tuples of a comparison: their relation, the variant which found them, and their
+/// columns.
+type Differences = BTreeSet<(String, String, Vec<String>)>;
+
+/// The waived differences of each directory of facts.
+pub(crate) struct Waivers {
+    waivers: Vec<(PathBuf, Differences)>,
+}
+
+/// The result of checking a comparison against the waivers, see `Waivers::check`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct WaiverCheck {
+    /// The number of differences which are not waived.
+    pub(crate) unexpected: usize,
+
+    /// The number of waived differences which didn't occur, and can be removed from the waivers.
+    pub(crate) stale: usize,
+}
+
+impl Waivers {
+    /// Loads the waiver file at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::from_json(&text).map_err(|error| {
+            // the position is part of the `Error::Parse`, instead of its message
+            let position = format!(" at line {} column {}", error.line(), error.column());
+            let message = error.to_string();
+            Error::Parse {
+                file: Some(path.to_owned()),
+                line: error.line(),
+                column: error.column(),
+                message: message.trim_end_matches(&position).to_string(),
+            }
+        })
+    }
+
+    /// Parses the waivers of a JSON file.
+    pub(crate) fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        let members: BTreeMap<String, Comparison> = serde_json::from_str(text)?;
+        let waivers = members
+            .into_iter()
+            .map(|(facts_dir, comparison)| (PathBuf::from(facts_dir), differences(comparison)))
+            .collect();
+        Ok(Waivers { waivers })
+    }
+
+    /// Checks the differences found by `Algorithm::Compare` on the `facts_dir` against its
+    /// waiver, if any.
+    pub(crate) fn check(
+        &self,
+        facts_dir: &Path,
+        comparison: &ErrorsDiff<LocalFacts>,
+        tables: &InternerTables,
+    ) -> WaiverCheck {
+        let found = differences(Comparison::new(comparison, tables));
+
+        let empty = Differences::new();
+        let waived = self
+            .waivers
+            .iter()
+            .find(|(waived_dir, _)| facts_dir.ends_with(waived_dir))
+            .map_or(&empty, |(_, differences)| differences);
+        WaiverCheck {
+            unexpected: found.difference(waived).count(),
+            stale: waived.difference(&found).count(),
+        }
+    }
+}
+
+/// The differences of a comparison: the tuples found by each variant, by relation.
+fn differences(comparison: Comparison) -> Differences {
+    let mut differences = Differences::new();
+    for (relation, variants) in [
+        ("errors", comparison.errors),
+        ("subset_errors", comparison.subset_errors),
+    ] {
+        for (variant, tuples) in [
+            ("naive", variants.naive),
+            ("datafrog_opt", variants.datafrog_opt),
+        ] {
+            for columns in tuples {
+                differences.insert((relation.to_string(), variant.to_string(), columns));
+            }
+        }
+    }
+    differences
+}